@@ -0,0 +1,40 @@
+[[group(0), binding(1)]]
+var t_input: texture_2d<f32>;
+[[group(0), binding(3)]]
+var s_linear: sampler;
+
+// Vertex shader
+
+struct VertexOutput {
+    [[builtin(position)]] position: vec4<f32>;
+    [[location(0)]] uv: vec2<f32>;
+};
+
+// single triangle covering the whole frame
+[[stage(vertex)]]
+fn main(
+    [[builtin(vertex_index)]] index: u32,
+) -> VertexOutput {
+    var out: VertexOutput;
+
+    let x = f32((index << 1u) & 2u) * 2.0 - 1.0;
+    let y = f32(index & 2u) * 2.0 - 1.0;
+    out.position = vec4<f32>(x, y, 0.0, 1.0);
+    out.uv = vec2<f32>(x * 0.5 + 0.5, 0.5 - y * 0.5);
+
+    return out;
+}
+
+// Fragment shader
+
+struct FragmentOutput {
+    [[location(0)]] color: vec4<f32>;
+};
+
+// copies the input, the blending of the pipeline composites it onto the frame
+[[stage(fragment)]]
+fn main(input: VertexOutput) -> FragmentOutput {
+    var out: FragmentOutput;
+    out.color = textureSample(t_input, s_linear, input.uv);
+    return out;
+}
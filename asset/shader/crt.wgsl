@@ -0,0 +1,71 @@
+[[block]]
+struct PostUniforms {
+    // curvature, scanline strength, vignette strength, unused
+    params: vec4<f32>;
+    texel_size: vec4<f32>;
+};
+
+[[group(0), binding(0)]]
+var<uniform> post: PostUniforms;
+[[group(0), binding(1)]]
+var t_input: texture_2d<f32>;
+[[group(0), binding(3)]]
+var s_linear: sampler;
+
+// Vertex shader
+
+struct VertexOutput {
+    [[builtin(position)]] position: vec4<f32>;
+    [[location(0)]] uv: vec2<f32>;
+};
+
+// single triangle covering the whole frame
+[[stage(vertex)]]
+fn main(
+    [[builtin(vertex_index)]] index: u32,
+) -> VertexOutput {
+    var out: VertexOutput;
+
+    let x = f32((index << 1u) & 2u) * 2.0 - 1.0;
+    let y = f32(index & 2u) * 2.0 - 1.0;
+    out.position = vec4<f32>(x, y, 0.0, 1.0);
+    out.uv = vec2<f32>(x * 0.5 + 0.5, 0.5 - y * 0.5);
+
+    return out;
+}
+
+// Fragment shader
+
+struct FragmentOutput {
+    [[location(0)]] color: vec4<f32>;
+};
+
+[[stage(fragment)]]
+fn main(input: VertexOutput) -> FragmentOutput {
+    var out: FragmentOutput;
+
+    // bulge the frame like the glass of a tube
+    let centered = input.uv * 2.0 - 1.0;
+    let bent = centered * (1.0 + post.params.x * dot(centered, centered));
+    let uv = bent * 0.5 + 0.5;
+
+    if (uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+        out.color = vec4<f32>(0.0, 0.0, 0.0, 1.0);
+        return out;
+    }
+
+    let sampled = textureSample(t_input, s_linear, uv);
+    var color = sampled.rgb;
+
+    // one dark line every other output row
+    let row = floor(uv.y / post.texel_size.w);
+    let scanline = 0.5 + 0.5 * cos(row * 3.14159265);
+    color = color * (1.0 - post.params.y * scanline);
+
+    let vignette = 1.0 - post.params.z * dot(bent, bent) * 0.5;
+    color = color * clamp(vignette, 0.0, 1.0);
+
+    // keep the alpha so the frame canvasses below show through
+    out.color = vec4<f32>(color, sampled.a);
+    return out;
+}
@@ -0,0 +1,41 @@
+[[block]]
+struct Grading {
+    add: vec4<f32>;
+};
+
+[[group(0), binding(0)]]
+var<uniform> grading: Grading;
+
+// Vertex shader
+
+struct VertexOutput {
+    [[builtin(position)]] position: vec4<f32>;
+};
+
+// single triangle covering the whole frame
+[[stage(vertex)]]
+fn main(
+    [[builtin(vertex_index)]] index: u32,
+) -> VertexOutput {
+    var out: VertexOutput;
+
+    let x = f32((index << 1u) & 2u) * 2.0 - 1.0;
+    let y = f32(index & 2u) * 2.0 - 1.0;
+    out.position = vec4<f32>(x, y, 0.0, 1.0);
+
+    return out;
+}
+
+// Fragment shader
+
+struct FragmentOutput {
+    [[location(0)]] color: vec4<f32>;
+};
+
+// the multiply is applied via the blend constant
+[[stage(fragment)]]
+fn main(input: VertexOutput) -> FragmentOutput {
+    var out: FragmentOutput;
+    out.color = grading.add;
+    return out;
+}
@@ -12,6 +12,8 @@ struct Uniforms {
     camera_view: mat4x4<f32>;
     camera_proj: mat4x4<f32>;
     px_range_factor: f32;
+    // tint and opacity of the layer, multiplied into the tint of all instances
+    tint: vec4<f32>;
 };
 
 [[group(1), binding(0)]]
@@ -22,6 +24,7 @@ var<uniform> uniforms: Uniforms;
 struct VertexInput {
     [[location(0)]] position: vec3<f32>;
     [[location(1)]] tex_coords: vec2<f32>;
+    [[location(2)]] color: vec4<f32>;
 
     [[location(5)]] model_c0: vec4<f32>;
     [[location(6)]] model_c1: vec4<f32>;
@@ -33,6 +36,12 @@ struct VertexInput {
     [[location(9)]] scale: vec3<f32>;
     [[location(10)]] tint: vec4<f32>;
     [[location(11)]] texture_layer: i32;
+    // [outline color, outline width in distance range units]
+    [[location(12)]] outline: vec4<f32>;
+    // [shadow color, shadow opacity]
+    [[location(13)]] shadow: vec4<f32>;
+    // in atlas texels
+    [[location(14)]] shadow_offset: vec2<f32>;
 };
 
 struct VertexOutput {
@@ -41,6 +50,9 @@ struct VertexOutput {
     [[location(1)]] tint: vec4<f32>;
     [[location(2)]] distance_factor: f32;
     [[location(3)]] texture_layer: i32;
+    [[location(4)]] outline: vec4<f32>;
+    [[location(5)]] shadow: vec4<f32>;
+    [[location(6)]] shadow_offset: vec2<f32>;
 };
 
 [[stage(vertex)]]
@@ -50,8 +62,11 @@ fn main(
     var out: VertexOutput;
 
     out.tex_coords = input.tex_coords;
-    out.tint = input.tint;
+    out.tint = input.tint * input.color * uniforms.tint;
     out.texture_layer = input.texture_layer;
+    out.outline = input.outline;
+    out.shadow = input.shadow;
+    out.shadow_offset = input.shadow_offset;
 
     var model: mat4x4<f32> = mat4x4<f32>(input.model_c0, input.model_c1, input.model_c2, input.model_c3);
     var scaled: vec3<f32> = input.position * vec3<f32>(input.scale.x, input.scale.x, input.scale.x);
@@ -83,12 +98,27 @@ fn main(input: VertexOutput) -> FragmentOutput {
     var out: FragmentOutput;
 
     var msd: vec3<f32> = textureSample(t_diffuse, s_diffuse, input.tex_coords, input.texture_layer).rgb;
-    var sd: f32 = input.distance_factor * (median(msd.r, msd.g, msd.b) - 0.5);
-    var fill_alpha: f32 = clamp(sd + 0.5, 0.0, 1.0);
-    if (fill_alpha < 0.0001) {
+    var distance: f32 = median(msd.r, msd.g, msd.b) - 0.5;
+    var fill_alpha: f32 = clamp(input.distance_factor * distance + 0.5, 0.0, 1.0);
+    // the outline grows the glyph by its width, a zero width equals the fill
+    var alpha: f32 = clamp(input.distance_factor * (distance + input.outline.a) + 0.5, 0.0, 1.0);
+    var color: vec3<f32> = (input.tint.rgb * fill_alpha + input.outline.rgb * (alpha - fill_alpha)) / max(alpha, 0.0001);
+
+    if (input.shadow.a > 0.0) {
+        var texel_size: vec2<f32> = 1.0 / vec2<f32>(textureDimensions(t_diffuse));
+        var shadow_coords: vec2<f32> = input.tex_coords - input.shadow_offset * texel_size;
+        var shadow_msd: vec3<f32> = textureSampleLevel(t_diffuse, s_diffuse, shadow_coords, input.texture_layer, 0.0).rgb;
+        var shadow_distance: f32 = median(shadow_msd.r, shadow_msd.g, shadow_msd.b) - 0.5 + input.outline.a;
+        var shadow_alpha: f32 = clamp(input.distance_factor * shadow_distance + 0.5, 0.0, 1.0) * input.shadow.a * (1.0 - alpha);
+
+        color = (color * alpha + input.shadow.rgb * shadow_alpha) / max(alpha + shadow_alpha, 0.0001);
+        alpha = alpha + shadow_alpha;
+    }
+
+    if (alpha < 0.0001) {
         discard;
     }
-    out.color = vec4<f32>(input.tint.rgb, input.tint.a * fill_alpha);
+    out.color = vec4<f32>(color, input.tint.a * alpha);
 
     return out;
 }
\ No newline at end of file
@@ -4,6 +4,8 @@ struct Uniforms {
     camera_view: mat4x4<f32>;
     camera_proj: mat4x4<f32>;
     px_range_factor: f32;
+    // tint and opacity of the layer, multiplied into the tint of all instances
+    tint: vec4<f32>;
 };
 
 [[group(1), binding(0)]]
@@ -14,6 +16,7 @@ var<uniform> uniforms: Uniforms;
 struct VertexInput {
     [[location(0)]] position: vec3<f32>;
     [[location(1)]] tex_coords: vec2<f32>;
+    [[location(2)]] color: vec4<f32>;
 
     [[location(5)]] model_c0: vec4<f32>;
     [[location(6)]] model_c1: vec4<f32>;
@@ -24,6 +27,7 @@ struct VertexInput {
     [[location(9)]] scale: vec3<f32>;
     [[location(10)]] tint: vec4<f32>;
     [[location(11)]] texture_layer: i32;
+    [[location(15)]] uv_flip: vec2<f32>;
 };
 
 struct VertexOutput {
@@ -39,8 +43,8 @@ fn main(
 ) -> VertexOutput {
     var out: VertexOutput;
 
-    out.tex_coords = input.tex_coords;
-    out.tint = input.tint;
+    out.tex_coords = mix(input.tex_coords, vec2<f32>(1.0, 1.0) - input.tex_coords, input.uv_flip);
+    out.tint = input.tint * input.color * uniforms.tint;
     out.texture_layer = input.texture_layer;
 
     var model: mat4x4<f32> = mat4x4<f32>(input.model_c0, input.model_c1, input.model_c2, input.model_c3);
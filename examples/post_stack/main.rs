@@ -0,0 +1,178 @@
+use carousel::prelude::*;
+use nalgebra::{Point2, Rotation2, Vector2};
+
+/// two frame canvasses where only the top one has a post processing chain,
+/// the unprocessed canvas below shows through the transparent parts of the top one
+fn main() -> anyhow::Result<()> {
+    env_logger::builder()
+        .filter_level(log::LevelFilter::Info)
+        .filter_module("wgpu_core", log::LevelFilter::Warn)
+        .init();
+
+    let runtime = Runtime::builder(2_097_152)
+        .add(RenderServer::new)
+        .add(TimeServer::new)
+        .add(|b| AssetServer::builder(b).finish())
+        .add_group(|mut g| {
+            let resource = SimResource {
+                setup_builder: g.register(SetupState::handler),
+                running_builder: g.register(RunningState::handler),
+            };
+            SimServer::builder(g, || resource).init_fn(|resources| {
+                let setup_builder = resources.resource.setup_builder.clone();
+                State::from(setup_builder.init_finish(resources, SetupState).unwrap())
+            })
+        })
+        .finish_main_group(|g| {
+            PlatformServer::new(
+                AssetPath::sys("display.json"),
+                AssetPath::sys("actions.json"),
+                g,
+            )
+        });
+
+    Engine::builder()?
+        .with_sys_path("examples/hello_world/sys/")
+        .with_runtime(runtime)
+        .finish()
+        .start()
+}
+
+pub struct SimResource {
+    setup_builder: ClosedSimHandlerBuilder<SetupState, SimResource, State>,
+    running_builder: ClosedSimHandlerBuilder<RunningState, SimResource, State>,
+}
+
+enum State {
+    Setup(SimHandler<SetupState, SimResource, State>),
+    Running(SimHandler<RunningState, SimResource, State>),
+}
+
+impl SimState<SimResource> for State {
+    fn handle<M: MessageView>(
+        &mut self,
+        resources: &mut SimResources<SimResource>,
+        message: &M,
+    ) -> Option<StateInstruction<Self>> {
+        match self {
+            State::Setup(s) => s.handle(resources, message),
+            State::Running(s) => s.handle(resources, message),
+        }
+    }
+}
+
+impl From<SimHandler<SetupState, SimResource, State>> for State {
+    fn from(s: SimHandler<SetupState, SimResource, State>) -> Self {
+        Self::Setup(s)
+    }
+}
+
+impl From<SimHandler<RunningState, SimResource, State>> for State {
+    fn from(s: SimHandler<RunningState, SimResource, State>) -> Self {
+        Self::Running(s)
+    }
+}
+
+struct SetupState;
+
+impl SetupState {
+    fn handler(
+        h: OpenSimHandlerBuilder<SetupState, SimResource, State>,
+    ) -> OpenSimHandlerBuilder<SetupState, SimResource, State> {
+        h.on(Self::on_frame_requested_event)
+    }
+
+    fn on_frame_requested_event(
+        _state: &mut SetupState,
+        resources: &mut SimResources<SimResource>,
+        _event: &FrameRequestedEvent,
+    ) -> StateInstruction<State> {
+        let assets = resources.assets.client();
+        let render = &resources.render;
+        let camera_rect = Vector2::new(1280.0, 720.0);
+        let camera_eye = Point2::new(0.0, 0.0);
+
+        let main_camera = render.camera(camera_rect, camera_eye);
+        let main_layer = render.layer();
+        let sprite = main_layer.spawn(
+            Sprite::builder()
+                .with_size(Vector2::new(256.0, 256.0))
+                .with_texture(assets.load(AssetPath::sys("hello_world.json"))),
+        );
+
+        let crt_camera = render.camera(camera_rect, camera_eye);
+        let crt_layer = render.layer();
+        let text = crt_layer.spawn(
+            Text::builder()
+                .with_content("Only this text is post processed")
+                .with_point(16.0)
+                .with_position(Point2::new(-300.0, 200.0)),
+        );
+
+        let main_canvas = render
+            .canvas_frame()
+            .with_priority(0)
+            .cover_layer(&main_layer, &main_camera, [0.0, 0.0, 0.0, 1.0])
+            .finish();
+
+        // transparent, so the main canvas remains visible below the text
+        let crt_canvas = render
+            .canvas_frame()
+            .with_priority(1)
+            .cover_layer(&crt_layer, &crt_camera, [0.0, 0.0, 0.0, 0.0])
+            .with_post_effect(
+                PostEffect::new(render.defaults.crt_post_shader.clone())
+                    .with_params([0.1, 0.5, 0.5, 0.0]),
+            )
+            .finish();
+
+        std::mem::drop(assets);
+
+        let running = RunningState {
+            main_camera,
+            main_layer,
+            main_canvas,
+            crt_camera,
+            crt_layer,
+            crt_canvas,
+            sprite,
+            text,
+        };
+
+        let running_builder = resources.resource.running_builder.clone();
+        StateInstruction::pop_push(running_builder.init_finish(resources, running).unwrap())
+    }
+}
+
+#[allow(dead_code)]
+struct RunningState {
+    main_camera: Camera,
+    main_layer: CanvasLayer,
+    main_canvas: Canvas,
+    crt_camera: Camera,
+    crt_layer: CanvasLayer,
+    crt_canvas: Canvas,
+    sprite: Sprite,
+    text: Text,
+}
+
+impl RunningState {
+    fn handler(
+        h: OpenSimHandlerBuilder<RunningState, SimResource, State>,
+    ) -> OpenSimHandlerBuilder<RunningState, SimResource, State> {
+        h.on(Self::on_frame_requested_event)
+    }
+
+    fn on_frame_requested_event(
+        state: &mut RunningState,
+        _resources: &mut SimResources<SimResource>,
+        event: &FrameRequestedEvent,
+    ) -> StateInstruction<State> {
+        let normalized_secs = event.elapsed.as_secs_f32() % 5.0;
+
+        let mut sprite = state.sprite.modify();
+        sprite.rotation = Rotation2::new(normalized_secs * std::f32::consts::PI);
+
+        StateInstruction::Stay
+    }
+}
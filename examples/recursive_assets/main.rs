@@ -14,6 +14,7 @@ fn main() -> anyhow::Result<()> {
             AssetServer::builder(b)
                 .add_serde::<RecursiveAsset>()
                 .add_serde::<ChildAsset>()
+                .with_table_filter::<ChildAsset>(AssetFilter::extensions(["json"]))
                 .finish()
         })
         .add_group(|mut g| {
@@ -109,6 +110,30 @@ impl LoadingState {
         h: OpenSimHandlerBuilder<LoadingState, SimResource, State>,
     ) -> OpenSimHandlerBuilder<LoadingState, SimResource, State> {
         h.on(Self::on_frame_requested_event)
+            .on(Self::on_load_progress_event)
+            .on(Self::on_asset_load_failed_event::<RecursiveAsset>)
+            .on(Self::on_asset_load_failed_event::<WeakAssetTable<ChildAsset>>)
+    }
+
+    fn on_load_progress_event(
+        _state: &mut LoadingState,
+        _resources: &mut SimResources<SimResource>,
+        event: &LoadProgressEvent,
+    ) -> StateInstruction<State> {
+        println!("loading {}/{}", event.loaded, event.total);
+
+        StateInstruction::Stay
+    }
+
+    fn on_asset_load_failed_event<T: Send + Sync + 'static>(
+        _state: &mut LoadingState,
+        resources: &mut SimResources<SimResource>,
+        event: &AssetLoadFailedEvent<T>,
+    ) -> StateInstruction<State> {
+        println!("failed to load {}: {}", event.uri, event.error);
+        resources.shutdown_handle().request_shutdown();
+
+        StateInstruction::Stay
     }
 
     fn on_frame_requested_event(
@@ -126,13 +151,14 @@ impl LoadingState {
                 let lazy_loading = assets.upgrade(lazy);
                 println!("lazy[{}]: {:?}", i, assets.try_get(&lazy_loading));
             }
+            println!("inline: {:?}", assets.try_get(&recursive.inline));
         }
 
         if let Some(table) = assets.try_get(&state.table) {
             for (k, v) in table.iter() {
                 println!("table[{}]: {:?}", k, v);
             }
-            resources.context.shutdown_switch().request_shutdown();
+            resources.shutdown_handle().request_shutdown();
         }
 
         StateInstruction::Stay
@@ -144,6 +170,8 @@ impl LoadingState {
 pub struct RecursiveAsset {
     eager: Vec<StrongAssetId<ChildAsset>>,
     lazy: Vec<WeakAssetId<ChildAsset>>,
+    #[serde(deserialize_with = "inline_asset")]
+    inline: StrongAssetId<ChildAsset>,
 }
 
 #[derive(Deserialize, Debug)]
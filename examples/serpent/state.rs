@@ -83,6 +83,17 @@ impl SimState<SimResource> for State {
             State::GameOver(s) => s.handle(resources, message),
         }
     }
+
+    fn name(&self) -> &str {
+        match self {
+            State::Setup(_) => "setup",
+            State::MenuSetup(_) => "menuSetup",
+            State::Menu(_) => "menu",
+            State::GameSetup(_) => "gameSetup",
+            State::GameRun(_) => "gameRun",
+            State::GameOver(_) => "gameOver",
+        }
+    }
 }
 
 impl From<SimHandler<SetupState, SimResource, State>> for State {
@@ -23,10 +23,13 @@ impl SetupState {
         let camera_rect = Vector2::new(LOGICAL_WIDTH as f32, LOGICAL_HEIGHT as f32);
         let camera_eye = Point2::new(0.0, 0.0);
 
-        let main_camera = render.camera(camera_rect, camera_eye);
+        // the logical rect stays square on non square windows
+        let mut main_camera = render.camera(camera_rect, camera_eye);
+        main_camera.modify().projection.scale_mode = CameraScaleMode::FitLetterbox;
         let main_layer = render.layer();
 
-        let ui_camera = render.camera(camera_rect, camera_eye);
+        let mut ui_camera = render.camera(camera_rect, camera_eye);
+        ui_camera.modify().projection.scale_mode = CameraScaleMode::FitLetterbox;
         let ui_layer = render.layer();
 
         let canvas = render
@@ -25,14 +25,13 @@ impl GameSetupState {
     ) -> StateInstruction<State> {
         let render_resource = resources.resource.render.as_ref().unwrap();
 
-        let speed = 0.19;
-        TimeServer::schedule(
-            Duration::from_secs_f32(speed),
-            AdvanceSerpentCommand,
+        let advance_timer = TimeServer::schedule_interval_sim(
+            Duration::from_secs_f32(0.19),
+            || AdvanceSerpentCommand,
             resources.context.sender(),
         );
 
-        let game_run = GameRunState::new(speed, render_resource);
+        let game_run = GameRunState::new(advance_timer, render_resource);
         let game_run_builder = resources.resource.game_run_builder.clone();
         StateInstruction::pop_push(game_run_builder.init_finish(resources, game_run).unwrap())
     }
@@ -96,11 +95,12 @@ pub struct GameRunState {
     food: Food,
     food_list: Vec<Point2<isize>>,
     serpent: Vec<SerpentSegment>,
-    speed: f32,
+    advance_timer: TimerHandle,
+    paused: bool,
 }
 
 impl GameRunState {
-    pub fn new(speed: f32, render_resource: &RenderResources) -> Self {
+    pub fn new(advance_timer: TimerHandle, render_resource: &RenderResources) -> Self {
         let mut grid_path = Path::builder();
         for i in 0..11 {
             let i_extends = (i - 5) as f32 * GRID_ENTRY_EXTENDS;
@@ -137,7 +137,8 @@ impl GameRunState {
             food,
             food_list,
             serpent: vec![serpent_head],
-            speed,
+            advance_timer,
+            paused: false,
         }
     }
 
@@ -150,9 +151,24 @@ impl GameRunState {
 
     fn on_action_event(
         state: &mut GameRunState,
-        _resources: &mut SimResources<SimResource>,
+        resources: &mut SimResources<SimResource>,
         event: &ActionEvent,
     ) -> StateInstruction<State> {
+        if event.name.as_str() == "pause" {
+            if event.state == ActionState::Start {
+                state.paused = !state.paused;
+                resources.context.sender().send(SetSimPausedEvent {
+                    paused: state.paused,
+                });
+            }
+
+            return StateInstruction::Stay;
+        }
+
+        if state.paused {
+            return StateInstruction::Stay;
+        }
+
         let new_direction = match event.name.as_str() {
             "moveUp" => Vector2::new(0, 1),
             "moveLeft" => Vector2::new(-1, 0),
@@ -226,12 +242,6 @@ impl GameRunState {
             };
         }
 
-        TimeServer::schedule(
-            Duration::from_secs_f32(state.speed),
-            AdvanceSerpentCommand,
-            resources.context.sender(),
-        );
-
         StateInstruction::Stay
     }
 }
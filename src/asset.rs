@@ -1,20 +1,28 @@
 pub mod loader;
 pub mod notify;
+pub mod source;
 pub mod storage;
 
-use crate::asset::loader::{AssetCursor, AssetLoader, AssetTableLoader, SerdeAssetLoader};
+use crate::asset::loader::{
+    AssetCursor, AssetFilter, AssetLoader, AssetTableLoader, AssetTreeLoader, BincodeAssetLoader,
+    SerdeAssetLoader,
+};
 use crate::asset::notify::AssetChangeNotify;
-use crate::asset::storage::{Assets, AssetsPaths, InnerAssets};
+use crate::asset::source::{AssetSource, FsAssetSource, MountAssetSource};
+use crate::asset::storage::{Assets, AssetsPaths, GcStrategy, InnerAssets};
 use crate::platform::action::ActionsConfig;
+use crate::platform::input::ScanCodesConfig;
+use crate::platform::message::FrameRequestedEvent;
 use crate::platform::DisplayConfig;
 use crate::prelude::{AssetsClient, Font};
 use crate::render::mesh::MeshLoader;
 use crate::render::pipeline::{Pipeline, WGSLSourceLoader};
-use crate::render::view::{ImageLoader, Texture};
+use crate::render::text::FontLayout;
+use crate::render::view::{ImageLoader, Ktx2Loader, Texture};
 use crate::time::TimeServer;
-use crate::util::HashMap;
+use crate::util::{HashMap, HashSet};
 use crate::InitEvent;
-use crate::{some_or_break, some_or_continue};
+use crate::{some_or_break, some_or_continue, some_or_return};
 use internment::Intern;
 use parking_lot::Mutex;
 use relative_path::{RelativePath, RelativePathBuf};
@@ -23,6 +31,7 @@ use serde::de::{DeserializeOwned, Visitor};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::any::{Any, TypeId};
 use std::cmp::Ordering;
+use std::collections::VecDeque;
 use std::convert::TryFrom;
 use std::fmt::{Debug, Display, Formatter};
 use std::hash::Hasher;
@@ -32,32 +41,54 @@ use std::path::PathBuf;
 use std::rc::Rc;
 use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
 // TODO: mutable vs immutable assets (user)
-//  6. add save for UserAssets // errors? via events -> add load errors as well? -> typed?
+//  6. add load errors via events -> typed?
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum AssetPathKind {
     Sys,
     Usr,
+    /// named root added with `EngineBuilder::with_mount`, e.g. `mods/foo` for `mods/foo://`
+    Mount(Intern<String>),
 }
 
 impl AssetPathKind {
     #[inline]
-    pub fn protocol(&self) -> &'static str {
+    pub fn protocol(&self) -> &str {
         match self {
             AssetPathKind::Sys => "sys",
             AssetPathKind::Usr => "usr",
+            AssetPathKind::Mount(name) => name.as_str(),
         }
     }
 
+    /// mounts are resolved once their assets are accessed, unknown mounts fail to load
     #[inline]
     pub fn from_protocol(protocol: &str) -> Option<Self> {
         match protocol {
             "sys" => Some(Self::Sys),
             "usr" => Some(Self::Usr),
+            name => Self::mount(name),
+        }
+    }
+
+    /// mount names consist of alphanumeric characters, `-`, `_`, `.` and `/` separators,
+    /// the built-in sys and usr names can't be mounted
+    pub fn mount(name: &str) -> Option<Self> {
+        let valid = !name.is_empty()
+            && name.split('/').all(|segment| {
+                !segment.is_empty()
+                    && segment
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            });
+
+        match name {
+            "sys" | "usr" => None,
+            name if valid => Some(Self::Mount(Intern::new(name.to_owned()))),
             _ => None,
         }
     }
@@ -169,6 +200,40 @@ impl AssetPath {
             path: path.path(),
         }
     }
+
+    #[inline]
+    pub fn join<T: AsRef<RelativePath>>(&self, segment: T) -> Self {
+        Self {
+            kind: self.kind,
+            path: self.path.join(segment).path(),
+        }
+    }
+
+    #[inline]
+    pub fn with_extension<T: AsRef<str>>(&self, extension: T) -> Self {
+        Self {
+            kind: self.kind,
+            path: self.path.with_extension(extension).path(),
+        }
+    }
+
+    #[inline]
+    pub fn parent(&self) -> Option<Self> {
+        self.path.parent().map(|parent| Self {
+            kind: self.kind,
+            path: parent.path(),
+        })
+    }
+
+    #[inline]
+    pub fn file_name(&self) -> Option<&str> {
+        self.path.file_name()
+    }
+
+    #[inline]
+    pub fn extension(&self) -> Option<&str> {
+        self.path.extension()
+    }
 }
 
 impl FromStr for AssetPath {
@@ -482,6 +547,11 @@ impl<T: 'static> AssetId<T, Weak> {
         Self::new(AssetUri::Uuid(uuid))
     }
 
+    /// strong id that isn't counted by the asset storage, e.g. for render targets
+    pub(crate) fn untracked(self) -> StrongAssetId<T> {
+        self.into_strong(Arc::new(()))
+    }
+
     unsafe fn from_untyped(untyped: UntypedAssetId) -> Self {
         Self {
             untyped,
@@ -718,11 +788,19 @@ impl<T> From<LoadedAssetId<T>> for DynAssetId<T> {
 
 pub struct AssetServerBuilder {
     handler: OpenMessageHandlerBuilder<AssetServer>,
-    loaders: HashMap<TypeId, UntypedLoader>,
+    loaders: HashMap<TypeId, RegisteredLoader>,
+    table_filters: HashMap<TypeId, AssetFilter>,
     sync_queue_max: usize,
     gc_schedule: Duration,
     gc_max: usize,
+    gc_strategy: GcStrategy,
     hot_reloading: bool,
+    hot_reload_paths: Vec<AssetPath>,
+    hot_reload_excluded: HashSet<TypeId>,
+    unload_deleted: bool,
+    source: Option<Arc<dyn AssetSource>>,
+    mount_sources: HashMap<AssetPathKind, Arc<dyn AssetSource>>,
+    load_budget: Option<LoadBudget>,
 }
 
 impl AssetServerBuilder {
@@ -741,21 +819,91 @@ impl AssetServerBuilder {
         self
     }
 
+    pub fn with_gc_strategy(mut self, gc_strategy: GcStrategy) -> Self {
+        self.gc_strategy = gc_strategy;
+        self
+    }
+
     pub fn with_hot_reloading(mut self, hot_reloading: bool) -> Self {
         self.hot_reloading = hot_reloading;
         self
     }
 
+    /// only watch the given asset dirs instead of the whole sys / usr dirs, e.g. `sys://shaders`
+    pub fn with_hot_reload_path(mut self, asset_path: AssetPath) -> Self {
+        self.hot_reload_paths.push(asset_path);
+        self
+    }
+
+    /// changes of assets of this type won't trigger a reload
+    pub fn without_hot_reload<T: 'static>(mut self) -> Self {
+        self.hot_reload_excluded.insert(TypeId::of::<T>());
+        self
+    }
+
+    /// unload assets whose file has been deleted, otherwise the stale asset stays resident
+    pub fn with_unload_deleted(mut self, unload_deleted: bool) -> Self {
+        self.unload_deleted = unload_deleted;
+        self
+    }
+
+    /// spreads loads over frames, at most max_loads or max_duration of loading per frame,
+    /// a max_loads of 0 doesn't limit the count, at least one asset is loaded per frame,
+    /// loads before the first frame aren't deferred, e.g. the display config the frames wait for
+    pub fn with_load_budget(mut self, max_loads: usize, max_duration: Duration) -> Self {
+        self.load_budget = Some(LoadBudget {
+            max_loads: if max_loads == 0 {
+                usize::MAX
+            } else {
+                max_loads
+            },
+            max_duration,
+        });
+        self
+    }
+
+    /// replaces the default file system source of the sys / usr dirs
+    pub fn with_source<T: AssetSource>(mut self, source: T) -> Self {
+        self.source = Some(Arc::new(source));
+        self
+    }
+
+    /// serves the assets of the kind from their own source, e.g. a `ZipAssetSource`
+    pub fn with_mount_source<T: AssetSource>(mut self, kind: AssetPathKind, source: T) -> Self {
+        self.mount_sources.insert(kind, Arc::new(source));
+        self
+    }
+
     pub fn add_serde<T: DeserializeOwned + Send + Sync + 'static>(self) -> Self {
         self.add::<SerdeAssetLoader<T>>()
     }
 
+    /// loads `.bin` files of bincode serialized assets, instead of the json of `add_serde`
+    pub fn add_bincode<T: DeserializeOwned + Send + Sync + 'static>(self) -> Self {
+        self.add::<BincodeAssetLoader<T>>()
+    }
+
     pub fn add<T: AssetLoader>(mut self) -> Self {
+        let table_filter = self
+            .table_filters
+            .get(&TypeId::of::<T::Asset>())
+            .cloned()
+            .unwrap_or_default();
+
         unsafe {
-            self.insert_loader::<T>();
-            self.insert_loader::<AssetTableLoader<T::Asset, Weak>>();
-            self.insert_loader::<AssetTableLoader<T::Asset, Strong>>();
-            self.insert_loader::<AssetTableLoader<T::Asset, Loaded>>();
+            self.insert_loader::<T>(AssetFilter::Any);
+            self.insert_table_loaders::<T::Asset>(table_filter);
+        }
+
+        self
+    }
+
+    /// asset tables of this type only contain the files of the dir matching the filter
+    pub fn with_table_filter<T: Send + Sync + 'static>(mut self, filter: AssetFilter) -> Self {
+        self.table_filters.insert(TypeId::of::<T>(), filter.clone());
+
+        if self.loaders.contains_key(&TypeId::of::<T>()) {
+            unsafe { self.insert_table_loaders::<T>(filter) };
         }
 
         self
@@ -765,10 +913,18 @@ impl AssetServerBuilder {
         let Self {
             handler,
             loaders,
+            table_filters: _,
             sync_queue_max,
             gc_schedule,
             gc_max,
+            gc_strategy,
             hot_reloading,
+            hot_reload_paths,
+            hot_reload_excluded,
+            unload_deleted,
+            source,
+            mount_sources,
+            load_budget,
         } = self;
 
         let notify = if hot_reloading {
@@ -780,21 +936,32 @@ impl AssetServerBuilder {
         handler
             .on(on_init_event)
             .on(on_store_asset_event)
+            .on(on_transaction_asset_event)
             .on(on_sync_asset_event)
             .on(on_gc_assets_event)
             .on(on_notify_assets_event)
             .on(on_load_asset_event)
+            .on(on_save_asset_event)
+            .on(on_load_barrier_event)
+            .on(on_frame_requested_event)
             .init_fn(move |context| AssetServer {
                 loaders,
                 assets: Assets {
-                    inner: Arc::new(Default::default()),
+                    inner: Arc::new(InnerAssets::new(gc_strategy)),
                     sender: context.sender().clone(),
                     paths: Rc::new(AssetsPaths {
                         // TODO: why don't we init the asset server sys / usr dirs with the builder?
                         sys_dir: Default::default(),
                         usr_dir: Default::default(),
+                        mounts: Default::default(),
+                        source: Arc::new(FsAssetSource::new(
+                            Default::default(),
+                            Default::default(),
+                        )),
                     }),
                 },
+                source,
+                mount_sources,
                 sync: 0,
                 sync_requested: 0,
                 sync_queue: Default::default(),
@@ -802,37 +969,79 @@ impl AssetServerBuilder {
                 gc_at: 0,
                 gc_schedule,
                 gc_max,
+                gc_strategy,
                 notify,
+                hot_reload_paths,
+                hot_reload_excluded,
+                unload_deleted,
+                load_budget,
+                pending_loads: Default::default(),
+                frame_requested: false,
+                barriers: Default::default(),
+                failed: Default::default(),
+                progress: Default::default(),
             })
     }
 
-    unsafe fn insert_loader<T: AssetLoader>(&mut self) {
-        self.loaders.insert(
-            TypeId::of::<T::Asset>(),
-            Box::new(move |id, a, sq, dq| {
-                let asset_path = id
-                    .uri
-                    .asset_path()
-                    .ok_or_else(|| anyhow::anyhow!("asset path to load not found"))?;
-
-                let mut cursor = AssetCursor {
-                    asset_path,
-                    assets: a,
-                    sync_queue: sq,
-                    dependency_queue: dq,
-                };
+    unsafe fn insert_table_loaders<T: Send + Sync + 'static>(&mut self, filter: AssetFilter) {
+        self.insert_loader::<AssetTableLoader<T, Weak>>(filter.clone());
+        self.insert_loader::<AssetTableLoader<T, Strong>>(filter.clone());
+        self.insert_loader::<AssetTableLoader<T, Loaded>>(filter.clone());
+        self.insert_loader::<AssetTreeLoader<T, Weak>>(filter.clone());
+        self.insert_loader::<AssetTreeLoader<T, Strong>>(filter.clone());
+        self.insert_loader::<AssetTreeLoader<T, Loaded>>(filter);
+    }
+
+    unsafe fn insert_loader<T: AssetLoader>(&mut self, filter: AssetFilter) {
+        let load: UntypedLoader = Box::new(move |id, a, sq, dq| {
+            let asset_path = id
+                .uri
+                .asset_path()
+                .ok_or_else(|| anyhow::anyhow!("asset path to load not found"))?;
+
+            let mut cursor = AssetCursor {
+                asset_path,
+                filter: &filter,
+                assets: a,
+                sync_queue: sq,
+                dependency_queue: dq,
+            };
 
-                let asset = T::load(&mut cursor)?;
-                let typed_id: WeakAssetId<T::Asset> = WeakAssetId::from_untyped(id);
-                let entry = SyncQueueEntry::new(typed_id, asset, &a.sender);
-                sq.push(entry);
+            let asset = T::load(&mut cursor)?;
+            let typed_id: WeakAssetId<T::Asset> = WeakAssetId::from_untyped(id);
+            let entry = SyncQueueEntry::new(typed_id, asset, &a.sender);
+            sq.push(entry);
 
-                Ok(())
-            }),
+            Ok(())
+        });
+
+        self.loaders.insert(
+            TypeId::of::<T::Asset>(),
+            RegisteredLoader {
+                load,
+                failed: send_load_failed_event::<T::Asset>,
+            },
         );
     }
 }
 
+struct RegisteredLoader {
+    load: UntypedLoader,
+    failed: fn(UntypedAssetId, String, &MessageSender),
+}
+
+fn send_load_failed_event<T: 'static + Send + Sync>(
+    id: UntypedAssetId,
+    error: String,
+    sender: &MessageSender,
+) {
+    sender.send(AssetLoadFailedEvent {
+        id: unsafe { WeakAssetId::<T>::from_untyped(id) },
+        uri: id.uri,
+        error,
+    });
+}
+
 type UntypedLoader = Box<
     dyn FnMut(
             UntypedAssetId,
@@ -849,6 +1058,7 @@ pub(crate) struct SyncQueueEntry {
     asset_id: UntypedAssetId,
     asset: UntypedAsset,
     loaded_event: Option<UntypedMessage>,
+    reloaded_event: Option<UntypedMessage>,
     unloaded_event: Option<UntypedMessage>,
 }
 
@@ -863,6 +1073,8 @@ impl SyncQueueEntry {
             kind: AssetEventKind::Load,
         });
 
+        let reloaded_event = sender.prepare(AssetReloadedEvent { id });
+
         let unloaded_event = sender.prepare(AssetEvent {
             id,
             kind: AssetEventKind::Unload,
@@ -872,6 +1084,7 @@ impl SyncQueueEntry {
             asset_id: id.untyped,
             asset: Box::new(asset),
             loaded_event,
+            reloaded_event,
             unloaded_event,
         }
     }
@@ -882,9 +1095,17 @@ pub(crate) struct DependencyQueueEntry {
     force: bool,
 }
 
+#[derive(Debug, Copy, Clone)]
+struct LoadBudget {
+    max_loads: usize,
+    max_duration: Duration,
+}
+
 pub struct AssetServer {
-    loaders: HashMap<TypeId, UntypedLoader>,
+    loaders: HashMap<TypeId, RegisteredLoader>,
     assets: Assets,
+    source: Option<Arc<dyn AssetSource>>,
+    mount_sources: HashMap<AssetPathKind, Arc<dyn AssetSource>>,
     sync: u64,
     sync_requested: u64,
     sync_queue: Vec<SyncQueueEntry>,
@@ -892,18 +1113,156 @@ pub struct AssetServer {
     gc_at: usize,
     gc_schedule: Duration,
     gc_max: usize,
+    gc_strategy: GcStrategy,
     notify: Option<AssetChangeNotify>,
+    hot_reload_paths: Vec<AssetPath>,
+    hot_reload_excluded: HashSet<TypeId>,
+    unload_deleted: bool,
+    load_budget: Option<LoadBudget>,
+    pending_loads: VecDeque<LoadAssetEvent>,
+    frame_requested: bool,
+    barriers: HashMap<Uuid, PendingBarrier>,
+    failed: HashSet<UntypedAssetId>,
+    progress: LoadProgressEvent,
+}
+
+struct PendingBarrier {
+    pending: HashSet<UntypedAssetId>,
+    failed: usize,
 }
 
 impl AssetServer {
+    /// resolves the assets of the pending barriers and sends the events of completed barriers
+    fn resolve_barriers<'a, I>(&mut self, asset_ids: I, failed: bool)
+    where
+        I: IntoIterator<Item = &'a UntypedAssetId>,
+    {
+        if self.barriers.is_empty() {
+            return;
+        }
+
+        for asset_id in asset_ids {
+            for barrier in self.barriers.values_mut() {
+                if barrier.pending.remove(asset_id) && failed {
+                    barrier.failed += 1;
+                }
+            }
+        }
+
+        let sender = &self.assets.sender;
+        self.barriers.retain(|id, barrier| {
+            if !barrier.pending.is_empty() {
+                return true;
+            }
+
+            sender.send(LoadBarrierCompleteEvent {
+                id: *id,
+                failed: barrier.failed,
+            });
+            false
+        });
+    }
+
+    /// sends the current progress and starts counting anew once all loads completed
+    fn send_progress(&mut self, context: &mut RuntimeContext) {
+        context.sender().send(self.progress);
+
+        if self.progress.loaded >= self.progress.total {
+            self.progress = LoadProgressEvent::default();
+        }
+    }
+
+    /// returns false if the asset or any of its dependencies could not be loaded
+    fn load_asset(&mut self, event: &LoadAssetEvent) -> bool {
+        let mut dependency_queue = Vec::default();
+        let mut load_asset_id = event.id;
+        let mut force = event.force;
+        let counted = !event.reload;
+
+        loop {
+            if force || !self.assets.client().has_untyped(&load_asset_id) {
+                let loader = match self.loaders.get_mut(&load_asset_id.tid) {
+                    Some(loader) => loader,
+                    None => panic!(
+                        "AssetLoader not found for type of {:?}",
+                        load_asset_id.tname
+                    ),
+                };
+
+                let dependencies = dependency_queue.len();
+                let load_result = (loader.load)(
+                    load_asset_id,
+                    &mut self.assets,
+                    &mut self.sync_queue,
+                    &mut dependency_queue,
+                );
+                if counted {
+                    self.progress.total += dependency_queue.len().saturating_sub(dependencies);
+                }
+
+                if let Err(e) = load_result {
+                    if counted {
+                        // the remaining dependencies won't be loaded anymore
+                        self.progress.loaded += 1 + dependency_queue.len();
+                    }
+
+                    self.failed.insert(load_asset_id);
+                    self.failed.insert(event.id);
+                    self.resolve_barriers([load_asset_id, event.id].iter(), true);
+
+                    let loader = self.loaders.get(&load_asset_id.tid).unwrap();
+                    let sender = &self.assets.sender;
+                    (loader.failed)(load_asset_id, e.to_string(), sender);
+
+                    if load_asset_id == event.id {
+                        log::error!("Could not load asset {:?}: {}", load_asset_id, e);
+                    } else {
+                        log::error!(
+                            "Could not load asset {:?} as dependent asset {:?} failed: {}",
+                            event.id,
+                            load_asset_id,
+                            e
+                        );
+
+                        if let Some(loader) = self.loaders.get(&event.id.tid) {
+                            let error = format!("dependency {:?} failed: {}", load_asset_id, e);
+                            (loader.failed)(event.id, error, sender);
+                        }
+                    }
+
+                    return false;
+                }
+            }
+
+            if counted {
+                self.progress.loaded += 1;
+            }
+
+            if let Some(next) = dependency_queue.pop() {
+                load_asset_id = next.asset_id;
+                force = next.force;
+            } else {
+                return true;
+            }
+        }
+    }
+
     pub fn empty(handler: OpenMessageHandlerBuilder<AssetServer>) -> AssetServerBuilder {
         AssetServerBuilder {
             handler,
             loaders: Default::default(),
+            table_filters: Default::default(),
             sync_queue_max: usize::MAX,
             gc_schedule: Duration::from_secs(1),
             gc_max: usize::MAX,
+            gc_strategy: GcStrategy::default(),
             hot_reloading: true,
+            hot_reload_paths: Vec::default(),
+            hot_reload_excluded: Default::default(),
+            unload_deleted: false,
+            source: None,
+            mount_sources: Default::default(),
+            load_budget: None,
         }
     }
 
@@ -911,19 +1270,40 @@ impl AssetServer {
         Self::empty(handler)
             .add_serde::<DisplayConfig>()
             .add_serde::<ActionsConfig>()
+            .add_serde::<ScanCodesConfig>()
             .add_serde::<Texture>()
             .add_serde::<Pipeline>()
             .add_serde::<Font>()
+            .add_serde::<FontLayout>()
             .add::<WGSLSourceLoader>()
             .add::<MeshLoader>()
             .add::<ImageLoader>()
+            .add::<Ktx2Loader>()
     }
 }
 
 fn on_init_event(state: &mut AssetServer, context: &mut RuntimeContext, event: &InitEvent) {
+    let source = state.source.clone().unwrap_or_else(|| {
+        let fs_source = event.mounts.iter().fold(
+            FsAssetSource::new(event.sys_dir.clone(), event.usr_dir.clone()),
+            |fs_source, (kind, dir)| fs_source.with_mount(*kind, dir.clone()),
+        );
+        Arc::new(fs_source)
+    });
+    let source: Arc<dyn AssetSource> = if state.mount_sources.is_empty() {
+        source
+    } else {
+        let mount_source = state.mount_sources.iter().fold(
+            MountAssetSource::with_default(source),
+            |mount_source, (kind, source)| mount_source.with_shared_source(*kind, source.clone()),
+        );
+        Arc::new(mount_source)
+    };
     state.assets.paths = Rc::new(AssetsPaths {
         sys_dir: event.sys_dir.clone(),
         usr_dir: event.usr_dir.clone(),
+        mounts: event.mounts.clone(),
+        source,
     });
 
     log::info!("assets created");
@@ -931,26 +1311,59 @@ fn on_init_event(state: &mut AssetServer, context: &mut RuntimeContext, event: &
         inner: state.assets.inner.clone(),
         sys_dir: state.assets.paths.sys_dir.clone(),
         usr_dir: state.assets.paths.usr_dir.clone(),
+        mounts: state.assets.paths.mounts.clone(),
+        source: state.assets.paths.source.clone(),
     });
 
     TimeServer::schedule(state.gc_schedule, GcAssetsEvent, context.sender());
 
+    if state.source.is_some() && state.notify.take().is_some() {
+        log::info!("hot reloading is only supported for the file system asset source");
+    }
+
     if let Some(notify) = &mut state.notify {
         log::info!("start watching assets for changes");
-        if let Err(e) = notify.watch(&state.assets.paths.sys_dir) {
-            log::warn!(
-                "could not watch sys asset dir {}: {}",
-                state.assets.paths.sys_dir.display(),
-                e
-            )
+        if state.hot_reload_paths.is_empty() {
+            if let Err(e) = notify.watch(&state.assets.paths.sys_dir) {
+                log::warn!(
+                    "could not watch sys asset dir {}: {}",
+                    state.assets.paths.sys_dir.display(),
+                    e
+                )
+            }
+
+            if let Err(e) = notify.watch(&state.assets.paths.usr_dir) {
+                log::warn!(
+                    "could not watch usr asset dir {}: {}",
+                    state.assets.paths.usr_dir.display(),
+                    e
+                )
+            }
+
+            for (kind, dir) in &state.assets.paths.mounts {
+                if let Err(e) = notify.watch(dir) {
+                    log::warn!(
+                        "could not watch {} asset dir {}: {}",
+                        kind.protocol(),
+                        dir.display(),
+                        e
+                    )
+                }
+            }
         }
 
-        if let Err(e) = notify.watch(&state.assets.paths.usr_dir) {
-            log::warn!(
-                "could not watch usr asset dir {}: {}",
-                state.assets.paths.sys_dir.display(),
-                e
-            )
+        for asset_path in &state.hot_reload_paths {
+            let asset_dir = match state.assets.paths.asset_dir(&asset_path.kind()) {
+                Some(asset_dir) => asset_dir,
+                None => {
+                    log::warn!("could not watch unmounted asset dir {}", asset_path);
+                    continue;
+                }
+            };
+            let dir = asset_path.path().to_path(asset_dir);
+            if let Err(e) = notify.watch(&dir) {
+                log::warn!("could not watch asset dir {}: {}", asset_path, e)
+            }
         }
 
         TimeServer::schedule(
@@ -966,54 +1379,120 @@ fn on_load_asset_event(
     context: &mut RuntimeContext,
     event: &LoadAssetEvent,
 ) {
-    let mut dependency_queue = Vec::default();
-    let mut load_asset_id = event.id;
-    let mut force = event.force;
+    // barriers wait for the new attempt instead of counting the previous failure
+    state.failed.remove(&event.id);
+
+    if !event.reload {
+        state.progress.total += 1;
+    }
+
+    if state.load_budget.is_some() && state.frame_requested {
+        state.pending_loads.push_back(event.clone());
+        if !event.reload {
+            state.send_progress(context);
+        }
+        return;
+    }
+
     let start_sync_queue_len = state.sync_queue.len();
 
-    loop {
-        if force || !state.assets.client().has_untyped(&load_asset_id) {
-            let loader = match state.loaders.get_mut(&load_asset_id.tid) {
-                Some(loader) => loader,
-                None => panic!(
-                    "AssetLoader not found for type of {:?}",
-                    load_asset_id.tname
-                ),
-            };
+    if !state.load_asset(event) {
+        // rollback
+        state.sync_queue.truncate(start_sync_queue_len);
+    }
+    if !event.reload {
+        state.send_progress(context);
+    }
 
-            let load_result = (loader)(
-                load_asset_id,
-                &mut state.assets,
-                &mut state.sync_queue,
-                &mut dependency_queue,
-            );
-
-            if let Err(e) = load_result {
-                if load_asset_id == event.id {
-                    log::error!("Could not load asset {:?}: {}", load_asset_id, e);
-                } else {
-                    log::error!(
-                        "Could not load asset {:?} as dependent asset {:?} failed: {}",
-                        event.id,
-                        load_asset_id,
-                        e
-                    );
-                }
+    if start_sync_queue_len < state.sync_queue.len() {
+        state.sync_requested += 1;
+        context.sender().send(SyncAssetEvent {
+            sync: state.sync_requested,
+        });
+    }
+}
 
-                // rollback
-                state.sync_queue.truncate(start_sync_queue_len);
-                break;
-            }
-        }
+fn on_save_asset_event(
+    state: &mut AssetServer,
+    context: &mut RuntimeContext,
+    event: &SaveAssetEvent,
+) {
+    let result = if event.path.kind == AssetPathKind::Usr {
+        state.assets.paths.source.write(&event.path, &event.bytes)
+    } else {
+        Err(anyhow::anyhow!("only usr assets can be saved"))
+    };
 
-        if let Some(next) = dependency_queue.pop() {
-            load_asset_id = next.asset_id;
-            force = next.force;
-        } else {
+    let error = result.err().map(|e| {
+        log::error!("Could not save asset {:?}: {}", event.path, e);
+        e.to_string()
+    });
+
+    context.sender().send(SaveResultEvent {
+        path: event.path,
+        error,
+    });
+}
+
+fn on_frame_requested_event(
+    state: &mut AssetServer,
+    context: &mut RuntimeContext,
+    _event: &FrameRequestedEvent,
+) {
+    state.frame_requested = true;
+    let budget = some_or_return!(state.load_budget);
+    if state.pending_loads.is_empty() {
+        return;
+    }
+
+    let start = Instant::now();
+    let start_sync_queue_len = state.sync_queue.len();
+
+    for loaded in 0..budget.max_loads {
+        if loaded > 0 && start.elapsed() >= budget.max_duration {
             break;
         }
+
+        let load = some_or_break!(state.pending_loads.pop_front());
+        let load_sync_queue_len = state.sync_queue.len();
+        if !state.load_asset(&load) {
+            // rollback
+            state.sync_queue.truncate(load_sync_queue_len);
+        }
     }
 
+    log::debug!(
+        "budgeted asset loading with {} pending load(s) left",
+        state.pending_loads.len()
+    );
+    state.send_progress(context);
+
+    if start_sync_queue_len < state.sync_queue.len() {
+        state.sync_requested += 1;
+        context.sender().send(SyncAssetEvent {
+            sync: state.sync_requested,
+        });
+    }
+}
+
+fn on_transaction_asset_event(
+    state: &mut AssetServer,
+    context: &mut RuntimeContext,
+    event: &TransactionAssetEvent,
+) {
+    let start_sync_queue_len = state.sync_queue.len();
+
+    for load in &event.loads {
+        if !state.load_asset(load) {
+            log::error!("rollback asset transaction as {:?} failed", load.id);
+            state.sync_queue.truncate(start_sync_queue_len);
+            return;
+        }
+    }
+
+    // the whole queue is synced at once, which commits the transaction in a single batch
+    state.sync_queue.extend(event.entries.lock().drain(..));
+
     if start_sync_queue_len < state.sync_queue.len() {
         state.sync_requested += 1;
         context.sender().send(SyncAssetEvent {
@@ -1031,6 +1510,7 @@ fn on_store_asset_event(
         asset_id: event.id,
         asset: event.asset.lock().take().expect("store asset"),
         loaded_event: event.load_event.lock().take(),
+        reloaded_event: None,
         unloaded_event: event.unload_event.lock().take(),
     };
     state.sync_queue.push(sync_queue_entry);
@@ -1065,7 +1545,41 @@ fn on_sync_asset_event(
     );
     state.sync = state.sync_requested;
 
+    let synced: Vec<UntypedAssetId> = state.sync_queue.iter().map(|e| e.asset_id).collect();
     unsafe { state.assets.extend(state.sync_queue.drain(..)) };
+
+    for asset_id in &synced {
+        state.failed.remove(asset_id);
+    }
+    state.resolve_barriers(&synced, false);
+}
+
+fn on_load_barrier_event(
+    state: &mut AssetServer,
+    _context: &mut RuntimeContext,
+    event: &LoadBarrierEvent,
+) {
+    let client = state.assets.client();
+    let mut failed = 0;
+    let pending: HashSet<UntypedAssetId> = event
+        .assets
+        .iter()
+        .filter(|asset_id| {
+            if state.failed.contains(asset_id) {
+                failed += 1;
+                return false;
+            }
+
+            !client.has_untyped(asset_id)
+        })
+        .copied()
+        .collect();
+    std::mem::drop(client);
+
+    state
+        .barriers
+        .insert(event.id, PendingBarrier { pending, failed });
+    state.resolve_barriers(None, false);
 }
 
 fn on_gc_assets_event(
@@ -1074,7 +1588,14 @@ fn on_gc_assets_event(
     _event: &GcAssetsEvent,
 ) {
     log::debug!("start assets gc");
-    state.gc_at = state.assets.gc(state.gc_at, state.gc_max);
+    state.gc_at = state
+        .assets
+        .gc(state.gc_at, state.gc_max, state.gc_strategy);
+
+    // failures of collected assets are forgotten, as loading them again starts anew
+    let assets = &state.assets;
+    state.failed.retain(|id| assets.is_registered(id));
+
     TimeServer::schedule(state.gc_schedule, GcAssetsEvent, context.sender());
 }
 
@@ -1088,12 +1609,38 @@ fn on_notify_assets_event(
     for changed in state.notify.as_mut().unwrap().changes_iter() {
         let assets = &state.assets;
         let mut asset_path = some_or_continue!(assets.paths.asset_path(&changed.path));
-        let asset_dir = assets.paths.asset_dir(&asset_path.kind);
+        let asset_dir = some_or_continue!(assets.paths.asset_dir(&asset_path.kind));
+
+        if changed.removed {
+            for asset_id in assets.asset_ids_for_path(asset_path) {
+                log::info!("asset file deleted: {:?}", asset_id);
+                if state.unload_deleted {
+                    assets.unload(&asset_id);
+                }
+
+                context.sender().send(AssetDeletedEvent {
+                    id: asset_id,
+                    path: asset_path,
+                });
+            }
+
+            // parent folder assets are reloaded without the deleted asset
+            asset_path = some_or_continue!(asset_path
+                .path
+                .parent()
+                .and_then(|p| assets.paths.asset_path(&p.to_path(asset_dir))));
+        }
+
         loop {
             for asset_id in assets.asset_ids_for_path(asset_path) {
+                if state.hot_reload_excluded.contains(&asset_id.tid) {
+                    continue;
+                }
+
                 context.sender().send(LoadAssetEvent {
                     id: asset_id,
                     force: true,
+                    reload: true,
                 });
             }
 
@@ -1116,6 +1663,8 @@ pub struct AssetsCreatedEvent {
     inner: Arc<InnerAssets>,
     sys_dir: PathBuf,
     usr_dir: PathBuf,
+    mounts: Vec<(AssetPathKind, PathBuf)>,
+    source: Arc<dyn AssetSource>,
 }
 
 impl AssetsCreatedEvent {
@@ -1128,15 +1677,106 @@ impl AssetsCreatedEvent {
             paths: Rc::new(AssetsPaths {
                 sys_dir: self.sys_dir.clone(),
                 usr_dir: self.usr_dir.clone(),
+                mounts: self.mounts.clone(),
+                source: self.source.clone(),
             }),
         }
     }
 }
 
+/// the file of a watched asset has been deleted
+#[derive(Debug, Clone)]
+pub struct AssetDeletedEvent {
+    pub id: UntypedAssetId,
+    pub path: AssetPath,
+}
+
+impl AssetDeletedEvent {
+    #[inline]
+    pub fn is_asset<T, S>(&self, id: &AssetId<T, S>) -> bool {
+        self.id == id.untyped
+    }
+}
+
+/// the asset couldn't be loaded, e.g. as the file is missing or malformed
+#[derive(Debug, Clone)]
+pub struct AssetLoadFailedEvent<T> {
+    pub id: AssetId<T, Weak>,
+    pub uri: AssetUri,
+    pub error: String,
+}
+
+impl<T> AssetLoadFailedEvent<T> {
+    #[inline]
+    pub fn is_asset<S>(&self, id: &AssetId<T, S>) -> bool {
+        self.id.untyped == id.untyped
+    }
+}
+
+/// the number of loaded and queued assets, including discovered dependencies
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LoadProgressEvent {
+    pub loaded: usize,
+    pub total: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct LoadAssetEvent {
     pub id: UntypedAssetId,
     pub force: bool,
+    /// hot reloads aren't counted by the `LoadProgressEvent`
+    pub reload: bool,
+}
+
+struct LoadBarrierEvent {
+    id: Uuid,
+    assets: Vec<UntypedAssetId>,
+}
+
+/// keeps the assets of `AssetsClient::load_all` alive and identifies their completion event
+#[derive(Debug)]
+pub struct LoadBarrier<T> {
+    id: Uuid,
+    assets: Vec<StrongAssetId<T>>,
+}
+
+impl<T> LoadBarrier<T> {
+    #[inline]
+    pub fn assets(&self) -> &[StrongAssetId<T>] {
+        &self.assets
+    }
+
+    #[inline]
+    pub fn is_complete(&self, event: &LoadBarrierCompleteEvent) -> bool {
+        self.id == event.id
+    }
+}
+
+/// every asset of the barrier has either been loaded or failed to load
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadBarrierCompleteEvent {
+    pub id: Uuid,
+    pub failed: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct SaveAssetEvent {
+    pub path: AssetPath,
+    pub bytes: Vec<u8>,
+}
+
+/// outcome of `AssetsClient::save`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveResultEvent {
+    pub path: AssetPath,
+    pub error: Option<String>,
+}
+
+impl SaveResultEvent {
+    #[inline]
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
 }
 
 pub struct StoreAssetEvent {
@@ -1171,6 +1811,11 @@ impl StoreAssetEvent {
     }
 }
 
+pub struct TransactionAssetEvent {
+    entries: Mutex<Vec<SyncQueueEntry>>,
+    loads: Vec<LoadAssetEvent>,
+}
+
 struct SyncAssetEvent {
     pub sync: u64,
 }
@@ -1185,6 +1830,20 @@ pub struct AssetEvent<T> {
     pub kind: AssetEventKind,
 }
 
+/// a loaded asset has been loaded again, e.g. by hot reloading its changed file,
+/// it's sent after the load event of the asset
+#[derive(Debug, Clone)]
+pub struct AssetReloadedEvent<T> {
+    pub id: AssetId<T, Weak>,
+}
+
+impl<T> AssetReloadedEvent<T> {
+    #[inline]
+    pub fn is_asset<S>(&self, id: &AssetId<T, S>) -> bool {
+        self.id.untyped == id.untyped
+    }
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum AssetEventKind {
     Load,
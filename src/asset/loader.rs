@@ -11,7 +11,54 @@ use serde::de::DeserializeOwned;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::cell::RefCell;
 use std::marker::PhantomData;
-use std::ops::DerefMut;
+use std::ops::{Deref, DerefMut};
+use uuid::Uuid;
+
+/// selects the children of a dir asset, e.g. the entries of an asset table
+#[derive(Debug, Clone)]
+pub enum AssetFilter {
+    Any,
+    Extensions(Vec<String>),
+    /// matched against the file name of the child
+    Glob(glob::Pattern),
+}
+
+impl AssetFilter {
+    pub fn extensions<I, S>(extensions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        AssetFilter::Extensions(extensions.into_iter().map(Into::into).collect())
+    }
+
+    pub fn glob(pattern: &str) -> anyhow::Result<Self> {
+        Ok(AssetFilter::Glob(glob::Pattern::new(pattern)?))
+    }
+
+    pub fn matches(&self, asset_path: &AssetPath) -> bool {
+        match self {
+            AssetFilter::Any => true,
+            AssetFilter::Extensions(extensions) => asset_path
+                .path
+                .extension()
+                .map(|e| extensions.iter().any(|extension| extension == e))
+                .unwrap_or(false),
+            AssetFilter::Glob(pattern) => asset_path
+                .path
+                .file_name()
+                .map(|file_name| pattern.matches(file_name))
+                .unwrap_or(false),
+        }
+    }
+}
+
+impl Default for AssetFilter {
+    #[inline]
+    fn default() -> Self {
+        AssetFilter::Any
+    }
+}
 
 pub struct AssetCursorChildren<'a, 'b> {
     cursor: &'b mut AssetCursor<'a>,
@@ -23,6 +70,7 @@ impl<'a, 'b> AssetCursorChildren<'a, 'b> {
     pub fn next(&mut self) -> Option<AssetCursor> {
         self.children.pop().map(move |ap| AssetCursor {
             asset_path: ap,
+            filter: self.cursor.filter,
             assets: self.cursor.assets,
             sync_queue: self.cursor.sync_queue,
             dependency_queue: self.cursor.dependency_queue,
@@ -37,6 +85,7 @@ impl<'a, 'b> AssetCursorChildren<'a, 'b> {
 
 pub struct AssetCursor<'a> {
     pub(crate) asset_path: AssetPath,
+    pub(crate) filter: &'a AssetFilter,
     pub(crate) assets: &'a mut Assets,
     pub(crate) sync_queue: &'a mut Vec<SyncQueueEntry>,
     pub(crate) dependency_queue: &'a mut Vec<DependencyQueueEntry>,
@@ -45,22 +94,12 @@ pub struct AssetCursor<'a> {
 impl<'a> AssetCursor<'a> {
     #[inline]
     pub fn is_file(&self) -> bool {
-        let path = self
-            .asset_path
-            .path
-            .to_path(self.assets.paths.asset_dir(&self.asset_path.kind));
-
-        path.is_file()
+        self.assets.paths.source().is_file(&self.asset_path)
     }
 
     #[inline]
     pub fn is_dir(&self) -> bool {
-        let path = self
-            .asset_path
-            .path
-            .to_path(self.assets.paths.asset_dir(&self.asset_path.kind));
-
-        path.is_dir()
+        self.assets.paths.source().is_dir(&self.asset_path)
     }
 
     #[inline]
@@ -70,13 +109,7 @@ impl<'a> AssetCursor<'a> {
 
     #[inline]
     pub fn read(&self) -> anyhow::Result<Vec<u8>> {
-        let path = self
-            .asset_path
-            .path
-            .to_path(self.assets.paths.asset_dir(&self.asset_path.kind));
-        log::info!("reading asset from: {}", path.display());
-        let bytes = std::fs::read(&path)?;
-        Ok(bytes)
+        self.assets.paths.source().read(&self.asset_path)
     }
 
     #[inline]
@@ -84,26 +117,12 @@ impl<'a> AssetCursor<'a> {
         self.asset_path.path.extension()
     }
 
-    // TODO: glob filter?
+    /// the dirs and the files matching the filter the loader has been added with
     #[inline]
     pub fn children<'b>(&'b mut self) -> anyhow::Result<AssetCursorChildren<'a, 'b>> {
-        let mut paths = Vec::new();
-
-        let path = self
-            .asset_path
-            .path
-            .to_path(self.assets.paths.asset_dir(&self.asset_path.kind));
-        let dir = std::fs::read_dir(path)?;
-        for entry in dir {
-            let entry = entry?;
-            let entry_path = entry.path();
-
-            if let Some(file_name) = entry_path.file_name().and_then(|s| s.to_str()) {
-                let entry_rel_path = Intern::new(self.asset_path.path.join(file_name));
-                paths.push(AssetPath::new(self.asset_path.kind, entry_rel_path));
-            }
-        }
-
+        let source = self.assets.paths.source();
+        let mut paths = source.read_dir(&self.asset_path)?;
+        paths.retain(|path| source.is_dir(path) || self.filter.matches(path));
         paths.reverse();
 
         Ok(AssetCursorChildren {
@@ -178,6 +197,37 @@ thread_local!(static SERDE_THREAD_LOCAL: RefCell<Option<SerdeThreadLocal>> = Ref
 struct SerdeThreadLocal {
     assets: Assets,
     dependency_queue: Vec<DependencyQueueEntry>,
+    sync_queue: Vec<SyncQueueEntry>,
+}
+
+/// provides the deserializer context for asset ids that are deserialized by the given function
+fn deserialize_asset<T, F>(cursor: &mut AssetCursor, deserialize: F) -> anyhow::Result<T>
+where
+    F: FnOnce(&str, &[u8]) -> anyhow::Result<T>,
+{
+    SERDE_THREAD_LOCAL.with(|stl| {
+        *stl.borrow_mut() = Some(SerdeThreadLocal {
+            assets: cursor.assets.to_owned(),
+            dependency_queue: Vec::default(),
+            sync_queue: Vec::default(),
+        });
+
+        let extension = cursor.extension().ok_or_else(|| {
+            anyhow::anyhow!("could not derive file type of asset: {}", cursor.asset_path)
+        })?;
+
+        let asset = deserialize(extension, &cursor.read()?)?;
+
+        let mut borrow_stl = stl.borrow_mut();
+        let tls = borrow_stl.as_mut().unwrap();
+        // inlined assets are synced together with the asset that contains them
+        cursor.sync_queue.extend(tls.sync_queue.drain(..));
+        cursor
+            .dependency_queue
+            .extend(tls.dependency_queue.drain(..));
+
+        Ok(asset)
+    })
 }
 
 pub struct SerdeAssetLoader<T> {
@@ -189,36 +239,32 @@ impl<T: DeserializeOwned + Send + Sync + 'static> AssetLoader for SerdeAssetLoad
 
     #[inline]
     fn load(cursor: &mut AssetCursor) -> anyhow::Result<Self::Asset> {
-        SERDE_THREAD_LOCAL.with(|stl| {
-            *stl.borrow_mut() = Some(SerdeThreadLocal {
-                assets: cursor.assets.to_owned(),
-                dependency_queue: Vec::default(),
-            });
-
-            let extension = cursor.extension().ok_or_else(|| {
-                anyhow::anyhow!(
-                    "could not derive file type for serde asset loader: {}",
-                    cursor.asset_path
-                )
-            })?;
-
-            let asset = match extension {
-                "json" => serde_json::from_slice(&cursor.read()?)?,
-                s => Err(anyhow::anyhow!(
-                    "unhandled file type for serde asset loader: {}",
-                    s
-                ))?,
-            };
-
-            cursor.dependency_queue.extend(
-                stl.borrow_mut()
-                    .as_mut()
-                    .unwrap()
-                    .dependency_queue
-                    .drain(..),
-            );
+        deserialize_asset(cursor, |extension, bytes| match extension {
+            "json" => Ok(serde_json::from_slice(bytes)?),
+            s => Err(anyhow::anyhow!(
+                "unhandled file type for serde asset loader: {}",
+                s
+            )),
+        })
+    }
+}
 
-            Ok(asset)
+/// compact binary alternative to the json of the `SerdeAssetLoader`, e.g. for baked level data
+pub struct BincodeAssetLoader<T> {
+    _pd: PhantomData<T>,
+}
+
+impl<T: DeserializeOwned + Send + Sync + 'static> AssetLoader for BincodeAssetLoader<T> {
+    type Asset = T;
+
+    #[inline]
+    fn load(cursor: &mut AssetCursor) -> anyhow::Result<Self::Asset> {
+        deserialize_asset(cursor, |extension, bytes| match extension {
+            "bin" => Ok(bincode::deserialize(bytes)?),
+            s => Err(anyhow::anyhow!(
+                "unhandled file type for bincode asset loader: {}",
+                s
+            )),
         })
     }
 }
@@ -240,12 +286,8 @@ impl<'de, T: Send + Sync + 'static> Deserialize<'de> for AssetId<T, Strong> {
     where
         D: Deserializer<'de>,
     {
-        // TODO: also allow inlined assets (inline vs embedded?)
-        //  * uuid(even needed? only if referenced multiple times? serialization vs deserialization ... -> that's not inline but embedded?)
-        //  * data for T => insert into Assets (check deadlocks)
-        //  How? the api expects Vec<u8>
-        //  "SceneFiles" that have a main Asset that reference other embedded/inlined assets
-        //  AssetIds need to define if they are external/embedded/inlined
+        // inlined assets are deserialized with inline_asset
+        // TODO: embedded assets that are referenced multiple times in the same file?
 
         let uri = deserializer.deserialize_string(AssetUriVisitor)?;
         let weak: WeakAssetId<T> = WeakAssetId::new(uri);
@@ -276,6 +318,39 @@ impl<'de, T: Send + Sync + 'static> Deserialize<'de> for AssetId<T, Strong> {
     }
 }
 
+/**
+Deserializes the value of the field as asset that is stored under a new uuid,
+e.g. `#[serde(deserialize_with = "inline_asset")] material: StrongAssetId<Material>`
+*/
+pub fn inline_asset<'de, D, T>(deserializer: D) -> Result<StrongAssetId<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Send + Sync + 'static,
+{
+    let asset = T::deserialize(deserializer)?;
+    let weak: WeakAssetId<T> = WeakAssetId::new(AssetUri::Uuid(Uuid::new_v4()));
+
+    SERDE_THREAD_LOCAL.with(|maybe_tls| {
+        let mut borrow_maybe_tls = maybe_tls.borrow_mut();
+        match borrow_maybe_tls.deref_mut() {
+            Some(tls) => {
+                let strong = match tls.assets.client().register_asset(&weak) {
+                    RegisterAssetResult::Preexisting(id) => id,
+                    RegisterAssetResult::Unfamiliar(id) => id,
+                };
+
+                let entry = SyncQueueEntry::new(weak, asset, &tls.assets.sender);
+                tls.sync_queue.push(entry);
+
+                Ok(strong)
+            }
+            None => Err(serde::de::Error::custom(
+                "inline assets can only be deserialized by the asset server",
+            )),
+        }
+    })
+}
+
 impl<'de, T: Send + Sync + 'static> Deserialize<'de> for AssetId<T, Loaded> {
     #[inline]
     fn deserialize<D>(deserializer: D) -> Result<LoadedAssetId<T>, D::Error>
@@ -463,3 +538,91 @@ impl<T: Send + Sync + 'static> AssetLoader for AssetTableLoader<T, Loaded> {
         }
     }
 }
+
+pub type WeakAssetTree<T> = AssetTree<T, Weak>;
+pub type StrongAssetTree<T> = AssetTree<T, Strong>;
+pub type LoadedAssetTree<T> = AssetTree<T, Loaded>;
+
+/// asset table over a dir and all of its sub dirs, keyed by the full asset path like the table,
+/// e.g. `units/enemies/goblin.json` in the tree of `units`
+#[derive(Debug)]
+pub struct AssetTree<T: 'static, S>(AssetTable<T, S>);
+
+impl<T: 'static, S> Deref for AssetTree<T, S> {
+    type Target = AssetTable<T, S>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+pub struct AssetTreeLoader<T, S> {
+    _pd_t: PhantomData<T>,
+    _pd_s: PhantomData<S>,
+}
+
+impl<T: Send + Sync + 'static, S> AssetTreeLoader<T, S> {
+    fn load_dir<F>(
+        cursor: &mut AssetCursor,
+        underlying: &mut IndexMap<Intern<RelativePathBuf>, AssetId<T, S>>,
+        load_file: &mut F,
+    ) -> anyhow::Result<()>
+    where
+        F: FnMut(&mut AssetCursor) -> AssetId<T, S>,
+    {
+        let mut children = cursor.children()?;
+        while let Some(mut child) = children.next() {
+            if child.is_dir() {
+                Self::load_dir(&mut child, underlying, load_file)?;
+                continue;
+            }
+
+            if !child.is_file() {
+                continue;
+            }
+
+            underlying.insert(child.asset_path.path, load_file(&mut child));
+        }
+
+        Ok(())
+    }
+
+    fn load_tree<F>(cursor: &mut AssetCursor, mut load_file: F) -> anyhow::Result<AssetTree<T, S>>
+    where
+        F: FnMut(&mut AssetCursor) -> AssetId<T, S>,
+    {
+        let mut underlying = IndexMap::default();
+        Self::load_dir(cursor, &mut underlying, &mut load_file)?;
+
+        Ok(AssetTree(AssetTable(underlying)))
+    }
+}
+
+impl<T: Send + Sync + 'static> AssetLoader for AssetTreeLoader<T, Weak> {
+    type Asset = AssetTree<T, Weak>;
+
+    fn load(cursor: &mut AssetCursor) -> anyhow::Result<Self::Asset> {
+        Self::load_tree(cursor, |child| {
+            WeakAssetId::new(AssetUri::AssetPath(child.asset_path))
+        })
+    }
+}
+
+impl<T: Send + Sync + 'static> AssetLoader for AssetTreeLoader<T, Strong> {
+    type Asset = AssetTree<T, Strong>;
+
+    fn load(cursor: &mut AssetCursor) -> anyhow::Result<Self::Asset> {
+        Self::load_tree(cursor, |child| child.queue_load(child.asset_path))
+    }
+}
+
+impl<T: Send + Sync + 'static> AssetLoader for AssetTreeLoader<T, Loaded> {
+    type Asset = AssetTree<T, Loaded>;
+
+    fn load(cursor: &mut AssetCursor) -> anyhow::Result<Self::Asset> {
+        Self::load_tree(cursor, |child| unsafe {
+            child.queue_load_optimistic(child.asset_path)
+        })
+    }
+}
@@ -13,9 +13,10 @@ impl AssetChangeNotify {
             match res {
                 Ok(event) => {
                     let event: notify::event::Event = event;
-                    if event.kind.is_create() || event.kind.is_modify() {
+                    if event.kind.is_create() || event.kind.is_modify() || event.kind.is_remove() {
+                        let removed = event.kind.is_remove();
                         for path in event.paths {
-                            let _ = sender.send(AssetChanged { path });
+                            let _ = sender.send(AssetChanged { path, removed });
                         }
                     }
                 }
@@ -49,4 +50,5 @@ impl AssetChangeNotify {
 
 pub(crate) struct AssetChanged {
     pub path: PathBuf,
+    pub removed: bool,
 }
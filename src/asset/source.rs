@@ -0,0 +1,869 @@
+use crate::asset::{AssetPath, AssetPathKind};
+use crate::util::HashMap;
+use internment::Intern;
+use parking_lot::RwLock;
+use relative_path::RelativePath;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+pub trait AssetSource: Send + Sync + 'static {
+    fn is_file(&self, asset_path: &AssetPath) -> bool;
+
+    fn is_dir(&self, asset_path: &AssetPath) -> bool;
+
+    fn read(&self, asset_path: &AssetPath) -> anyhow::Result<Vec<u8>>;
+
+    fn read_dir(&self, asset_path: &AssetPath) -> anyhow::Result<Vec<AssetPath>>;
+
+    /// sources are read only by default
+    fn write(&self, asset_path: &AssetPath, _bytes: &[u8]) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!("asset source is read only: {}", asset_path))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FsAssetSource {
+    sys_dir: PathBuf,
+    usr_dir: PathBuf,
+    mounts: HashMap<AssetPathKind, PathBuf>,
+}
+
+impl FsAssetSource {
+    #[inline]
+    pub fn new(sys_dir: PathBuf, usr_dir: PathBuf) -> Self {
+        Self {
+            sys_dir,
+            usr_dir,
+            mounts: Default::default(),
+        }
+    }
+
+    /// serves the assets of the mount from the dir
+    #[inline]
+    pub fn with_mount(mut self, kind: AssetPathKind, dir: PathBuf) -> Self {
+        self.mounts.insert(kind, dir);
+        self
+    }
+
+    #[inline]
+    fn to_path(&self, asset_path: &AssetPath) -> anyhow::Result<PathBuf> {
+        let asset_dir = match asset_path.kind {
+            AssetPathKind::Sys => &self.sys_dir,
+            AssetPathKind::Usr => &self.usr_dir,
+            kind => self
+                .mounts
+                .get(&kind)
+                .ok_or_else(|| anyhow::anyhow!("asset mount not found: {}", asset_path))?,
+        };
+
+        Ok(asset_path.path.to_path(asset_dir))
+    }
+}
+
+impl AssetSource for FsAssetSource {
+    #[inline]
+    fn is_file(&self, asset_path: &AssetPath) -> bool {
+        self.to_path(asset_path)
+            .map(|path| path.is_file())
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    fn is_dir(&self, asset_path: &AssetPath) -> bool {
+        self.to_path(asset_path)
+            .map(|path| path.is_dir())
+            .unwrap_or_default()
+    }
+
+    fn read(&self, asset_path: &AssetPath) -> anyhow::Result<Vec<u8>> {
+        let path = self.to_path(asset_path)?;
+        log::info!("reading asset from: {}", path.display());
+        let bytes = std::fs::read(&path)?;
+        Ok(bytes)
+    }
+
+    fn read_dir(&self, asset_path: &AssetPath) -> anyhow::Result<Vec<AssetPath>> {
+        let mut paths = Vec::new();
+
+        let dir = std::fs::read_dir(self.to_path(asset_path)?)?;
+        for entry in dir {
+            let entry = entry?;
+            let entry_path = entry.path();
+
+            if let Some(file_name) = entry_path.file_name().and_then(|s| s.to_str()) {
+                let entry_rel_path = Intern::new(asset_path.path.join(file_name));
+                paths.push(AssetPath::new(asset_path.kind, entry_rel_path));
+            }
+        }
+
+        Ok(paths)
+    }
+
+    fn write(&self, asset_path: &AssetPath, bytes: &[u8]) -> anyhow::Result<()> {
+        let path = self.to_path(asset_path)?;
+        log::info!("writing asset to: {}", path.display());
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, bytes)?;
+        Ok(())
+    }
+}
+
+/// directories are implied by the paths of the inserted files
+#[derive(Debug, Default)]
+pub struct MemoryAssetSource {
+    files: RwLock<HashMap<AssetPath, Vec<u8>>>,
+    dirs: RwLock<BTreeSet<AssetPath>>,
+}
+
+impl MemoryAssetSource {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn with_file<T: Into<Vec<u8>>>(self, asset_path: AssetPath, bytes: T) -> Self {
+        self.insert(asset_path, bytes);
+        self
+    }
+
+    pub fn insert<T: Into<Vec<u8>>>(&self, asset_path: AssetPath, bytes: T) {
+        let mut dirs = self.dirs.write();
+        let mut parent = asset_path.parent();
+        while let Some(dir) = parent {
+            parent = dir.parent();
+            dirs.insert(dir);
+        }
+
+        self.files.write().insert(asset_path, bytes.into());
+    }
+
+    pub fn remove(&self, asset_path: &AssetPath) -> Option<Vec<u8>> {
+        self.files.write().remove(asset_path)
+    }
+}
+
+impl AssetSource for MemoryAssetSource {
+    #[inline]
+    fn is_file(&self, asset_path: &AssetPath) -> bool {
+        self.files.read().contains_key(asset_path)
+    }
+
+    #[inline]
+    fn is_dir(&self, asset_path: &AssetPath) -> bool {
+        self.dirs.read().contains(asset_path)
+    }
+
+    fn read(&self, asset_path: &AssetPath) -> anyhow::Result<Vec<u8>> {
+        log::info!("reading asset from memory: {}", asset_path);
+        self.files
+            .read()
+            .get(asset_path)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("asset not found in memory: {}", asset_path))
+    }
+
+    fn read_dir(&self, asset_path: &AssetPath) -> anyhow::Result<Vec<AssetPath>> {
+        if !self.is_dir(asset_path) {
+            return Err(anyhow::anyhow!(
+                "asset dir not found in memory: {}",
+                asset_path
+            ));
+        }
+
+        let is_child = |child: &AssetPath| {
+            child.kind == asset_path.kind
+                && child.path.parent().map(RelativePath::normalize)
+                    == Some(asset_path.path.normalize())
+        };
+
+        let files = self.files.read();
+        let dirs = self.dirs.read();
+        let paths = files
+            .keys()
+            .chain(dirs.iter())
+            .filter(|child| is_child(child))
+            .copied()
+            .collect();
+
+        Ok(paths)
+    }
+
+    fn write(&self, asset_path: &AssetPath, bytes: &[u8]) -> anyhow::Result<()> {
+        log::info!("writing asset to memory: {}", asset_path);
+        self.insert(*asset_path, bytes);
+        Ok(())
+    }
+}
+
+/// serves the assets of the added kinds from their own source, e.g. a mod from an archive,
+/// and the remaining assets from the default source
+pub struct MountAssetSource {
+    default: Arc<dyn AssetSource>,
+    sources: HashMap<AssetPathKind, Arc<dyn AssetSource>>,
+}
+
+impl MountAssetSource {
+    #[inline]
+    pub fn new<T: AssetSource>(default: T) -> Self {
+        Self::with_default(Arc::new(default))
+    }
+
+    #[inline]
+    pub(crate) fn with_default(default: Arc<dyn AssetSource>) -> Self {
+        Self {
+            default,
+            sources: Default::default(),
+        }
+    }
+
+    #[inline]
+    pub fn with_source<T: AssetSource>(self, kind: AssetPathKind, source: T) -> Self {
+        self.with_shared_source(kind, Arc::new(source))
+    }
+
+    #[inline]
+    pub(crate) fn with_shared_source(
+        mut self,
+        kind: AssetPathKind,
+        source: Arc<dyn AssetSource>,
+    ) -> Self {
+        self.sources.insert(kind, source);
+        self
+    }
+
+    #[inline]
+    fn source(&self, asset_path: &AssetPath) -> &dyn AssetSource {
+        self.sources
+            .get(&asset_path.kind)
+            .unwrap_or(&self.default)
+            .as_ref()
+    }
+}
+
+impl AssetSource for MountAssetSource {
+    #[inline]
+    fn is_file(&self, asset_path: &AssetPath) -> bool {
+        self.source(asset_path).is_file(asset_path)
+    }
+
+    #[inline]
+    fn is_dir(&self, asset_path: &AssetPath) -> bool {
+        self.source(asset_path).is_dir(asset_path)
+    }
+
+    #[inline]
+    fn read(&self, asset_path: &AssetPath) -> anyhow::Result<Vec<u8>> {
+        self.source(asset_path).read(asset_path)
+    }
+
+    #[inline]
+    fn read_dir(&self, asset_path: &AssetPath) -> anyhow::Result<Vec<AssetPath>> {
+        self.source(asset_path).read_dir(asset_path)
+    }
+
+    #[inline]
+    fn write(&self, asset_path: &AssetPath, bytes: &[u8]) -> anyhow::Result<()> {
+        self.source(asset_path).write(asset_path, bytes)
+    }
+}
+
+/// serves assets from a zip archive, the asset paths are relative to the archive root
+#[cfg(feature = "archive")]
+pub struct ZipAssetSource {
+    archive: parking_lot::Mutex<zip::ZipArchive<std::fs::File>>,
+    files: BTreeSet<relative_path::RelativePathBuf>,
+    dirs: BTreeSet<relative_path::RelativePathBuf>,
+}
+
+#[cfg(feature = "archive")]
+impl ZipAssetSource {
+    pub fn open<T: AsRef<std::path::Path>>(path: T) -> anyhow::Result<Self> {
+        let file = std::fs::File::open(path.as_ref())?;
+        let archive = zip::ZipArchive::new(file)?;
+        let mut files = BTreeSet::new();
+        let mut dirs = BTreeSet::new();
+        dirs.insert(relative_path::RelativePathBuf::new());
+
+        for name in archive.file_names() {
+            let entry_path = RelativePath::new(name).normalize();
+            let mut parent = entry_path.parent();
+            while let Some(dir) = parent {
+                dirs.insert(dir.to_owned());
+                parent = dir.parent();
+            }
+
+            if name.ends_with('/') {
+                dirs.insert(entry_path);
+            } else {
+                files.insert(entry_path);
+            }
+        }
+
+        log::info!(
+            "opened asset archive {} with {} files",
+            path.as_ref().display(),
+            files.len()
+        );
+
+        Ok(Self {
+            archive: parking_lot::Mutex::new(archive),
+            files,
+            dirs,
+        })
+    }
+}
+
+#[cfg(feature = "archive")]
+impl AssetSource for ZipAssetSource {
+    #[inline]
+    fn is_file(&self, asset_path: &AssetPath) -> bool {
+        self.files.contains(&asset_path.path.normalize())
+    }
+
+    #[inline]
+    fn is_dir(&self, asset_path: &AssetPath) -> bool {
+        self.dirs.contains(&asset_path.path.normalize())
+    }
+
+    fn read(&self, asset_path: &AssetPath) -> anyhow::Result<Vec<u8>> {
+        use std::io::Read;
+
+        log::info!("reading asset from archive: {}", asset_path);
+        let mut archive = self.archive.lock();
+        let mut file = archive.by_name(asset_path.path.normalize().as_str())?;
+        let mut bytes = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn read_dir(&self, asset_path: &AssetPath) -> anyhow::Result<Vec<AssetPath>> {
+        let dir = asset_path.path.normalize();
+        if !self.dirs.contains(&dir) {
+            return Err(anyhow::anyhow!(
+                "asset dir not found in archive: {}",
+                asset_path
+            ));
+        }
+
+        let paths = self
+            .files
+            .iter()
+            .chain(self.dirs.iter())
+            .filter(|child| child.parent() == Some(dir.as_relative_path()))
+            .map(|child| AssetPath::new(asset_path.kind, Intern::new(child.clone())))
+            .collect();
+
+        Ok(paths)
+    }
+}
+
+/// serves the sys assets from a directory embedded into the binary via `include_dir!`
+#[cfg(feature = "embed")]
+pub struct EmbeddedAssetSource {
+    sys: include_dir::Dir<'static>,
+    usr: Option<Box<dyn AssetSource>>,
+}
+
+#[cfg(feature = "embed")]
+impl EmbeddedAssetSource {
+    #[inline]
+    pub fn new(sys: include_dir::Dir<'static>) -> Self {
+        Self { sys, usr: None }
+    }
+
+    /// usr and mounted assets are not embedded, e.g. pass a `FsAssetSource` for user data
+    #[inline]
+    pub fn with_usr_source<T: AssetSource>(mut self, usr: T) -> Self {
+        self.usr = Some(Box::new(usr));
+        self
+    }
+
+    fn get_dir(&self, path: &RelativePath) -> Option<include_dir::Dir<'static>> {
+        let path = path.normalize();
+        if path.as_str().is_empty() {
+            Some(self.sys)
+        } else {
+            self.sys.get_dir(path.as_str())
+        }
+    }
+
+    fn usr(&self, asset_path: &AssetPath) -> anyhow::Result<&dyn AssetSource> {
+        self.usr
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("no usr source for asset: {}", asset_path))
+    }
+}
+
+#[cfg(feature = "embed")]
+impl AssetSource for EmbeddedAssetSource {
+    fn is_file(&self, asset_path: &AssetPath) -> bool {
+        match asset_path.kind {
+            AssetPathKind::Sys => self
+                .sys
+                .get_file(asset_path.path.normalize().as_str())
+                .is_some(),
+            AssetPathKind::Usr | AssetPathKind::Mount(_) => self
+                .usr
+                .as_ref()
+                .map(|usr| usr.is_file(asset_path))
+                .unwrap_or_default(),
+        }
+    }
+
+    fn is_dir(&self, asset_path: &AssetPath) -> bool {
+        match asset_path.kind {
+            AssetPathKind::Sys => self.get_dir(&asset_path.path).is_some(),
+            AssetPathKind::Usr | AssetPathKind::Mount(_) => self
+                .usr
+                .as_ref()
+                .map(|usr| usr.is_dir(asset_path))
+                .unwrap_or_default(),
+        }
+    }
+
+    fn read(&self, asset_path: &AssetPath) -> anyhow::Result<Vec<u8>> {
+        match asset_path.kind {
+            AssetPathKind::Sys => {
+                log::info!("reading embedded asset: {}", asset_path);
+                self.sys
+                    .get_file(asset_path.path.normalize().as_str())
+                    .map(|file| file.contents().to_vec())
+                    .ok_or_else(|| anyhow::anyhow!("embedded asset not found: {}", asset_path))
+            }
+            AssetPathKind::Usr | AssetPathKind::Mount(_) => self.usr(asset_path)?.read(asset_path),
+        }
+    }
+
+    fn read_dir(&self, asset_path: &AssetPath) -> anyhow::Result<Vec<AssetPath>> {
+        match asset_path.kind {
+            AssetPathKind::Sys => {
+                let dir = self.get_dir(&asset_path.path).ok_or_else(|| {
+                    anyhow::anyhow!("embedded asset dir not found: {}", asset_path)
+                })?;
+
+                let mut paths = Vec::new();
+                let entry_paths = dir
+                    .files()
+                    .iter()
+                    .map(|file| file.path())
+                    .chain(dir.dirs().iter().map(|dir| dir.path()));
+                for entry_path in entry_paths {
+                    if let Some(file_name) = entry_path.file_name().and_then(|s| s.to_str()) {
+                        let entry_rel_path = Intern::new(asset_path.path.join(file_name));
+                        paths.push(AssetPath::new(asset_path.kind, entry_rel_path));
+                    }
+                }
+
+                Ok(paths)
+            }
+            AssetPathKind::Usr | AssetPathKind::Mount(_) => {
+                self.usr(asset_path)?.read_dir(asset_path)
+            }
+        }
+    }
+
+    fn write(&self, asset_path: &AssetPath, bytes: &[u8]) -> anyhow::Result<()> {
+        match asset_path.kind {
+            AssetPathKind::Sys => Err(anyhow::anyhow!(
+                "embedded asset is read only: {}",
+                asset_path
+            )),
+            AssetPathKind::Usr | AssetPathKind::Mount(_) => {
+                self.usr(asset_path)?.write(asset_path, bytes)
+            }
+        }
+    }
+}
+
+/// fetches assets with blocking http requests, which stall the asset server until completed,
+/// `http://` and `https://` paths are requested as is,
+/// other paths are requested from the base url of their kind
+#[cfg(feature = "http")]
+pub struct HttpAssetSource {
+    client: reqwest::blocking::Client,
+    base_urls: HashMap<AssetPathKind, String>,
+    fallback: Option<Box<dyn AssetSource>>,
+}
+
+#[cfg(feature = "http")]
+impl HttpAssetSource {
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            client: reqwest::blocking::Client::new(),
+            base_urls: Default::default(),
+            fallback: None,
+        }
+    }
+
+    /// e.g. `https://example.com/assets/sys` for the sys assets of a web build
+    #[inline]
+    pub fn with_base_url<T: Into<String>>(mut self, kind: AssetPathKind, base_url: T) -> Self {
+        self.base_urls.insert(kind, base_url.into());
+        self
+    }
+
+    /// serves the kinds without base url, e.g. pass a `FsAssetSource` for user data
+    #[inline]
+    pub fn with_fallback_source<T: AssetSource>(mut self, fallback: T) -> Self {
+        self.fallback = Some(Box::new(fallback));
+        self
+    }
+
+    fn is_remote(&self, asset_path: &AssetPath) -> bool {
+        matches!(asset_path.kind.protocol(), "http" | "https")
+            || self.base_urls.contains_key(&asset_path.kind)
+    }
+
+    fn url(&self, asset_path: &AssetPath) -> anyhow::Result<String> {
+        let path = asset_path.path.normalize();
+        match asset_path.kind.protocol() {
+            protocol @ ("http" | "https") => Ok(format!("{}://{}", protocol, path)),
+            _ => self
+                .base_urls
+                .get(&asset_path.kind)
+                .map(|base_url| format!("{}/{}", base_url.trim_end_matches('/'), path))
+                .ok_or_else(|| anyhow::anyhow!("no base url for asset: {}", asset_path)),
+        }
+    }
+
+    fn fallback(&self, asset_path: &AssetPath) -> anyhow::Result<&dyn AssetSource> {
+        self.fallback
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("no fallback source for asset: {}", asset_path))
+    }
+}
+
+#[cfg(feature = "http")]
+impl Default for HttpAssetSource {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "http")]
+impl AssetSource for HttpAssetSource {
+    fn is_file(&self, asset_path: &AssetPath) -> bool {
+        if !self.is_remote(asset_path) {
+            return self
+                .fallback
+                .as_ref()
+                .map(|fallback| fallback.is_file(asset_path))
+                .unwrap_or_default();
+        }
+
+        self.url(asset_path)
+            .ok()
+            .and_then(|url| self.client.head(url).send().ok())
+            .map(|response| response.status().is_success())
+            .unwrap_or_default()
+    }
+
+    /// http doesn't list dirs, so remote dirs don't exist
+    fn is_dir(&self, asset_path: &AssetPath) -> bool {
+        !self.is_remote(asset_path)
+            && self
+                .fallback
+                .as_ref()
+                .map(|fallback| fallback.is_dir(asset_path))
+                .unwrap_or_default()
+    }
+
+    fn read(&self, asset_path: &AssetPath) -> anyhow::Result<Vec<u8>> {
+        if !self.is_remote(asset_path) {
+            return self.fallback(asset_path)?.read(asset_path);
+        }
+
+        let url = self.url(asset_path)?;
+        log::info!("fetching asset from: {}", url);
+        let response = self.client.get(&url).send()?.error_for_status()?;
+        Ok(response.bytes()?.to_vec())
+    }
+
+    fn read_dir(&self, asset_path: &AssetPath) -> anyhow::Result<Vec<AssetPath>> {
+        if !self.is_remote(asset_path) {
+            return self.fallback(asset_path)?.read_dir(asset_path);
+        }
+
+        Err(anyhow::anyhow!(
+            "remote asset dirs can't be listed: {}",
+            asset_path
+        ))
+    }
+
+    fn write(&self, asset_path: &AssetPath, bytes: &[u8]) -> anyhow::Result<()> {
+        if !self.is_remote(asset_path) {
+            return self.fallback(asset_path)?.write(asset_path, bytes);
+        }
+
+        Err(anyhow::anyhow!("remote asset is read only: {}", asset_path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::loader::WeakAssetTree;
+    use crate::asset::storage::Assets;
+    use crate::asset::{
+        AssetEvent, AssetEventKind, AssetLoadFailedEvent, AssetServer, AssetsCreatedEvent,
+        StrongAssetId,
+    };
+    use crate::InitEvent;
+    use parking_lot::Mutex;
+    use roundabout::prelude::*;
+    use serde::Deserialize;
+    use std::collections::HashSet;
+    use std::time::Instant;
+
+    fn source() -> MemoryAssetSource {
+        MemoryAssetSource::new()
+            .with_file(AssetPath::sys("a/b.json"), r#"{"name":"b"}"#)
+            .with_file(AssetPath::sys("a/c/d.json"), r#"{"name":"d"}"#)
+    }
+
+    #[test]
+    fn read_inserted_file() {
+        let source = source();
+
+        assert_eq!(
+            source.read(&AssetPath::sys("a/b.json")).unwrap(),
+            br#"{"name":"b"}"#
+        );
+        assert!(source.read(&AssetPath::sys("a/e.json")).is_err());
+        assert!(source.read(&AssetPath::usr("a/b.json")).is_err());
+    }
+
+    #[test]
+    fn dirs_implied_by_files() {
+        let source = source();
+
+        assert!(source.is_dir(&AssetPath::sys("a")));
+        assert!(source.is_dir(&AssetPath::sys("a/c")));
+        assert!(!source.is_dir(&AssetPath::sys("a/b.json")));
+        assert!(!source.is_dir(&AssetPath::usr("a")));
+
+        assert!(source.is_file(&AssetPath::sys("a/b.json")));
+        assert!(!source.is_file(&AssetPath::sys("a/c")));
+    }
+
+    #[test]
+    fn read_dir_lists_children() {
+        let source = source();
+
+        let children: HashSet<AssetPath> = source
+            .read_dir(&AssetPath::sys("a"))
+            .unwrap()
+            .into_iter()
+            .collect();
+        let expected: HashSet<AssetPath> = [AssetPath::sys("a/b.json"), AssetPath::sys("a/c")]
+            .into_iter()
+            .collect();
+        assert_eq!(children, expected);
+
+        assert_eq!(
+            source.read_dir(&AssetPath::sys("a/c")).unwrap(),
+            [AssetPath::sys("a/c/d.json")]
+        );
+        assert!(source.read_dir(&AssetPath::sys("a/b.json")).is_err());
+        assert!(source.read_dir(&AssetPath::sys("e")).is_err());
+    }
+
+    #[test]
+    fn write_then_read() {
+        let source = source();
+        let asset_path = AssetPath::usr("saves/1.json");
+
+        source.write(&asset_path, b"{}").unwrap();
+        assert_eq!(source.read(&asset_path).unwrap(), b"{}");
+        assert!(source.is_dir(&AssetPath::usr("saves")));
+
+        source.write(&asset_path, b"[]").unwrap();
+        assert_eq!(source.read(&asset_path).unwrap(), b"[]");
+
+        assert_eq!(source.remove(&asset_path), Some(b"[]".to_vec()));
+        assert!(!source.is_file(&asset_path));
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct NamedAsset {
+        name: String,
+    }
+
+    struct Probe {
+        assets: Option<Assets>,
+        asset: Option<StrongAssetId<NamedAsset>>,
+        result: Arc<Mutex<Option<Result<String, String>>>>,
+    }
+
+    fn on_assets_created_event(
+        state: &mut Probe,
+        context: &mut RuntimeContext,
+        event: &AssetsCreatedEvent,
+    ) {
+        let mut assets = event.assets(context.sender().to_owned());
+        let asset = assets.client().load(AssetPath::sys("a/c/d.json"));
+        state.asset = Some(asset);
+        state.assets = Some(assets);
+    }
+
+    fn on_asset_event(
+        state: &mut Probe,
+        context: &mut RuntimeContext,
+        event: &AssetEvent<NamedAsset>,
+    ) {
+        if event.kind != AssetEventKind::Load {
+            return;
+        }
+
+        let assets = state.assets.as_mut().unwrap();
+        let name = assets
+            .client()
+            .try_get(state.asset.as_ref().unwrap())
+            .map(|asset| asset.name.clone())
+            .ok_or_else(|| "loaded asset not found".to_owned());
+        *state.result.lock() = Some(name);
+        context.shutdown_switch().request_shutdown();
+    }
+
+    fn on_asset_load_failed_event(
+        state: &mut Probe,
+        context: &mut RuntimeContext,
+        event: &AssetLoadFailedEvent<NamedAsset>,
+    ) {
+        *state.result.lock() = Some(Err(event.error.clone()));
+        context.shutdown_switch().request_shutdown();
+    }
+
+    #[test]
+    fn load_through_server() {
+        let result = Arc::new(Mutex::new(None));
+        let probe_result = result.clone();
+
+        let runtime = Runtime::builder(65_536)
+            .add(|b| {
+                AssetServer::empty(b)
+                    .with_hot_reloading(false)
+                    .with_source(source())
+                    .add_serde::<NamedAsset>()
+                    .finish()
+            })
+            .add(move |b| {
+                let result = probe_result.clone();
+                b.on(on_assets_created_event)
+                    .on(on_asset_event)
+                    .on(on_asset_load_failed_event)
+                    .init_fn(move |_| Probe {
+                        assets: None,
+                        asset: None,
+                        result: result.clone(),
+                    })
+            })
+            .finish();
+
+        runtime.start(InitEvent {
+            start: Instant::now(),
+            dir: Default::default(),
+            sys_dir: Default::default(),
+            usr_dir: Default::default(),
+            mounts: Vec::default(),
+            shutdown: Default::default(),
+        });
+
+        assert_eq!(result.lock().take(), Some(Ok("d".to_owned())));
+    }
+
+    struct TreeProbe {
+        assets: Option<Assets>,
+        tree: Option<StrongAssetId<WeakAssetTree<NamedAsset>>>,
+        keys: Arc<Mutex<Option<Vec<String>>>>,
+    }
+
+    fn on_tree_assets_created_event(
+        state: &mut TreeProbe,
+        context: &mut RuntimeContext,
+        event: &AssetsCreatedEvent,
+    ) {
+        let mut assets = event.assets(context.sender().to_owned());
+        let tree = assets.client().load(AssetPath::sys("a"));
+        state.tree = Some(tree);
+        state.assets = Some(assets);
+    }
+
+    fn on_tree_asset_event(
+        state: &mut TreeProbe,
+        context: &mut RuntimeContext,
+        event: &AssetEvent<WeakAssetTree<NamedAsset>>,
+    ) {
+        if event.kind != AssetEventKind::Load {
+            return;
+        }
+
+        let assets = state.assets.as_mut().unwrap();
+        let keys = assets
+            .client()
+            .try_get(state.tree.as_ref().unwrap())
+            .map(|tree| {
+                let mut keys: Vec<String> = tree.iter().map(|(key, _)| key.to_string()).collect();
+                keys.sort();
+                keys
+            });
+        *state.keys.lock() = keys;
+        context.shutdown_switch().request_shutdown();
+    }
+
+    fn on_tree_load_failed_event(
+        _state: &mut TreeProbe,
+        context: &mut RuntimeContext,
+        _event: &AssetLoadFailedEvent<WeakAssetTree<NamedAsset>>,
+    ) {
+        context.shutdown_switch().request_shutdown();
+    }
+
+    #[test]
+    fn tree_keyed_by_asset_path() {
+        let keys = Arc::new(Mutex::new(None));
+        let probe_keys = keys.clone();
+
+        let runtime = Runtime::builder(65_536)
+            .add(|b| {
+                AssetServer::empty(b)
+                    .with_hot_reloading(false)
+                    .with_source(source())
+                    .add_serde::<NamedAsset>()
+                    .finish()
+            })
+            .add(move |b| {
+                let keys = probe_keys.clone();
+                b.on(on_tree_assets_created_event)
+                    .on(on_tree_asset_event)
+                    .on(on_tree_load_failed_event)
+                    .init_fn(move |_| TreeProbe {
+                        assets: None,
+                        tree: None,
+                        keys: keys.clone(),
+                    })
+            })
+            .finish();
+
+        runtime.start(InitEvent {
+            start: Instant::now(),
+            dir: Default::default(),
+            sys_dir: Default::default(),
+            usr_dir: Default::default(),
+            mounts: Vec::default(),
+            shutdown: Default::default(),
+        });
+
+        // the files of sub dirs are keyed by their full path as well
+        assert_eq!(
+            keys.lock().take(),
+            Some(vec!["a/b.json".to_owned(), "a/c/d.json".to_owned()])
+        );
+    }
+}
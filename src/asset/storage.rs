@@ -1,25 +1,38 @@
+use crate::asset::source::AssetSource;
 use crate::asset::{
-    AssetId, AssetPath, AssetPathKind, AssetUri, LoadAssetEvent, Loaded, StoreAssetEvent,
-    StrongAssetId, SyncQueueEntry, UntypedAsset, UntypedAssetId, WeakAssetId,
+    AssetId, AssetPath, AssetPathKind, AssetUri, LoadAssetEvent, LoadBarrier, LoadBarrierEvent,
+    Loaded, SaveAssetEvent, SaveResultEvent, StoreAssetEvent, StrongAssetId, SyncQueueEntry,
+    TransactionAssetEvent, UntypedAsset, UntypedAssetId, WeakAssetId,
 };
 use crate::prelude::LoadedAssetId;
-use crate::util::{HashMap, IndexMap, OrderWindow};
+use crate::util::{HashMap, HashSet, IndexMap, OrderWindow};
 use internment::Intern;
-use parking_lot::{RwLock, RwLockReadGuard};
+use parking_lot::{Mutex, RwLock, RwLockReadGuard};
 use relative_path::RelativePath;
 use roundabout::prelude::{MessageSender, UntypedMessage};
+use serde::Serialize;
 use std::borrow::Borrow;
 use std::collections::BTreeSet;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
 
 pub struct AssetsPaths {
     pub(crate) sys_dir: PathBuf,
     pub(crate) usr_dir: PathBuf,
+    pub(crate) mounts: Vec<(AssetPathKind, PathBuf)>,
+    pub(crate) source: Arc<dyn AssetSource>,
 }
 
 impl AssetsPaths {
+    #[inline]
+    pub fn source(&self) -> &dyn AssetSource {
+        self.source.as_ref()
+    }
+
     #[inline]
     pub fn sys_dir(&self) -> &Path {
         &self.sys_dir
@@ -30,46 +43,107 @@ impl AssetsPaths {
         &self.usr_dir
     }
 
-    #[inline]
-    pub fn asset_dir(&self, kind: &AssetPathKind) -> &Path {
-        match kind {
-            AssetPathKind::Sys => &self.sys_dir,
-            AssetPathKind::Usr => &self.usr_dir,
-        }
+    /// the sys, usr and mounted dirs
+    pub fn asset_dirs(&self) -> impl Iterator<Item = (AssetPathKind, &Path)> + '_ {
+        [
+            (AssetPathKind::Sys, self.sys_dir.as_path()),
+            (AssetPathKind::Usr, self.usr_dir.as_path()),
+        ]
+        .into_iter()
+        .chain(self.mounts.iter().map(|(kind, dir)| (*kind, dir.as_path())))
     }
 
+    /// none for mounts that haven't been added to the engine
     #[inline]
+    pub fn asset_dir(&self, kind: &AssetPathKind) -> Option<&Path> {
+        self.asset_dirs()
+            .find(|(dir_kind, _)| dir_kind == kind)
+            .map(|(_, dir)| dir)
+    }
+
+    /// the kind of the innermost dir containing the path, as mounts can be nested
     pub fn asset_path_kind(&self, path: &Path) -> Option<AssetPathKind> {
-        if path.starts_with(&self.sys_dir) {
-            return Some(AssetPathKind::Sys);
+        self.asset_dirs()
+            .filter(|(_, dir)| path.starts_with(dir))
+            .max_by_key(|(_, dir)| dir.components().count())
+            .map(|(kind, _)| kind)
+    }
+
+    pub fn asset_path(&self, path: &Path) -> Option<AssetPath> {
+        let kind = self.asset_path_kind(path)?;
+        let relative_path_string = path.strip_prefix(self.asset_dir(&kind)?).ok()?.to_str()?;
+        let path = Intern::new(RelativePath::new(relative_path_string).to_owned());
+
+        Some(AssetPath::new(kind, path))
+    }
+}
+
+/// decides which unreferenced assets are unloaded by the periodic gc
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GcStrategy {
+    /// unloads assets as soon as they aren't referenced by strong ids anymore
+    Unreferenced,
+    /// keeps unreferenced assets until they haven't been accessed for max_idle,
+    /// e.g. to avoid reloading assets of a streamed world
+    LeastRecentlyUsed { max_idle: Duration },
+}
+
+impl Default for GcStrategy {
+    #[inline]
+    fn default() -> Self {
+        GcStrategy::Unreferenced
+    }
+}
+
+/// last access time of assets, only tracked for the least recently used gc strategy
+pub(crate) struct AssetAccess {
+    start: Instant,
+    tracking: AtomicBool,
+    accessed_at: RwLock<HashMap<UntypedAssetId, AtomicU64>>,
+}
+
+impl AssetAccess {
+    #[inline]
+    fn now(&self) -> u64 {
+        self.start.elapsed().as_millis() as u64
+    }
+
+    #[inline]
+    fn touch(&self, asset_id: &UntypedAssetId) {
+        if !self.tracking.load(Ordering::Relaxed) {
+            return;
         }
 
-        if path.starts_with(&self.usr_dir) {
-            return Some(AssetPathKind::Usr);
+        let now = self.now();
+        if let Some(accessed_at) = self.accessed_at.read().get(asset_id) {
+            accessed_at.store(now, Ordering::Relaxed);
+            return;
         }
 
-        None
+        self.accessed_at
+            .write()
+            .insert(*asset_id, AtomicU64::new(now));
     }
 
-    #[inline]
-    pub fn asset_path(&self, path: &Path) -> Option<AssetPath> {
-        path.strip_prefix(&self.sys_dir)
-            .ok()
-            .map(|p| (AssetPathKind::Sys, p))
-            .or_else(|| {
-                path.strip_prefix(&self.usr_dir)
-                    .map(|p| (AssetPathKind::Usr, p))
-                    .ok()
-            })
-            .and_then(|(kind, relative_path_string)| {
-                if let Some(relative_path_string) = relative_path_string.to_str() {
-                    let path = Intern::new(RelativePath::new(relative_path_string).to_owned());
-                    let asset_path = AssetPath::new(kind, path);
-                    Some(asset_path)
-                } else {
-                    None
-                }
-            })
+    fn idle(&self, asset_id: &UntypedAssetId) -> Duration {
+        let accessed_at = self
+            .accessed_at
+            .read()
+            .get(asset_id)
+            .map(|accessed_at| accessed_at.load(Ordering::Relaxed))
+            .unwrap_or_default();
+
+        Duration::from_millis(self.now().saturating_sub(accessed_at))
+    }
+}
+
+impl Default for AssetAccess {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+            tracking: AtomicBool::new(false),
+            accessed_at: Default::default(),
+        }
     }
 }
 
@@ -81,6 +155,17 @@ pub(crate) struct InnerAssets {
     counters: RwLock<IndexMap<UntypedAssetId, Arc<()>>>,
     path_id_index: RwLock<BTreeSet<(AssetPath, OrderWindow<UntypedAssetId>)>>,
     unloaded_events: RwLock<HashMap<UntypedAssetId, UntypedMessage>>,
+    pinned: RwLock<HashSet<UntypedAssetId>>,
+    access: AssetAccess,
+}
+
+impl InnerAssets {
+    pub(crate) fn new(gc_strategy: GcStrategy) -> Self {
+        let inner = Self::default();
+        let lru = matches!(gc_strategy, GcStrategy::LeastRecentlyUsed { .. });
+        inner.access.tracking.store(lru, Ordering::Relaxed);
+        inner
+    }
 }
 
 #[derive(Clone)]
@@ -101,6 +186,8 @@ impl Assets {
         AssetsClient {
             underlying: self.inner.underlying.read(),
             counters: &self.inner.counters,
+            pinned: &self.inner.pinned,
+            access: &self.inner.access,
             sender: &self.sender,
         }
     }
@@ -125,7 +212,9 @@ impl Assets {
                 .map(Arc::strong_count)
                 .unwrap_or_default();
             if count > 0 {
-                underlying.insert(entry.asset_id, entry.asset);
+                let reloaded = underlying.insert(entry.asset_id, entry.asset).is_some();
+                // loading counts as an access, so the asset isn't idle since the start
+                self.inner.access.touch(&entry.asset_id);
 
                 if let Some(asset_path) = entry.asset_id.uri.asset_path() {
                     path_id_index.insert((asset_path, OrderWindow::new(entry.asset_id)));
@@ -135,6 +224,10 @@ impl Assets {
                     loaded_events.push(loaded_event);
                 }
 
+                if let Some(reloaded_event) = entry.reloaded_event.filter(|_| reloaded) {
+                    loaded_events.push(reloaded_event);
+                }
+
                 if let Some(unloaded_event) = entry.unloaded_event {
                     unloaded_events.insert(entry.asset_id, unloaded_event);
                 }
@@ -147,10 +240,13 @@ impl Assets {
         }
     }
 
-    pub(crate) fn gc(&self, at: usize, max: usize) -> usize {
-        let (gc_assets, next) = {
+    pub(crate) fn gc(&self, at: usize, max: usize, strategy: GcStrategy) -> usize {
+        let (mut gc_assets, next) = {
             let counters = self.inner.counters.read();
-            let next = at.saturating_add(max).max(counters.len()) % counters.len();
+            let next = match at.saturating_add(max) {
+                next if next >= counters.len() => 0,
+                next => next,
+            };
             let gc_assets: Vec<UntypedAssetId> = counters
                 .iter()
                 .skip(at)
@@ -167,6 +263,10 @@ impl Assets {
             (gc_assets, next)
         };
 
+        if let GcStrategy::LeastRecentlyUsed { max_idle } = strategy {
+            gc_assets.retain(|gc_asset| self.inner.access.idle(gc_asset) >= max_idle);
+        }
+
         if gc_assets.is_empty() {
             return next;
         }
@@ -175,6 +275,8 @@ impl Assets {
         let mut counters = self.inner.counters.write();
         let mut path_id_index = self.inner.path_id_index.write();
         let mut unloaded_events = self.inner.unloaded_events.write();
+        let mut accessed_at = self.inner.access.accessed_at.write();
+        let pinned = self.inner.pinned.read();
 
         for gc_asset in gc_assets {
             let counts = counters
@@ -182,10 +284,11 @@ impl Assets {
                 .map(Arc::strong_count)
                 .unwrap_or_default();
 
-            if counts < 2 {
+            if counts < 2 && !pinned.contains(&gc_asset) {
                 log::info!("unloading asset: {:?}", gc_asset);
                 counters.remove(&gc_asset);
                 underlying.remove(&gc_asset);
+                accessed_at.remove(&gc_asset);
                 if let Some(asset_path) = gc_asset.uri.asset_path() {
                     path_id_index.remove(&(asset_path, OrderWindow::new(gc_asset)));
                 }
@@ -198,6 +301,24 @@ impl Assets {
         next
     }
 
+    /// removes the asset while keeping its counters and path, so that it can be loaded again
+    pub(crate) fn unload(&self, asset_id: &UntypedAssetId) {
+        let mut underlying = self.inner.underlying.write();
+        let mut unloaded_events = self.inner.unloaded_events.write();
+
+        if underlying.remove(asset_id).is_some() {
+            log::info!("unloading asset: {:?}", asset_id);
+            if let Some(unloaded_event) = unloaded_events.remove(asset_id) {
+                self.sender.send_untyped(unloaded_event);
+            }
+        }
+    }
+
+    /// if the asset is still referenced or loaded, i.e. it hasn't been collected by the gc
+    pub(crate) fn is_registered(&self, asset_id: &UntypedAssetId) -> bool {
+        self.inner.counters.read().contains_key(asset_id)
+    }
+
     pub(crate) fn asset_ids_for_path(&self, asset_path: AssetPath) -> Vec<UntypedAssetId> {
         use std::ops::Bound::Included;
         let path_id_index = self.inner.path_id_index.read();
@@ -214,6 +335,8 @@ impl Assets {
 pub struct AssetsClient<'a> {
     underlying: RwLockReadGuard<'a, HashMap<UntypedAssetId, UntypedAsset>>,
     counters: &'a RwLock<IndexMap<UntypedAssetId, Arc<()>>>,
+    pinned: &'a RwLock<HashSet<UntypedAssetId>>,
+    access: &'a AssetAccess,
     sender: &'a MessageSender,
 }
 
@@ -230,6 +353,7 @@ impl<'a> AssetsClient<'a> {
                 let load_asset_event = LoadAssetEvent {
                     id: weak.untyped,
                     force: false,
+                    reload: false,
                 };
                 self.sender.borrow().send(load_asset_event);
 
@@ -238,6 +362,19 @@ impl<'a> AssetsClient<'a> {
         }
     }
 
+    /// loads all assets and sends a `LoadBarrierCompleteEvent` once each has loaded or failed
+    pub fn load_all<T: Send + Sync + 'static>(&self, asset_paths: &[AssetPath]) -> LoadBarrier<T> {
+        let assets: Vec<StrongAssetId<T>> = asset_paths.iter().map(|p| self.load(*p)).collect();
+        let id = Uuid::new_v4();
+
+        self.sender.borrow().send(LoadBarrierEvent {
+            id,
+            assets: assets.iter().map(|a| a.untyped).collect(),
+        });
+
+        LoadBarrier { id, assets }
+    }
+
     #[inline]
     pub fn upgrade<T: Send + Sync + 'static>(&self, weak: &WeakAssetId<T>) -> StrongAssetId<T> {
         match weak.untyped.uri {
@@ -297,6 +434,87 @@ impl<'a> AssetsClient<'a> {
         strong_asset_id
     }
 
+    /// serializes the asset as json into the usr dir,
+    /// the asset server answers with a `SaveResultEvent`
+    pub fn save<T: Serialize>(&self, asset_path: AssetPath, asset: &T) {
+        let bytes = match serde_json::to_vec_pretty(asset) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                log::error!("Could not serialize asset {:?}: {}", asset_path, e);
+                self.sender.borrow().send(SaveResultEvent {
+                    path: asset_path,
+                    error: Some(e.to_string()),
+                });
+                return;
+            }
+        };
+
+        log::info!("queue save asset: {:?}", asset_path);
+        self.sender.borrow().send(SaveAssetEvent {
+            path: asset_path,
+            bytes,
+        });
+    }
+
+    /// all stores and loads of the transaction become visible in the same sync,
+    /// if any load fails none of them will be synced
+    pub fn transaction<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut AssetTransaction) -> R,
+    {
+        let mut transaction = AssetTransaction {
+            client: self,
+            entries: Default::default(),
+            loads: Default::default(),
+        };
+        let result = f(&mut transaction);
+
+        let AssetTransaction { entries, loads, .. } = transaction;
+        if entries.is_empty() && loads.is_empty() {
+            return result;
+        }
+
+        log::info!(
+            "queue asset transaction of {} store(s) and {} load(s)",
+            entries.len(),
+            loads.len()
+        );
+        self.sender.borrow().send(TransactionAssetEvent {
+            entries: Mutex::new(entries),
+            loads,
+        });
+
+        result
+    }
+
+    /// number of assets that are currently loaded
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.underlying.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.underlying.is_empty()
+    }
+
+    /// keeps the asset loaded even if there are no strong ids left, e.g. for a shared atlas
+    #[inline]
+    pub fn pin<T>(&self, id: &StrongAssetId<T>) {
+        self.pinned.write().insert(id.untyped);
+    }
+
+    /// the asset is unloaded again by the gc once there are no strong ids left
+    #[inline]
+    pub fn unpin<T, S>(&self, id: &AssetId<T, S>) {
+        self.pinned.write().remove(&id.untyped);
+    }
+
+    #[inline]
+    pub fn is_pinned<T, S>(&self, id: &AssetId<T, S>) -> bool {
+        self.pinned.read().contains(&id.untyped)
+    }
+
     #[inline]
     pub fn has<T, S>(&self, id: &AssetId<T, S>) -> bool {
         self.has_untyped(&id.untyped)
@@ -309,6 +527,7 @@ impl<'a> AssetsClient<'a> {
 
     #[inline]
     pub fn get<T: std::any::Any>(&self, id: &AssetId<T, Loaded>) -> &T {
+        self.access.touch(&id.untyped);
         let t = self.underlying.get(&id.untyped).unwrap();
         // see std::any::Any::downcast_ref()
         // the type check was already done via the typed asset id
@@ -319,6 +538,7 @@ impl<'a> AssetsClient<'a> {
     #[inline]
     pub fn try_get<T: std::any::Any, S>(&self, id: &AssetId<T, S>) -> Option<&T> {
         self.underlying.get(&id.untyped).map(|t| {
+            self.access.touch(&id.untyped);
             // see std::any::Any::downcast_ref()
             // the type check was already done via the typed asset id
             let any: &(dyn std::any::Any + Send + Sync) = t.as_ref();
@@ -348,6 +568,50 @@ impl<'a> AssetsClient<'a> {
     }
 }
 
+pub struct AssetTransaction<'a, 'b> {
+    client: &'b AssetsClient<'a>,
+    entries: Vec<SyncQueueEntry>,
+    loads: Vec<LoadAssetEvent>,
+}
+
+impl<'a, 'b> AssetTransaction<'a, 'b> {
+    #[inline]
+    pub fn load<T: Send + Sync + 'static>(&mut self, asset_path: AssetPath) -> StrongAssetId<T> {
+        let weak = WeakAssetId::new(AssetUri::AssetPath(asset_path));
+
+        match self.client.register_asset(&weak) {
+            RegisterAssetResult::Preexisting(id) => id,
+            RegisterAssetResult::Unfamiliar(id) => {
+                self.loads.push(LoadAssetEvent {
+                    id: weak.untyped,
+                    force: false,
+                    reload: false,
+                });
+
+                id
+            }
+        }
+    }
+
+    #[inline]
+    pub fn store<T: Send + Sync + 'static, AI: Into<WeakAssetId<T>>>(
+        &mut self,
+        asset_id: AI,
+        asset: T,
+    ) -> StrongAssetId<T> {
+        let asset_id = asset_id.into();
+        let strong_asset_id = match self.client.register_asset(&asset_id) {
+            RegisterAssetResult::Preexisting(id) => id,
+            RegisterAssetResult::Unfamiliar(id) => id,
+        };
+
+        self.entries
+            .push(SyncQueueEntry::new(asset_id, asset, self.client.sender));
+
+        strong_asset_id
+    }
+}
+
 pub(crate) enum RegisterAssetResult<T> {
     Preexisting(StrongAssetId<T>),
     Unfamiliar(StrongAssetId<T>),
@@ -0,0 +1,88 @@
+use internment::Intern;
+use roundabout::prelude::*;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DormantServerEventKind {
+    Wake,
+    Sleep,
+}
+
+/// wakes or puts to sleep the dormant server registered under the name,
+/// the server handler receives the event as well to set up / tear down its state
+#[derive(Debug, Clone)]
+pub struct DormantServerEvent {
+    pub name: Intern<String>,
+    pub kind: DormantServerEventKind,
+}
+
+impl DormantServerEvent {
+    #[inline]
+    pub fn wake(name: &str) -> Self {
+        Self {
+            name: Intern::new(name.to_owned()),
+            kind: DormantServerEventKind::Wake,
+        }
+    }
+
+    #[inline]
+    pub fn sleep(name: &str) -> Self {
+        Self {
+            name: Intern::new(name.to_owned()),
+            kind: DormantServerEventKind::Sleep,
+        }
+    }
+}
+
+struct DormantControl {
+    name: Intern<String>,
+    awake: bool,
+}
+
+/// a server group which lies dormant until woken by a `DormantServerEvent`,
+/// e.g. for optional subsystems like a profiler or a console that are attached on demand
+pub struct DormantServer;
+
+impl DormantServer {
+    pub fn new<T, F>(name: &str, mut group: MessageGroupBuilder, handler_fn: F) -> MessageGroup
+    where
+        T: 'static,
+        F: FnOnce(OpenMessageHandlerBuilder<T>) -> InitMessageHandlerBuilder<T>,
+    {
+        let name = Intern::new(name.to_owned());
+        let control_builder = group.register(|b| {
+            b.on(on_dormant_server_event)
+                .init(DormantControl { name, awake: false })
+        });
+        let handler_builder = group.register(handler_fn);
+
+        group.init(move |recv, mut context| {
+            let mut control = control_builder.finish(&context).unwrap();
+            let mut handler = handler_builder.finish(&context).unwrap();
+
+            recv.stream(|message| {
+                let was_awake = control.state.awake;
+                control.handle(&mut context, message);
+                // the handler sees the wake and the sleep event
+                if was_awake || control.state.awake {
+                    handler.handle(&mut context, message);
+                }
+            })
+        })
+    }
+}
+
+fn on_dormant_server_event(
+    state: &mut DormantControl,
+    _context: &mut RuntimeContext,
+    event: &DormantServerEvent,
+) {
+    if event.name != state.name {
+        return;
+    }
+
+    let awake = event.kind == DormantServerEventKind::Wake;
+    if state.awake != awake {
+        log::info!("dormant server {} awake: {}", state.name, awake);
+        state.awake = awake;
+    }
+}
@@ -1,11 +1,16 @@
 #![feature(map_first_last)]
 
+use crate::asset::AssetPathKind;
+use crate::platform::message::ShutdownRequestedEvent;
+use parking_lot::Mutex;
 use relative_path::{RelativePath, RelativePathBuf};
 use roundabout::prelude::*;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Instant;
 
 mod asset;
+mod dormant;
 mod platform;
 pub mod prelude;
 mod render;
@@ -19,12 +24,97 @@ pub struct InitEvent {
     pub dir: PathBuf,
     pub sys_dir: PathBuf,
     pub usr_dir: PathBuf,
+    pub mounts: Vec<(AssetPathKind, PathBuf)>,
+    pub shutdown: ShutdownHandle,
+}
+
+#[derive(Default)]
+struct ShutdownHandleState {
+    requested: bool,
+    target: Option<ShutdownTarget>,
+}
+
+enum ShutdownTarget {
+    /// the platform server stops the runtime after the final frame
+    Platform(MessageSender),
+    /// stops the runtime directly, for runtimes without a platform server
+    Runtime(ShutdownSwitch),
+}
+
+impl ShutdownTarget {
+    fn request_shutdown(&self) {
+        match self {
+            ShutdownTarget::Platform(sender) => sender.send(ShutdownRequestedEvent),
+            ShutdownTarget::Runtime(switch) => switch.request_shutdown(),
+        }
+    }
+}
+
+/// cloneable handle to request the shutdown of the runtime from any thread,
+/// requests before the runtime has been started are deferred until it is,
+/// the handle of the `InitEvent` is bound by the platform server, which broadcasts the request
+/// as `ShutdownRequestedEvent` before the final frame,
+/// runtimes without a platform server have to create their handles with `ShutdownHandle::new`
+#[derive(Default, Clone)]
+pub struct ShutdownHandle(Arc<Mutex<ShutdownHandleState>>);
+
+impl ShutdownHandle {
+    /// stops the runtime of the context directly, without a final frame of the platform server
+    #[inline]
+    pub fn new(context: &RuntimeContext) -> Self {
+        let handle = Self::default();
+        handle.bind(ShutdownTarget::Runtime(context.shutdown_switch().clone()));
+        handle
+    }
+
+    pub(crate) fn bind_platform(&self, context: &RuntimeContext) {
+        self.bind(ShutdownTarget::Platform(context.sender().to_owned()));
+    }
+
+    fn bind(&self, target: ShutdownTarget) {
+        let mut state = self.0.lock();
+        if state.target.is_some() {
+            return;
+        }
+
+        if state.requested {
+            log::info!("request deferred runtime shutdown");
+            target.request_shutdown();
+        }
+        state.target = Some(target);
+    }
+
+    pub fn request_shutdown(&self) {
+        let mut state = self.0.lock();
+        if std::mem::replace(&mut state.requested, true) {
+            return;
+        }
+
+        if let Some(target) = state.target.as_ref() {
+            log::info!("request runtime shutdown");
+            target.request_shutdown();
+        }
+    }
+
+    #[inline]
+    pub fn is_requested(&self) -> bool {
+        self.0.lock().requested
+    }
+}
+
+impl std::fmt::Debug for ShutdownHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShutdownHandle")
+            .field("requested", &self.is_requested())
+            .finish()
+    }
 }
 
 pub struct EngineBuilder {
     dir: PathBuf,
     sys_path: RelativePathBuf,
     usr_path: RelativePathBuf,
+    mounts: Vec<(AssetPathKind, PathBuf)>,
     runtime: Runtime,
 }
 
@@ -36,6 +126,7 @@ impl EngineBuilder {
             dir: current_dir,
             sys_path: RelativePath::new("sys/").to_owned(),
             usr_path: RelativePath::new("usr/").to_owned(),
+            mounts: Vec::default(),
             runtime: Runtime::builder(4_194_304).finish(),
         })
     }
@@ -55,6 +146,16 @@ impl EngineBuilder {
         self
     }
 
+    /// serves the assets of `name://` paths from the dir, which is relative to the engine dir,
+    /// e.g. `with_mount("mods/foo", "mods/foo/assets")` for mod assets
+    pub fn with_mount<T: Into<PathBuf>>(mut self, name: &str, dir: T) -> Self {
+        let kind = AssetPathKind::mount(name)
+            .unwrap_or_else(|| panic!("invalid asset mount name: {}", name));
+        self.mounts.retain(|(mounted, _)| *mounted != kind);
+        self.mounts.push((kind, dir.into()));
+        self
+    }
+
     pub fn with_runtime(mut self, runtime: Runtime) -> Self {
         self.runtime = runtime;
         self
@@ -63,12 +164,19 @@ impl EngineBuilder {
     pub fn finish(self) -> Engine {
         let sys_dir = self.sys_path.to_path(&self.dir);
         let usr_dir = self.usr_path.to_path(&self.dir);
+        let mounts = self
+            .mounts
+            .into_iter()
+            .map(|(kind, dir)| (kind, self.dir.join(dir)))
+            .collect();
 
         let init = InitEvent {
             start: Instant::now(),
             dir: self.dir,
             sys_dir,
             usr_dir,
+            mounts,
+            shutdown: Default::default(),
         };
 
         Engine {
@@ -92,6 +200,11 @@ impl Engine {
         Ok(Self::builder()?.with_runtime(runtime).finish())
     }
 
+    #[inline]
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.init.shutdown.clone()
+    }
+
     pub fn start(self) -> anyhow::Result<()> {
         self.runtime.start(self.init);
         Ok(())
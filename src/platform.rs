@@ -1,4 +1,8 @@
 pub mod action;
+pub mod clipboard;
+pub mod clock;
+pub mod cursor;
+pub mod gamepad;
 pub mod input;
 pub mod key;
 pub mod message;
@@ -8,19 +12,29 @@ use crate::asset::{
     AssetEvent, AssetEventKind, AssetPath, AssetsCreatedEvent, StrongAssetId, WeakAssetId,
 };
 use crate::platform::action::{Actions, ActionsConfig};
-use crate::platform::input::Inputs;
+use crate::platform::clock::SimClock;
+use crate::platform::cursor::{CursorGrab, CursorIcon, CursorTheme, Cursors};
+use crate::platform::gamepad::Gamepads;
+use crate::platform::input::{Inputs, ScanCodesConfig};
 use crate::platform::message::{
-    DisplayCreatedEvent, DisplayResizedEvent, FrameRequestedEvent, ResumedEvent, SuspendedEvent,
+    ClipboardTextEvent, CursorImageEvent, DisplayCreatedEvent, DisplayResizedEvent,
+    DisplayScaleChangedEvent, DisplaysEvent, FrameRequestedEvent, GrabCursorEvent,
+    QueryDisplaysEvent, RequestClipboardEvent, ResumedEvent, SetClipboardEvent, SetCursorEvent,
+    SetCursorIconEvent, SetCursorVisibleEvent, SetSimPausedEvent, ShutdownRequestedEvent,
+    SuspendedEvent,
 };
-use crate::render::message::DrawnEvent;
+use crate::render::message::{DrawnEvent, SetPresentModeEvent};
+use crate::render::stats::StatsOverlayConfig;
+use crate::render::{Backends, DepthFormat, PowerPreference, PresentMode, Samples};
 use crate::sim::SimulatedEvent;
 use crate::InitEvent;
 use roundabout::prelude::*;
 use serde::Deserialize;
 use std::time::Instant;
-use winit::dpi::PhysicalSize;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
 use winit::event::{Event, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
+use winit::monitor::MonitorHandle;
 use winit::window::WindowBuilder;
 
 #[derive(Debug, Clone, Deserialize)]
@@ -31,6 +45,17 @@ pub struct DisplayConfig {
     pub size: [u32; 2],
     pub maximized: bool,
     pub fullscreen: Fullscreen,
+    /// index into the available monitors the window is opened on, defaults to the primary monitor
+    pub monitor: Option<usize>,
+    pub cursors: CursorTheme,
+    /// multi sampling of the frame, falls back to four samples if not supported
+    pub samples: Samples,
+    pub depth_format: DepthFormat,
+    pub present_mode: PresentMode,
+    /// only applied on startup like the samples and the depth format
+    pub backends: Backends,
+    pub power_preference: PowerPreference,
+    pub stats_overlay: StatsOverlayConfig,
 }
 
 impl Default for DisplayConfig {
@@ -41,15 +66,46 @@ impl Default for DisplayConfig {
             size: [1280, 720],
             maximized: false,
             fullscreen: Fullscreen::Windowed,
+            monitor: None,
+            cursors: CursorTheme::default(),
+            samples: Samples::default(),
+            depth_format: DepthFormat::default(),
+            present_mode: PresentMode::default(),
+            backends: Backends::default(),
+            power_preference: PowerPreference::default(),
+            stats_overlay: StatsOverlayConfig::default(),
         }
     }
 }
 
+impl DisplayConfig {
+    #[inline]
+    pub fn with_backends(mut self, backends: Backends) -> Self {
+        self.backends = backends;
+        self
+    }
+
+    #[inline]
+    pub fn with_power_preference(mut self, power_preference: PowerPreference) -> Self {
+        self.power_preference = power_preference;
+        self
+    }
+}
+
 #[derive(Debug, Copy, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum Fullscreen {
     Windowed,
     Borderless,
+    /// video mode of the monitor with the given size and the closest refresh rate,
+    /// falls back to borderless if the monitor doesn't support the size
+    #[serde(rename_all = "camelCase")]
+    Exclusive {
+        width: u32,
+        height: u32,
+        #[serde(default)]
+        refresh_rate: Option<u16>,
+    },
 }
 
 impl Default for Fullscreen {
@@ -58,15 +114,86 @@ impl Default for Fullscreen {
     }
 }
 
-impl Into<Option<winit::window::Fullscreen>> for Fullscreen {
-    fn into(self) -> Option<winit::window::Fullscreen> {
+impl Fullscreen {
+    fn on_monitor(self, monitor: Option<MonitorHandle>) -> Option<winit::window::Fullscreen> {
         match self {
             Fullscreen::Windowed => None,
-            Fullscreen::Borderless => Some(winit::window::Fullscreen::Borderless(None)),
+            Fullscreen::Borderless => Some(winit::window::Fullscreen::Borderless(monitor)),
+            Fullscreen::Exclusive {
+                width,
+                height,
+                refresh_rate,
+            } => {
+                let video_mode = monitor.as_ref().and_then(|monitor| {
+                    monitor
+                        .video_modes()
+                        .filter(|mode| mode.size() == PhysicalSize::new(width, height))
+                        .min_by_key(|mode| {
+                            let refresh_rate_diff = match refresh_rate {
+                                Some(refresh_rate) => {
+                                    (mode.refresh_rate() as i32 - refresh_rate as i32).abs()
+                                }
+                                None => -(mode.refresh_rate() as i32),
+                            };
+                            (refresh_rate_diff, -(mode.bit_depth() as i32))
+                        })
+                });
+
+                match video_mode {
+                    Some(video_mode) => {
+                        log::info!("exclusive fullscreen with: {:?}", video_mode);
+                        Some(winit::window::Fullscreen::Exclusive(video_mode))
+                    }
+                    None => {
+                        log::warn!(
+                            "no video mode with {}x{} available, fall back to borderless",
+                            width,
+                            height
+                        );
+                        Some(winit::window::Fullscreen::Borderless(monitor))
+                    }
+                }
+            }
         }
     }
 }
 
+/// a monitor with its supported video modes for exclusive fullscreen
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorInfo {
+    pub name: Option<String>,
+    pub size: [u32; 2],
+    pub position: [i32; 2],
+    pub scale_factor: f64,
+    pub video_modes: Vec<VideoModeInfo>,
+}
+
+impl From<MonitorHandle> for MonitorInfo {
+    fn from(monitor: MonitorHandle) -> Self {
+        Self {
+            name: monitor.name(),
+            size: monitor.size().into(),
+            position: monitor.position().into(),
+            scale_factor: monitor.scale_factor(),
+            video_modes: monitor
+                .video_modes()
+                .map(|mode| VideoModeInfo {
+                    size: mode.size().into(),
+                    refresh_rate: mode.refresh_rate(),
+                    bit_depth: mode.bit_depth(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct VideoModeInfo {
+    pub size: [u32; 2],
+    pub refresh_rate: u16,
+    pub bit_depth: u16,
+}
+
 enum ConfigOriginInner<T> {
     Inline(T),
     AssetPath(AssetPath),
@@ -124,6 +251,15 @@ impl From<ActionsConfig> for ConfigOrigin<ActionsConfig> {
     }
 }
 
+impl From<ScanCodesConfig> for ConfigOrigin<ScanCodesConfig> {
+    fn from(t: ScanCodesConfig) -> Self {
+        ConfigOrigin {
+            inner: ConfigOriginInner::Inline(t),
+            dirty: true,
+        }
+    }
+}
+
 impl<T> From<AssetPath> for ConfigOrigin<T> {
     fn from(asset_path: AssetPath) -> Self {
         ConfigOrigin {
@@ -136,11 +272,18 @@ impl<T> From<AssetPath> for ConfigOrigin<T> {
 pub struct PlatformServer {
     display_config: ConfigOrigin<DisplayConfig>,
     actions_config: ConfigOrigin<ActionsConfig>,
-    start: Instant,
-    curr: Instant,
+    scan_codes_config: ConfigOrigin<ScanCodesConfig>,
+    clock: SimClock,
     requested_frame: u64,
     drawn_frame: u64,
     simulated_frame: u64,
+    shutdown_frame: Option<u64>,
+    requested_cursor: Option<Option<String>>,
+    requested_cursor_icon: Option<CursorIcon>,
+    requested_cursor_visible: Option<bool>,
+    requested_cursor_grab: Option<CursorGrab>,
+    requested_displays: bool,
+    clipboard: Option<arboard::Clipboard>,
     assets: Option<Assets>,
 }
 
@@ -151,30 +294,67 @@ impl PlatformServer {
     pub fn new<DC: Into<ConfigOrigin<DisplayConfig>>, AC: Into<ConfigOrigin<ActionsConfig>>>(
         display_config: DC,
         actions_config: AC,
-        mut group: MessageGroupBuilder,
+        group: MessageGroupBuilder,
     ) -> MessageGroup {
+        Self::with_scan_codes(
+            display_config,
+            actions_config,
+            ScanCodesConfig::default(),
+            group,
+        )
+    }
+
+    /**
+    The scan codes config overrides the built-in scancode map of the platform, e.g. from a usr asset
+    */
+    pub fn with_scan_codes<DC, AC, SC>(
+        display_config: DC,
+        actions_config: AC,
+        scan_codes_config: SC,
+        mut group: MessageGroupBuilder,
+    ) -> MessageGroup
+    where
+        DC: Into<ConfigOrigin<DisplayConfig>>,
+        AC: Into<ConfigOrigin<ActionsConfig>>,
+        SC: Into<ConfigOrigin<ScanCodesConfig>>,
+    {
         let display_config = display_config.into();
         let actions_config = actions_config.into();
+        let scan_codes_config = scan_codes_config.into();
 
         let platform_builder = group.register(|h| {
             h.on(on_init_event)
                 .on(on_asset_created_event)
                 .on(on_display_config_loaded_event)
                 .on(on_actions_config_loaded_event)
+                .on(on_scan_codes_config_loaded_event)
                 .on(on_drawn_event)
                 .on(on_simulated_event)
-                .init_fn(|_| {
-                    let start = Instant::now();
-                    PlatformServer {
-                        display_config,
-                        actions_config,
-                        start,
-                        curr: start,
-                        requested_frame: 0,
-                        drawn_frame: 0,
-                        simulated_frame: 0,
-                        assets: None,
-                    }
+                .on(on_set_cursor_event)
+                .on(on_set_cursor_icon_event)
+                .on(on_set_cursor_visible_event)
+                .on(on_grab_cursor_event)
+                .on(on_query_displays_event)
+                .on(on_request_clipboard_event)
+                .on(on_set_clipboard_event)
+                .on(on_set_sim_paused_event)
+                .on(on_shutdown_requested_event)
+                .init_fn(|_| PlatformServer {
+                    display_config,
+                    actions_config,
+                    scan_codes_config,
+                    clock: SimClock::new(Instant::now()),
+                    requested_frame: 0,
+                    drawn_frame: 0,
+                    simulated_frame: 0,
+                    shutdown_frame: None,
+                    requested_cursor: None,
+                    requested_cursor_icon: None,
+                    requested_cursor_visible: None,
+                    requested_cursor_grab: None,
+                    requested_displays: false,
+                    clipboard: None,
+                    assets: None,
                 })
         });
 
@@ -183,11 +363,37 @@ impl PlatformServer {
             run_event_loop(platform, recv, context);
         })
     }
+
+    /// lazily connects to the clipboard of the platform
+    fn clipboard(&mut self) -> Option<&mut arboard::Clipboard> {
+        if self.clipboard.is_none() {
+            match arboard::Clipboard::new() {
+                Ok(clipboard) => self.clipboard = Some(clipboard),
+                Err(e) => log::warn!("clipboard not available: {}", e),
+            }
+        }
+
+        self.clipboard.as_mut()
+    }
+
+    /// shuts down after the next frame, which all servers receive after the shutdown event
+    fn request_shutdown(&mut self) {
+        if self.shutdown_frame.is_none() {
+            log::info!("shutdown requested");
+            self.shutdown_frame = Some(self.requested_frame + 1);
+        }
+    }
+
+    fn is_shutdown_frame(&self) -> bool {
+        self.shutdown_frame
+            .map(|frame| self.drawn_frame >= frame && self.simulated_frame >= frame)
+            .unwrap_or_default()
+    }
 }
 
-fn on_init_event(state: &mut PlatformServer, _context: &mut RuntimeContext, event: &InitEvent) {
-    state.start = event.start;
-    state.curr = event.start;
+fn on_init_event(state: &mut PlatformServer, context: &mut RuntimeContext, event: &InitEvent) {
+    event.shutdown.bind_platform(context);
+    state.clock = SimClock::new(event.start);
 }
 
 fn on_asset_created_event(
@@ -201,6 +407,7 @@ fn on_asset_created_event(
     let asset = state.assets.as_mut().unwrap().client();
     state.display_config.init(&asset);
     state.actions_config.init(&asset);
+    state.scan_codes_config.init(&asset);
 }
 
 fn on_display_config_loaded_event(
@@ -223,6 +430,109 @@ fn on_actions_config_loaded_event(
     }
 }
 
+fn on_scan_codes_config_loaded_event(
+    state: &mut PlatformServer,
+    _context: &mut RuntimeContext,
+    event: &AssetEvent<ScanCodesConfig>,
+) {
+    if AssetEventKind::Load == event.kind {
+        state.scan_codes_config.dirty(&event.id);
+    }
+}
+
+fn on_set_cursor_event(
+    state: &mut PlatformServer,
+    _context: &mut RuntimeContext,
+    event: &SetCursorEvent,
+) {
+    state.requested_cursor = Some(event.name.clone());
+    state.requested_cursor_icon = None;
+}
+
+fn on_set_cursor_icon_event(
+    state: &mut PlatformServer,
+    _context: &mut RuntimeContext,
+    event: &SetCursorIconEvent,
+) {
+    state.requested_cursor = None;
+    state.requested_cursor_icon = Some(event.icon);
+}
+
+fn on_set_cursor_visible_event(
+    state: &mut PlatformServer,
+    _context: &mut RuntimeContext,
+    event: &SetCursorVisibleEvent,
+) {
+    state.requested_cursor_visible = Some(event.visible);
+}
+
+fn on_set_sim_paused_event(
+    state: &mut PlatformServer,
+    _context: &mut RuntimeContext,
+    event: &SetSimPausedEvent,
+) {
+    if event.paused {
+        state.clock.pause(Instant::now());
+    } else {
+        state.clock.resume(Instant::now());
+    }
+}
+
+fn on_shutdown_requested_event(
+    state: &mut PlatformServer,
+    _context: &mut RuntimeContext,
+    _event: &ShutdownRequestedEvent,
+) {
+    state.request_shutdown();
+}
+
+fn on_grab_cursor_event(
+    state: &mut PlatformServer,
+    _context: &mut RuntimeContext,
+    event: &GrabCursorEvent,
+) {
+    state.requested_cursor_grab = Some(event.mode);
+}
+
+fn on_request_clipboard_event(
+    state: &mut PlatformServer,
+    context: &mut RuntimeContext,
+    _event: &RequestClipboardEvent,
+) {
+    let text = state
+        .clipboard()
+        .and_then(|clipboard| match clipboard.get_text() {
+            Ok(text) => Some(text),
+            Err(e) => {
+                log::debug!("no clipboard text: {}", e);
+                None
+            }
+        })
+        .filter(|text| !text.is_empty());
+
+    context.sender().send(ClipboardTextEvent { text });
+}
+
+fn on_set_clipboard_event(
+    state: &mut PlatformServer,
+    _context: &mut RuntimeContext,
+    event: &SetClipboardEvent,
+) {
+    if let Some(clipboard) = state.clipboard() {
+        if let Err(e) = clipboard.set_text(event.text.clone()) {
+            log::warn!("failed to set clipboard text: {}", e);
+        }
+    }
+}
+
+fn on_query_displays_event(
+    state: &mut PlatformServer,
+    _context: &mut RuntimeContext,
+    _event: &QueryDisplaysEvent,
+) {
+    state.requested_displays = true;
+}
+
 fn on_drawn_event(state: &mut PlatformServer, _context: &mut RuntimeContext, event: &DrawnEvent) {
     state.drawn_frame = state.drawn_frame.max(event.frame);
 }
@@ -253,7 +563,17 @@ fn run_event_loop(
         return;
     }
 
-    let window_builder = {
+    let event_loop = EventLoop::new();
+    let (
+        window_builder,
+        samples,
+        depth_format,
+        present_mode,
+        backends,
+        power_preference,
+        cursor_theme,
+        stats_overlay,
+    ) = {
         let assets = platform.state.assets.as_mut().unwrap().client();
         let config = platform
             .state
@@ -262,29 +582,76 @@ fn run_event_loop(
             .expect("loaded display config");
 
         log::info!("starting display with: {:?}", config);
-        WindowBuilder::new()
+        let monitor = config.monitor.and_then(|index| {
+            let monitor = event_loop.available_monitors().nth(index);
+            if monitor.is_none() {
+                log::warn!("monitor {} not available, fall back to the primary", index);
+            }
+            monitor
+        });
+
+        let fullscreen = config
+            .fullscreen
+            .on_monitor(monitor.clone().or_else(|| event_loop.primary_monitor()));
+        let mut window_builder = WindowBuilder::new()
             .with_title(&config.title)
             .with_resizable(config.resizable)
             .with_maximized(config.maximized)
-            .with_fullscreen(config.fullscreen.into())
-            .with_inner_size(PhysicalSize::new(config.size[0], config.size[1]))
+            .with_fullscreen(fullscreen)
+            .with_inner_size(PhysicalSize::new(config.size[0], config.size[1]));
+
+        if let Some(monitor) = monitor {
+            // center the window on the selected monitor
+            let position = monitor.position();
+            let monitor_size = monitor.size();
+            let x = (monitor_size.width as i32 - config.size[0] as i32) / 2;
+            let y = (monitor_size.height as i32 - config.size[1] as i32) / 2;
+            window_builder = window_builder.with_position(PhysicalPosition::new(
+                position.x + x.max(0),
+                position.y + y.max(0),
+            ));
+        }
+
+        (
+            window_builder,
+            config.samples,
+            config.depth_format,
+            config.present_mode,
+            config.backends,
+            config.power_preference,
+            config.cursors.clone(),
+            config.stats_overlay.clone(),
+        )
     };
 
-    let event_loop = EventLoop::new();
     let window = window_builder.build(&event_loop).unwrap();
 
-    let instance = wgpu::Instance::new(wgpu::Backends::PRIMARY);
+    log::info!("request adapter from {:?} backends", backends);
+    let instance = wgpu::Instance::new(backends.into());
     let window_surface = unsafe { instance.create_surface(&window) };
     let mut inputs = Inputs::new(window.inner_size().into());
+    let mut cursors = Cursors::new(cursor_theme);
+    let mut gamepads = Gamepads::new();
+    let mut cursor_visible = true;
+    let mut cursor_grab = CursorGrab::None;
+    let mut cursor_image = None;
+    let mut cursor_textures = Vec::new();
     let mut actions = {
         // Optimization: move to a separate MessageHandler
         let assets = platform.state.assets.as_mut().unwrap().client();
         let config = platform.state.actions_config.take(&assets);
         Actions::new(config.cloned().unwrap_or_default())
     };
+    gamepads.set_deadzone(actions.config().gamepad_deadzone());
 
     context.sender().send(DisplayCreatedEvent::new(
         window.inner_size().into(),
+        window.scale_factor(),
+        samples,
+        depth_format,
+        present_mode,
+        power_preference,
+        stats_overlay,
         instance,
         window_surface,
     ));
@@ -311,9 +678,14 @@ fn run_event_loop(
                 window.set_title(&config.title);
                 window.set_resizable(config.resizable);
                 window.set_maximized(config.maximized);
-                window.set_fullscreen(config.fullscreen.into());
+                let fullscreen = config.fullscreen.on_monitor(window.current_monitor());
+                window.set_fullscreen(fullscreen);
                 window.set_inner_size(PhysicalSize::new(config.size[0], config.size[1]));
                 inputs.set_cursor_rect(config.size);
+                cursors.set_theme(config.cursors.clone(), &window);
+                context.sender().send(SetPresentModeEvent {
+                    present_mode: config.present_mode,
+                });
                 // required as a window size change here won't trigger the WindowEvent::Resized event
                 context
                     .sender()
@@ -324,17 +696,67 @@ fn run_event_loop(
                 let assets = platform.state.assets.as_mut().unwrap().client();
                 let config = platform.state.actions_config.take(&assets);
                 actions.set_config(config.cloned().unwrap_or_default());
+                gamepads.set_deadzone(actions.config().gamepad_deadzone());
             }
 
+            if platform.state.scan_codes_config.dirty {
+                let assets = platform.state.assets.as_mut().unwrap().client();
+                let config = platform.state.scan_codes_config.take(&assets);
+                inputs.set_scan_codes(config.cloned().unwrap_or_default());
+            }
+
+            if let Some(name) = platform.state.requested_cursor.take() {
+                cursors.set_cursor(name, &window);
+            }
+            if let Some(icon) = platform.state.requested_cursor_icon.take() {
+                cursors.set_icon(icon, &window);
+            }
+            let mut cursor_changed = false;
+            if let Some(visible) = platform.state.requested_cursor_visible.take() {
+                cursor_visible = visible;
+                cursor_changed = true;
+            }
+            if let Some(grab) = platform.state.requested_cursor_grab.take() {
+                // winit only confines the cursor, locking additionally hides it
+                if let Err(e) = window.set_cursor_grab(grab != CursorGrab::None) {
+                    log::warn!("failed to grab cursor with {:?}: {}", grab, e);
+                }
+                cursor_grab = grab;
+                cursor_changed = true;
+            }
+            cursors.update(Instant::now(), &window);
+
+            // cursor images are drawn by the render server in place of the system cursor
+            let visible = cursor_visible && cursor_grab != CursorGrab::Locked;
+            let image = cursors.image().filter(|_| visible);
+            let textures = cursors.textures();
+            if image != cursor_image || textures != cursor_textures {
+                cursor_changed |= image != cursor_image;
+                cursor_image = image;
+                cursor_textures = textures.clone();
+                context.sender().send(CursorImageEvent { image, textures });
+            }
+            if cursor_changed {
+                window.set_cursor_visible(visible && cursors.image().is_none());
+            }
+
+            if std::mem::take(&mut platform.state.requested_displays) {
+                let current_monitor = window.current_monitor();
+                let current = window
+                    .available_monitors()
+                    .position(|monitor| Some(monitor) == current_monitor);
+                let monitors = window.available_monitors().map(MonitorInfo::from).collect();
+                context.sender().send(DisplaysEvent { monitors, current });
+            }
+
+            gamepads.push_events(&mut inputs);
             actions.push_inputs(&inputs);
             actions.apply_actions(context.sender());
             inputs.apply_inputs(context.sender());
 
             let frame_requested = {
                 let at = Instant::now();
-                let delta = at.duration_since(platform.state.curr);
-                let elapsed = at.duration_since(platform.state.start);
-                platform.state.curr = at;
+                let (elapsed, delta) = platform.state.clock.tick(at);
 
                 let frame = platform.state.requested_frame + 1;
                 platform.state.requested_frame = frame;
@@ -344,6 +766,8 @@ fn run_event_loop(
                     at,
                     elapsed,
                     delta,
+                    wall_elapsed: platform.state.clock.wall_elapsed(at),
+                    paused: platform.state.clock.is_paused(),
                 }
             };
             context.sender().send(frame_requested);
@@ -357,6 +781,10 @@ fn run_event_loop(
             if let Err(e) = wait_frame_result {
                 log::info!("shutdown platform server: {}", e);
                 *control_flow = ControlFlow::Exit;
+            } else if platform.state.is_shutdown_frame() {
+                log::info!("shutdown after the final frame");
+                context.shutdown_switch().request_shutdown();
+                *control_flow = ControlFlow::Exit;
             }
         }
         Event::WindowEvent {
@@ -371,8 +799,22 @@ fn run_event_loop(
                         });
                         inputs.set_cursor_rect((*size).into());
                     }
+                    WindowEvent::ScaleFactorChanged {
+                        scale_factor,
+                        new_inner_size,
+                    } => {
+                        log::info!("display scale factor changed to: {}", scale_factor);
+                        context.sender().send(DisplayScaleChangedEvent {
+                            scale_factor: *scale_factor,
+                        });
+                        context.sender().send(DisplayResizedEvent {
+                            size: (**new_inner_size).into(),
+                        });
+                        inputs.set_cursor_rect((**new_inner_size).into());
+                    }
                     WindowEvent::CloseRequested => {
-                        context.shutdown_switch().request_shutdown();
+                        platform.state.request_shutdown();
+                        context.sender().send(ShutdownRequestedEvent);
                     }
                     WindowEvent::Destroyed => {
                         context.shutdown_switch().request_shutdown();
@@ -383,6 +825,9 @@ fn run_event_loop(
                 }
             }
         }
+        Event::DeviceEvent { ref event, .. } => {
+            inputs.push_device_event(event);
+        }
         Event::LoopDestroyed => {
             log::info!("loop destroyed");
             context.shutdown_switch().request_shutdown();
@@ -1,8 +1,12 @@
+use crate::platform::gamepad::{AxisDirection, GamepadAxis, GamepadButton};
 use crate::platform::input::{InputEvent, Inputs, MouseButton, ScrollDirection};
 use crate::platform::key::ScanCode;
-use crate::platform::message::{ActionEvent, KeyInputEvent, MouseInputEvent, ScrollInputEvent};
+use crate::platform::message::{
+    ActionEvent, GamepadAxisInputEvent, GamepadButtonInputEvent, KeyInputEvent, MouseInputEvent,
+    ScrollInputEvent,
+};
 use crate::some_or_continue;
-use crate::util::{HashMap, IndexMap};
+use crate::util::{HashMap, HashSet, IndexMap};
 use indexmap::map::Entry;
 use internment::Intern;
 use roundabout::prelude::MessageSender;
@@ -16,42 +20,199 @@ pub enum ActionTrigger {
     Mouse(MouseButton),
     Scroll(ScrollDirection),
     Key(ScanCode),
+    GamepadButton(GamepadButton),
+    /// e.g. `{"input": "gamepadAxis", "select": ["leftStickY", "positive"]}`
+    GamepadAxis(GamepadAxis, AxisDirection),
 }
 
-#[derive(Default, Debug, Clone, Deserialize)]
-#[serde(
-    from = "Vec<(ActionTrigger, Intern<String>)>",
-    into = "Vec<(ActionTrigger, Intern<String>)>"
-)]
-pub struct ActionsConfig(HashMap<ActionTrigger, Intern<String>>);
+/// shapes the analog value of an action, e.g. of a gamepad stick
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ActionAxis {
+    /// magnitudes up to the deadzone are treated as zero
+    pub deadzone: f32,
+    pub scale: f32,
+}
+
+impl Default for ActionAxis {
+    fn default() -> Self {
+        Self {
+            deadzone: 0.0,
+            scale: 1.0,
+        }
+    }
+}
+
+impl ActionAxis {
+    /// rescales the magnitude past the deadzone to start at zero, so the curve stays continuous
+    pub fn apply(&self, value: f32) -> f32 {
+        let deadzone = self.deadzone.clamp(0.0, 1.0);
+        let magnitude = value.abs();
+        if magnitude <= deadzone || deadzone >= 1.0 {
+            return 0.0;
+        }
+
+        value.signum() * (magnitude - deadzone) / (1.0 - deadzone) * self.scale
+    }
+}
+
+/// either only the bindings or the bindings with the axes and chords of the actions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum ActionsConfigRepr {
+    Bindings(Vec<(ActionTrigger, Intern<String>)>),
+    #[serde(rename_all = "camelCase")]
+    Full {
+        bindings: Vec<(ActionTrigger, Intern<String>)>,
+        #[serde(default)]
+        axes: HashMap<Intern<String>, ActionAxis>,
+        #[serde(default)]
+        chords: Vec<(Vec<ScanCode>, Intern<String>)>,
+        #[serde(default = "ActionsConfig::default_gamepad_deadzone")]
+        gamepad_deadzone: f32,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "ActionsConfigRepr", into = "ActionsConfigRepr")]
+pub struct ActionsConfig {
+    bindings: HashMap<ActionTrigger, Intern<String>>,
+    axes: HashMap<Intern<String>, ActionAxis>,
+    chords: Vec<(Vec<ScanCode>, Intern<String>)>,
+    gamepad_deadzone: f32,
+}
+
+impl Default for ActionsConfig {
+    fn default() -> Self {
+        Self {
+            bindings: Default::default(),
+            axes: Default::default(),
+            chords: Default::default(),
+            gamepad_deadzone: Self::default_gamepad_deadzone(),
+        }
+    }
+}
+
+impl ActionsConfig {
+    #[inline]
+    pub fn default_gamepad_deadzone() -> f32 {
+        0.1
+    }
+
+    /// gamepad stick and trigger values below are treated as zero,
+    /// on top of the deadzone of the gamepad mapping and before the deadzone of an `ActionAxis`
+    #[inline]
+    pub fn with_gamepad_deadzone(mut self, gamepad_deadzone: f32) -> Self {
+        self.gamepad_deadzone = gamepad_deadzone;
+        self
+    }
+
+    #[inline]
+    pub fn gamepad_deadzone(&self) -> f32 {
+        self.gamepad_deadzone
+    }
+
+    /// the action is only active while all keys are held, e.g. `[leftControl, s]`,
+    /// the key completing the chord doesn't trigger its own binding
+    #[inline]
+    pub fn with_chord(mut self, keys: &[ScanCode], action: &str) -> Self {
+        self.chords
+            .push((keys.to_vec(), Intern::new(action.to_owned())));
+        self
+    }
+
+    #[inline]
+    pub fn chords(&self) -> &[(Vec<ScanCode>, Intern<String>)] {
+        &self.chords
+    }
+
+    #[inline]
+    pub fn chords_mut(&mut self) -> &mut Vec<(Vec<ScanCode>, Intern<String>)> {
+        &mut self.chords
+    }
+
+    #[inline]
+    pub fn with_axis(mut self, action: &str, axis: ActionAxis) -> Self {
+        self.axes.insert(Intern::new(action.to_owned()), axis);
+        self
+    }
+
+    #[inline]
+    pub fn axes(&self) -> &HashMap<Intern<String>, ActionAxis> {
+        &self.axes
+    }
+
+    #[inline]
+    pub fn axes_mut(&mut self) -> &mut HashMap<Intern<String>, ActionAxis> {
+        &mut self.axes
+    }
+
+    #[inline]
+    pub fn axis(&self, action: &Intern<String>) -> ActionAxis {
+        self.axes.get(action).copied().unwrap_or_default()
+    }
+}
 
 impl Deref for ActionsConfig {
     type Target = HashMap<ActionTrigger, Intern<String>>;
 
     #[inline]
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.bindings
     }
 }
 
 impl DerefMut for ActionsConfig {
     #[inline]
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.bindings
     }
 }
 
 impl From<Vec<(ActionTrigger, Intern<String>)>> for ActionsConfig {
     #[inline]
     fn from(vectorized: Vec<(ActionTrigger, Intern<String>)>) -> Self {
-        Self(HashMap::from_iter(vectorized.into_iter()))
+        Self {
+            bindings: HashMap::from_iter(vectorized.into_iter()),
+            ..Default::default()
+        }
     }
 }
 
 impl Into<Vec<(ActionTrigger, Intern<String>)>> for ActionsConfig {
     #[inline]
     fn into(self) -> Vec<(ActionTrigger, Intern<String>)> {
-        self.0.into_iter().collect()
+        self.bindings.into_iter().collect()
+    }
+}
+
+impl From<ActionsConfigRepr> for ActionsConfig {
+    fn from(repr: ActionsConfigRepr) -> Self {
+        match repr {
+            ActionsConfigRepr::Bindings(bindings) => bindings.into(),
+            ActionsConfigRepr::Full {
+                bindings,
+                axes,
+                chords,
+                gamepad_deadzone,
+            } => Self {
+                bindings: HashMap::from_iter(bindings.into_iter()),
+                axes,
+                chords,
+                gamepad_deadzone,
+            },
+        }
+    }
+}
+
+impl From<ActionsConfig> for ActionsConfigRepr {
+    fn from(config: ActionsConfig) -> Self {
+        ActionsConfigRepr::Full {
+            bindings: config.bindings.into_iter().collect(),
+            axes: config.axes,
+            chords: config.chords,
+            gamepad_deadzone: config.gamepad_deadzone,
+        }
     }
 }
 
@@ -76,6 +237,8 @@ pub struct Actions {
     buffer: Vec<ActionEvent>,
     tick: u64,
     trigger_value_cache: Vec<(ActionTrigger, f32)>,
+    held_keys: HashSet<ScanCode>,
+    active_chords: HashSet<usize>,
 }
 
 impl Actions {
@@ -86,6 +249,11 @@ impl Actions {
         }
     }
 
+    #[inline]
+    pub(crate) fn config(&self) -> &ActionsConfig {
+        &self.config
+    }
+
     pub(crate) fn set_config(&mut self, config: ActionsConfig) {
         for (action, _) in self.current.drain(..) {
             self.buffer.push(ActionEvent {
@@ -95,9 +263,62 @@ impl Actions {
             });
         }
 
+        self.active_chords.clear();
         self.config = config;
     }
 
+    /// returns true if the key press completes or repeats an active chord
+    fn push_chord_key(&mut self, scan: ScanCode, pressed: bool) -> bool {
+        if !pressed {
+            self.held_keys.remove(&scan);
+
+            // the binding of the key still ends as it could have been started before the chord
+            for (index, (keys, action)) in self.config.chords.iter().enumerate() {
+                if keys.contains(&scan) && self.active_chords.remove(&index) {
+                    self.current.remove(action);
+                    self.buffer.push(ActionEvent {
+                        name: *action,
+                        state: ActionState::End,
+                        value: 0.0,
+                    });
+                }
+            }
+
+            return false;
+        }
+
+        self.held_keys.insert(scan);
+
+        let mut consumed = false;
+        for (index, (keys, action)) in self.config.chords.iter().enumerate() {
+            if !keys.contains(&scan) || !keys.iter().all(|key| self.held_keys.contains(key)) {
+                continue;
+            }
+
+            consumed = true;
+            if !self.active_chords.insert(index) {
+                continue;
+            }
+
+            let value = self.config.axis(action).apply(1.0);
+            if let Entry::Vacant(entry) = self.current.entry(*action) {
+                entry.insert(CurrentActionState {
+                    state: ActionState::Start,
+                    value,
+                    tick: self.tick,
+                });
+
+                self.buffer.push(ActionEvent {
+                    name: *action,
+                    state: ActionState::Start,
+                    value,
+                });
+            }
+        }
+
+        consumed
+    }
+
     pub(crate) fn push_inputs(&mut self, inputs: &Inputs) {
         // TODO:
         //  this allows multiple actions to be fired per frame, do we want this?
@@ -114,26 +335,45 @@ impl Actions {
                     self.trigger_value_cache
                         .push((ActionTrigger::Scroll(*direction), 0.0));
                 }
-                InputEvent::Key(KeyInputEvent { scan, value }) => {
+                InputEvent::Key(KeyInputEvent { scan, value, .. }) => {
+                    if self.push_chord_key(*scan, value.abs() > f32::EPSILON) {
+                        continue;
+                    }
+
                     self.trigger_value_cache
                         .push((ActionTrigger::Key(*scan), *value));
                 }
-                InputEvent::Cursor(_) => {
+                InputEvent::GamepadButton(GamepadButtonInputEvent { button, value, .. }) => {
+                    self.trigger_value_cache
+                        .push((ActionTrigger::GamepadButton(*button), *value));
+                }
+                InputEvent::GamepadAxis(GamepadAxisInputEvent { axis, value, .. }) => {
+                    for direction in [AxisDirection::Positive, AxisDirection::Negative] {
+                        self.trigger_value_cache.push((
+                            ActionTrigger::GamepadAxis(*axis, direction),
+                            direction.select(*value),
+                        ));
+                    }
+                }
+                InputEvent::Cursor(_) | InputEvent::Text(_) => {
                     continue;
                 }
             };
 
             for (trigger, value) in self.trigger_value_cache.drain(..) {
                 let action = *some_or_continue!(self.config.get(&trigger));
+                let value = self.config.axis(&action).apply(value);
 
                 let is_end = value.abs() <= f32::EPSILON;
                 if is_end {
-                    self.current.remove(&action);
-                    self.buffer.push(ActionEvent {
-                        name: action,
-                        state: ActionState::End,
-                        value: 0.0,
-                    });
+                    // axes report zero for the opposite direction of an action without a start
+                    if self.current.remove(&action).is_some() {
+                        self.buffer.push(ActionEvent {
+                            name: action,
+                            state: ActionState::End,
+                            value: 0.0,
+                        });
+                    }
 
                     continue;
                 }
@@ -0,0 +1,28 @@
+use crate::platform::message::{RequestClipboardEvent, SetClipboardEvent};
+use roundabout::prelude::MessageSender;
+
+/// access to the clipboard owned by the platform server
+#[derive(Debug, Clone)]
+pub struct Clipboard {
+    sender: MessageSender,
+}
+
+impl Clipboard {
+    #[inline]
+    pub fn new(sender: MessageSender) -> Self {
+        Self { sender }
+    }
+
+    /// the platform server answers with a `ClipboardTextEvent`
+    #[inline]
+    pub fn get_text(&self) {
+        self.sender.send(RequestClipboardEvent);
+    }
+
+    #[inline]
+    pub fn set_text(&self, text: &str) {
+        self.sender.send(SetClipboardEvent {
+            text: text.to_owned(),
+        });
+    }
+}
@@ -0,0 +1,109 @@
+use std::time::{Duration, Instant};
+
+/// simulation time that doesn't advance while paused
+#[derive(Debug, Clone, Copy)]
+pub struct SimClock {
+    start: Instant,
+    ticked: Duration,
+    paused_at: Option<Instant>,
+    paused_total: Duration,
+}
+
+impl SimClock {
+    pub fn new(start: Instant) -> Self {
+        Self {
+            start,
+            ticked: Duration::ZERO,
+            paused_at: None,
+            paused_total: Duration::ZERO,
+        }
+    }
+
+    #[inline]
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    pub fn pause(&mut self, at: Instant) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(at);
+        }
+    }
+
+    pub fn resume(&mut self, at: Instant) {
+        if let Some(paused_at) = self.paused_at.take() {
+            self.paused_total += at.saturating_duration_since(paused_at);
+        }
+    }
+
+    /// the wall clock time elapsed since the start, paused spans included
+    #[inline]
+    pub fn wall_elapsed(&self, at: Instant) -> Duration {
+        at.saturating_duration_since(self.start)
+    }
+
+    /// the sim time elapsed since the start, paused spans excluded
+    pub fn elapsed(&self, at: Instant) -> Duration {
+        let at = self.paused_at.unwrap_or(at);
+        at.saturating_duration_since(self.start)
+            .saturating_sub(self.paused_total)
+    }
+
+    /// advances the clock to the new frame and returns the elapsed and delta sim time
+    pub fn tick(&mut self, at: Instant) -> (Duration, Duration) {
+        let elapsed = self.elapsed(at);
+        let delta = elapsed.saturating_sub(self.ticked);
+        self.ticked = elapsed;
+
+        (elapsed, delta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn tick() {
+        let start = Instant::now();
+        let mut clock = SimClock::new(start);
+
+        assert_eq!(clock.tick(start + ms(10)), (ms(10), ms(10)));
+        assert_eq!(clock.tick(start + ms(25)), (ms(25), ms(15)));
+        assert_eq!(clock.tick(start + ms(25)), (ms(25), ms(0)));
+    }
+
+    #[test]
+    fn pause() {
+        let start = Instant::now();
+        let mut clock = SimClock::new(start);
+
+        clock.tick(start + ms(10));
+        clock.pause(start + ms(15));
+        assert!(clock.is_paused());
+        assert_eq!(clock.tick(start + ms(20)), (ms(15), ms(5)));
+        assert_eq!(clock.tick(start + ms(40)), (ms(15), ms(0)));
+        assert_eq!(clock.wall_elapsed(start + ms(40)), ms(40));
+    }
+
+    #[test]
+    fn resume() {
+        let start = Instant::now();
+        let mut clock = SimClock::new(start);
+
+        clock.pause(start + ms(10));
+        // pausing again doesn't move the pause start
+        clock.pause(start + ms(20));
+        clock.resume(start + ms(30));
+        assert!(!clock.is_paused());
+        assert_eq!(clock.tick(start + ms(40)), (ms(20), ms(20)));
+
+        // resuming a running clock is a no-op
+        clock.resume(start + ms(50));
+        assert_eq!(clock.tick(start + ms(60)), (ms(40), ms(20)));
+    }
+}
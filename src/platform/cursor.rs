@@ -0,0 +1,337 @@
+use crate::asset::AssetPath;
+use crate::util::HashMap;
+use serde::Deserialize;
+use std::ops::{Deref, DerefMut};
+use std::time::{Duration, Instant};
+use winit::window::Window;
+
+/// system cursor icons, images are drawn as a `CursorImage` instead
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CursorIcon {
+    Default,
+    Crosshair,
+    Hand,
+    Arrow,
+    Move,
+    Text,
+    Wait,
+    Help,
+    Progress,
+    NotAllowed,
+    ContextMenu,
+    Cell,
+    VerticalText,
+    Alias,
+    Copy,
+    NoDrop,
+    Grab,
+    Grabbing,
+    AllScroll,
+    ZoomIn,
+    ZoomOut,
+    EResize,
+    NResize,
+    NeResize,
+    NwResize,
+    SResize,
+    SeResize,
+    SwResize,
+    WResize,
+    EwResize,
+    NsResize,
+    NeswResize,
+    NwseResize,
+    ColResize,
+    RowResize,
+}
+
+impl Default for CursorIcon {
+    fn default() -> Self {
+        CursorIcon::Default
+    }
+}
+
+impl From<CursorIcon> for winit::window::CursorIcon {
+    fn from(icon: CursorIcon) -> Self {
+        use winit::window::CursorIcon as W;
+
+        match icon {
+            CursorIcon::Default => W::Default,
+            CursorIcon::Crosshair => W::Crosshair,
+            CursorIcon::Hand => W::Hand,
+            CursorIcon::Arrow => W::Arrow,
+            CursorIcon::Move => W::Move,
+            CursorIcon::Text => W::Text,
+            CursorIcon::Wait => W::Wait,
+            CursorIcon::Help => W::Help,
+            CursorIcon::Progress => W::Progress,
+            CursorIcon::NotAllowed => W::NotAllowed,
+            CursorIcon::ContextMenu => W::ContextMenu,
+            CursorIcon::Cell => W::Cell,
+            CursorIcon::VerticalText => W::VerticalText,
+            CursorIcon::Alias => W::Alias,
+            CursorIcon::Copy => W::Copy,
+            CursorIcon::NoDrop => W::NoDrop,
+            CursorIcon::Grab => W::Grab,
+            CursorIcon::Grabbing => W::Grabbing,
+            CursorIcon::AllScroll => W::AllScroll,
+            CursorIcon::ZoomIn => W::ZoomIn,
+            CursorIcon::ZoomOut => W::ZoomOut,
+            CursorIcon::EResize => W::EResize,
+            CursorIcon::NResize => W::NResize,
+            CursorIcon::NeResize => W::NeResize,
+            CursorIcon::NwResize => W::NwResize,
+            CursorIcon::SResize => W::SResize,
+            CursorIcon::SeResize => W::SeResize,
+            CursorIcon::SwResize => W::SwResize,
+            CursorIcon::WResize => W::WResize,
+            CursorIcon::EwResize => W::EwResize,
+            CursorIcon::NsResize => W::NsResize,
+            CursorIcon::NeswResize => W::NeswResize,
+            CursorIcon::NwseResize => W::NwseResize,
+            CursorIcon::ColResize => W::ColResize,
+            CursorIcon::RowResize => W::RowResize,
+        }
+    }
+}
+
+/// confinement of the cursor to the window
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CursorGrab {
+    None,
+    /// the cursor can't leave the window
+    Confined,
+    /// the cursor is hidden and confined, only the relative mouse motion is meaningful
+    Locked,
+}
+
+impl Default for CursorGrab {
+    fn default() -> Self {
+        CursorGrab::None
+    }
+}
+
+/// the platform only supports system cursor icons, so the system cursor is hidden
+/// and the render server draws the texture at the cursor position instead
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CursorImage {
+    pub texture: AssetPath,
+    /// in pixels
+    pub size: [f32; 2],
+    /// point of the image at the cursor position, in pixels from the top left corner
+    #[serde(default)]
+    pub hotspot: [f32; 2],
+}
+
+/// e.g. `"hand"` or `{"texture": "cursor/hand.json", "size": [32, 32], "hotspot": [8, 0]}`
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum CursorFrame {
+    Icon(CursorIcon),
+    Image(CursorImage),
+}
+
+impl From<CursorIcon> for CursorFrame {
+    fn from(icon: CursorIcon) -> Self {
+        CursorFrame::Icon(icon)
+    }
+}
+
+impl From<CursorImage> for CursorFrame {
+    fn from(image: CursorImage) -> Self {
+        CursorFrame::Image(image)
+    }
+}
+
+/// a cursor cycling through its frames, a single frame is a static cursor
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CursorStyle {
+    pub frames: Vec<CursorFrame>,
+    /// seconds per frame
+    #[serde(default)]
+    pub frame_duration: f32,
+}
+
+impl From<CursorIcon> for CursorStyle {
+    fn from(icon: CursorIcon) -> Self {
+        Self {
+            frames: vec![icon.into()],
+            frame_duration: 0.0,
+        }
+    }
+}
+
+impl From<CursorImage> for CursorStyle {
+    fn from(image: CursorImage) -> Self {
+        Self {
+            frames: vec![image.into()],
+            frame_duration: 0.0,
+        }
+    }
+}
+
+/// named cursors that can be switched between with a `SetCursorEvent`
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct CursorTheme(HashMap<String, CursorStyle>);
+
+impl CursorTheme {
+    #[inline]
+    pub fn with_cursor<S: Into<CursorStyle>>(mut self, name: &str, style: S) -> Self {
+        self.0.insert(name.to_owned(), style.into());
+        self
+    }
+}
+
+impl Deref for CursorTheme {
+    type Target = HashMap<String, CursorStyle>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for CursorTheme {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+pub(crate) struct Cursors {
+    theme: CursorTheme,
+    name: Option<String>,
+    frame: usize,
+    frame_start: Instant,
+    icon: CursorIcon,
+    image: Option<CursorImage>,
+    fixed_icon: Option<CursorIcon>,
+}
+
+impl Cursors {
+    pub fn new(theme: CursorTheme) -> Self {
+        Self {
+            theme,
+            name: None,
+            frame: 0,
+            frame_start: Instant::now(),
+            icon: CursorIcon::Default,
+            image: None,
+            fixed_icon: None,
+        }
+    }
+
+    pub fn set_theme(&mut self, theme: CursorTheme, window: &Window) {
+        self.theme = theme;
+        self.frame = 0;
+        self.apply(window);
+    }
+
+    /// unknown names fall back to the default system cursor
+    pub fn set_cursor(&mut self, name: Option<String>, window: &Window) {
+        if self.name == name && self.fixed_icon.is_none() {
+            return;
+        }
+
+        self.name = name;
+        self.fixed_icon = None;
+        self.frame = 0;
+        self.frame_start = Instant::now();
+        self.apply(window);
+    }
+
+    /// overrides the theme until the next named cursor is set
+    pub fn set_icon(&mut self, icon: CursorIcon, window: &Window) {
+        self.name = None;
+        self.frame = 0;
+        self.fixed_icon = Some(icon);
+        self.apply(window);
+    }
+
+    /// advances the frame of an animated cursor
+    pub fn update(&mut self, now: Instant, window: &Window) {
+        let (frame_count, frame_duration) = match self.style() {
+            Some(style) if style.frames.len() > 1 && style.frame_duration > 0.0 => {
+                (style.frames.len(), style.frame_duration)
+            }
+            _ => return,
+        };
+
+        let elapsed = now
+            .saturating_duration_since(self.frame_start)
+            .as_secs_f32();
+        let steps = (elapsed / frame_duration) as usize;
+        if steps == 0 {
+            return;
+        }
+
+        self.frame = (self.frame + steps) % frame_count;
+        self.frame_start += Duration::from_secs_f32(steps as f32 * frame_duration);
+        self.apply(window);
+    }
+
+    /// image drawn in place of the hidden system cursor
+    #[inline]
+    pub fn image(&self) -> Option<CursorImage> {
+        self.image
+    }
+
+    /// textures of the image frames of the current cursor
+    pub fn textures(&self) -> Vec<AssetPath> {
+        let style = match (self.fixed_icon, self.style()) {
+            (None, Some(style)) => style,
+            _ => return Vec::new(),
+        };
+
+        style
+            .frames
+            .iter()
+            .filter_map(|frame| match frame {
+                CursorFrame::Image(image) => Some(image.texture),
+                CursorFrame::Icon(_) => None,
+            })
+            .collect()
+    }
+
+    fn style(&self) -> Option<&CursorStyle> {
+        self.name.as_ref().and_then(|name| self.theme.get(name))
+    }
+
+    fn apply(&mut self, window: &Window) {
+        let frame = match (self.fixed_icon, self.style()) {
+            (Some(icon), _) => icon.into(),
+            (None, Some(style)) => style
+                .frames
+                .get(self.frame % style.frames.len().max(1))
+                .copied()
+                .unwrap_or_else(|| CursorIcon::Default.into()),
+            (None, None) => {
+                if let Some(name) = &self.name {
+                    log::warn!(
+                        "cursor {} is missing, fall back to the default cursor",
+                        name
+                    );
+                }
+                CursorIcon::Default.into()
+            }
+        };
+
+        let icon = match frame {
+            CursorFrame::Icon(icon) => {
+                self.image = None;
+                icon
+            }
+            CursorFrame::Image(image) => {
+                self.image = Some(image);
+                return;
+            }
+        };
+
+        if icon != self.icon {
+            self.icon = icon;
+            window.set_cursor_icon(icon.into());
+        }
+    }
+}
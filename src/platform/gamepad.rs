@@ -0,0 +1,188 @@
+use crate::platform::action::ActionsConfig;
+use crate::platform::input::{InputEvent, Inputs};
+use crate::platform::message::{GamepadAxisInputEvent, GamepadButtonInputEvent};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    C,
+    Z,
+    LeftTrigger,
+    LeftTrigger2,
+    RightTrigger,
+    RightTrigger2,
+    Select,
+    Start,
+    Mode,
+    LeftThumb,
+    RightThumb,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+impl GamepadButton {
+    fn from_gilrs(button: gilrs::Button) -> Option<Self> {
+        let button = match button {
+            gilrs::Button::South => GamepadButton::South,
+            gilrs::Button::East => GamepadButton::East,
+            gilrs::Button::North => GamepadButton::North,
+            gilrs::Button::West => GamepadButton::West,
+            gilrs::Button::C => GamepadButton::C,
+            gilrs::Button::Z => GamepadButton::Z,
+            gilrs::Button::LeftTrigger => GamepadButton::LeftTrigger,
+            gilrs::Button::LeftTrigger2 => GamepadButton::LeftTrigger2,
+            gilrs::Button::RightTrigger => GamepadButton::RightTrigger,
+            gilrs::Button::RightTrigger2 => GamepadButton::RightTrigger2,
+            gilrs::Button::Select => GamepadButton::Select,
+            gilrs::Button::Start => GamepadButton::Start,
+            gilrs::Button::Mode => GamepadButton::Mode,
+            gilrs::Button::LeftThumb => GamepadButton::LeftThumb,
+            gilrs::Button::RightThumb => GamepadButton::RightThumb,
+            gilrs::Button::DPadUp => GamepadButton::DPadUp,
+            gilrs::Button::DPadDown => GamepadButton::DPadDown,
+            gilrs::Button::DPadLeft => GamepadButton::DPadLeft,
+            gilrs::Button::DPadRight => GamepadButton::DPadRight,
+            gilrs::Button::Unknown => return None,
+        };
+
+        Some(button)
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    LeftZ,
+    RightStickX,
+    RightStickY,
+    RightZ,
+    DPadX,
+    DPadY,
+}
+
+impl GamepadAxis {
+    fn from_gilrs(axis: gilrs::Axis) -> Option<Self> {
+        let axis = match axis {
+            gilrs::Axis::LeftStickX => GamepadAxis::LeftStickX,
+            gilrs::Axis::LeftStickY => GamepadAxis::LeftStickY,
+            gilrs::Axis::LeftZ => GamepadAxis::LeftZ,
+            gilrs::Axis::RightStickX => GamepadAxis::RightStickX,
+            gilrs::Axis::RightStickY => GamepadAxis::RightStickY,
+            gilrs::Axis::RightZ => GamepadAxis::RightZ,
+            gilrs::Axis::DPadX => GamepadAxis::DPadX,
+            gilrs::Axis::DPadY => GamepadAxis::DPadY,
+            gilrs::Axis::Unknown => return None,
+        };
+
+        Some(axis)
+    }
+}
+
+/// half of an axis an action can be bound to, e.g. the up direction of the left stick
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AxisDirection {
+    Positive,
+    Negative,
+}
+
+impl AxisDirection {
+    /// the magnitude of the axis value in this direction
+    #[inline]
+    pub fn select(self, value: f32) -> f32 {
+        match self {
+            AxisDirection::Positive => value.max(0.0),
+            AxisDirection::Negative => (-value).max(0.0),
+        }
+    }
+}
+
+pub(crate) struct Gamepads {
+    gilrs: Option<gilrs::Gilrs>,
+    deadzone: f32,
+}
+
+impl Gamepads {
+    pub(crate) fn new() -> Self {
+        let gilrs = match gilrs::Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(e) => {
+                log::warn!("gamepads not available: {}", e);
+                None
+            }
+        };
+
+        Self {
+            gilrs,
+            deadzone: ActionsConfig::default_gamepad_deadzone(),
+        }
+    }
+
+    /// values below are treated as zero, see `ActionsConfig::with_gamepad_deadzone`
+    #[inline]
+    pub(crate) fn set_deadzone(&mut self, deadzone: f32) {
+        self.deadzone = deadzone;
+    }
+
+    #[inline]
+    fn apply_deadzone(value: f32, deadzone: f32) -> f32 {
+        if value.abs() < deadzone {
+            0.0
+        } else {
+            value
+        }
+    }
+
+    /// drains the pending gamepad events into the input queue
+    pub(crate) fn push_events(&mut self, inputs: &mut Inputs) {
+        let deadzone = self.deadzone;
+        let gilrs = match self.gilrs.as_mut() {
+            Some(gilrs) => gilrs,
+            None => return,
+        };
+
+        while let Some(gilrs::Event { id, event, .. }) = gilrs.next_event() {
+            let gamepad = usize::from(id);
+            match event {
+                gilrs::EventType::ButtonChanged(button, value, _) => {
+                    if let Some(button) = GamepadButton::from_gilrs(button) {
+                        inputs.push_input(InputEvent::GamepadButton(GamepadButtonInputEvent {
+                            gamepad,
+                            button,
+                            value: Self::apply_deadzone(value, deadzone),
+                        }));
+                    }
+                }
+                gilrs::EventType::AxisChanged(axis, value, _) => {
+                    if let Some(axis) = GamepadAxis::from_gilrs(axis) {
+                        inputs.push_input(InputEvent::GamepadAxis(GamepadAxisInputEvent {
+                            gamepad,
+                            axis,
+                            value: Self::apply_deadzone(value, deadzone),
+                        }));
+                    }
+                }
+                gilrs::EventType::Connected => {
+                    log::info!(
+                        "gamepad {} connected: {}",
+                        gamepad,
+                        gilrs.gamepad(id).name()
+                    );
+                }
+                gilrs::EventType::Disconnected => {
+                    log::info!("gamepad {} disconnected", gamepad);
+                }
+                _ => {}
+            }
+        }
+    }
+}
@@ -1,12 +1,15 @@
 use crate::platform::key::ScanCode;
 use crate::platform::message::{
-    CursorInputEvent, KeyInputEvent, MouseInputEvent, PointerInputEvent, ScrollInputEvent,
+    CursorInputEvent, GamepadAxisInputEvent, GamepadButtonInputEvent, KeyInputEvent,
+    MouseInputEvent, MouseMotionEvent, PointerInputEvent, ScrollInputEvent, TextInputEvent,
 };
 use crate::prelude::{Camera, MessageSender};
-use crate::util::Bounded;
+use crate::util::{Bounded, HashMap};
 use nalgebra::{Point2, Vector2};
 use serde::{Deserialize, Serialize};
-use std::ops::Deref;
+use std::iter::FromIterator;
+use std::ops::{Deref, DerefMut};
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -32,30 +35,147 @@ pub enum PointerKind {
     Tertiary,
 }
 
+#[derive(Debug, Default, Hash, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub logo: bool,
+}
+
+impl Modifiers {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        !(self.shift || self.ctrl || self.alt || self.logo)
+    }
+}
+
+impl From<winit::event::ModifiersState> for Modifiers {
+    fn from(state: winit::event::ModifiersState) -> Self {
+        Self {
+            shift: state.shift(),
+            ctrl: state.ctrl(),
+            alt: state.alt(),
+            logo: state.logo(),
+        }
+    }
+}
+
+/// hardware scancodes that take precedence over the built-in platform map
+#[derive(Default, Debug, Clone, Deserialize)]
+#[serde(from = "Vec<(u32, ScanCode)>", into = "Vec<(u32, ScanCode)>")]
+pub struct ScanCodesConfig(HashMap<u32, ScanCode>);
+
+impl Deref for ScanCodesConfig {
+    type Target = HashMap<u32, ScanCode>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for ScanCodesConfig {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl From<Vec<(u32, ScanCode)>> for ScanCodesConfig {
+    #[inline]
+    fn from(vectorized: Vec<(u32, ScanCode)>) -> Self {
+        Self(HashMap::from_iter(vectorized.into_iter()))
+    }
+}
+
+impl Into<Vec<(u32, ScanCode)>> for ScanCodesConfig {
+    #[inline]
+    fn into(self) -> Vec<(u32, ScanCode)> {
+        self.0.into_iter().collect()
+    }
+}
+
+/// counts consecutive presses of a pointer for double and triple clicks
+#[derive(Debug, Default)]
+struct PointerClicks {
+    last: Option<(PointerKind, Instant, Option<Vector2<f64>>)>,
+    count: u32,
+}
+
+impl PointerClicks {
+    const TIMEOUT: Duration = Duration::from_millis(500);
+    /// in physical pixels
+    const DISTANCE: f64 = 4.0;
+
+    fn press(&mut self, kind: PointerKind, position: Option<Vector2<f64>>, at: Instant) -> u32 {
+        let consecutive = match self.last {
+            Some((last_kind, last_at, last_position)) => {
+                let near = match (last_position, position) {
+                    (Some(last), Some(curr)) => (curr - last).norm() <= Self::DISTANCE,
+                    (None, None) => true,
+                    _ => false,
+                };
+                last_kind == kind && at.saturating_duration_since(last_at) <= Self::TIMEOUT && near
+            }
+            None => false,
+        };
+
+        self.count = if consecutive { self.count + 1 } else { 1 };
+        self.last = Some((kind, at, position));
+        self.count
+    }
+
+    /// releases report the count of their press
+    fn release(&self, kind: PointerKind) -> u32 {
+        match self.last {
+            Some((last_kind, ..)) if last_kind == kind => self.count,
+            _ => 1,
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub(crate) enum InputEvent {
     Mouse(MouseInputEvent),
     Scroll(ScrollInputEvent),
     Key(KeyInputEvent),
     Cursor(CursorInputEvent),
+    Text(TextInputEvent),
+    GamepadButton(GamepadButtonInputEvent),
+    GamepadAxis(GamepadAxisInputEvent),
 }
 
 pub struct Inputs {
     buffer: Vec<InputEvent>,
+    scan_codes: ScanCodesConfig,
+    modifiers: Modifiers,
     cursor: Cursor,
     raw_cursor: Option<Vector2<f64>>,
     cursor_left: bool,
     cursor_rect: [u32; 2],
+    motion: Vector2<f64>,
+    focused: bool,
+    clicks: PointerClicks,
 }
 
 impl Inputs {
+    /// converts pixel scroll deltas, e.g. of trackpads, into the lines of a mouse wheel
+    const PIXELS_PER_LINE: f64 = 20.0;
+
     pub(crate) fn new(cursor_rect: [u32; 2]) -> Self {
         Self {
             buffer: Default::default(),
+            scan_codes: Default::default(),
+            modifiers: Default::default(),
             cursor: Cursor::empty(cursor_rect),
             raw_cursor: None,
             cursor_left: false,
             cursor_rect,
+            motion: Vector2::zeros(),
+            focused: true,
+            clicks: Default::default(),
         }
     }
 
@@ -64,34 +184,77 @@ impl Inputs {
         self.cursor = self.cursor.with_cursor_rect(cursor_rect);
     }
 
+    pub(crate) fn set_scan_codes(&mut self, scan_codes: ScanCodesConfig) {
+        self.scan_codes = scan_codes;
+    }
+
     pub(crate) fn queued_events(&self) -> &[InputEvent] {
         &self.buffer
     }
 
+    #[inline]
+    pub(crate) fn push_input(&mut self, input: InputEvent) {
+        self.buffer.push(input);
+    }
+
+    /// device events are also received while the window isn't focused
+    pub(crate) fn push_device_event(&mut self, event: &winit::event::DeviceEvent) {
+        if let winit::event::DeviceEvent::MouseMotion { delta: (x, y) } = event {
+            if self.focused {
+                self.motion += Vector2::new(*x, *y);
+            }
+        }
+    }
+
+    #[inline]
+    fn push_scroll(&mut self, direction: ScrollDirection, value: f32) {
+        self.buffer
+            .push(InputEvent::Scroll(ScrollInputEvent { direction, value }));
+    }
+
     pub(crate) fn push_event(&mut self, event: &winit::event::WindowEvent) {
         match event {
             winit::event::WindowEvent::DroppedFile(_) => {}
             winit::event::WindowEvent::HoveredFile(_) => {}
             winit::event::WindowEvent::HoveredFileCancelled => {}
-            winit::event::WindowEvent::ReceivedCharacter(_) => {}
-            winit::event::WindowEvent::Focused(_) => {}
+            winit::event::WindowEvent::ReceivedCharacter(character) => {
+                if !character.is_control() {
+                    self.buffer.push(InputEvent::Text(TextInputEvent {
+                        character: *character,
+                    }));
+                }
+            }
+            winit::event::WindowEvent::Focused(focused) => {
+                self.focused = *focused;
+            }
             winit::event::WindowEvent::KeyboardInput { input, .. } => {
-                // TODO: this is completely broken (e.g. arrows), fix with https://github.com/rust-windowing/winit/issues/1806
-                match ScanCode::from_windows(input.scancode as u8) {
+                // the virtual keycode is only used for keys that don't depend on the layout
+                let scan = self
+                    .scan_codes
+                    .get(&input.scancode)
+                    .copied()
+                    .or_else(|| input.virtual_keycode.and_then(ScanCode::from_virtual))
+                    .or_else(|| ScanCode::from_winit(input.scancode));
+                match scan {
                     Some(scan) => {
                         let value = match input.state {
                             winit::event::ElementState::Pressed => 1.0,
                             winit::event::ElementState::Released => 0.0,
                         };
-                        self.buffer
-                            .push(InputEvent::Key(KeyInputEvent { scan, value }));
+                        self.buffer.push(InputEvent::Key(KeyInputEvent {
+                            scan,
+                            value,
+                            modifiers: self.modifiers,
+                        }));
                     }
                     None => {
                         log::debug!("ignoring unknown scancode of: {}", input.scancode);
                     }
                 };
             }
-            winit::event::WindowEvent::ModifiersChanged(_) => {}
+            winit::event::WindowEvent::ModifiersChanged(state) => {
+                self.modifiers = (*state).into();
+            }
             winit::event::WindowEvent::CursorMoved { position, .. } => {
                 // we can't push a cursor input here as we don't have access
                 // to a possible updated size for the raw position transformation
@@ -106,19 +269,23 @@ impl Inputs {
             winit::event::WindowEvent::MouseWheel { delta, .. } => match delta {
                 winit::event::MouseScrollDelta::LineDelta(h, v) => {
                     if h.abs() > f32::EPSILON {
-                        self.buffer.push(InputEvent::Scroll(ScrollInputEvent {
-                            direction: ScrollDirection::Horizontal,
-                            value: *h,
-                        }));
+                        self.push_scroll(ScrollDirection::Horizontal, *h);
                     }
                     if v.abs() > f32::EPSILON {
-                        self.buffer.push(InputEvent::Scroll(ScrollInputEvent {
-                            direction: ScrollDirection::Vertical,
-                            value: *v,
-                        }));
+                        self.push_scroll(ScrollDirection::Vertical, *v);
+                    }
+                }
+                winit::event::MouseScrollDelta::PixelDelta(delta) => {
+                    // trackpads report many small deltas, which must not be filtered as noise
+                    let h = (delta.x / Self::PIXELS_PER_LINE) as f32;
+                    let v = (delta.y / Self::PIXELS_PER_LINE) as f32;
+                    if h != 0.0 {
+                        self.push_scroll(ScrollDirection::Horizontal, h);
+                    }
+                    if v != 0.0 {
+                        self.push_scroll(ScrollDirection::Vertical, v);
                     }
                 }
-                winit::event::MouseScrollDelta::PixelDelta(_) => {}
             },
             winit::event::WindowEvent::MouseInput { state, button, .. } => {
                 let button = match button {
@@ -155,6 +322,11 @@ impl Inputs {
             }));
         }
 
+        if self.motion != Vector2::zeros() {
+            sender.send(MouseMotionEvent { delta: self.motion });
+            self.motion = Vector2::zeros();
+        }
+
         for input in self.buffer.drain(..) {
             match input {
                 InputEvent::Mouse(event) => {
@@ -169,11 +341,19 @@ impl Inputs {
                         }
                     };
 
+                    let clicks = if event.value.abs() > f32::EPSILON {
+                        let position = self.cursor.raw_transform();
+                        self.clicks.press(pointer_kind, position, Instant::now())
+                    } else {
+                        self.clicks.release(pointer_kind)
+                    };
+
                     sender.send(PointerInputEvent {
                         id: 0,
                         kind: pointer_kind,
                         cursor: self.cursor,
                         value: event.value,
+                        clicks,
                     });
                 }
                 InputEvent::Scroll(event) => {
@@ -185,6 +365,15 @@ impl Inputs {
                 InputEvent::Cursor(event) => {
                     sender.send(event);
                 }
+                InputEvent::Text(event) => {
+                    sender.send(event);
+                }
+                InputEvent::GamepadButton(event) => {
+                    sender.send(event);
+                }
+                InputEvent::GamepadAxis(event) => {
+                    sender.send(event);
+                }
             }
         }
     }
@@ -169,6 +169,52 @@ impl ScanCode {
         }
     }
 
+    /// Convert a hardware scancode as reported by winit on the current platform.
+    ///
+    /// winit reports evdev codes on linux, which are offset by 8 to the X11 keycodes of the map.
+    pub fn from_winit(hardware_scancode: u32) -> Option<ScanCode> {
+        #[cfg(target_os = "linux")]
+        let hardware_scancode = hardware_scancode + 8;
+
+        u8::try_from(hardware_scancode)
+            .ok()
+            .and_then(ScanCode::from_platform)
+    }
+
+    /// Convert the layout independent keys of a virtual keycode.
+    ///
+    /// Windows shares the scancodes of the arrow and navigation keys with the keypad
+    /// and of the left and right modifiers, which are only distinguishable by the virtual keycode.
+    pub fn from_virtual(virtual_keycode: winit::event::VirtualKeyCode) -> Option<ScanCode> {
+        use winit::event::VirtualKeyCode as V;
+
+        let scan = match virtual_keycode {
+            V::Up => ScanCode::Up,
+            V::Down => ScanCode::Down,
+            V::Left => ScanCode::Left,
+            V::Right => ScanCode::Right,
+            V::Home => ScanCode::Home,
+            V::End => ScanCode::End,
+            V::PageUp => ScanCode::PageUp,
+            V::PageDown => ScanCode::PageDown,
+            V::Insert => ScanCode::Insert,
+            V::Delete => ScanCode::Delete,
+            V::LControl => ScanCode::LeftControl,
+            V::RControl => ScanCode::RightControl,
+            V::LShift => ScanCode::LeftShift,
+            V::RShift => ScanCode::RightShift,
+            V::LAlt => ScanCode::LeftAlt,
+            V::RAlt => ScanCode::RightAlt,
+            V::LWin => ScanCode::LeftGui,
+            V::RWin => ScanCode::RightGui,
+            V::NumpadEnter => ScanCode::PadEnter,
+            V::NumpadDivide => ScanCode::PadDivide,
+            _ => return None,
+        };
+
+        Some(scan)
+    }
+
     pub fn from_windows(hardware_scancode: u8) -> Option<ScanCode> {
         if (hardware_scancode as usize) < scancode_windows::MAP.len() {
             scancode_windows::MAP[hardware_scancode as usize]
@@ -583,3 +629,86 @@ mod scancode_windows {
         Some(Menu),
     ];
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::event::VirtualKeyCode;
+
+    #[test]
+    fn windows_scancodes() {
+        assert_eq!(ScanCode::from_windows(0x01), Some(ScanCode::Escape));
+        assert_eq!(ScanCode::from_windows(0x02), Some(ScanCode::Num1));
+        assert_eq!(ScanCode::from_windows(0x10), Some(ScanCode::Q));
+        assert_eq!(ScanCode::from_windows(0x1c), Some(ScanCode::Enter));
+        assert_eq!(ScanCode::from_windows(0x1d), Some(ScanCode::LeftControl));
+        assert_eq!(ScanCode::from_windows(0x1e), Some(ScanCode::A));
+        assert_eq!(ScanCode::from_windows(0x2a), Some(ScanCode::LeftShift));
+        assert_eq!(ScanCode::from_windows(0x36), Some(ScanCode::RightShift));
+        assert_eq!(ScanCode::from_windows(0x39), Some(ScanCode::Space));
+        assert_eq!(ScanCode::from_windows(0x3b), Some(ScanCode::F1));
+        assert_eq!(ScanCode::from_windows(0x00), None);
+        assert_eq!(ScanCode::from_windows(0xff), None);
+    }
+
+    #[test]
+    fn linux_scancodes() {
+        // X11 keycodes, the evdev codes offset by 8
+        assert_eq!(ScanCode::from_linux(9), Some(ScanCode::Escape));
+        assert_eq!(ScanCode::from_linux(10), Some(ScanCode::Num1));
+        assert_eq!(ScanCode::from_linux(24), Some(ScanCode::Q));
+        assert_eq!(ScanCode::from_linux(36), Some(ScanCode::Enter));
+        assert_eq!(ScanCode::from_linux(37), Some(ScanCode::LeftControl));
+        assert_eq!(ScanCode::from_linux(38), Some(ScanCode::A));
+        assert_eq!(ScanCode::from_linux(50), Some(ScanCode::LeftShift));
+        assert_eq!(ScanCode::from_linux(62), Some(ScanCode::RightShift));
+        assert_eq!(ScanCode::from_linux(65), Some(ScanCode::Space));
+        assert_eq!(ScanCode::from_linux(67), Some(ScanCode::F1));
+        assert_eq!(ScanCode::from_linux(0), None);
+        assert_eq!(ScanCode::from_linux(0xff), None);
+    }
+
+    #[test]
+    fn macos_scancodes() {
+        assert_eq!(ScanCode::from_macos(0x00), Some(ScanCode::A));
+        assert_eq!(ScanCode::from_macos(0x01), Some(ScanCode::S));
+        assert_eq!(ScanCode::from_macos(0x1d), Some(ScanCode::Num0));
+        assert_eq!(ScanCode::from_macos(0x24), Some(ScanCode::Enter));
+        assert_eq!(ScanCode::from_macos(0x30), Some(ScanCode::Tab));
+        assert_eq!(ScanCode::from_macos(0x31), Some(ScanCode::Space));
+        assert_eq!(ScanCode::from_macos(0x33), Some(ScanCode::Backspace));
+        assert_eq!(ScanCode::from_macos(0x35), Some(ScanCode::Escape));
+        assert_eq!(ScanCode::from_macos(0x38), Some(ScanCode::LeftShift));
+        assert_eq!(ScanCode::from_macos(0xff), None);
+    }
+
+    #[test]
+    fn winit_scancodes() {
+        #[cfg(target_os = "windows")]
+        assert_eq!(ScanCode::from_winit(0x1e), Some(ScanCode::A));
+        #[cfg(target_os = "linux")]
+        assert_eq!(ScanCode::from_winit(30), Some(ScanCode::A));
+        #[cfg(target_os = "macos")]
+        assert_eq!(ScanCode::from_winit(0x00), Some(ScanCode::A));
+
+        assert_eq!(ScanCode::from_winit(1000), None);
+    }
+
+    #[test]
+    fn virtual_keycodes() {
+        assert_eq!(
+            ScanCode::from_virtual(VirtualKeyCode::Up),
+            Some(ScanCode::Up)
+        );
+        assert_eq!(
+            ScanCode::from_virtual(VirtualKeyCode::RControl),
+            Some(ScanCode::RightControl)
+        );
+        assert_eq!(
+            ScanCode::from_virtual(VirtualKeyCode::NumpadEnter),
+            Some(ScanCode::PadEnter)
+        );
+        // layout dependent keys are left to the scancode tables
+        assert_eq!(ScanCode::from_virtual(VirtualKeyCode::A), None);
+    }
+}
@@ -1,18 +1,38 @@
+use crate::asset::AssetPath;
 use crate::platform::action::ActionState;
-use crate::platform::input::{Cursor, MouseButton, PointerKind, ScrollDirection};
+use crate::platform::cursor::{CursorGrab, CursorIcon, CursorImage};
+use crate::platform::gamepad::{GamepadAxis, GamepadButton};
+use crate::platform::input::{Cursor, Modifiers, MouseButton, PointerKind, ScrollDirection};
 use crate::platform::key::ScanCode;
+use crate::platform::MonitorInfo;
+use crate::render::stats::StatsOverlayConfig;
+use crate::render::{DepthFormat, PowerPreference, PresentMode, Samples};
 use internment::Intern;
+use nalgebra::Vector2;
 use parking_lot::Mutex;
 use std::time::{Duration, Instant};
 
 pub struct DisplayCreatedEvent {
     pub window_size: [u32; 2],
+    /// physical pixels per logical pixel, e.g. 2.0 on retina displays
+    pub scale_factor: f64,
+    pub samples: Samples,
+    pub depth_format: DepthFormat,
+    pub present_mode: PresentMode,
+    pub power_preference: PowerPreference,
+    pub stats_overlay: StatsOverlayConfig,
     pub render_resources: Mutex<Option<DisplayRenderResources>>,
 }
 
 impl DisplayCreatedEvent {
     pub fn new(
         window_size: [u32; 2],
+        scale_factor: f64,
+        samples: Samples,
+        depth_format: DepthFormat,
+        present_mode: PresentMode,
+        power_preference: PowerPreference,
+        stats_overlay: StatsOverlayConfig,
         instance: wgpu::Instance,
         window_surface: wgpu::Surface,
     ) -> Self {
@@ -23,6 +43,12 @@ impl DisplayCreatedEvent {
 
         Self {
             window_size,
+            scale_factor,
+            samples,
+            depth_format,
+            present_mode,
+            power_preference,
+            stats_overlay,
             render_resources,
         }
     }
@@ -37,8 +63,84 @@ pub struct DisplayRenderResources {
 pub struct FrameRequestedEvent {
     pub frame: u64,
     pub at: Instant,
+    /// sim time since the start, it doesn't advance while the sim is paused
     pub elapsed: Duration,
+    /// sim time since the last frame, zero while the sim is paused
     pub delta: Duration,
+    /// wall clock time since the start, it keeps advancing while the sim is paused
+    pub wall_elapsed: Duration,
+    pub paused: bool,
+}
+
+/// switches to the named cursor of the cursor theme, `None` restores the default cursor
+#[derive(Debug, Clone)]
+pub struct SetCursorEvent {
+    pub name: Option<String>,
+}
+
+impl SetCursorEvent {
+    #[inline]
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: Some(name.to_owned()),
+        }
+    }
+
+    #[inline]
+    pub fn reset() -> Self {
+        Self { name: None }
+    }
+}
+
+/// switches to the system cursor icon regardless of the cursor theme,
+/// until the next `SetCursorEvent`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SetCursorIconEvent {
+    pub icon: CursorIcon,
+}
+
+/// hides the system cursor while it is over the window, e.g. for a custom rendered cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SetCursorVisibleEvent {
+    pub visible: bool,
+}
+
+/// broadcast when a cursor image replaces the system cursor, drawn by the render server,
+/// `None` once a system cursor is shown again or the cursor is hidden
+#[derive(Debug, Clone, PartialEq)]
+pub struct CursorImageEvent {
+    pub image: Option<CursorImage>,
+    /// the textures of all frames of the current cursor, the ones of replaced cursors are dropped
+    pub textures: Vec<AssetPath>,
+}
+
+/// broadcast when the window is closed or a shutdown is requested, e.g. to write a save file,
+/// the runtime shuts down after the next frame has been drawn and simulated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShutdownRequestedEvent;
+
+/// freezes the sim time of the frame events, frames are still requested and drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SetSimPausedEvent {
+    pub paused: bool,
+}
+
+/// not all platforms support confining the cursor, in which case the grab is ignored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GrabCursorEvent {
+    pub mode: CursorGrab,
+}
+
+/// requests a `DisplaysEvent` from the platform server
+#[derive(Debug, Clone, Copy)]
+pub struct QueryDisplaysEvent;
+
+/// available monitors in the order of the `monitor` index of the `DisplayConfig`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisplaysEvent {
+    pub monitors: Vec<MonitorInfo>,
+    /// index of the monitor the window is on
+    pub current: Option<usize>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -56,12 +158,25 @@ pub struct DisplayResizedEvent {
     pub size: [u32; 2],
 }
 
+/// sent if the window moved to a display with a different scale factor,
+/// e.g. to rescale the point size of texts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DisplayScaleChangedEvent {
+    pub scale_factor: f64,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct MouseInputEvent {
     pub button: MouseButton,
     pub value: f32,
 }
 
+/// raw mouse movement since the last frame, independent of the cursor position and window size
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MouseMotionEvent {
+    pub delta: Vector2<f64>,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct ScrollInputEvent {
     pub direction: ScrollDirection,
@@ -79,6 +194,8 @@ pub struct PointerInputEvent {
     pub kind: PointerKind,
     pub cursor: Cursor,
     pub value: f32,
+    /// consecutive presses at the same position, e.g. 2 for a double click
+    pub clicks: u32,
 }
 
 impl PointerInputEvent {
@@ -96,6 +213,11 @@ impl PointerInputEvent {
         self.kind == kind && self.value.abs() <= f32::EPSILON
     }
 
+    #[inline]
+    pub fn double_clicked(&self, kind: PointerKind) -> bool {
+        self.started(kind) && self.clicks == 2
+    }
+
     #[inline]
     pub fn started(&self, kind: PointerKind) -> bool {
         // We currently only send started / ended events as we only support mouse
@@ -107,6 +229,44 @@ impl PointerInputEvent {
 pub struct KeyInputEvent {
     pub scan: ScanCode,
     pub value: f32,
+    pub modifiers: Modifiers,
+}
+
+/// requests a `ClipboardTextEvent` from the platform server
+#[derive(Debug, Copy, Clone)]
+pub struct RequestClipboardEvent;
+
+/// `None` if the clipboard is empty or doesn't contain utf8 text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipboardTextEvent {
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetClipboardEvent {
+    pub text: String,
+}
+
+/// typed character, control characters like backspace are only reported as `KeyInputEvent`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TextInputEvent {
+    pub character: char,
+}
+
+/// `gamepad` identifies the gamepad as long as it is connected
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GamepadButtonInputEvent {
+    pub gamepad: usize,
+    pub button: GamepadButton,
+    pub value: f32,
+}
+
+/// the value is in the range of -1.0 to 1.0, up and right are positive
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GamepadAxisInputEvent {
+    pub gamepad: usize,
+    pub axis: GamepadAxis,
+    pub value: f32,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -1,40 +1,82 @@
 // TODO: rework public access
 pub use crate::asset::loader::{
-    AssetCursor, AssetLoader, LoadedAssetTable, StrongAssetTable, WeakAssetTable,
+    inline_asset, AssetCursor, AssetFilter, AssetLoader, LoadedAssetTable, LoadedAssetTree,
+    StrongAssetTable, StrongAssetTree, WeakAssetTable, WeakAssetTree,
 };
-pub use crate::asset::storage::{Assets, AssetsClient, AssetsPaths};
+#[cfg(feature = "embed")]
+pub use crate::asset::source::EmbeddedAssetSource;
+#[cfg(feature = "http")]
+pub use crate::asset::source::HttpAssetSource;
+#[cfg(feature = "archive")]
+pub use crate::asset::source::ZipAssetSource;
+pub use crate::asset::source::{AssetSource, FsAssetSource, MemoryAssetSource, MountAssetSource};
+pub use crate::asset::storage::{AssetTransaction, Assets, AssetsClient, AssetsPaths, GcStrategy};
 pub use crate::asset::{
-    AssetEvent, AssetEventKind, AssetId, AssetPath, AssetPathKind, AssetPathParam, AssetServer,
-    AssetUri, AssetsCreatedEvent, DynAssetId, LoadAssetEvent, Loaded, LoadedAssetId, Strong,
-    StrongAssetId, Weak, WeakAssetId,
+    AssetDeletedEvent, AssetEvent, AssetEventKind, AssetId, AssetLoadFailedEvent, AssetPath,
+    AssetPathKind, AssetPathParam, AssetReloadedEvent, AssetServer, AssetUri, AssetsCreatedEvent,
+    DynAssetId, LoadAssetEvent, LoadBarrier, LoadBarrierCompleteEvent, LoadProgressEvent, Loaded,
+    LoadedAssetId, SaveResultEvent, Strong, StrongAssetId, Weak, WeakAssetId,
+};
+pub use crate::dormant::{DormantServer, DormantServerEvent, DormantServerEventKind};
+pub use crate::platform::action::{ActionAxis, ActionState, ActionTrigger, ActionsConfig};
+pub use crate::platform::clipboard::Clipboard;
+pub use crate::platform::cursor::{
+    CursorFrame, CursorGrab, CursorIcon, CursorImage, CursorStyle, CursorTheme,
+};
+pub use crate::platform::gamepad::{AxisDirection, GamepadAxis, GamepadButton};
+pub use crate::platform::input::{
+    Cursor, Modifiers, MouseButton, PointerKind, ScanCodesConfig, ScrollDirection, WorldCursor,
 };
-pub use crate::platform::action::{ActionState, ActionTrigger, ActionsConfig};
-pub use crate::platform::input::{Cursor, MouseButton, PointerKind, ScrollDirection, WorldCursor};
 pub use crate::platform::key::ScanCode;
 pub use crate::platform::message::{
-    ActionEvent, CursorInputEvent, DisplayCreatedEvent, DisplayRenderResources,
-    DisplayResizedEvent, FrameRequestedEvent, KeyInputEvent, MouseInputEvent, PointerInputEvent,
-    ResumedEvent, ScrollInputEvent, SuspendedEvent,
+    ActionEvent, ClipboardTextEvent, CursorImageEvent, CursorInputEvent, DisplayCreatedEvent,
+    DisplayRenderResources, DisplayResizedEvent, DisplayScaleChangedEvent, DisplaysEvent,
+    FrameRequestedEvent, GamepadAxisInputEvent, GamepadButtonInputEvent, GrabCursorEvent,
+    KeyInputEvent, MouseInputEvent, MouseMotionEvent, PointerInputEvent, QueryDisplaysEvent,
+    ResumedEvent, ScrollInputEvent, SetCursorEvent, SetCursorIconEvent, SetCursorVisibleEvent,
+    SetSimPausedEvent, ShutdownRequestedEvent, SuspendedEvent, TextInputEvent,
 };
-pub use crate::platform::{DisplayConfig, PlatformServer};
-pub use crate::render::canvas::CanvasFrame;
+pub use crate::platform::{DisplayConfig, Fullscreen, MonitorInfo, PlatformServer, VideoModeInfo};
+pub use crate::render::camera::{CameraScaleMode, CameraSnapshot};
+pub use crate::render::canvas::{CanvasFrame, CanvasSnapshot, ClipRect, LayerSnapshot, Viewport};
 pub use crate::render::client::{
-    Camera, Canvas, CanvasBuilder, CanvasLayer, Curve, CurveBuilder, CurveModify, Instance,
-    InstanceBuilder, InstanceModify, LayerSpawner, RawRectangle, RawSprite, Rectangle,
-    RectangleBuilder, RectangleModify, RenderClient, Sprite, SpriteBuilder, SpriteModify, Text,
-    TextBuilder, TextModify,
-};
-pub use crate::render::curve::{LineCap, LineJoin, Path, PathBuilder, RawCurve, StrokeOptions};
-pub use crate::render::message::DrawnEvent;
-pub use crate::render::pipeline::{Pipeline, PipelineBuilder};
-pub use crate::render::text::{Font, HorizontalAlignment, RawText, VerticalAlignment};
-pub use crate::render::view::{FilterMode, Texture};
-pub use crate::render::RenderServer;
+    Camera, Canvas, CanvasBuilder, CanvasLayer, Curve, CurveBuilder, CurveModify, Group,
+    GroupBuilder, GroupModify, Instance, InstanceBuilder, InstanceModify, LayerSpawner, RawGroup,
+    RawRectangle, RawSprite, Rectangle, RectangleBorder, RectangleBuilder, RectangleModify,
+    RenderClient, Sprite, SpriteAnimation, SpriteBuilder, SpriteModify, Text, TextBuilder,
+    TextModify,
+};
+pub use crate::render::curve::{
+    LineCap, LineJoin, Path, PathBuilder, RawCurve, StrokeOptions, WorldOrScreen,
+};
+pub use crate::render::message::{
+    CapturedFrameEvent, DebugSceneEvent, DrawnEvent, PickedEvent, SetColorGradingEvent,
+    SetPresentModeEvent, ShaderCompileFailedEvent, TextLaidOutEvent, UpdateTextureRegionEvent,
+};
+pub use crate::render::pipeline::{
+    InstanceScale, Pipeline, PipelineBuilder, StencilConfig, StencilMode,
+};
+pub use crate::render::post::PostEffect;
+pub use crate::render::stats::StatsOverlayConfig;
+pub use crate::render::text::{
+    Font, HorizontalAlignment, LineHeight, RawText, TextBackground, TextOutline, TextOverflow,
+    TextShadow, VerticalAlignment,
+};
+pub use crate::render::view::{
+    AddressMode, CompressedFormat, CompressedImage, FilterMode, Texture,
+};
+pub use crate::render::{
+    Backends, DepthFormat, PowerPreference, PresentMode, RenderServer, Samples,
+};
+pub use crate::sim::console::{Console, ConsoleCommand, ConsoleCommandEvent};
+pub use crate::sim::harness::{ScriptedState, StateRecorder};
+pub use crate::sim::input::InputSnapshot;
+pub use crate::sim::stack::{StateStack, StateStackContext};
 pub use crate::sim::{
-    ClosedSimHandlerBuilder, InitSimHandlerBuilder, OpenSimHandlerBuilder, SimHandler,
-    SimResources, SimServer, SimState, SimStateEvent, SimulatedEvent, StateInstruction,
+    ClosedSimHandlerBuilder, FixedUpdateEvent, InitSimHandlerBuilder, OpenSimHandlerBuilder,
+    SimHandler, SimResources, SimServer, SimState, SimStateEvent, SimulatedEvent, StateInstruction,
 };
-pub use crate::time::TimeServer;
+pub use crate::time::{TimeServer, TimerClock, TimerHandle};
 pub use crate::util::{Bounded, Bounds};
-pub use crate::{Engine, InitEvent};
+pub use crate::{Engine, InitEvent, ShutdownHandle};
 pub use roundabout::prelude::*;
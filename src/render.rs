@@ -1,50 +1,195 @@
 pub mod buffer;
 pub mod camera;
 pub mod canvas;
+pub mod capture;
 pub mod client;
+pub mod cursor;
 pub mod curve;
+pub mod grading;
 pub mod mesh;
 pub mod message;
 pub mod pipeline;
+pub mod post;
+pub mod stats;
+pub mod svg;
 pub mod text;
 pub mod view;
 
 use crate::asset::storage::Assets;
-use crate::asset::{AssetEvent, AssetEventKind, AssetsCreatedEvent};
-use crate::platform::message::{DisplayCreatedEvent, DisplayResizedEvent, FrameRequestedEvent};
+use crate::asset::{AssetEvent, AssetEventKind, AssetsCreatedEvent, WeakAssetId};
+use crate::platform::message::{
+    ActionEvent, CursorImageEvent, CursorInputEvent, DisplayCreatedEvent, DisplayResizedEvent,
+    FrameRequestedEvent,
+};
 use crate::prelude::AssetsClient;
 use crate::render::camera::Cameras;
 use crate::render::canvas::Canvasses;
-use crate::render::client::RenderDefaults;
+use crate::render::client::{CanvasLayer, RenderDefaults};
+use crate::render::cursor::CursorOverlay;
 use crate::render::curve::Curves;
+use crate::render::grading::ColorGrading;
 use crate::render::mesh::{Mesh, Meshes};
 use crate::render::message::{
     CameraEvent, CameraEventKind, CanvasEvent, CanvasEventKind, CanvasLayerEvent,
-    CanvasLayerEventKind, CurveEvent, CurveEventKind, DrawnEvent, InstanceEvent, InstanceEventKind,
-    RenderCreatedEvent, TextEvent, TextEventKind,
+    CanvasLayerEventKind, CaptureFrameEvent, CapturedFrameEvent, CurveEvent, CurveEventKind,
+    DebugSceneEvent, DebugSceneRequestEvent, DrawnEvent, GroupEvent, GroupEventKind, InstanceEvent,
+    InstanceEventKind, PickRequestEvent, PickedEvent, RenderCreatedEvent, SetColorGradingEvent,
+    SetPresentModeEvent, ShaderCompileFailedEvent, TextEvent, TextEventKind,
+    UpdateTextureRegionEvent,
 };
-use crate::render::pipeline::{Pipeline, Pipelines, WGSLSource};
-use crate::render::text::{Font, FontLayout, Texts};
-use crate::render::view::{RealizedView, Texture, Textures};
+use crate::render::pipeline::{validated, Pipeline, Pipelines, WGSLSource};
+use crate::render::stats::{StatsOverlay, StatsOverlayConfig};
+use crate::render::text::{Font, FontLayout, TextInstances, Texts};
+use crate::render::view::{CompressedImage, RealizedView, Texture, Textures};
+use crate::some_or_return;
 use image::DynamicImage;
 use roundabout::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::ops::Deref;
+use std::sync::Arc;
+use uuid::Uuid;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum Samples {
-    // WGPU currently only supports 1 and 4 samples.
-    // We currently always create multi-sampled textures and therefore only support the singe multi sampling mode of 4.
+    // Multi-sampled textures are always created, therefore a single sample isn't supported.
     // One = 1,
+    Two = 2,
     Four = 4,
+    Eight = 8,
+}
+
+impl Samples {
+    pub const ALL: [Samples; 3] = [Samples::Two, Samples::Four, Samples::Eight];
+}
+
+impl Default for Samples {
+    fn default() -> Self {
+        Samples::Four
+    }
 }
 
 impl From<Samples> for u32 {
     fn from(samples: Samples) -> Self {
         match samples {
             // Samples::One => 1,
+            Samples::Two => 2,
             Samples::Four => 4,
+            Samples::Eight => 8,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DepthFormat {
+    Depth32Float,
+    Depth24Plus,
+    Depth24PlusStencil8,
+}
+
+impl DepthFormat {
+    #[inline]
+    pub fn has_stencil(self) -> bool {
+        matches!(self, DepthFormat::Depth24PlusStencil8)
+    }
+}
+
+/// unsupported present modes fall back to fifo, which is vsync and supported by all adapters
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PresentMode {
+    Fifo,
+    Mailbox,
+    Immediate,
+}
+
+impl Default for PresentMode {
+    fn default() -> Self {
+        PresentMode::Fifo
+    }
+}
+
+impl From<PresentMode> for wgpu::PresentMode {
+    fn from(present_mode: PresentMode) -> Self {
+        match present_mode {
+            PresentMode::Fifo => wgpu::PresentMode::Fifo,
+            PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+            PresentMode::Immediate => wgpu::PresentMode::Immediate,
+        }
+    }
+}
+
+impl Default for DepthFormat {
+    fn default() -> Self {
+        DepthFormat::Depth32Float
+    }
+}
+
+/// graphics apis the adapter is requested from, e.g. to pin one while debugging driver bugs
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Backends {
+    /// vulkan, metal, dx12 and webgpu
+    Primary,
+    /// gl and dx11
+    Secondary,
+    Vulkan,
+    Metal,
+    Dx12,
+    Dx11,
+    Gl,
+}
+
+impl Default for Backends {
+    fn default() -> Self {
+        Backends::Primary
+    }
+}
+
+impl From<Backends> for wgpu::Backends {
+    fn from(backends: Backends) -> Self {
+        match backends {
+            Backends::Primary => wgpu::Backends::PRIMARY,
+            Backends::Secondary => wgpu::Backends::SECONDARY,
+            Backends::Vulkan => wgpu::Backends::VULKAN,
+            Backends::Metal => wgpu::Backends::METAL,
+            Backends::Dx12 => wgpu::Backends::DX12,
+            Backends::Dx11 => wgpu::Backends::DX11,
+            Backends::Gl => wgpu::Backends::GL,
+        }
+    }
+}
+
+/// picks between e.g. the integrated and the dedicated gpu of laptops
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PowerPreference {
+    LowPower,
+    HighPerformance,
+}
+
+impl Default for PowerPreference {
+    fn default() -> Self {
+        PowerPreference::HighPerformance
+    }
+}
+
+impl From<PowerPreference> for wgpu::PowerPreference {
+    fn from(power_preference: PowerPreference) -> Self {
+        match power_preference {
+            PowerPreference::LowPower => wgpu::PowerPreference::LowPower,
+            PowerPreference::HighPerformance => wgpu::PowerPreference::HighPerformance,
+        }
+    }
+}
+
+impl From<DepthFormat> for wgpu::TextureFormat {
+    fn from(depth_format: DepthFormat) -> Self {
+        match depth_format {
+            DepthFormat::Depth32Float => wgpu::TextureFormat::Depth32Float,
+            DepthFormat::Depth24Plus => wgpu::TextureFormat::Depth24Plus,
+            DepthFormat::Depth24PlusStencil8 => wgpu::TextureFormat::Depth24PlusStencil8,
         }
     }
 }
@@ -63,6 +208,7 @@ pub struct Renderer {
     canvasses: Canvasses,
     texts: Texts,
     curves: Curves,
+    grading: ColorGrading,
 }
 
 impl Renderer {
@@ -73,20 +219,34 @@ impl Renderer {
         instance: wgpu::Instance,
         surface: wgpu::Surface,
         samples: Samples,
+        depth_format: DepthFormat,
+        present_mode: PresentMode,
+        power_preference: PowerPreference,
     ) -> anyhow::Result<Self> {
         let adapter = instance
             .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
+                power_preference: power_preference.into(),
                 force_fallback_adapter: false,
                 compatible_surface: Some(&surface),
             })
             .await
             .ok_or_else(|| anyhow::anyhow!("Missing gpu adapter"))?;
 
+        let adapter_info = adapter.get_info();
+        log::info!(
+            "render with adapter {} ({:?}, {:?})",
+            adapter_info.name,
+            adapter_info.backend,
+            adapter_info.device_type
+        );
+
+        let compression = adapter.features() & Textures::COMPRESSION_FEATURES;
+        log::info!("supported texture compression: {:?}", compression);
+
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
-                    features: wgpu::Features::empty(),
+                    features: compression,
                     limits: wgpu::Limits::default(),
                     label: None,
                 },
@@ -94,12 +254,35 @@ impl Renderer {
             )
             .await?;
 
+        let anisotropic_filtering = adapter
+            .get_downlevel_properties()
+            .flags
+            .contains(wgpu::DownlevelFlags::ANISOTROPIC_FILTERING);
+        if !anisotropic_filtering {
+            log::info!("anisotropic filtering isn't supported by the adapter");
+        }
+
+        // some drivers fail to configure surfaces with formats they don't prefer
+        let frame_format = surface
+            .get_preferred_format(&adapter)
+            .unwrap_or(RealizedView::FRAME_TEXTURE_FORMAT);
+        log::info!("render into {:?} frames", frame_format);
+
+        let supported_samples = Self::supported_samples(&device, frame_format, depth_format);
+        let samples = if supported_samples.contains(&samples) {
+            samples
+        } else {
+            log::warn!("{:?} samples not supported, fall back to four", samples);
+            Samples::Four
+        };
+        log::info!("render with {} samples", u32::from(samples));
+
         let surface_configuration = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: RealizedView::FRAME_TEXTURE_FORMAT,
+            format: frame_format,
             width: size[0],
             height: size[1],
-            present_mode: wgpu::PresentMode::Mailbox,
+            present_mode: present_mode.into(),
         };
         surface.configure(&device, &surface_configuration);
 
@@ -151,17 +334,41 @@ impl Renderer {
                 push_constant_ranges: &[],
             });
 
-        let pipelines = Pipelines::new(&assets, render_pipeline_layout, samples);
-        let textures = Textures::new(&assets, diffuse_bind_group_layout);
+        let pipelines = Pipelines::new(
+            &assets,
+            render_pipeline_layout,
+            samples,
+            frame_format,
+            depth_format,
+        );
+        let textures = Textures::new(
+            &assets,
+            diffuse_bind_group_layout,
+            anisotropic_filtering,
+            compression,
+            frame_format,
+        );
         let meshes = Meshes::new(&assets);
         let cameras = Cameras::default();
-        let canvasses = Canvasses::new(uniform_bind_group_layout, size, samples);
-        let texts = Texts::new(&assets)?;
+        let canvasses = Canvasses::new(
+            &assets,
+            &device,
+            uniform_bind_group_layout,
+            size,
+            samples,
+            frame_format,
+            depth_format,
+        );
+        let texts = Texts::new(&assets, &pipelines, &textures)?;
         let curves = Curves::new(textures.white_texture.clone());
+        let grading = ColorGrading::new(&device, frame_format);
 
-        let render_defaults = RenderDefaults::new(&pipelines, &textures, &meshes, &texts)?;
+        let render_defaults =
+            RenderDefaults::new(&pipelines, &textures, &meshes, &texts, &canvasses)?;
         sender.send(RenderCreatedEvent {
             defaults: Box::new(render_defaults),
+            samples,
+            supported_samples,
         });
 
         Ok(Renderer {
@@ -178,16 +385,84 @@ impl Renderer {
             canvasses,
             texts,
             curves,
+            grading,
         })
     }
 
+    /// the samples the frame and depth buffers can be created with,
+    /// 4 samples are supported by all adapters
+    fn supported_samples(
+        device: &wgpu::Device,
+        frame_format: wgpu::TextureFormat,
+        depth_format: DepthFormat,
+    ) -> Vec<Samples> {
+        let mut supported = Vec::with_capacity(Samples::ALL.len());
+
+        for samples in Samples::ALL {
+            if samples == Samples::Four {
+                supported.push(samples);
+                continue;
+            }
+
+            let probe = validated(device, || {
+                for format in [frame_format, depth_format.into()] {
+                    device.create_texture(&wgpu::TextureDescriptor {
+                        label: Some("samples_probe"),
+                        size: wgpu::Extent3d {
+                            width: 1,
+                            height: 1,
+                            depth_or_array_layers: 1,
+                        },
+                        mip_level_count: 1,
+                        sample_count: samples.into(),
+                        dimension: wgpu::TextureDimension::D2,
+                        format,
+                        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                    });
+                }
+            });
+
+            match probe {
+                Ok(()) => supported.push(samples),
+                Err(e) => log::debug!("{:?} samples not supported: {}", samples, e),
+            }
+        }
+
+        supported
+    }
+
     pub fn resize(&mut self, size: [u32; 2]) {
         self.size = size;
         self.surface_configuration.width = size[0];
         self.surface_configuration.height = size[1];
         self.surface
             .configure(&self.device, &self.surface_configuration);
-        self.canvasses.resize(&self.device, self.size);
+        self.canvasses
+            .resize(&self.device, &mut self.textures, self.size);
+    }
+
+    pub fn set_present_mode(&mut self, present_mode: PresentMode) {
+        let present_mode = present_mode.into();
+        if self.surface_configuration.present_mode != present_mode {
+            log::info!("set present mode: {:?}", present_mode);
+            self.surface_configuration.present_mode = present_mode;
+            self.surface
+                .configure(&self.device, &self.surface_configuration);
+        }
+    }
+
+    /// draws the current scene into an offscreen frame and reads it back as rgba image
+    pub fn capture_frame(&mut self) -> anyhow::Result<DynamicImage> {
+        self.canvasses.capture(
+            &self.device,
+            &self.queue,
+            &self.cameras,
+            &self.pipelines,
+            &self.textures,
+            &self.meshes,
+            &self.grading,
+            self.size,
+        )
     }
 
     pub fn render(&mut self) -> anyhow::Result<()> {
@@ -199,6 +474,7 @@ impl Renderer {
             &self.pipelines,
             &self.textures,
             &self.meshes,
+            &self.grading,
         )
     }
 }
@@ -207,6 +483,11 @@ impl Renderer {
 pub struct RenderServer {
     assets: Option<Assets>,
     renderer: Option<Renderer>,
+    stats_config: Option<StatsOverlayConfig>,
+    stats: Option<StatsOverlay>,
+    cursor: CursorOverlay,
+    /// drawn on top of the last frame canvas, dropped after the stats and cursor instances
+    overlay: Option<CanvasLayer>,
 }
 
 impl RenderServer {
@@ -217,20 +498,32 @@ impl RenderServer {
         handler
             .on(on_assets_created_event)
             .on(on_display_created_event)
+            .on(on_render_created_event)
             .on(on_display_resized_event)
             .on(on_camera_event)
             .on(on_canvas_layer_event)
             .on(on_canvas_event)
             .on(on_wgsl_source_asset_event)
             .on(on_image_asset_event)
+            .on(on_compressed_image_asset_event)
             .on(on_texture_asset_event)
             .on(on_pipeline_asset_event)
             .on(on_mesh_asset_event)
             .on(on_instance_event)
+            .on(on_group_event)
             .on(on_font_layout_asset_event)
             .on(on_font_asset_event)
             .on(on_text_event)
             .on(on_curve_event)
+            .on(on_set_color_grading_event)
+            .on(on_update_texture_region_event)
+            .on(on_set_present_mode_event)
+            .on(on_debug_scene_request_event)
+            .on(on_pick_request_event)
+            .on(on_capture_frame_event)
+            .on(on_action_event)
+            .on(on_cursor_image_event)
+            .on(on_cursor_input_event)
             .on(on_frame_requested_event)
             .init_default()
     }
@@ -270,11 +563,62 @@ fn on_display_created_event(
         event.window_size,
         render_resources.instance,
         render_resources.window_surface,
-        Samples::Four,
+        event.samples,
+        event.depth_format,
+        event.present_mode,
+        event.power_preference,
     ))
     .expect("renderer creation");
 
     state.renderer = Some(renderer);
+    state.stats_config = Some(event.stats_overlay.clone());
+}
+
+fn on_render_created_event(
+    state: &mut RenderServer,
+    context: &mut RuntimeContext,
+    event: &RenderCreatedEvent,
+) {
+    let renderer = some_or_return!(state.renderer.as_mut());
+    let render = event.render_client(context.sender().to_owned());
+    let overlay = render.layer();
+    renderer.canvasses.set_overlay(Some(overlay.id()));
+
+    if let Some(config) = state.stats_config.take() {
+        if config.is_enabled() {
+            state.stats = Some(StatsOverlay::new(config, &overlay));
+        }
+    }
+    state.overlay = Some(overlay);
+}
+
+fn on_action_event(state: &mut RenderServer, _context: &mut RuntimeContext, event: &ActionEvent) {
+    if let Some(stats) = &mut state.stats {
+        stats.on_action(event);
+    }
+}
+
+fn on_cursor_image_event(
+    state: &mut RenderServer,
+    _context: &mut RuntimeContext,
+    event: &CursorImageEvent,
+) {
+    let overlay = some_or_return!(state.overlay.as_ref());
+    let assets = some_or_return!(state.assets.as_mut()).client();
+    state
+        .cursor
+        .set_image(event.image, &event.textures, overlay, &assets);
+}
+
+fn on_cursor_input_event(
+    state: &mut RenderServer,
+    _context: &mut RuntimeContext,
+    event: &CursorInputEvent,
+) {
+    let overlay = some_or_return!(state.overlay.as_ref());
+    state
+        .cursor
+        .set_position(event.cursor.raw_transform(), overlay);
 }
 
 fn on_display_resized_event(
@@ -322,13 +666,29 @@ fn on_canvas_layer_event(
                 .canvasses
                 .insert_canvas_layer(&renderer.device, event.id);
         }
+        CanvasLayerEventKind::Opacity(opacity) => {
+            renderer
+                .canvasses
+                .update_canvas_layer_opacity(&event.id, opacity);
+        }
+        CanvasLayerEventKind::Tint(tint) => {
+            renderer.canvasses.update_canvas_layer_tint(&event.id, tint);
+        }
+        CanvasLayerEventKind::Culling(culling) => {
+            renderer
+                .canvasses
+                .update_canvas_layer_culling(&event.id, culling);
+        }
+        CanvasLayerEventKind::Clip(clip) => {
+            renderer.canvasses.update_canvas_layer_clip(&event.id, clip);
+        }
         CanvasLayerEventKind::Dropped => {
             renderer.canvasses.remove_canvas_layer(&event.id);
         }
     };
 }
 
-fn on_canvas_event(state: &mut RenderServer, _context: &mut RuntimeContext, event: &CanvasEvent) {
+fn on_canvas_event(state: &mut RenderServer, context: &mut RuntimeContext, event: &CanvasEvent) {
     let renderer = state
         .renderer
         .as_mut()
@@ -340,27 +700,152 @@ fn on_canvas_event(state: &mut RenderServer, _context: &mut RuntimeContext, even
                 .lock()
                 .take()
                 .expect("canvas created on canvas creation");
+
+            // shaders loaded before the canvas referenced them
+            let assets = state.assets.as_mut().unwrap().client();
+            for effect in &created.post_effects {
+                if renderer.canvasses.has_post_shader(&effect.shader) {
+                    continue;
+                }
+                if let Some(source) = assets.try_get(&effect.shader) {
+                    if let Err(e) = renderer.canvasses.upsert_post_shader(
+                        &renderer.device,
+                        effect.shader,
+                        source,
+                    ) {
+                        shader_compile_failed(context, effect.shader, e);
+                    }
+                }
+            }
+
             renderer.canvasses.upsert_canvas(
                 &renderer.device,
                 &mut renderer.cameras,
+                &mut renderer.textures,
                 event.id,
                 created.size,
                 created.priority,
                 created.frame,
+                created.depth,
                 created.frames,
+                created.post_effects,
             );
         }
         CanvasEventKind::Dropped => {
-            renderer
-                .canvasses
-                .remove_canvas(&mut renderer.cameras, &event.id);
+            renderer.canvasses.remove_canvas(
+                &mut renderer.cameras,
+                &mut renderer.textures,
+                &event.id,
+            );
         }
     };
 }
 
-fn on_wgsl_source_asset_event(
+fn on_set_color_grading_event(
+    state: &mut RenderServer,
+    _context: &mut RuntimeContext,
+    event: &SetColorGradingEvent,
+) {
+    let renderer = state
+        .renderer
+        .as_mut()
+        .expect("render to be available before color grading");
+
+    renderer
+        .grading
+        .set(&renderer.queue, event.multiply, event.add);
+}
+
+fn on_update_texture_region_event(
+    state: &mut RenderServer,
+    _context: &mut RuntimeContext,
+    event: &UpdateTextureRegionEvent,
+) {
+    let renderer = state
+        .renderer
+        .as_mut()
+        .expect("render to be available before texture region update");
+
+    if let Err(e) = renderer.textures.update_region(
+        &renderer.queue,
+        &event.texture,
+        event.layer,
+        event.origin,
+        event.size,
+        &event.rgba,
+    ) {
+        log::warn!("ignore region update of texture {:?}: {}", event.texture, e);
+    }
+}
+
+fn on_set_present_mode_event(
     state: &mut RenderServer,
     _context: &mut RuntimeContext,
+    event: &SetPresentModeEvent,
+) {
+    let renderer = state
+        .renderer
+        .as_mut()
+        .expect("render to be available before present mode");
+
+    renderer.set_present_mode(event.present_mode);
+}
+
+fn on_debug_scene_request_event(
+    state: &mut RenderServer,
+    context: &mut RuntimeContext,
+    _event: &DebugSceneRequestEvent,
+) {
+    let renderer = some_or_return!(state.renderer.as_ref());
+
+    context.sender().send(DebugSceneEvent {
+        cameras: renderer.cameras.snapshot(),
+        layers: renderer.canvasses.layer_snapshot(),
+        canvasses: renderer.canvasses.canvas_snapshot(),
+    });
+}
+
+fn on_pick_request_event(
+    state: &mut RenderServer,
+    context: &mut RuntimeContext,
+    event: &PickRequestEvent,
+) {
+    let renderer = some_or_return!(state.renderer.as_ref());
+
+    context.sender().send(PickedEvent {
+        layer: event.layer,
+        world_point: event.world_point,
+        instance: renderer.canvasses.pick(
+            &event.layer,
+            &renderer.pipelines,
+            &renderer.meshes,
+            event.world_point,
+        ),
+    });
+}
+
+fn on_capture_frame_event(
+    state: &mut RenderServer,
+    context: &mut RuntimeContext,
+    _event: &CaptureFrameEvent,
+) {
+    let renderer = some_or_return!(state.renderer.as_mut());
+
+    match renderer.capture_frame() {
+        Ok(image) => {
+            context.sender().send(CapturedFrameEvent {
+                image: Arc::new(image),
+            });
+        }
+        Err(e) => {
+            log::error!("failed to capture frame: {}", e);
+        }
+    }
+}
+
+fn on_wgsl_source_asset_event(
+    state: &mut RenderServer,
+    context: &mut RuntimeContext,
     event: &AssetEvent<WGSLSource>,
 ) {
     let renderer = state
@@ -372,18 +857,38 @@ fn on_wgsl_source_asset_event(
         AssetEventKind::Load => {
             let assets = state.assets.as_mut().unwrap().client();
             if let Some(source) = assets.try_get(&event.id) {
-                renderer
+                if let Err(e) = renderer
                     .pipelines
-                    .upsert_shader(&renderer.device, event.id, source);
+                    .upsert_shader(&renderer.device, event.id, source)
+                {
+                    shader_compile_failed(context, event.id, e);
+                    return;
+                }
+
+                if renderer.canvasses.uses_post_shader(&event.id) {
+                    if let Err(e) =
+                        renderer
+                            .canvasses
+                            .upsert_post_shader(&renderer.device, event.id, source)
+                    {
+                        shader_compile_failed(context, event.id, e);
+                    }
+                }
 
                 for pipeline_id in renderer.pipelines.pipelines_for_shader(event.id) {
                     if let Some(pipeline) = assets.try_get(&pipeline_id) {
-                        if !renderer.pipelines.upsert_pipeline(
+                        match renderer.pipelines.upsert_pipeline(
                             &renderer.device,
                             pipeline_id,
                             pipeline,
                         ) {
-                            renderer.pipelines.queue_pipeline(pipeline_id, pipeline);
+                            Ok(true) => {}
+                            Ok(false) => renderer.pipelines.queue_pipeline(pipeline_id, pipeline),
+                            Err(e) => {
+                                // retried on the next reload of its shaders
+                                renderer.pipelines.queue_pipeline(pipeline_id, pipeline);
+                                shader_compile_failed(context, event.id, e);
+                            }
                         }
                     }
                 }
@@ -391,13 +896,26 @@ fn on_wgsl_source_asset_event(
         }
         AssetEventKind::Unload => {
             renderer.pipelines.remove_shader(&event.id);
+            renderer.canvasses.remove_post_shader(&event.id);
         }
     };
 }
 
+fn shader_compile_failed(
+    context: &mut RuntimeContext,
+    id: WeakAssetId<WGSLSource>,
+    error: anyhow::Error,
+) {
+    log::error!("failed to compile shader {:?}: {}", id, error);
+    context.sender().send(ShaderCompileFailedEvent {
+        id,
+        message: error.to_string(),
+    });
+}
+
 fn on_pipeline_asset_event(
     state: &mut RenderServer,
-    _context: &mut RuntimeContext,
+    context: &mut RuntimeContext,
     event: &AssetEvent<Pipeline>,
 ) {
     let renderer = state
@@ -412,11 +930,17 @@ fn on_pipeline_asset_event(
                 renderer
                     .canvasses
                     .update_pipeline_priority(&event.id, pipeline.priority);
-                if !renderer
+                match renderer
                     .pipelines
                     .upsert_pipeline(&renderer.device, event.id, pipeline)
                 {
-                    renderer.pipelines.queue_pipeline(event.id, pipeline);
+                    Ok(true) => {}
+                    Ok(false) => renderer.pipelines.queue_pipeline(event.id, pipeline),
+                    Err(e) => {
+                        renderer.pipelines.queue_pipeline(event.id, pipeline);
+                        // reported for the vertex shader, usually the fragment shader as well
+                        shader_compile_failed(context, pipeline.vs_source.to_weak(), e);
+                    }
                 }
             }
         }
@@ -436,15 +960,15 @@ fn on_image_asset_event(
             .expect("render to be available before image");
 
         let assets = state.assets.as_mut().unwrap().client();
-        if let Some(image) = assets.try_get(&event.id) {
+        if assets.has(&event.id) {
             for texture_id in renderer.textures.textures_for_image(event.id) {
                 if let Some(texture) = assets.try_get(&texture_id) {
                     renderer.textures.upsert_texture(
                         &renderer.device,
                         &renderer.queue,
+                        &assets,
                         texture_id,
                         texture,
-                        image,
                     );
                 }
             }
@@ -452,6 +976,32 @@ fn on_image_asset_event(
     }
 }
 
+fn on_compressed_image_asset_event(
+    state: &mut RenderServer,
+    _context: &mut RuntimeContext,
+    event: &AssetEvent<CompressedImage>,
+) {
+    if AssetEventKind::Load == event.kind {
+        let renderer = state
+            .renderer
+            .as_mut()
+            .expect("render to be available before compressed image");
+
+        let assets = state.assets.as_mut().unwrap().client();
+        for texture_id in renderer.textures.textures_for_compressed_image(event.id) {
+            if let Some(texture) = assets.try_get(&texture_id) {
+                renderer.textures.upsert_texture(
+                    &renderer.device,
+                    &renderer.queue,
+                    &assets,
+                    texture_id,
+                    texture,
+                );
+            }
+        }
+    }
+}
+
 fn on_texture_asset_event(
     state: &mut RenderServer,
     _context: &mut RuntimeContext,
@@ -466,17 +1016,13 @@ fn on_texture_asset_event(
         AssetEventKind::Load => {
             let assets = state.assets.as_mut().unwrap().client();
             if let Some(texture) = assets.try_get(&event.id) {
-                if let Some(image) = assets.try_get(&texture.image) {
-                    renderer.textures.upsert_texture(
-                        &renderer.device,
-                        &renderer.queue,
-                        event.id,
-                        texture,
-                        image,
-                    );
-                } else {
-                    renderer.textures.queue_texture(event.id, texture);
-                }
+                renderer.textures.upsert_texture(
+                    &renderer.device,
+                    &renderer.queue,
+                    &assets,
+                    event.id,
+                    texture,
+                );
             }
         }
         AssetEventKind::Unload => {
@@ -557,9 +1103,27 @@ fn on_instance_event(
     }
 }
 
+fn on_group_event(state: &mut RenderServer, _context: &mut RuntimeContext, event: &GroupEvent) {
+    let renderer = state
+        .renderer
+        .as_mut()
+        .expect("render to be available before group");
+
+    match event.kind {
+        GroupEventKind::Created(world) | GroupEventKind::Modified(world) => {
+            renderer
+                .canvasses
+                .upsert_group(&event.layer, event.id, world);
+        }
+        GroupEventKind::Dropped => {
+            renderer.canvasses.remove_group(&event.layer, &event.id);
+        }
+    }
+}
+
 fn on_font_layout_asset_event(
     state: &mut RenderServer,
-    _context: &mut RuntimeContext,
+    context: &mut RuntimeContext,
     event: &AssetEvent<FontLayout>,
 ) {
     if event.kind == AssetEventKind::Load {
@@ -578,7 +1142,7 @@ fn on_font_layout_asset_event(
                     .expect("text");
 
                 if let Some(font) = assets.try_get(&raw_text.font) {
-                    let raw_instance = renderer
+                    let instances = renderer
                         .texts
                         .upsert_text(
                             &assets,
@@ -591,19 +1155,7 @@ fn on_font_layout_asset_event(
                         )
                         .expect("upsert text");
 
-                    let priority = renderer
-                        .pipelines
-                        .get_pipeline(&raw_instance.pipeline)
-                        .map(|p| p.pipeline.priority)
-                        .unwrap_or_default();
-
-                    renderer.canvasses.upsert_instance(
-                        &renderer.device,
-                        &canvas_layer_id,
-                        text_id,
-                        priority,
-                        raw_instance,
-                    );
+                    upsert_text_instances(renderer, context, &canvas_layer_id, text_id, instances);
                 }
             }
         }
@@ -612,7 +1164,7 @@ fn on_font_layout_asset_event(
 
 fn on_font_asset_event(
     state: &mut RenderServer,
-    _context: &mut RuntimeContext,
+    context: &mut RuntimeContext,
     event: &AssetEvent<Font>,
 ) {
     if event.kind == AssetEventKind::Load {
@@ -631,7 +1183,7 @@ fn on_font_asset_event(
                     .expect("text");
 
                 if let Some(font_layout) = assets.try_get(&font.layout) {
-                    let instance = renderer
+                    let instances = renderer
                         .texts
                         .upsert_text(
                             &assets,
@@ -644,18 +1196,15 @@ fn on_font_asset_event(
                         )
                         .expect("upsert text");
 
-                    let priority = renderer
-                        .pipelines
-                        .get_pipeline(&instance.pipeline)
-                        .map(|p| p.pipeline.priority)
-                        .unwrap_or_default();
-
-                    renderer.canvasses.upsert_instance(
-                        &renderer.device,
-                        &canvas_layer_id,
+                    upsert_text_instances(renderer, context, &canvas_layer_id, text_id, instances);
+                } else {
+                    // the text was queued without knowing the layout of the font,
+                    // requeue it so that it is flushed once the layout is loaded
+                    renderer.texts.queue_text(
+                        canvas_layer_id,
                         text_id,
-                        priority,
-                        instance,
+                        raw_text,
+                        Some(font.layout.to_weak()),
                     );
                 }
             }
@@ -663,7 +1212,7 @@ fn on_font_asset_event(
     }
 }
 
-fn on_text_event(state: &mut RenderServer, _context: &mut RuntimeContext, event: &TextEvent) {
+fn on_text_event(state: &mut RenderServer, context: &mut RuntimeContext, event: &TextEvent) {
     let renderer = state
         .renderer
         .as_mut()
@@ -679,30 +1228,22 @@ fn on_text_event(state: &mut RenderServer, _context: &mut RuntimeContext, event:
             raw,
             major_change: false,
         } => {
-            if let Some(raw_instance) =
+            if let Some(instances) =
                 renderer
                     .texts
                     .minor_update_text(event.layer, &event.id, raw.deref().to_owned())
             {
-                let priority = renderer
-                    .pipelines
-                    .get_pipeline(&raw_instance.pipeline)
-                    .map(|p| p.pipeline.priority)
-                    .unwrap_or_default();
-
-                renderer.canvasses.upsert_instance(
-                    &renderer.device,
-                    &event.layer,
-                    event.id,
-                    priority,
-                    raw_instance,
-                );
+                upsert_text_instances(renderer, context, &event.layer, event.id, instances);
             }
 
             return;
         }
         TextEventKind::Dropped => {
-            renderer.texts.remove_text(event.id);
+            if let Some(background_id) = renderer.texts.remove_text(event.id) {
+                renderer
+                    .canvasses
+                    .remove_instance(&event.layer, &background_id);
+            }
             renderer.canvasses.remove_instance(&event.layer, &event.id);
             return;
         }
@@ -713,7 +1254,7 @@ fn on_text_event(state: &mut RenderServer, _context: &mut RuntimeContext, event:
     let font_layout = font.and_then(|f| assets.try_get(&f.layout));
 
     if let (Some(font), Some(font_layout)) = (font, font_layout) {
-        let raw_instance = renderer
+        let instances = renderer
             .texts
             .upsert_text(
                 &assets,
@@ -726,6 +1267,34 @@ fn on_text_event(state: &mut RenderServer, _context: &mut RuntimeContext, event:
             )
             .expect("upsert text");
 
+        upsert_text_instances(renderer, context, &event.layer, event.id, instances);
+    } else {
+        renderer.texts.queue_text(
+            event.layer,
+            event.id,
+            raw_text.deref().to_owned(),
+            font.map(|f| f.layout.to_weak()),
+        )
+    }
+}
+
+fn upsert_text_instances(
+    renderer: &mut Renderer,
+    context: &mut RuntimeContext,
+    layer_id: &Uuid,
+    text_id: Uuid,
+    instances: TextInstances,
+) {
+    if let Some(laid_out) = instances.laid_out {
+        context.sender().send(laid_out);
+    }
+
+    if let Some(background_id) = instances.dropped_background {
+        renderer.canvasses.remove_instance(layer_id, &background_id);
+    }
+
+    let text_instance = std::iter::once((text_id, instances.text));
+    for (instance_id, raw_instance) in text_instance.chain(instances.background) {
         let priority = renderer
             .pipelines
             .get_pipeline(&raw_instance.pipeline)
@@ -734,18 +1303,11 @@ fn on_text_event(state: &mut RenderServer, _context: &mut RuntimeContext, event:
 
         renderer.canvasses.upsert_instance(
             &renderer.device,
-            &event.layer,
-            event.id,
+            layer_id,
+            instance_id,
             priority,
             raw_instance,
         );
-    } else {
-        renderer.texts.queue_text(
-            event.layer,
-            event.id,
-            raw_text.deref().to_owned(),
-            font.map(|f| f.layout.to_weak()),
-        )
     }
 }
 
@@ -795,10 +1357,19 @@ fn on_curve_event(state: &mut RenderServer, _context: &mut RuntimeContext, event
     };
 
     let assets = state.assets.as_mut().unwrap().client();
+    let px_factor = renderer
+        .canvasses
+        .layer_px_factor(&event.layer, &renderer.cameras);
 
     let raw_instance = renderer
         .curves
-        .upsert_curve(&assets, event.layer, event.id, raw_curve.deref().to_owned())
+        .upsert_curve(
+            &assets,
+            event.layer,
+            event.id,
+            raw_curve.deref().to_owned(),
+            px_factor,
+        )
         .expect("upsert curve");
 
     let priority = renderer
@@ -821,9 +1392,34 @@ fn on_frame_requested_event(
     context: &mut RuntimeContext,
     event: &FrameRequestedEvent,
 ) {
+    let mut instances = 0;
+    let mut asset_count = 0;
+    if let Some(stats) = &mut state.stats {
+        stats.on_frame_requested(event);
+    }
+
     if let Some(renderer) = &mut state.renderer {
+        if let Some(assets) = state.assets.as_mut() {
+            let assets = assets.client();
+            let canvasses = &renderer.canvasses;
+            let cameras = &renderer.cameras;
+            renderer.curves.update_screen_curves(&assets, |layer_id| {
+                canvasses.layer_px_factor(layer_id, cameras)
+            });
+            asset_count = assets.len();
+        }
+
         renderer.render().expect("render");
+        instances = renderer.canvasses.instance_count();
+    }
+
+    if let Some(stats) = &mut state.stats {
+        stats.on_drawn(instances, asset_count);
     }
 
-    context.sender().send(DrawnEvent { frame: event.frame });
+    context.sender().send(DrawnEvent {
+        frame: event.frame,
+        instances,
+        assets: asset_count,
+    });
 }
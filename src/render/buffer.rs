@@ -6,9 +6,17 @@ use serde::Deserialize;
 pub struct Vertex {
     pub position: [f32; 3],
     pub tex_coords: [f32; 2],
+    /// multiplied with the tint of the instance
+    #[serde(default = "Vertex::default_color")]
+    pub color: [f32; 4],
 }
 
 impl Vertex {
+    #[inline]
+    pub fn default_color() -> [f32; 4] {
+        [1.0; 4]
+    }
+
     pub fn desc() -> wgpu::VertexBufferLayout<'static> {
         wgpu::VertexBufferLayout {
             array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
@@ -26,6 +34,12 @@ impl Vertex {
                     offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
                     shader_location: 1,
                 },
+                // color
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x4,
+                    offset: std::mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                },
             ],
         }
     }
@@ -38,6 +52,12 @@ pub struct Instance {
     pub scale: [f32; 3],
     pub tint: [f32; 4],
     pub texture_layer: i32,
+    pub outline: [f32; 4],
+    pub shadow: [f32; 4],
+    pub shadow_offset: [f32; 2],
+    pub uv_flip: [f32; 2],
+    /// free for custom shaders, e.g. a fill fraction or a time value
+    pub custom: [f32; 4],
 }
 
 impl Instance {
@@ -86,6 +106,36 @@ impl Instance {
                     shader_location: 11,
                     format: wgpu::VertexFormat::Sint32,
                 },
+                // outline
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 24]>() as wgpu::BufferAddress,
+                    shader_location: 12,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                // shadow
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 28]>() as wgpu::BufferAddress,
+                    shader_location: 13,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                // shadow_offset
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 32]>() as wgpu::BufferAddress,
+                    shader_location: 14,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                // uv_flip
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 34]>() as wgpu::BufferAddress,
+                    shader_location: 15,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                // custom, vertex attributes are limited to 16 locations
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 36]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
             ],
         }
     }
@@ -97,5 +147,9 @@ pub struct Uniforms {
     pub camera_view: [[f32; 4]; 4],
     pub camera_proj: [[f32; 4]; 4],
     pub px_range_factor: f32,
+    // vec4 is 16 byte aligned in wgsl
+    pub _padding: [f32; 3],
+    /// the rgb tint and the opacity of the layer
+    pub tint: [f32; 4],
     // TODO: add time since start (scaled and native)
 }
@@ -1,6 +1,6 @@
 use crate::util::{Counted, HashMap};
-use nalgebra::{Isometry3, Matrix4, Orthographic3, Point3, Vector2, Vector3, Point2};
-use std::cmp::Ordering;
+use nalgebra::{Isometry3, Matrix4, Orthographic3, Point2, Point3, Vector2, Vector3};
+use serde::{Deserialize, Serialize};
 use std::ops::{Deref, DerefMut};
 use uuid::Uuid;
 
@@ -14,12 +14,37 @@ const OPENGL_TO_WGPU_MATRIX: Matrix4<f32> = Matrix4::new(
     0.0, 0.0, 0.0, 1.0,
 );
 
+/// how the rect of the camera maps to attachments with a different aspect ratio
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CameraScaleMode {
+    /// extends the rect along one axis, so more of the world is visible
+    Expand,
+    /// distorts the rect to the aspect ratio of the attachment
+    Stretch,
+    /// keeps the rect and adds bars in the clear color around it
+    FitLetterbox,
+    /// shrinks the rect along one axis, so parts of it are cut off
+    FillCrop,
+    /// keeps the width of the rect and adapts its height
+    FixedWidth,
+    /// keeps the height of the rect and adapts its width
+    FixedHeight,
+}
+
+impl Default for CameraScaleMode {
+    fn default() -> Self {
+        CameraScaleMode::Expand
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct OrthographicProjection {
     pub rect: Vector2<f32>,
     pub zoom: f32,
     pub znear: f32,
     pub zfar: f32,
+    pub scale_mode: CameraScaleMode,
 }
 
 impl OrthographicProjection {
@@ -30,6 +55,7 @@ impl OrthographicProjection {
             znear: 0.001,
             zfar: 20000.0,
             zoom: 1.0,
+            scale_mode: CameraScaleMode::default(),
         }
     }
 
@@ -61,29 +87,40 @@ impl OrthographicProjection {
         Vector2::new(base.x / zoomed.x, base.y / zoomed.y)
     }
 
-    #[inline]
+    /// the projection fitted to the aspect ratio of the base according to the scale mode
     pub fn scaled(&self, base: Vector2<f32>) -> Self {
         let mut a = self.to_owned();
 
         let aspect = base.x / base.y;
-        let min_aspect = a.rect.x / a.rect.y;
-        match min_aspect
-            .partial_cmp(&aspect)
-            .expect("not NaN for delta_aspect")
-        {
-            Ordering::Less => {
-                a.rect.x = a.rect.x / min_aspect * aspect;
-            }
-            Ordering::Equal => {}
-            Ordering::Greater => {
-                let inverse_min_aspect = 1.0 / min_aspect;
-                let inverse_aspect = 1.0 / aspect;
-                a.rect.y = a.rect.y / inverse_min_aspect * inverse_aspect;
-            }
+        let rect_aspect = a.rect.x / a.rect.y;
+        let fixed_width = match self.scale_mode {
+            CameraScaleMode::Stretch | CameraScaleMode::FitLetterbox => return a,
+            CameraScaleMode::Expand => rect_aspect > aspect,
+            CameraScaleMode::FillCrop => rect_aspect < aspect,
+            CameraScaleMode::FixedWidth => true,
+            CameraScaleMode::FixedHeight => false,
+        };
+
+        if fixed_width {
+            a.rect.y = a.rect.x / aspect;
+        } else {
+            a.rect.x = a.rect.y * aspect;
         }
 
         a
     }
+
+    /// origin and size of the part of the base that is drawn into,
+    /// only letterboxing doesn't draw into the whole base
+    pub fn viewport(&self, base: Vector2<f32>) -> (Point2<f32>, Vector2<f32>) {
+        if self.scale_mode != CameraScaleMode::FitLetterbox {
+            return (Point2::origin(), base);
+        }
+
+        let scale = (base.x / self.rect.x).min(base.y / self.rect.y);
+        let size = self.rect * scale;
+        (Point2::from((base - size) / 2.0), size)
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -106,7 +143,11 @@ impl RawCamera {
 
     #[inline]
     pub fn view(&self) -> Isometry3<f32> {
-        Isometry3::look_at_rh(&Point3::new(self.eye.x, self.eye.y, ZFAR / 2.0), &Point3::new(self.eye.x, self.eye.y, - 1.0), &Vector3::y())
+        Isometry3::look_at_rh(
+            &Point3::new(self.eye.x, self.eye.y, ZFAR / 2.0),
+            &Point3::new(self.eye.x, self.eye.y, -1.0),
+            &Vector3::y(),
+        )
     }
 
     #[inline]
@@ -119,6 +160,12 @@ impl RawCamera {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CameraSnapshot {
+    pub id: Uuid,
+    pub camera: RawCamera,
+}
+
 #[derive(Default)]
 pub struct Cameras {
     underlying: HashMap<Uuid, Counted<RawCamera>>,
@@ -159,4 +206,14 @@ impl Cameras {
     pub fn get(&self, camera_id: &Uuid) -> Option<RawCamera> {
         self.underlying.get(camera_id).map(Deref::deref).copied()
     }
+
+    pub fn snapshot(&self) -> Vec<CameraSnapshot> {
+        self.underlying
+            .iter()
+            .map(|(id, camera)| CameraSnapshot {
+                id: *id,
+                camera: *camera.deref(),
+            })
+            .collect()
+    }
 }
@@ -1,29 +1,87 @@
-use crate::asset::{AssetId, Weak, WeakAssetId};
+use crate::asset::storage::AssetsClient;
+use crate::asset::{AssetId, StrongAssetId, Weak, WeakAssetId};
 use crate::render::buffer::{Instance, Uniforms};
-use crate::render::camera::{Cameras, RawCamera};
-use crate::render::mesh::{Mesh, Meshes};
-use crate::render::pipeline::{Pipeline, Pipelines};
+use crate::render::camera::{CameraScaleMode, Cameras, OrthographicProjection, RawCamera};
+use crate::render::capture::FrameCapture;
+use crate::render::grading::ColorGrading;
+use crate::render::mesh::{Aabb, Mesh, Meshes};
+use crate::render::pipeline::{InstanceScale, Pipeline, Pipelines, WGSLSource};
+use crate::render::post::{PostEffect, PostProcessing, RealizedPostChain};
 use crate::render::view::{RealizedView, Texture, Textures};
-use crate::render::Samples;
-use crate::some_or_continue;
+use crate::render::{DepthFormat, Samples};
 use crate::util::{Counted, HashMap, IndexMap};
-use nalgebra::{Isometry3, Similarity3, Vector2, Vector3};
+use crate::{some_or_continue, some_or_return};
+use image::DynamicImage;
+use nalgebra::{Isometry3, Matrix4, Point2, Point3, Similarity3, Vector2, Vector3};
+use std::cmp::Ordering;
 use std::collections::btree_map::Entry;
 use std::collections::BTreeMap;
 use std::marker::PhantomData;
+use std::ops::Range;
 use uuid::Uuid;
 use wgpu::util::DeviceExt;
 
+/// signed distance field effects, only evaluated by the text pipeline (see text.wgsl)
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct DistanceEffects {
+    /// outline color and width in distance range units, a zero width draws no outline
+    pub outline: [f32; 4],
+    /// shadow color and opacity, a zero opacity skips sampling the shadow
+    pub shadow: [f32; 4],
+    /// shadow offset in atlas texels
+    pub shadow_offset: [f32; 2],
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct RawInstance<S> {
     pub pipeline: AssetId<Pipeline, S>,
     pub mesh: AssetId<Mesh, S>,
     pub texture: AssetId<Texture, S>,
     pub texture_layer: u32,
+    /// mirrors the texture coordinates horizontally and vertically
+    pub flip: [bool; 2],
     pub model: Isometry3<f32>,
     pub scale: Vector3<f32>,
     pub tint: [f32; 4],
+    /// passed to the shader as is, zero by default
+    pub custom: [f32; 4],
+    pub effects: DistanceEffects,
     pub world: Similarity3<f32>,
+    /// the world is relative to the world of the group, if any
+    pub group: Option<Uuid>,
+}
+
+impl<S> PartialEq for RawInstance<S> {
+    fn eq(&self, other: &Self) -> bool {
+        // destructured, so a new field can't be skipped by the comparison
+        let RawInstance {
+            pipeline,
+            mesh,
+            texture,
+            texture_layer,
+            flip,
+            model,
+            scale,
+            tint,
+            custom,
+            effects,
+            world,
+            group,
+        } = self;
+
+        pipeline == &other.pipeline
+            && mesh == &other.mesh
+            && texture == &other.texture
+            && texture_layer == &other.texture_layer
+            && flip == &other.flip
+            && model == &other.model
+            && scale == &other.scale
+            && tint == &other.tint
+            && custom == &other.custom
+            && effects == &other.effects
+            && world == &other.world
+            && group == &other.group
+    }
 }
 
 impl<S> RawInstance<S> {
@@ -33,10 +91,33 @@ impl<S> RawInstance<S> {
             mesh: self.mesh.to_weak(),
             texture: self.texture.to_weak(),
             texture_layer: self.texture_layer,
+            flip: self.flip,
             model: self.model,
             scale: self.scale,
             tint: self.tint,
+            custom: self.custom,
+            effects: self.effects,
             world: self.world,
+            group: self.group,
+        }
+    }
+
+    fn to_instance(&self, group_world: Option<&Similarity3<f32>>) -> Instance {
+        let world = match group_world {
+            Some(group_world) => group_world * self.world,
+            None => self.world,
+        };
+
+        Instance {
+            model: (world * self.model).to_homogeneous().into(),
+            scale: self.scale.into(),
+            tint: self.tint,
+            texture_layer: self.texture_layer as i32,
+            outline: self.effects.outline,
+            shadow: self.effects.shadow,
+            shadow_offset: self.effects.shadow_offset,
+            uv_flip: self.flip.map(|flip| if flip { 1.0 } else { 0.0 }),
+            custom: self.custom,
         }
     }
 }
@@ -45,23 +126,187 @@ impl<S> RawInstance<S> {
 struct InstanceEntry {
     raw: RawInstance<Weak>,
     priority: usize,
-    buffer_index: u64,
     buffer_offset: usize,
 }
 
+impl InstanceEntry {
+    #[inline]
+    fn render_key(&self) -> RenderKey {
+        RenderKey {
+            priority: self.priority,
+            pipeline: self.raw.pipeline,
+            mesh: self.raw.mesh,
+            texture: self.raw.texture,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
 struct RenderKey {
     priority: usize,
     pipeline: WeakAssetId<Pipeline>,
     mesh: WeakAssetId<Mesh>,
     texture: WeakAssetId<Texture>,
-    buffer_index: u64,
 }
 
+/// instances sharing a render key, drawn with a single instanced draw call
+#[derive(Default)]
 struct RenderEntry {
-    instance_buffer: wgpu::Buffer,
+    instance_buffer: Option<wgpu::Buffer>,
     capacity: u32,
     instances: Vec<Uuid>,
+    data: Vec<Instance>,
+    /// world bounds of the instances and the mesh bounds they were derived from
+    bounds: Vec<Option<(Aabb, Aabb)>>,
+    visible: Vec<Range<u32>>,
+    dirty: bool,
+    sorted: bool,
+}
+
+impl RenderEntry {
+    fn push(&mut self, instance_id: Uuid, instance: Instance) -> usize {
+        self.instances.push(instance_id);
+        self.data.push(instance);
+        self.bounds.push(None);
+        self.dirty = true;
+        self.sorted = false;
+        self.instances.len() - 1
+    }
+
+    fn update(&mut self, offset: usize, instance: Instance) {
+        self.data[offset] = instance;
+        self.bounds[offset] = None;
+        self.dirty = true;
+        self.sorted = false;
+    }
+
+    /// returns the instance that has been moved into the offset
+    fn swap_remove(&mut self, offset: usize) -> Option<Uuid> {
+        self.instances.swap_remove(offset);
+        self.data.swap_remove(offset);
+        self.bounds.swap_remove(offset);
+        self.dirty = true;
+        self.sorted = false;
+        self.instances.get(offset).copied()
+    }
+
+    /// world z of the instance at the offset
+    #[inline]
+    fn z(&self, offset: usize) -> f32 {
+        self.data[offset].model[3][2]
+    }
+
+    /// orders the instances back to front, so blended instances of the entry can be drawn in runs,
+    /// returns if the offsets of the instances changed
+    fn sort_by_z(&mut self) -> bool {
+        if self.sorted {
+            return false;
+        }
+        self.sorted = true;
+
+        let in_order = (1..self.data.len()).all(|offset| self.z(offset - 1) <= self.z(offset));
+        if in_order {
+            return false;
+        }
+
+        let mut order = (0..self.data.len()).collect::<Vec<_>>();
+        order.sort_by(|a, b| {
+            self.z(*a)
+                .partial_cmp(&self.z(*b))
+                .unwrap_or(Ordering::Equal)
+        });
+
+        self.instances = order.iter().map(|offset| self.instances[*offset]).collect();
+        self.data = order.iter().map(|offset| self.data[*offset]).collect();
+        self.bounds = order.iter().map(|offset| self.bounds[*offset]).collect();
+        self.dirty = true;
+        true
+    }
+
+    /// collects the ranges of instances that might be visible,
+    /// without mesh bounds or view projection all instances are visible
+    fn cull(
+        &mut self,
+        mesh_bounds: Option<Aabb>,
+        view_proj: Option<&Matrix4<f32>>,
+        instance_scale: InstanceScale,
+    ) {
+        self.visible.clear();
+
+        let (mesh_bounds, view_proj) = match (mesh_bounds, view_proj) {
+            (Some(mesh_bounds), Some(view_proj)) => (mesh_bounds, view_proj),
+            _ => {
+                self.visible.push(0..self.data.len() as u32);
+                return;
+            }
+        };
+
+        let mut start = None;
+        for (offset, instance) in self.data.iter().enumerate() {
+            let world_bounds = match self.bounds[offset] {
+                Some((from, world_bounds)) if from == mesh_bounds => world_bounds,
+                _ => {
+                    let model = Matrix4::from(instance.model)
+                        * Matrix4::new_nonuniform_scaling(&instance_scale.spatial(instance.scale));
+                    let world_bounds = mesh_bounds.transform(&model);
+                    self.bounds[offset] = Some((mesh_bounds, world_bounds));
+                    world_bounds
+                }
+            };
+
+            match (world_bounds.intersects_clip(view_proj), start) {
+                (true, None) => start = Some(offset as u32),
+                (false, Some(s)) => {
+                    self.visible.push(s..offset as u32);
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(s) = start {
+            self.visible.push(s..self.data.len() as u32);
+        }
+    }
+
+    /// grows the instance buffer to the next power of two, the instances are written on flush
+    fn reserve(&mut self, device: &wgpu::Device) {
+        let len = self.data.len() as u32;
+        if self.instance_buffer.is_some() && len <= self.capacity {
+            return;
+        }
+
+        self.capacity = len.next_power_of_two();
+        self.instance_buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Instance Buffer"),
+            size: (self.capacity as usize * std::mem::size_of::<Instance>()) as _,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        }));
+        self.dirty = true;
+    }
+
+    fn flush(&mut self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder) {
+        if !self.dirty || self.data.is_empty() {
+            return;
+        }
+        self.reserve(device);
+        self.dirty = false;
+
+        // Optimization: only copy the changed instances
+        let update_instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Update Instance Buffer"),
+            contents: bytemuck::cast_slice(&self.data),
+            usage: wgpu::BufferUsages::COPY_SRC,
+        });
+        encoder.copy_buffer_to_buffer(
+            &update_instance_buffer,
+            0,
+            self.instance_buffer.as_ref().unwrap(),
+            0,
+            (self.data.len() * std::mem::size_of::<Instance>()) as _,
+        );
+    }
 }
 
 pub struct RealizedCanvasLayer {
@@ -69,7 +314,11 @@ pub struct RealizedCanvasLayer {
     uniform_buffer: wgpu::Buffer,
     instance_index: BTreeMap<Uuid, InstanceEntry>,
     render_index: BTreeMap<RenderKey, RenderEntry>,
-    buffer_counter: u64,
+    opacity: f32,
+    tint: [f32; 3],
+    culling: bool,
+    clip: Option<ClipRect>,
+    groups: HashMap<Uuid, Similarity3<f32>>,
 }
 
 impl RealizedCanvasLayer {
@@ -98,38 +347,64 @@ impl RealizedCanvasLayer {
             uniform_buffer,
             instance_index: Default::default(),
             render_index: Default::default(),
-            buffer_counter: 0,
+            opacity: 1.0,
+            tint: [1.0; 3],
+            culling: true,
+            clip: None,
+            groups: Default::default(),
         }
     }
 
-    pub fn update_pipeline_priority(&mut self, pipeline: &WeakAssetId<Pipeline>, priority: usize) {
-        // Optimization: don't iterate over all instances
+    #[inline]
+    pub fn instance_count(&self) -> usize {
+        self.instance_index.len()
+    }
 
-        for (_key, entry) in &mut self.instance_index {
-            if !(entry.raw.pipeline.is_same_asset(pipeline) && entry.priority != priority) {
-                continue;
-            }
+    #[inline]
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.opacity = opacity;
+    }
 
-            let prev = some_or_continue!(self.render_index.remove(&RenderKey {
-                priority: entry.priority,
-                pipeline: entry.raw.pipeline,
-                mesh: entry.raw.mesh,
-                texture: entry.raw.texture,
-                buffer_index: entry.buffer_index,
-            }));
+    #[inline]
+    pub fn set_tint(&mut self, tint: [f32; 3]) {
+        self.tint = tint;
+    }
 
-            self.render_index.insert(
-                RenderKey {
-                    priority,
-                    pipeline: entry.raw.pipeline,
-                    mesh: entry.raw.mesh,
-                    texture: entry.raw.texture,
-                    buffer_index: entry.buffer_index,
-                },
-                prev,
-            );
+    #[inline]
+    pub fn set_culling(&mut self, culling: bool) {
+        self.culling = culling;
+    }
+
+    #[inline]
+    pub fn set_clip(&mut self, clip: Option<ClipRect>) {
+        self.clip = clip;
+    }
 
-            entry.priority = priority;
+    pub fn update_pipeline_priority(&mut self, pipeline: &WeakAssetId<Pipeline>, priority: usize) {
+        let render_keys = self
+            .render_index
+            .keys()
+            .filter(|key| key.pipeline.is_same_asset(pipeline) && key.priority != priority)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for render_key in render_keys {
+            let prev = some_or_continue!(self.render_index.remove(&render_key));
+            let entry = self
+                .render_index
+                .entry(RenderKey {
+                    priority,
+                    ..render_key
+                })
+                .or_default();
+
+            for (instance_id, instance) in prev.instances.into_iter().zip(prev.data) {
+                let buffer_offset = entry.push(instance_id, instance);
+                if let Some(instance_entry) = self.instance_index.get_mut(&instance_id) {
+                    instance_entry.priority = priority;
+                    instance_entry.buffer_offset = buffer_offset;
+                }
+            }
         }
     }
 
@@ -140,84 +415,126 @@ impl RealizedCanvasLayer {
         priority: usize,
         raw: RawInstance<Weak>,
     ) {
-        // Optimization: batching
-
-        let buffer_index = self.buffer_counter;
-        self.buffer_counter += 1;
-        let instance_entry = InstanceEntry {
+        let mut instance_entry = InstanceEntry {
             raw,
             priority,
-            buffer_index,
             buffer_offset: 0,
         };
+        let render_key = instance_entry.render_key();
+        let group_world = raw.group.and_then(|group| self.groups.get(&group).copied());
+
+        if let Some(current) = self.instance_index.get_mut(&instance_id) {
+            // Optimization: here vs modify
+            if current.raw == instance_entry.raw {
+                // identical, no changes needed
+                return;
+            }
 
-        match self.instance_index.entry(instance_id) {
-            Entry::Occupied(mut e) => {
-                let current = e.get_mut();
-
-                // Optimization: here vs modify
-                if &current.raw.model == &instance_entry.raw.model
-                    && &current.raw.tint == &instance_entry.raw.tint
-                    && &current.raw.scale == &instance_entry.raw.scale
-                    && &current.raw.texture == &instance_entry.raw.texture
-                    && &current.raw.mesh == &instance_entry.raw.mesh
-                    && &current.raw.pipeline == &instance_entry.raw.pipeline
-                    && &current.raw.world == &instance_entry.raw.world
-                {
-                    // identical, no changes needed
-                    return;
+            if current.render_key() == render_key {
+                // same batch, update the instance in place
+                let entry = self
+                    .render_index
+                    .get_mut(&render_key)
+                    .expect("render entry of instance");
+                entry.update(current.buffer_offset, raw.to_instance(group_world.as_ref()));
+                current.raw = raw;
+                return;
+            }
+
+            self.remove_instance(&instance_id);
+        }
+
+        let entry = self.render_index.entry(render_key).or_default();
+        instance_entry.buffer_offset =
+            entry.push(instance_id, raw.to_instance(group_world.as_ref()));
+        entry.reserve(device);
+        self.instance_index.insert(instance_id, instance_entry);
+    }
+
+    /// the instance with the highest z index, then the highest priority, containing the point
+    pub fn pick(
+        &self,
+        pipelines: &Pipelines,
+        meshes: &Meshes,
+        world_point: Point2<f32>,
+    ) -> Option<Uuid> {
+        let mut picked: Option<(f32, usize, Uuid)> = None;
+
+        for (render_key, entry) in &self.render_index {
+            let bounds = some_or_continue!(meshes
+                .get_mesh(&render_key.mesh)
+                .and_then(|mesh| mesh.bounds));
+            let instance_scale = instance_scale(pipelines, render_key);
+
+            for (instance_id, instance) in entry.instances.iter().zip(&entry.data) {
+                let z = instance.model[3][2];
+                if let Some((picked_z, picked_priority, _)) = picked {
+                    if (z, render_key.priority) <= (picked_z, picked_priority) {
+                        continue;
+                    }
                 }
 
-                let prev = std::mem::replace(current, instance_entry);
+                let model = Matrix4::from(instance.model)
+                    * Matrix4::new_nonuniform_scaling(&instance_scale.spatial(instance.scale));
+                let inverse = some_or_continue!(model.try_inverse());
+                let local = inverse.transform_point(&Point3::new(world_point.x, world_point.y, z));
 
-                self.render_index.remove(&RenderKey {
-                    priority: prev.priority,
-                    pipeline: prev.raw.pipeline,
-                    mesh: prev.raw.mesh,
-                    texture: prev.raw.texture,
-                    buffer_index: prev.buffer_index,
-                });
+                if local.x >= bounds.min.x
+                    && local.x <= bounds.max.x
+                    && local.y >= bounds.min.y
+                    && local.y <= bounds.max.y
+                {
+                    picked = Some((z, render_key.priority, *instance_id));
+                }
             }
-            Entry::Vacant(e) => {
-                e.insert(instance_entry);
+        }
+
+        picked.map(|(_, _, instance_id)| instance_id)
+    }
+
+    /// moves the instances of the group along with its world
+    pub fn upsert_group(&mut self, group_id: Uuid, world: Similarity3<f32>) {
+        self.groups.insert(group_id, world);
+        self.update_group_instances(group_id);
+    }
+
+    /// the instances of a removed group are left at their own world
+    pub fn remove_group(&mut self, group_id: &Uuid) {
+        if self.groups.remove(group_id).is_some() {
+            self.update_group_instances(*group_id);
+        }
+    }
+
+    fn update_group_instances(&mut self, group_id: Uuid) {
+        let group_world = self.groups.get(&group_id);
+
+        // Optimization: index the instances by group
+        for instance_entry in self.instance_index.values() {
+            if instance_entry.raw.group != Some(group_id) {
+                continue;
             }
-        };
 
-        // TODO: update partial buffer, encoder.copy_buffer_to_buffer(..)
-        let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Instance Buffer"),
-            contents: bytemuck::cast_slice(&vec![Instance {
-                model: (raw.world * raw.model).to_homogeneous().into(),
-                scale: raw.scale.into(),
-                tint: raw.tint,
-                texture_layer: raw.texture_layer as i32,
-            }]),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
-        let render_entry = RenderEntry {
-            instance_buffer,
-            capacity: 1,
-            instances: vec![instance_id],
-        };
-        let render_key = RenderKey {
-            priority,
-            pipeline: raw.pipeline,
-            mesh: raw.mesh,
-            texture: raw.texture,
-            buffer_index,
-        };
-        self.render_index.insert(render_key, render_entry);
+            let entry = some_or_continue!(self.render_index.get_mut(&instance_entry.render_key()));
+            entry.update(
+                instance_entry.buffer_offset,
+                instance_entry.raw.to_instance(group_world),
+            );
+        }
     }
 
     pub fn remove_instance(&mut self, instance_id: &Uuid) {
-        if let Some(instance) = self.instance_index.remove(instance_id) {
-            self.render_index.remove(&RenderKey {
-                priority: instance.priority,
-                pipeline: instance.raw.pipeline,
-                mesh: instance.raw.mesh,
-                texture: instance.raw.texture,
-                buffer_index: instance.buffer_index,
-            });
+        let instance = some_or_return!(self.instance_index.remove(instance_id));
+        let render_key = instance.render_key();
+        let entry = some_or_return!(self.render_index.get_mut(&render_key));
+
+        if let Some(moved_id) = entry.swap_remove(instance.buffer_offset) {
+            if let Some(moved) = self.instance_index.get_mut(&moved_id) {
+                moved.buffer_offset = instance.buffer_offset;
+            }
+        }
+
+        if entry.instances.is_empty() {
+            self.render_index.remove(&render_key);
         }
     }
 
@@ -230,20 +547,57 @@ impl RealizedCanvasLayer {
         target: Option<&wgpu::TextureView>,
         attachment: &RealizedView,
         color_load_ops: wgpu::LoadOp<wgpu::Color>,
-        depth_texture_view: &wgpu::TextureView,
-        depth_load_ops: wgpu::LoadOp<f32>,
+        depth: Option<(
+            &wgpu::TextureView,
+            wgpu::LoadOp<f32>,
+            Option<wgpu::LoadOp<u32>>,
+        )>,
         encoder: &mut wgpu::CommandEncoder,
         camera: RawCamera,
+        viewport: Viewport,
     ) -> anyhow::Result<()> {
-        // Optimization: culling
-
-        let projection_base =
+        let attachment_size =
             Vector2::new(attachment.size.width as f32, attachment.size.height as f32);
+        let (frame_origin, frame_size) = viewport.within(attachment_size);
+        let (camera_origin, projection_base) = camera.projection.viewport(frame_size);
+        let viewport_origin = frame_origin + camera_origin.coords;
         let projection_scaled = camera.projection.scaled(projection_base);
+        let camera_view = camera.view().to_homogeneous();
+        let camera_proj = projection_scaled.to_homogeneous();
+        let view_proj = camera_proj * camera_view;
+        let scissor = self.clip.map(|clip| {
+            clip.scissor(&view_proj, viewport_origin, projection_base)
+                .map(|s| s.clamp(attachment_size))
+                .filter(|[_, _, width, height]| *width > 0 && *height > 0)
+        });
+        let view_proj = self.culling.then(|| view_proj);
+
+        for (render_key, entry) in &mut self.render_index {
+            if is_blended(pipelines, render_key) && entry.sort_by_z() {
+                for (offset, instance_id) in entry.instances.iter().enumerate() {
+                    if let Some(instance_entry) = self.instance_index.get_mut(instance_id) {
+                        instance_entry.buffer_offset = offset;
+                    }
+                }
+            }
+
+            entry.flush(device, encoder);
+            let mesh_bounds = meshes
+                .get_mesh(&render_key.mesh)
+                .and_then(|mesh| mesh.bounds);
+            entry.cull(
+                mesh_bounds,
+                view_proj.as_ref(),
+                instance_scale(pipelines, render_key),
+            );
+        }
+
         let uniforms = Uniforms {
-            camera_view: camera.view().to_homogeneous().into(),
-            camera_proj: projection_scaled.to_homogeneous().into(),
+            camera_view: camera_view.into(),
+            camera_proj: camera_proj.into(),
             px_range_factor: projection_scaled.px_range_factor(projection_base).x,
+            _padding: Default::default(),
+            tint: [self.tint[0], self.tint[1], self.tint[2], self.opacity],
         };
         let update_uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Update Uniform Buffer"),
@@ -268,21 +622,43 @@ impl RealizedCanvasLayer {
                         store: true,
                     },
                 }],
-                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                    view: depth_texture_view,
-                    depth_ops: Some(wgpu::Operations {
-                        load: depth_load_ops,
-                        store: true,
-                    }),
-                    stencil_ops: None,
+                depth_stencil_attachment: depth.map(|(view, load, stencil_load)| {
+                    wgpu::RenderPassDepthStencilAttachment {
+                        view,
+                        depth_ops: Some(wgpu::Operations { load, store: true }),
+                        stencil_ops: stencil_load
+                            .map(|load| wgpu::Operations { load, store: true }),
+                    }
                 }),
                 label: None,
             });
+            render_pass.set_viewport(
+                viewport_origin.x,
+                viewport_origin.y,
+                projection_base.x,
+                projection_base.y,
+                0.0,
+                1.0,
+            );
+            match scissor {
+                Some(Some([x, y, width, height])) => {
+                    render_pass.set_scissor_rect(x, y, width, height)
+                }
+                // the pass still clears, but nothing of the layer is visible
+                Some(None) => return Ok(()),
+                None => {}
+            }
 
             let mut curr_pipeline: Option<&WeakAssetId<Pipeline>> = None;
+            let mut curr_key: Option<&RenderKey> = None;
+            let mut index_length = 0;
+
+            for (render_key, entry, instances) in self.draws(pipelines) {
+                if curr_key == Some(render_key) {
+                    render_pass.draw_indexed(0..index_length, 0, instances);
+                    continue;
+                }
 
-            // Optimization: batching
-            for (render_key, entry) in &self.render_index {
                 // Optimization: add pipeline swap instructions to render index
 
                 match curr_pipeline {
@@ -291,7 +667,12 @@ impl RealizedCanvasLayer {
                         // pipeline changed
                         let pipeline =
                             some_or_continue!(pipelines.get_pipeline(&render_key.pipeline));
-                        render_pass.set_pipeline(&pipeline.render_pipeline);
+                        if depth.is_some() {
+                            render_pass.set_pipeline(&pipeline.render_pipeline);
+                            render_pass.set_stencil_reference(pipeline.pipeline.stencil.reference);
+                        } else {
+                            render_pass.set_pipeline(&pipeline.flat_render_pipeline);
+                        }
                         render_pass.set_bind_group(1, &self.uniform_bind_group, &[]);
                         curr_pipeline = Some(&render_key.pipeline);
                     }
@@ -300,41 +681,201 @@ impl RealizedCanvasLayer {
                 let realized_texture = some_or_continue!(textures.get_texture(&render_key.texture));
                 render_pass.set_bind_group(0, &realized_texture.bind_group, &[]);
 
+                let instance_buffer = some_or_continue!(entry.instance_buffer.as_ref());
                 let realized_mesh = some_or_continue!(meshes.get_mesh(&render_key.mesh));
                 render_pass.set_vertex_buffer(0, realized_mesh.vertex_buffer.slice(..));
-                render_pass.set_vertex_buffer(1, entry.instance_buffer.slice(..));
+                render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
                 render_pass.set_index_buffer(
                     realized_mesh.index_buffer.slice(..),
                     wgpu::IndexFormat::Uint32,
                 );
-                render_pass.draw_indexed(
-                    0..realized_mesh.index_length,
-                    0,
-                    0..entry.instances.len() as _,
-                );
+                curr_key = Some(render_key);
+                index_length = realized_mesh.index_length;
+                render_pass.draw_indexed(0..index_length, 0, instances);
             }
         }
 
         Ok(())
     }
+
+    /// the visible instances in draw order, opaque entries in render key order
+    /// followed by the blended instances back to front across entries,
+    /// the priority orders blended instances with the same z
+    fn draws(&self, pipelines: &Pipelines) -> Vec<(&RenderKey, &RenderEntry, Range<u32>)> {
+        let mut draws = Vec::with_capacity(self.render_index.len());
+        let mut blended = Vec::default();
+
+        for (render_key, entry) in &self.render_index {
+            if !is_blended(pipelines, render_key) {
+                for instances in &entry.visible {
+                    draws.push((render_key, entry, instances.clone()));
+                }
+                continue;
+            }
+
+            for instances in &entry.visible {
+                for offset in instances.clone() {
+                    blended.push((entry.z(offset as usize), render_key, entry, offset));
+                }
+            }
+        }
+
+        blended.sort_by(|a, b| {
+            a.0.partial_cmp(&b.0)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| a.1.cmp(b.1))
+                .then_with(|| a.3.cmp(&b.3))
+        });
+
+        for (_, render_key, entry, offset) in blended {
+            match draws.last_mut() {
+                Some((key, _, instances)) if *key == render_key && instances.end == offset => {
+                    instances.end += 1;
+                }
+                _ => draws.push((render_key, entry, offset..offset + 1)),
+            }
+        }
+
+        draws
+    }
+}
+
+#[inline]
+fn is_blended(pipelines: &Pipelines, render_key: &RenderKey) -> bool {
+    pipelines
+        .get_pipeline(&render_key.pipeline)
+        .map(|pipeline| pipeline.pipeline.is_blended())
+        .unwrap_or_default()
 }
 
+fn instance_scale(pipelines: &Pipelines, render_key: &RenderKey) -> InstanceScale {
+    pipelines
+        .get_pipeline(&render_key.pipeline)
+        .map(|pipeline| pipeline.pipeline.instance_scale)
+        .unwrap_or_default()
+}
+
+/// world space rectangle of a layer outside of which nothing is rasterized,
+/// e.g. to keep the content of a scrolled panel within its container
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ClipRect {
+    pub min: Point2<f32>,
+    pub max: Point2<f32>,
+}
+
+impl ClipRect {
+    #[inline]
+    pub fn new(min: Point2<f32>, max: Point2<f32>) -> Self {
+        Self { min, max }
+    }
+
+    /// the pixel rectangle covering the clip rect as seen through the camera
+    fn scissor(
+        &self,
+        view_proj: &Matrix4<f32>,
+        viewport_origin: Point2<f32>,
+        viewport_size: Vector2<f32>,
+    ) -> Option<PixelRect> {
+        let corners = [
+            Point3::new(self.min.x, self.min.y, 0.0),
+            Point3::new(self.max.x, self.min.y, 0.0),
+            Point3::new(self.min.x, self.max.y, 0.0),
+            Point3::new(self.max.x, self.max.y, 0.0),
+        ];
+
+        // the y axis of the clip space points up, the one of the pixels down
+        let pixels = corners.iter().map(|corner| {
+            let ndc = view_proj.transform_point(corner);
+            Point3::new(
+                viewport_origin.x + (ndc.x + 1.0) / 2.0 * viewport_size.x,
+                viewport_origin.y + (1.0 - ndc.y) / 2.0 * viewport_size.y,
+                0.0,
+            )
+        });
+
+        Aabb::from_points(pixels).map(|aabb| PixelRect {
+            min: aabb.min.xy(),
+            max: aabb.max.xy(),
+        })
+    }
+}
+
+struct PixelRect {
+    min: Point2<f32>,
+    max: Point2<f32>,
+}
+
+impl PixelRect {
+    /// x, y, width and height within the attachment as expected by the scissor
+    fn clamp(&self, attachment_size: Vector2<f32>) -> [u32; 4] {
+        let min = self.min.coords.sup(&Vector2::zeros()).inf(&attachment_size);
+        let max = self.max.coords.sup(&min).inf(&attachment_size);
+        let (x, y) = (min.x.floor() as u32, min.y.floor() as u32);
+
+        [
+            x,
+            y,
+            (max.x.ceil() as u32).saturating_sub(x),
+            (max.y.ceil() as u32).saturating_sub(y),
+        ]
+    }
+}
+
+/// the part of the canvas a layer is drawn into, relative to the size of the canvas
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Viewport {
+    pub origin: Point2<f32>,
+    pub size: Vector2<f32>,
+}
+
+impl Viewport {
+    #[inline]
+    pub fn new(origin: Point2<f32>, size: Vector2<f32>) -> Self {
+        Self { origin, size }
+    }
+
+    /// origin and size of the viewport in pixels of the attachment,
+    /// clamped to the attachment since wgpu rejects viewports outside of it
+    #[inline]
+    pub fn within(&self, attachment_size: Vector2<f32>) -> (Point2<f32>, Vector2<f32>) {
+        let (zero, one) = (Vector2::zeros(), Vector2::new(1.0, 1.0));
+        let origin = self.origin.coords.sup(&zero).inf(&one);
+        let size = self.size.sup(&zero).inf(&(one - origin));
+
+        (
+            Point2::from(origin.component_mul(&attachment_size)),
+            size.component_mul(&attachment_size),
+        )
+    }
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self::new(Point2::origin(), Vector2::new(1.0, 1.0))
+    }
+}
+
+/// the clear of cover frames isn't limited to their viewport,
+/// e.g. split screen covers the left and stacks the right viewport
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum CanvasFrame<'a> {
     Cover {
         layer: Uuid,
         camera: Uuid,
         clear_color: [f64; 4],
+        viewport: Viewport,
         _pd: PhantomData<&'a ()>,
     },
     Merge {
         layer: Uuid,
         camera: Uuid,
+        viewport: Viewport,
         _pd: PhantomData<&'a ()>,
     },
     Stack {
         layer: Uuid,
         camera: Uuid,
+        viewport: Viewport,
         _pd: PhantomData<&'a ()>,
     },
 }
@@ -346,27 +887,41 @@ impl<'a> CanvasFrame<'a> {
                 layer,
                 camera,
                 clear_color,
+                viewport,
                 ..
             } => CanvasFrame::Cover {
                 layer,
                 camera,
                 clear_color,
+                viewport,
                 _pd: Default::default(),
             },
-            CanvasFrame::Merge { layer, camera, .. } => CanvasFrame::Merge {
+            CanvasFrame::Merge {
+                layer,
+                camera,
+                viewport,
+                ..
+            } => CanvasFrame::Merge {
                 layer,
                 camera,
+                viewport,
                 _pd: Default::default(),
             },
-            CanvasFrame::Stack { layer, camera, .. } => CanvasFrame::Stack {
+            CanvasFrame::Stack {
                 layer,
                 camera,
+                viewport,
+                ..
+            } => CanvasFrame::Stack {
+                layer,
+                camera,
+                viewport,
                 _pd: Default::default(),
             },
         }
     }
 
-    fn layer(&self) -> Uuid {
+    pub fn layer(&self) -> Uuid {
         match self {
             CanvasFrame::Cover { layer, .. } => *layer,
             CanvasFrame::Merge { layer, .. } => *layer,
@@ -374,18 +929,55 @@ impl<'a> CanvasFrame<'a> {
         }
     }
 
-    fn camera(&self) -> Uuid {
+    pub fn camera(&self) -> Uuid {
         match self {
             CanvasFrame::Cover { camera, .. } => *camera,
             CanvasFrame::Merge { camera, .. } => *camera,
             CanvasFrame::Stack { camera, .. } => *camera,
         }
     }
+
+    pub fn viewport(&self) -> Viewport {
+        match self {
+            CanvasFrame::Cover { viewport, .. } => *viewport,
+            CanvasFrame::Merge { viewport, .. } => *viewport,
+            CanvasFrame::Stack { viewport, .. } => *viewport,
+        }
+    }
+
+    pub(crate) fn set_viewport(&mut self, new_viewport: Viewport) {
+        match self {
+            CanvasFrame::Cover { viewport, .. } => *viewport = new_viewport,
+            CanvasFrame::Merge { viewport, .. } => *viewport = new_viewport,
+            CanvasFrame::Stack { viewport, .. } => *viewport = new_viewport,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LayerSnapshot {
+    pub id: Uuid,
+    pub instances: usize,
+    pub opacity: f32,
+    pub tint: [f32; 3],
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CanvasSnapshot {
+    pub id: Uuid,
+    pub size: [u32; 2],
+    pub priority: usize,
+    pub frame: bool,
+    pub depth: bool,
+    pub frames: Vec<CanvasFrame<'static>>,
+    pub post_effects: usize,
 }
 
 pub struct RealizedCanvas {
     frame_buffer: RealizedView,
-    depth_buffer: RealizedView,
+    depth_buffer: Option<RealizedView>,
+    stencil: bool,
+    post: Option<RealizedPostChain>,
     frames: Vec<CanvasFrame<'static>>,
     swap_chain_sized: bool,
     priority: usize,
@@ -400,14 +992,23 @@ impl RealizedCanvas {
         frame: bool,
         frames: Vec<CanvasFrame<'static>>,
         samples: u32,
+        frame_format: wgpu::TextureFormat,
+        depth_format: Option<DepthFormat>,
         swap_chain_sized: bool,
     ) -> Self {
-        let frame_buffer = RealizedView::frame_buffer(device, size, samples, Some("frame_buffer"));
-        let depth_buffer = RealizedView::depth_buffer(device, size, samples, Some("depth_buffer"));
+        let frame_buffer =
+            RealizedView::frame_buffer(device, size, samples, frame_format, Some("frame_buffer"));
+        let depth_buffer = depth_format.map(|format| {
+            RealizedView::depth_buffer(device, size, samples, format.into(), Some("depth_buffer"))
+        });
 
         Self {
             frame_buffer,
             depth_buffer,
+            stencil: depth_format
+                .map(DepthFormat::has_stencil)
+                .unwrap_or_default(),
+            post: None,
             frames,
             swap_chain_sized,
             priority,
@@ -420,22 +1021,34 @@ pub struct Canvasses {
     canvasses: IndexMap<Uuid, RealizedCanvas>,
     layers: HashMap<Uuid, Counted<RealizedCanvasLayer>>,
     uniform_bind_group_layout: wgpu::BindGroupLayout,
+    post: PostProcessing,
     samples: Samples,
+    frame_format: wgpu::TextureFormat,
+    depth_format: DepthFormat,
     swap_chain_size: [u32; 2],
+    overlay: Option<Uuid>,
 }
 
 impl Canvasses {
     pub fn new(
+        assets: &AssetsClient,
+        device: &wgpu::Device,
         uniform_bind_group_layout: wgpu::BindGroupLayout,
         swap_chain_size: [u32; 2],
         samples: Samples,
+        frame_format: wgpu::TextureFormat,
+        depth_format: DepthFormat,
     ) -> Self {
         Self {
             canvasses: Default::default(),
             layers: Default::default(),
             uniform_bind_group_layout,
+            post: PostProcessing::new(assets, device, frame_format),
             swap_chain_size,
             samples,
+            frame_format,
+            depth_format,
+            overlay: None,
         }
     }
 
@@ -461,15 +1074,46 @@ impl Canvasses {
         }
     }
 
+    pub fn update_canvas_layer_opacity(&mut self, canvas_layer_id: &Uuid, opacity: f32) {
+        if let Some(layer) = self.layers.get_mut(canvas_layer_id) {
+            log::debug!("update canvas layer opacity: {:?}", canvas_layer_id);
+            layer.set_opacity(opacity);
+        }
+    }
+
+    pub fn update_canvas_layer_tint(&mut self, canvas_layer_id: &Uuid, tint: [f32; 3]) {
+        if let Some(layer) = self.layers.get_mut(canvas_layer_id) {
+            log::debug!("update canvas layer tint: {:?}", canvas_layer_id);
+            layer.set_tint(tint);
+        }
+    }
+
+    pub fn update_canvas_layer_culling(&mut self, canvas_layer_id: &Uuid, culling: bool) {
+        if let Some(layer) = self.layers.get_mut(canvas_layer_id) {
+            log::debug!("update canvas layer culling: {:?}", canvas_layer_id);
+            layer.set_culling(culling);
+        }
+    }
+
+    pub fn update_canvas_layer_clip(&mut self, canvas_layer_id: &Uuid, clip: Option<ClipRect>) {
+        if let Some(layer) = self.layers.get_mut(canvas_layer_id) {
+            log::debug!("update canvas layer clip: {:?}", canvas_layer_id);
+            layer.set_clip(clip);
+        }
+    }
+
     pub fn upsert_canvas(
         &mut self,
         device: &wgpu::Device,
         cameras: &mut Cameras,
+        textures: &mut Textures,
         canvas_id: Uuid,
         size: Option<[u32; 2]>,
         priority: usize,
         frame: bool,
+        depth: bool,
         frames: Vec<CanvasFrame<'static>>,
+        post_effects: Vec<PostEffect<Weak>>,
     ) {
         log::debug!("upsert canvas: {:?}", canvas_id);
         for frame in &frames {
@@ -485,16 +1129,36 @@ impl Canvasses {
             cameras.inc_camera(&frame.camera());
         }
 
-        let realized = RealizedCanvas::new(
+        let canvas_size = size.unwrap_or_else(|| self.swap_chain_size);
+        let mut realized = RealizedCanvas::new(
             device,
-            size.unwrap_or_else(|| self.swap_chain_size),
+            canvas_size,
             priority,
             frame,
             frames,
             self.samples.into(),
+            self.frame_format,
+            depth.then(|| self.depth_format),
             size.is_none(),
         );
 
+        if frame {
+            textures.remove_canvas_texture(canvas_id);
+        } else {
+            textures.upsert_canvas_texture(device, canvas_id, canvas_size);
+        }
+
+        if !post_effects.is_empty() {
+            if frame {
+                realized.post = Some(self.post.create_chain(device, canvas_size, post_effects));
+            } else {
+                log::warn!(
+                    "ignore post effects of canvas {:?} as it isn't drawn to the frame",
+                    canvas_id
+                );
+            }
+        }
+
         if let Some(prev) = self.canvasses.insert(canvas_id, realized) {
             for frame in &prev.frames {
                 self.remove_canvas_layer(&frame.layer());
@@ -502,13 +1166,20 @@ impl Canvasses {
             }
         }
 
+        // stable, so canvasses with the same priority are drawn in creation order
         self.canvasses
             .sort_by(|_, v1, _, v2| v1.priority.cmp(&v2.priority));
     }
 
-    pub fn remove_canvas(&mut self, cameras: &mut Cameras, canvas_id: &Uuid) {
+    pub fn remove_canvas(
+        &mut self,
+        cameras: &mut Cameras,
+        textures: &mut Textures,
+        canvas_id: &Uuid,
+    ) {
         if let Some(prev) = self.canvasses.remove(canvas_id) {
             log::debug!("remove canvas: {:?}", canvas_id);
+            textures.remove_canvas_texture(*canvas_id);
             for frame in &prev.frames {
                 self.remove_canvas_layer(&frame.layer());
                 cameras.dec_camera(&frame.camera());
@@ -516,7 +1187,14 @@ impl Canvasses {
         }
     }
 
-    pub fn resize(&mut self, device: &wgpu::Device, size: [u32; 2]) {
+    /// the layer is drawn on top of the last frame canvas in pixels from the top left corner,
+    /// e.g. for the stats overlay and cursor images
+    #[inline]
+    pub fn set_overlay(&mut self, layer_id: Option<Uuid>) {
+        self.overlay = layer_id;
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, textures: &mut Textures, size: [u32; 2]) {
         if self.swap_chain_size == size {
             return;
         }
@@ -534,24 +1212,34 @@ impl Canvasses {
             .collect::<Vec<_>>();
 
         for resize_canvas_id in resize_canvas_ids {
-            let (priority, frame, frames) = {
+            let (priority, frame, depth, frames, post_effects) = {
                 let canvas = self.canvasses.get_mut(&resize_canvas_id).unwrap();
                 (
                     canvas.priority,
                     canvas.frame,
+                    canvas.depth_buffer.is_some(),
                     std::mem::take(&mut canvas.frames),
+                    canvas.post.take().map(|post| post.effects),
                 )
             };
 
-            let realized = RealizedCanvas::new(
+            let mut realized = RealizedCanvas::new(
                 device,
                 size,
                 priority,
                 frame,
                 frames,
                 self.samples.into(),
+                self.frame_format,
+                depth.then(|| self.depth_format),
                 true,
             );
+            realized.post =
+                post_effects.map(|effects| self.post.create_chain(device, size, effects));
+
+            if !frame {
+                textures.upsert_canvas_texture(device, resize_canvas_id, size);
+            }
 
             assert!(self.canvasses.insert(resize_canvas_id, realized).is_some());
         }
@@ -579,6 +1267,126 @@ impl Canvasses {
         }
     }
 
+    pub fn upsert_group(&mut self, layer_id: &Uuid, group_id: Uuid, world: Similarity3<f32>) {
+        if let Some(layer) = self.layers.get_mut(layer_id) {
+            log::debug!("upsert group: {:?}", group_id);
+            layer.upsert_group(group_id, world);
+        }
+    }
+
+    pub fn remove_group(&mut self, layer_id: &Uuid, group_id: &Uuid) {
+        if let Some(layer) = self.layers.get_mut(layer_id) {
+            log::debug!("remove group: {:?}", group_id);
+            layer.remove_group(group_id);
+        }
+    }
+
+    #[inline]
+    pub(crate) fn crt_post_shader(&self) -> &StrongAssetId<WGSLSource> {
+        &self.post.crt_shader
+    }
+
+    /// if any canvas has a post effect with the shader
+    pub fn uses_post_shader(&self, id: &WeakAssetId<WGSLSource>) -> bool {
+        self.canvasses.values().any(|canvas| {
+            canvas
+                .post
+                .iter()
+                .flat_map(|post| &post.effects)
+                .any(|effect| effect.shader.is_same_asset(id))
+        })
+    }
+
+    #[inline]
+    pub fn has_post_shader(&self, id: &WeakAssetId<WGSLSource>) -> bool {
+        self.post.has_shader(id)
+    }
+
+    #[inline]
+    pub fn upsert_post_shader(
+        &mut self,
+        device: &wgpu::Device,
+        id: WeakAssetId<WGSLSource>,
+        source: &WGSLSource,
+    ) -> anyhow::Result<()> {
+        self.post.upsert_shader(device, id, source)
+    }
+
+    #[inline]
+    pub fn remove_post_shader(&mut self, id: &WeakAssetId<WGSLSource>) {
+        self.post.remove_shader(id);
+    }
+
+    pub fn layer_snapshot(&self) -> Vec<LayerSnapshot> {
+        self.layers
+            .iter()
+            .map(|(id, layer)| LayerSnapshot {
+                id: *id,
+                instances: layer.instance_count(),
+                opacity: layer.opacity,
+                tint: layer.tint,
+            })
+            .collect()
+    }
+
+    /// in draw order
+    pub fn canvas_snapshot(&self) -> Vec<CanvasSnapshot> {
+        self.canvasses
+            .iter()
+            .map(|(id, canvas)| CanvasSnapshot {
+                id: *id,
+                size: [
+                    canvas.frame_buffer.size.width,
+                    canvas.frame_buffer.size.height,
+                ],
+                priority: canvas.priority,
+                frame: canvas.frame,
+                depth: canvas.depth_buffer.is_some(),
+                frames: canvas.frames.clone(),
+                post_effects: canvas
+                    .post
+                    .as_ref()
+                    .map(|post| post.effects.len())
+                    .unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    /// instances of all layers, including the ones of layers not drawn by any canvas
+    pub fn instance_count(&self) -> usize {
+        self.layers
+            .values()
+            .map(|layer| layer.instance_count())
+            .sum()
+    }
+
+    /// pixels per world unit of the first canvas frame drawing the layer
+    pub fn layer_px_factor(&self, layer_id: &Uuid, cameras: &Cameras) -> Option<f32> {
+        self.canvasses.values().find_map(|canvas| {
+            let frame = canvas.frames.iter().find(|f| &f.layer() == layer_id)?;
+            let camera = cameras.get(&frame.camera())?;
+            let size = canvas.frame_buffer.size;
+            let (_, frame_size) = frame
+                .viewport()
+                .within(Vector2::new(size.width as f32, size.height as f32));
+            let (_, base) = camera.projection.viewport(frame_size);
+            Some(camera.projection.scaled(base).px_range_factor(base).x)
+        })
+    }
+
+    #[inline]
+    pub fn pick(
+        &self,
+        layer_id: &Uuid,
+        pipelines: &Pipelines,
+        meshes: &Meshes,
+        world_point: Point2<f32>,
+    ) -> Option<Uuid> {
+        self.layers
+            .get(layer_id)?
+            .pick(pipelines, meshes, world_point)
+    }
+
     pub fn remove_instance(&mut self, layer_id: &Uuid, instance_id: &Uuid) {
         if let Some(layer) = self.layers.get_mut(layer_id) {
             log::debug!("remove instance: {:?}", instance_id);
@@ -595,6 +1403,7 @@ impl Canvasses {
         pipelines: &Pipelines,
         textures: &Textures,
         meshes: &Meshes,
+        grading: &ColorGrading,
     ) -> anyhow::Result<()> {
         let frame = surface.get_current_texture()?;
         let target = frame
@@ -605,35 +1414,125 @@ impl Canvasses {
             label: Some("Render Encoder"),
         });
 
-        for (_, canvas) in &mut self.canvasses {
+        self.encode(
+            device,
+            cameras,
+            pipelines,
+            textures,
+            meshes,
+            &target,
+            &mut encoder,
+        )?;
+        grading.draw(&mut encoder, &target);
+
+        queue.submit(std::iter::once(encoder.finish()));
+        frame.present();
+
+        Ok(())
+    }
+
+    /// draws the canvasses into an offscreen frame instead of the surface and reads it back
+    pub fn capture(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        cameras: &Cameras,
+        pipelines: &Pipelines,
+        textures: &Textures,
+        meshes: &Meshes,
+        grading: &ColorGrading,
+        size: [u32; 2],
+    ) -> anyhow::Result<DynamicImage> {
+        if size.contains(&0) {
+            return Err(anyhow::anyhow!("can't capture a frame of size {:?}", size));
+        }
+
+        let capture = FrameCapture::new(device, size, self.frame_format);
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Capture Encoder"),
+        });
+
+        self.encode(
+            device,
+            cameras,
+            pipelines,
+            textures,
+            meshes,
+            capture.target(),
+            &mut encoder,
+        )?;
+        grading.draw(&mut encoder, capture.target());
+        capture.copy(&mut encoder);
+
+        queue.submit(std::iter::once(encoder.finish()));
+        capture.read(device)
+    }
+
+    fn encode(
+        &mut self,
+        device: &wgpu::Device,
+        cameras: &Cameras,
+        pipelines: &Pipelines,
+        textures: &Textures,
+        meshes: &Meshes,
+        target: &wgpu::TextureView,
+        encoder: &mut wgpu::CommandEncoder,
+    ) -> anyhow::Result<()> {
+        let overlay_canvas = self
+            .canvasses
+            .iter()
+            .rev()
+            .find(|(_, canvas)| canvas.frame)
+            .map(|(id, _)| *id);
+
+        for (canvas_id, canvas) in &mut self.canvasses {
+            let post = canvas.post.as_ref().filter(|post| self.post.is_ready(post));
+            let resolve_target = if canvas.frame {
+                Some(post.map(|post| post.source()).unwrap_or(target))
+            } else {
+                textures.canvas_target(*canvas_id)
+            };
+
             for canvas_frame in &mut canvas.frames {
-                let (layer_id, camera_id, color_load_ops, depth_load_ops) = match canvas_frame {
-                    CanvasFrame::Cover {
-                        layer,
-                        camera,
-                        clear_color: [r, g, b, a],
-                        ..
-                    } => {
-                        let color = wgpu::Color {
-                            r: *r,
-                            g: *g,
-                            b: *b,
-                            a: *a,
-                        };
-                        (
+                let viewport = canvas_frame.viewport();
+                let (layer_id, camera_id, color_load_ops, depth_load_ops, stencil_load_ops) =
+                    match canvas_frame {
+                        CanvasFrame::Cover {
+                            layer,
+                            camera,
+                            clear_color: [r, g, b, a],
+                            ..
+                        } => {
+                            let color = wgpu::Color {
+                                r: *r,
+                                g: *g,
+                                b: *b,
+                                a: *a,
+                            };
+                            (
+                                layer,
+                                camera,
+                                wgpu::LoadOp::Clear(color),
+                                wgpu::LoadOp::Clear(1.0),
+                                wgpu::LoadOp::Clear(0),
+                            )
+                        }
+                        CanvasFrame::Merge { layer, camera, .. } => (
+                            layer,
+                            camera,
+                            wgpu::LoadOp::Load,
+                            wgpu::LoadOp::Load,
+                            wgpu::LoadOp::Load,
+                        ),
+                        CanvasFrame::Stack { layer, camera, .. } => (
                             layer,
                             camera,
-                            wgpu::LoadOp::Clear(color),
+                            wgpu::LoadOp::Load,
                             wgpu::LoadOp::Clear(1.0),
-                        )
-                    }
-                    CanvasFrame::Merge { layer, camera, .. } => {
-                        (layer, camera, wgpu::LoadOp::Load, wgpu::LoadOp::Load)
-                    }
-                    CanvasFrame::Stack { layer, camera, .. } => {
-                        (layer, camera, wgpu::LoadOp::Load, wgpu::LoadOp::Clear(1.0))
-                    }
-                };
+                            wgpu::LoadOp::Clear(0),
+                        ),
+                    };
 
                 let camera = cameras
                     .get(camera_id)
@@ -649,20 +1548,74 @@ impl Canvasses {
                     pipelines,
                     textures,
                     meshes,
-                    canvas.frame.then(|| &target),
+                    resolve_target,
                     &canvas.frame_buffer,
                     color_load_ops,
-                    &canvas.depth_buffer.view,
-                    depth_load_ops,
-                    &mut encoder,
+                    canvas.depth_buffer.as_ref().map(|depth_buffer| {
+                        (
+                            &depth_buffer.view,
+                            depth_load_ops,
+                            canvas.stencil.then(|| stencil_load_ops),
+                        )
+                    }),
+                    encoder,
                     camera,
+                    viewport,
                 )?;
             }
-        }
 
-        queue.submit(std::iter::once(encoder.finish()));
-        frame.present();
+            if let Some(post) = post {
+                self.post.draw(encoder, post, target);
+            }
+
+            // the overlay is drawn after the post effects, so they don't distort it
+            let overlay = self
+                .overlay
+                .filter(|_| overlay_canvas == Some(*canvas_id))
+                .and_then(|layer_id| self.layers.get_mut(&layer_id));
+            if let Some(layer) = overlay {
+                let size = canvas.frame_buffer.size;
+                let (overlay_target, overlay_load_ops) = match post {
+                    Some(post) => (
+                        Some(post.overlay()),
+                        wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    ),
+                    None => (resolve_target, wgpu::LoadOp::Load),
+                };
+                layer.draw(
+                    device,
+                    pipelines,
+                    textures,
+                    meshes,
+                    overlay_target,
+                    &canvas.frame_buffer,
+                    overlay_load_ops,
+                    canvas.depth_buffer.as_ref().map(|depth_buffer| {
+                        (
+                            &depth_buffer.view,
+                            wgpu::LoadOp::Clear(1.0),
+                            canvas.stencil.then(|| wgpu::LoadOp::Clear(0)),
+                        )
+                    }),
+                    encoder,
+                    overlay_camera([size.width, size.height]),
+                    Viewport::default(),
+                )?;
+
+                if let Some(post) = post {
+                    self.post.draw_overlay(encoder, post, target);
+                }
+            }
+        }
 
         Ok(())
     }
 }
+
+/// maps the world to the pixels of the attachment with the origin in the top left corner
+fn overlay_camera(size: [u32; 2]) -> RawCamera {
+    let rect = Vector2::new(size[0] as f32, size[1] as f32);
+    let mut projection = OrthographicProjection::new(rect);
+    projection.scale_mode = CameraScaleMode::Stretch;
+    RawCamera::from_parts(Point2::new(rect.x / 2.0, -rect.y / 2.0), projection)
+}
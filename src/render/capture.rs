@@ -0,0 +1,99 @@
+use crate::render::view::RealizedView;
+use image::{DynamicImage, ImageBuffer};
+use std::num::NonZeroU32;
+
+/// offscreen copy of the frame that can be read back from the gpu
+pub struct FrameCapture {
+    target: RealizedView,
+    buffer: wgpu::Buffer,
+    padded_bytes_per_row: u32,
+    bgra: bool,
+}
+
+impl FrameCapture {
+    const BYTES_PER_PIXEL: u32 = 4;
+
+    pub fn new(device: &wgpu::Device, size: [u32; 2], format: wgpu::TextureFormat) -> Self {
+        let target = RealizedView::capture_buffer(device, size, format, Some("capture_buffer"));
+
+        // wgpu requires the rows of a texture to buffer copy to be aligned
+        let unpadded_bytes_per_row = size[0] * Self::BYTES_PER_PIXEL;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = (unpadded_bytes_per_row + align - 1) / align * align;
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Capture Buffer"),
+            size: (padded_bytes_per_row * size[1]) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            target,
+            buffer,
+            padded_bytes_per_row,
+            bgra: matches!(
+                format,
+                wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+            ),
+        }
+    }
+
+    #[inline]
+    pub fn target(&self) -> &wgpu::TextureView {
+        &self.target.view
+    }
+
+    pub fn copy(&self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &self.target.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &self.buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(self.padded_bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            self.target.size,
+        );
+    }
+
+    /// blocks until the copy submitted to the queue is available
+    pub fn read(self, device: &wgpu::Device) -> anyhow::Result<DynamicImage> {
+        let width = self.target.size.width;
+        let height = self.target.size.height;
+
+        let slice = self.buffer.slice(..);
+        let mapping = slice.map_async(wgpu::MapMode::Read);
+        device.poll(wgpu::Maintain::Wait);
+        futures::executor::block_on(mapping)
+            .map_err(|e| anyhow::anyhow!("Failed to map capture buffer: {:?}", e))?;
+
+        let mut pixels = Vec::with_capacity((width * height * Self::BYTES_PER_PIXEL) as usize);
+        {
+            let data = slice.get_mapped_range();
+            for row in data.chunks_exact(self.padded_bytes_per_row as usize) {
+                let row = &row[..(width * Self::BYTES_PER_PIXEL) as usize];
+                if self.bgra {
+                    for bgra in row.chunks_exact(Self::BYTES_PER_PIXEL as usize) {
+                        pixels.extend_from_slice(&[bgra[2], bgra[1], bgra[0], bgra[3]]);
+                    }
+                } else {
+                    pixels.extend_from_slice(row);
+                }
+            }
+        }
+        self.buffer.unmap();
+
+        let image = ImageBuffer::from_raw(width, height, pixels)
+            .ok_or_else(|| anyhow::anyhow!("Capture buffer doesn't match the frame size"))?;
+
+        Ok(DynamicImage::ImageRgba8(image))
+    }
+}
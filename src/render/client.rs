@@ -1,5 +1,6 @@
 mod camera;
 mod curve;
+mod group;
 mod instance;
 mod rectangle;
 mod sprite;
@@ -7,23 +8,30 @@ mod text;
 
 pub use camera::*;
 pub use curve::*;
+pub use group::*;
 pub use instance::*;
 pub use rectangle::*;
 pub use sprite::*;
 pub use text::*;
 
-use crate::asset::StrongAssetId;
-use crate::render::canvas::CanvasFrame;
+use crate::asset::{Strong, StrongAssetId, WeakAssetId};
+use crate::platform::message::FrameRequestedEvent;
+use crate::render::canvas::{CanvasFrame, Canvasses, ClipRect, Viewport};
 use crate::render::mesh::{Mesh, Meshes};
 use crate::render::message::{
     CanvasEvent, CanvasEventCreated, CanvasEventKind, CanvasLayerEvent, CanvasLayerEventKind,
+    CaptureFrameEvent, DebugSceneRequestEvent, PickRequestEvent, SetColorGradingEvent,
+    SetPresentModeEvent, UpdateTextureRegionEvent,
 };
-use crate::render::pipeline::{Pipeline, Pipelines};
+use crate::render::pipeline::{Pipeline, Pipelines, WGSLSource};
+use crate::render::post::PostEffect;
 use crate::render::text::{Font, Texts};
 use crate::render::view::{Texture, Textures};
+use crate::render::{PresentMode, Samples};
 use nalgebra::{Point2, Vector2, Vector3};
 use parking_lot::Mutex;
 use roundabout::prelude::MessageSender;
+use std::cell::Cell;
 use std::marker::PhantomData;
 use std::ops::Deref;
 use std::rc::Rc;
@@ -34,13 +42,39 @@ use uuid::Uuid;
 #[derive(Clone)]
 pub struct RenderClient {
     pub defaults: Rc<RenderDefaults>,
+    samples: Samples,
+    supported_samples: Rc<[Samples]>,
+    animations: Rc<SpriteAnimations>,
     sender: MessageSender,
 }
 
 impl RenderClient {
     #[inline]
-    pub fn new(defaults: Rc<RenderDefaults>, sender: MessageSender) -> Self {
-        Self { defaults, sender }
+    pub fn new(
+        defaults: Rc<RenderDefaults>,
+        samples: Samples,
+        supported_samples: Vec<Samples>,
+        sender: MessageSender,
+    ) -> Self {
+        Self {
+            defaults,
+            samples,
+            supported_samples: supported_samples.into(),
+            animations: Default::default(),
+            sender,
+        }
+    }
+
+    /// the samples the renderer was created with
+    #[inline]
+    pub fn samples(&self) -> Samples {
+        self.samples
+    }
+
+    /// the samples supported by the adapter, e.g. to only offer valid options in a settings menu
+    #[inline]
+    pub fn supported_samples(&self) -> &[Samples] {
+        &self.supported_samples
     }
 
     #[inline]
@@ -50,7 +84,11 @@ impl RenderClient {
 
     #[inline]
     pub fn layer(&self) -> CanvasLayer {
-        CanvasLayer::new(self.defaults.clone(), self.sender.clone())
+        CanvasLayer::new(
+            self.defaults.clone(),
+            self.animations.clone(),
+            self.sender.clone(),
+        )
     }
 
     #[inline]
@@ -62,6 +100,55 @@ impl RenderClient {
     pub fn canvas_general(&self) -> CanvasBuilder<GeneralCanvas> {
         Canvas::general(&self.sender)
     }
+
+    #[inline]
+    pub fn set_color_grading(&self, multiply: [f32; 4], add: [f32; 4]) {
+        self.sender.send(SetColorGradingEvent { multiply, add });
+    }
+
+    #[inline]
+    pub fn set_present_mode(&self, present_mode: PresentMode) {
+        self.sender.send(SetPresentModeEvent { present_mode });
+    }
+
+    /// writes the rgba pixels into the rectangle of the texture layer, the size of the rectangle
+    /// is in pixels and the rows of the pixels are tightly packed,
+    /// textures with mipmaps or compressed formats can't be updated
+    #[inline]
+    pub fn update_texture_region(
+        &self,
+        texture: &StrongAssetId<Texture>,
+        layer: u32,
+        origin: [u32; 2],
+        size: [u32; 2],
+        rgba: Vec<u8>,
+    ) {
+        self.sender.send(UpdateTextureRegionEvent {
+            texture: texture.to_weak(),
+            layer,
+            origin,
+            size,
+            rgba,
+        });
+    }
+
+    /// the render server answers with a `CapturedFrameEvent`
+    #[inline]
+    pub fn capture_frame(&self) {
+        self.sender.send(CaptureFrameEvent);
+    }
+
+    /// the render server answers with a `DebugSceneEvent`
+    #[inline]
+    pub fn request_debug_scene(&self) {
+        self.sender.send(DebugSceneRequestEvent);
+    }
+
+    /// advances the animated sprites of all layers, the sim server calls this on every frame
+    #[inline]
+    pub fn animate(&self, event: &FrameRequestedEvent) {
+        self.animations.advance(event.elapsed, &self.sender);
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -73,6 +160,9 @@ pub struct RenderDefaults {
     pub white_texture: StrongAssetId<Texture>,
     pub empty_mesh: StrongAssetId<Mesh>,
     pub unit_square_mesh: StrongAssetId<Mesh>,
+    /// post shader with curvature, scanline and vignette strength as params,
+    /// e.g. `[0.1, 0.3, 0.5, 0.0]` for a retro look
+    pub crt_post_shader: StrongAssetId<WGSLSource>,
 }
 
 impl RenderDefaults {
@@ -81,6 +171,7 @@ impl RenderDefaults {
         textures: &Textures,
         meshes: &Meshes,
         texts: &Texts,
+        canvasses: &Canvasses,
     ) -> anyhow::Result<Self> {
         Ok(Self {
             unlit_pipeline: pipelines.unlit_pipeline.clone(),
@@ -90,6 +181,7 @@ impl RenderDefaults {
             white_texture: textures.white_texture.clone(),
             empty_mesh: meshes.empty_mesh.clone(),
             unit_square_mesh: meshes.unit_square_mesh.clone(),
+            crt_post_shader: canvasses.crt_post_shader().clone(),
         })
     }
 }
@@ -103,12 +195,20 @@ pub trait LayerSpawner {
 #[derive(Debug)]
 struct InnerCanvasLayer {
     id: Uuid,
+    opacity: Cell<f32>,
+    tint: Cell<[f32; 3]>,
+    clip: Cell<Option<ClipRect>>,
     defaults: Rc<RenderDefaults>,
+    animations: Rc<SpriteAnimations>,
     sender: MessageSender,
 }
 
 impl InnerCanvasLayer {
-    fn new(defaults: Rc<RenderDefaults>, sender: MessageSender) -> Self {
+    fn new(
+        defaults: Rc<RenderDefaults>,
+        animations: Rc<SpriteAnimations>,
+        sender: MessageSender,
+    ) -> Self {
         let id = Uuid::new_v4();
 
         sender.send(CanvasLayerEvent {
@@ -118,7 +218,11 @@ impl InnerCanvasLayer {
 
         InnerCanvasLayer {
             id,
+            opacity: Cell::new(1.0),
+            tint: Cell::new([1.0; 3]),
+            clip: Cell::new(None),
             defaults,
+            animations,
             sender,
         }
     }
@@ -138,8 +242,12 @@ impl Drop for InnerCanvasLayer {
 pub struct CanvasLayer(Rc<InnerCanvasLayer>);
 
 impl CanvasLayer {
-    pub(crate) fn new(defaults: Rc<RenderDefaults>, sender: MessageSender) -> Self {
-        Self(Rc::new(InnerCanvasLayer::new(defaults, sender)))
+    pub(crate) fn new(
+        defaults: Rc<RenderDefaults>,
+        animations: Rc<SpriteAnimations>,
+        sender: MessageSender,
+    ) -> Self {
+        Self(Rc::new(InnerCanvasLayer::new(defaults, animations, sender)))
     }
 
     #[inline]
@@ -152,6 +260,72 @@ impl CanvasLayer {
         &self.0.defaults
     }
 
+    #[inline]
+    pub fn opacity(&self) -> f32 {
+        self.0.opacity.get()
+    }
+
+    /// the opacity is multiplied into the alpha of all instances drawn through this layer
+    #[inline]
+    pub fn set_opacity(&self, opacity: f32) {
+        self.0.opacity.set(opacity);
+        self.0.sender.send(CanvasLayerEvent {
+            id: self.0.id,
+            kind: CanvasLayerEventKind::Opacity(opacity),
+        });
+    }
+
+    #[inline]
+    pub fn tint(&self) -> [f32; 3] {
+        self.0.tint.get()
+    }
+
+    /// the tint is multiplied into the color of all instances drawn through this layer,
+    /// e.g. to darken a whole scene without touching every instance, fade it with the opacity
+    #[inline]
+    pub fn set_tint(&self, tint: [f32; 3]) {
+        self.0.tint.set(tint);
+        self.0.sender.send(CanvasLayerEvent {
+            id: self.0.id,
+            kind: CanvasLayerEventKind::Tint(tint),
+        });
+    }
+
+    #[inline]
+    pub fn clip(&self) -> Option<ClipRect> {
+        self.0.clip.get()
+    }
+
+    /// content of the layer outside of the world space rect is discarded, `None` disables clipping
+    #[inline]
+    pub fn set_clip(&self, clip: Option<ClipRect>) {
+        self.0.clip.set(clip);
+        self.0.sender.send(CanvasLayerEvent {
+            id: self.0.id,
+            kind: CanvasLayerEventKind::Clip(clip),
+        });
+    }
+
+    /// the render server answers with a `PickedEvent` containing the topmost instance,
+    /// e.g. the id of a sprite, at the world point
+    #[inline]
+    pub fn pick(&self, world_point: Point2<f32>) {
+        self.0.sender.send(PickRequestEvent {
+            layer: self.0.id,
+            world_point,
+        });
+    }
+
+    /// instances outside of the camera are skipped by default,
+    /// disabling it draws every instance of the layer, e.g. to debug missing instances
+    #[inline]
+    pub fn set_culling(&self, culling: bool) {
+        self.0.sender.send(CanvasLayerEvent {
+            id: self.0.id,
+            kind: CanvasLayerEventKind::Culling(culling),
+        });
+    }
+
     #[inline]
     pub fn spawn<T: LayerSpawner>(&self, spawner: T) -> T::Handle {
         spawner.spawn(self)
@@ -162,6 +336,11 @@ impl CanvasLayer {
         &self.0.sender
     }
 
+    #[inline]
+    pub(crate) fn animations(&self) -> &Rc<SpriteAnimations> {
+        &self.0.animations
+    }
+
     #[inline]
     pub fn parts(&self) -> (Uuid, &RenderDefaults, &MessageSender) {
         let inner = self.0.deref();
@@ -181,6 +360,8 @@ pub struct CanvasBuilder<'a, T> {
     frames: Vec<CanvasFrame<'a>>,
     priority: usize,
     frame: bool,
+    depth: bool,
+    post_effects: Vec<PostEffect<Strong>>,
     sender: &'a MessageSender,
     _pd: PhantomData<T>,
 }
@@ -193,13 +374,31 @@ impl<'a> CanvasBuilder<'a, GeneralCanvas> {
     }
 }
 
+impl<'a> CanvasBuilder<'a, FrameCanvas> {
+    /// post effects are applied in order to the output of the canvas before it is presented
+    #[inline]
+    pub fn with_post_effect(mut self, effect: PostEffect<Strong>) -> Self {
+        self.post_effects.push(effect);
+        self
+    }
+}
+
 impl<'a, T> CanvasBuilder<'a, T> {
+    /// canvasses are drawn in ascending priority,
+    /// a canvas used as texture has to be drawn before the canvasses sampling it
     #[inline]
     pub fn with_priority(mut self, priority: usize) -> Self {
         self.priority = priority;
         self
     }
 
+    /// canvasses without depth don't allocate a depth buffer, e.g. for flat 2d ui
+    #[inline]
+    pub fn with_depth(mut self, depth: bool) -> Self {
+        self.depth = depth;
+        self
+    }
+
     #[inline]
     pub fn cover_layer(
         mut self,
@@ -211,6 +410,7 @@ impl<'a, T> CanvasBuilder<'a, T> {
             layer: layer.id(),
             camera: camera.id(),
             clear_color,
+            viewport: Default::default(),
             _pd: Default::default(),
         });
         self
@@ -221,6 +421,7 @@ impl<'a, T> CanvasBuilder<'a, T> {
         self.frames.push(CanvasFrame::Merge {
             layer: layer.id(),
             camera: camera.id(),
+            viewport: Default::default(),
             _pd: Default::default(),
         });
         self
@@ -231,11 +432,22 @@ impl<'a, T> CanvasBuilder<'a, T> {
         self.frames.push(CanvasFrame::Stack {
             layer: layer.id(),
             camera: camera.id(),
+            viewport: Default::default(),
             _pd: Default::default(),
         });
         self
     }
 
+    /// draws the previously added layer into a part of the canvas, e.g. for split screen,
+    /// the viewport is clamped to the canvas
+    #[inline]
+    pub fn with_viewport(mut self, viewport: Viewport) -> Self {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.set_viewport(viewport);
+        }
+        self
+    }
+
     #[inline]
     pub fn finish(self) -> Canvas {
         let id = Uuid::new_v4();
@@ -244,11 +456,13 @@ impl<'a, T> CanvasBuilder<'a, T> {
             size: self.size,
             priority: self.priority,
             frame: self.frame,
+            depth: self.depth,
             frames: self
                 .frames
                 .into_iter()
                 .map(CanvasFrame::into_static)
                 .collect(),
+            post_effects: self.post_effects.iter().map(PostEffect::to_weak).collect(),
         })));
 
         self.sender.send(CanvasEvent { id, kind });
@@ -256,8 +470,10 @@ impl<'a, T> CanvasBuilder<'a, T> {
         Canvas {
             id,
             size: self.size,
+            texture: (!self.frame).then(|| WeakAssetId::uuid(id).untracked()),
             sender: self.sender.to_owned(),
             priority: self.priority,
+            _post_effects: self.post_effects,
         }
     }
 }
@@ -267,7 +483,9 @@ pub struct Canvas {
     id: Uuid,
     size: Option<[u32; 2]>,
     priority: usize,
+    texture: Option<StrongAssetId<Texture>>,
     sender: MessageSender,
+    _post_effects: Vec<PostEffect<Strong>>,
 }
 
 impl Canvas {
@@ -277,6 +495,8 @@ impl Canvas {
             frames: Default::default(),
             priority: 0,
             frame: true,
+            depth: true,
+            post_effects: Vec::default(),
             sender,
             _pd: Default::default(),
         }
@@ -288,6 +508,8 @@ impl Canvas {
             frames: Default::default(),
             priority: 0,
             frame: false,
+            depth: true,
+            post_effects: Vec::default(),
             sender,
             _pd: Default::default(),
         }
@@ -297,6 +519,17 @@ impl Canvas {
     pub fn id(&self) -> Uuid {
         self.id
     }
+
+    /// the output of a general canvas, e.g. for sprites showing another view of the world
+    #[inline]
+    pub fn texture(&self) -> Option<&StrongAssetId<Texture>> {
+        self.texture.as_ref()
+    }
+
+    #[inline]
+    pub fn texture_id(&self) -> Option<WeakAssetId<Texture>> {
+        self.texture.as_ref().map(StrongAssetId::to_weak)
+    }
 }
 
 impl Drop for Canvas {
@@ -324,3 +557,7 @@ fn f32_one() -> f32 {
 fn arr4_one() -> [f32; 4] {
     [1.0, 1.0, 1.0, 1.0]
 }
+
+fn bool_true() -> bool {
+    true
+}
@@ -39,13 +39,18 @@ impl Camera {
         CameraModify(self)
     }
 
+    /// the transform is relative to the center of the base,
+    /// which is larger than the viewport of letterboxed cameras
     #[inline]
     pub fn relative_to_world(&self, transform: Vector2<f64>, base: Vector2<f32>) -> Point2<f64> {
-        let scaled = self.projection.scaled(base);
+        let (_, viewport) = self.projection.viewport(base);
+        let scaled = self.projection.scaled(viewport);
         let zoomed = scaled.zoomed();
+        let x = transform.x * base.x as f64 / viewport.x as f64;
+        let y = transform.y * base.y as f64 / viewport.y as f64;
         Point2::new(
-            zoomed.x as f64 * transform.x + self.eye.x as f64,
-            zoomed.y as f64 * transform.y + self.eye.y as f64,
+            zoomed.x as f64 * x + self.eye.x as f64,
+            zoomed.y as f64 * y + self.eye.y as f64,
         )
     }
 }
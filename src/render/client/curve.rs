@@ -1,9 +1,10 @@
 use crate::asset::{AssetId, Strong};
-use crate::render::client::{CanvasLayer, LayerSpawner, RenderDefaults};
-use crate::render::curve::{Path, RawCurve, StrokeOptions};
+use crate::render::client::{CanvasLayer, Group, LayerSpawner, RenderDefaults};
+use crate::render::curve::{Path, RawCurve, Segment, StrokeOptions, WorldOrScreen};
 use crate::render::message::{CurveEvent, CurveEventKind};
 use crate::render::pipeline::Pipeline;
-use nalgebra::{Point2, Rotation2, Similarity2, Vector2};
+use crate::util::{Bounded, Bounds};
+use nalgebra::{Isometry2, Point2, Rotation2, Similarity2, Vector2};
 use roundabout::prelude::MessageSender;
 use serde::Deserialize;
 use std::ops::{Deref, DerefMut};
@@ -32,6 +33,8 @@ pub struct CurveBuilder<S> {
     pub world: Similarity2<f32>,
     #[serde(default)]
     pub world_z_index: f32,
+    #[serde(skip)]
+    pub group: Option<Uuid>,
     #[serde(default)]
     pub hidden: bool,
 }
@@ -97,6 +100,13 @@ impl<S> CurveBuilder<S> {
         self
     }
 
+    /// the world becomes relative to the one of the group, which has to be on the same layer
+    #[inline]
+    pub fn with_group(mut self, group: &Group) -> Self {
+        self.group = Some(group.id());
+        self
+    }
+
     #[inline]
     pub fn with_hidden(mut self, hidden: bool) -> Self {
         self.hidden = hidden;
@@ -136,6 +146,7 @@ impl CurveBuilder<Strong> {
             tint: self.tint,
             world: self.world,
             world_z_index: self.world_z_index,
+            group: self.group,
         }
     }
 }
@@ -162,6 +173,7 @@ impl<S> Default for CurveBuilder<S> {
             tint: super::arr4_one(),
             world: Similarity2::identity(),
             world_z_index: 0.0,
+            group: None,
             hidden: false,
         }
     }
@@ -215,6 +227,44 @@ impl Curve {
     }
 }
 
+impl Bounded for Curve {
+    /// covers the control points of the path, widened by half of a world space line width
+    fn bounds(&self) -> Bounds {
+        let mut points = Vec::with_capacity(self.path.segments().len());
+        for segment in self.path.segments() {
+            match *segment {
+                Segment::Begin(point) | Segment::Line(point) => points.push(point),
+                Segment::Quadratic { ctrl, to } => points.extend([ctrl, to]),
+                Segment::Cubic { ctrl1, ctrl2, to } => points.extend([ctrl1, ctrl2, to]),
+                Segment::End | Segment::Close => {}
+            }
+        }
+
+        let first = points.first().copied().unwrap_or_else(Point2::origin);
+        let (mut min, mut max) = points
+            .iter()
+            .fold((first, first), |(min, max), p| (min.inf(p), max.sup(p)));
+
+        if self.stroke.line_width_space == WorldOrScreen::World {
+            let half_width = Vector2::repeat(self.stroke.line_width / 2.0);
+            min -= half_width;
+            max += half_width;
+        }
+
+        let (min, max) = (
+            min.coords.component_mul(&self.scale),
+            max.coords.component_mul(&self.scale),
+        );
+        let model = self.world * Isometry2::new(self.position.coords, self.rotation.angle());
+
+        let o = model * Point2::from(max);
+        let w = model * Point2::new(max.x, min.y);
+        let h = model * Point2::new(min.x, max.y);
+
+        Bounds { o, w, h }
+    }
+}
+
 impl Deref for Curve {
     type Target = RawCurve<Strong>;
 
@@ -0,0 +1,172 @@
+use crate::render::client::{CanvasLayer, LayerSpawner};
+use crate::render::message::{GroupEvent, GroupEventKind};
+use nalgebra::{Similarity2, Similarity3, Translation3, UnitQuaternion, Vector3};
+use roundabout::prelude::MessageSender;
+use serde::Deserialize;
+use std::ops::{Deref, DerefMut};
+use uuid::Uuid;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RawGroup {
+    pub world: Similarity2<f32>,
+    pub world_z_index: f32,
+}
+
+impl RawGroup {
+    fn to_world(&self) -> Similarity3<f32> {
+        Similarity3::from_parts(
+            Translation3::new(
+                self.world.isometry.translation.x,
+                self.world.isometry.translation.y,
+                self.world_z_index,
+            ),
+            UnitQuaternion::from_axis_angle(
+                &Vector3::z_axis(),
+                self.world.isometry.rotation.angle(),
+            ),
+            self.world.scaling(),
+        )
+    }
+}
+
+#[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupBuilder {
+    #[serde(default = "Similarity2::identity")]
+    pub world: Similarity2<f32>,
+    #[serde(default)]
+    pub world_z_index: f32,
+}
+
+impl GroupBuilder {
+    #[inline]
+    pub fn with_world(mut self, world: Similarity2<f32>) -> Self {
+        self.world = world;
+        self
+    }
+
+    #[inline]
+    pub fn with_world_z_index(mut self, world_z_index: f32) -> Self {
+        self.world_z_index = world_z_index;
+        self
+    }
+
+    fn finalize(self, layer: &CanvasLayer) -> Group {
+        let id = Uuid::new_v4();
+        let (layer_uuid, _, sender) = layer.parts();
+        let raw = RawGroup {
+            world: self.world,
+            world_z_index: self.world_z_index,
+        };
+
+        sender.send(GroupEvent {
+            id,
+            layer: layer_uuid,
+            kind: GroupEventKind::Created(raw.to_world()),
+        });
+
+        Group {
+            id,
+            layer: layer_uuid,
+            raw,
+            sender: sender.to_owned(),
+        }
+    }
+}
+
+impl LayerSpawner for GroupBuilder {
+    type Handle = Group;
+
+    #[inline]
+    fn spawn(self, layer: &CanvasLayer) -> Self::Handle {
+        self.finalize(layer)
+    }
+}
+
+impl Default for GroupBuilder {
+    fn default() -> Self {
+        Self {
+            world: Similarity2::identity(),
+            world_z_index: 0.0,
+        }
+    }
+}
+
+/// shared world of the objects spawned with it on the same layer,
+/// e.g. a character and its held item, their own world is relative to the one of the group
+#[derive(Debug)]
+pub struct Group {
+    id: Uuid,
+    layer: Uuid,
+    raw: RawGroup,
+    sender: MessageSender,
+}
+
+impl Group {
+    #[inline]
+    pub fn builder() -> GroupBuilder {
+        GroupBuilder::default()
+    }
+
+    #[inline]
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    #[inline]
+    pub fn modify(&mut self) -> GroupModify {
+        GroupModify { underlying: self }
+    }
+}
+
+impl Deref for Group {
+    type Target = RawGroup;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.raw
+    }
+}
+
+impl Drop for Group {
+    #[inline]
+    fn drop(&mut self) {
+        self.sender.send(GroupEvent {
+            id: self.id,
+            layer: self.layer,
+            kind: GroupEventKind::Dropped,
+        });
+    }
+}
+
+#[derive(Debug)]
+pub struct GroupModify<'a> {
+    underlying: &'a mut Group,
+}
+
+impl<'a> Deref for GroupModify<'a> {
+    type Target = RawGroup;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.underlying.raw
+    }
+}
+
+impl<'a> DerefMut for GroupModify<'a> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.underlying.raw
+    }
+}
+
+impl<'a> Drop for GroupModify<'a> {
+    #[inline]
+    fn drop(&mut self) {
+        self.underlying.sender.send(GroupEvent {
+            id: self.underlying.id,
+            layer: self.underlying.layer,
+            kind: GroupEventKind::Modified(self.underlying.raw.to_world()),
+        });
+    }
+}
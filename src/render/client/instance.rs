@@ -1,6 +1,6 @@
 use crate::asset::{AssetId, Strong};
 use crate::render::canvas::RawInstance;
-use crate::render::client::{CanvasLayer, LayerSpawner, RenderDefaults};
+use crate::render::client::{CanvasLayer, Group, LayerSpawner, RenderDefaults};
 use crate::render::mesh::Mesh;
 use crate::render::message::{InstanceEvent, InstanceEventKind};
 use crate::render::pipeline::Pipeline;
@@ -28,8 +28,12 @@ pub struct InstanceBuilder<S> {
     pub scale: Vector3<f32>,
     #[serde(default = "super::arr4_one")]
     pub tint: [f32; 4],
+    #[serde(default)]
+    pub custom: [f32; 4],
     #[serde(default = "Similarity3::identity")]
     pub world: Similarity3<f32>,
+    #[serde(skip)]
+    pub group: Option<Uuid>,
     #[serde(default)]
     pub hidden: bool,
 }
@@ -77,12 +81,26 @@ impl<S> InstanceBuilder<S> {
         self
     }
 
+    /// passed to the shader at location 3, e.g. a fill fraction
+    #[inline]
+    pub fn with_custom(mut self, custom: [f32; 4]) -> Self {
+        self.custom = custom;
+        self
+    }
+
     #[inline]
     pub fn with_world(mut self, world: Similarity3<f32>) -> Self {
         self.world = world;
         self
     }
 
+    /// the world becomes relative to the one of the group, which has to be on the same layer
+    #[inline]
+    pub fn with_group(mut self, group: &Group) -> Self {
+        self.group = Some(group.id());
+        self
+    }
+
     #[inline]
     pub fn with_hidden(mut self, hidden: bool) -> Self {
         self.hidden = hidden;
@@ -126,10 +144,14 @@ impl InstanceBuilder<Strong> {
                 .texture
                 .unwrap_or_else(|| defaults.white_texture.clone()),
             texture_layer: 0,
+            flip: Default::default(),
             model: self.model,
             scale: self.scale,
             tint: self.tint,
+            custom: self.custom,
+            effects: Default::default(),
             world: self.world,
+            group: self.group,
         }
     }
 }
@@ -153,7 +175,9 @@ impl<S> Default for InstanceBuilder<S> {
             model: Isometry3::identity(),
             scale: super::vector3_one(),
             tint: super::arr4_one(),
+            custom: [0.0; 4],
             world: Similarity3::identity(),
+            group: None,
             hidden: false,
         }
     }
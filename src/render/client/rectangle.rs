@@ -1,6 +1,6 @@
 use crate::asset::{AssetId, Strong, StrongAssetId, Weak};
 use crate::render::canvas::RawInstance;
-use crate::render::client::{CanvasLayer, LayerSpawner, RenderDefaults};
+use crate::render::client::{CanvasLayer, Group, LayerSpawner, RenderDefaults};
 use crate::render::mesh::Mesh;
 use crate::render::message::{InstanceEvent, InstanceEventKind};
 use crate::render::pipeline::Pipeline;
@@ -15,6 +15,14 @@ use serde::Deserialize;
 use std::ops::{Deref, DerefMut};
 use uuid::Uuid;
 
+/// outline drawn inside the edges of the rectangle, in the scaled units of the rectangle
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RectangleBorder {
+    pub width: f32,
+    pub color: [f32; 4],
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct RawRectangle<S> {
     pub pipeline: AssetId<Pipeline, S>,
@@ -24,8 +32,14 @@ pub struct RawRectangle<S> {
     pub size: Vector2<f32>,
     pub scale: Vector2<f32>,
     pub tint: [f32; 4],
+    /// passed to the shader as is
+    pub custom: [f32; 4],
+    pub fill: bool,
+    pub border: Option<RectangleBorder>,
     pub world: Similarity2<f32>,
     pub world_z_index: f32,
+    /// the world is relative to the one of the group
+    pub group: Option<Uuid>,
 }
 
 impl<S> RawRectangle<S> {
@@ -38,8 +52,12 @@ impl<S> RawRectangle<S> {
             size: self.size,
             scale: self.scale,
             tint: self.tint,
+            custom: self.custom,
+            fill: self.fill,
+            border: self.border,
             world: self.world,
             world_z_index: self.world_z_index,
+            group: self.group,
         }
     }
 
@@ -53,12 +71,15 @@ impl<S> RawRectangle<S> {
             mesh: unit_square_mesh,
             texture: white_texture,
             texture_layer: 0,
+            flip: Default::default(),
             model: Isometry3::from_parts(
                 Translation3::new(self.position.x, self.position.y, self.z_index),
                 UnitQuaternion::from_axis_angle(&Vector3::z_axis(), self.rotation.angle()),
             ),
             scale: Vector3::new(self.size.x * self.scale.x, self.size.y * self.scale.y, 1.0),
             tint: self.tint,
+            custom: self.custom,
+            effects: Default::default(),
             world: Similarity3::from_parts(
                 Translation3::new(
                     self.world.isometry.translation.x,
@@ -71,10 +92,53 @@ impl<S> RawRectangle<S> {
                 ),
                 self.world.scaling(),
             ),
+            group: self.group,
         }
     }
 }
 
+impl RawRectangle<Weak> {
+    /// lifts the border above the fill, both share the render key and z index otherwise,
+    /// large enough to be resolved by the depth buffer across the camera depth range
+    const BORDER_Z_OFFSET: f32 = 0.01;
+
+    /// top, bottom, left and right bar of the border
+    fn border_raw_instances(
+        &self,
+        unit_square_mesh: AssetId<Mesh, Weak>,
+        white_texture: AssetId<Texture, Weak>,
+    ) -> Option<[RawInstance<Weak>; 4]> {
+        let border = self.border?;
+        let extends = Vector2::new(self.size.x * self.scale.x, self.size.y * self.scale.y);
+        let half_extends = extends.abs() / 2.0;
+        let width = border
+            .width
+            .min(half_extends.x)
+            .min(half_extends.y)
+            .max(0.0);
+        let half_width = width / 2.0;
+
+        let bar = |offset: Vector2<f32>, size: Vector2<f32>| {
+            let mut raw = *self;
+            raw.position = self.position + self.rotation * offset;
+            raw.z_index = self.z_index + Self::BORDER_Z_OFFSET;
+            raw.size = size;
+            raw.scale = Vector2::new(1.0, 1.0);
+            raw.tint = border.color;
+            raw.into_raw_instance(unit_square_mesh, white_texture)
+        };
+
+        let horizontal = Vector2::new(half_extends.x * 2.0, width);
+        let vertical = Vector2::new(width, half_extends.y * 2.0 - width * 2.0);
+        Some([
+            bar(Vector2::new(0.0, half_extends.y - half_width), horizontal),
+            bar(Vector2::new(0.0, -half_extends.y + half_width), horizontal),
+            bar(Vector2::new(-half_extends.x + half_width, 0.0), vertical),
+            bar(Vector2::new(half_extends.x - half_width, 0.0), vertical),
+        ])
+    }
+}
+
 #[derive(Debug, Copy, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RectangleBuilder<S> {
@@ -92,10 +156,18 @@ pub struct RectangleBuilder<S> {
     pub scale: Vector2<f32>,
     #[serde(default = "super::arr4_one")]
     pub tint: [f32; 4],
+    #[serde(default)]
+    pub custom: [f32; 4],
+    #[serde(default = "super::bool_true")]
+    pub fill: bool,
+    #[serde(default)]
+    pub border: Option<RectangleBorder>,
     #[serde(default = "Similarity2::identity")]
     pub world: Similarity2<f32>,
     #[serde(default)]
     pub world_z_index: f32,
+    #[serde(skip)]
+    pub group: Option<Uuid>,
     #[serde(default)]
     pub hidden: bool,
 }
@@ -143,6 +215,26 @@ impl<S> RectangleBuilder<S> {
         self
     }
 
+    /// passed to the shader at location 3, e.g. a fill fraction
+    #[inline]
+    pub fn with_custom(mut self, custom: [f32; 4]) -> Self {
+        self.custom = custom;
+        self
+    }
+
+    /// set fill to false to only draw the border
+    #[inline]
+    pub fn with_fill(mut self, fill: bool) -> Self {
+        self.fill = fill;
+        self
+    }
+
+    #[inline]
+    pub fn with_border(mut self, width: f32, color: [f32; 4]) -> Self {
+        self.border = Some(RectangleBorder { width, color });
+        self
+    }
+
     #[inline]
     pub fn with_world(mut self, world: Similarity2<f32>) -> Self {
         self.world = world;
@@ -155,6 +247,13 @@ impl<S> RectangleBuilder<S> {
         self
     }
 
+    /// the world becomes relative to the one of the group, which has to be on the same layer
+    #[inline]
+    pub fn with_group(mut self, group: &Group) -> Self {
+        self.group = Some(group.id());
+        self
+    }
+
     #[inline]
     pub fn with_hidden(mut self, hidden: bool) -> Self {
         self.hidden = hidden;
@@ -172,27 +271,19 @@ impl RectangleBuilder<Strong> {
         let hidden = self.hidden;
         let raw_rectangle = self.into_raw(defaults);
 
-        if !hidden {
-            let raw_instance = raw_rectangle
-                .to_weak()
-                .into_raw_instance(unit_square_mesh.to_weak(), white_texture.to_weak());
-
-            sender.send(InstanceEvent {
-                id,
-                layer: layer_uuid,
-                kind: InstanceEventKind::Created(Box::new(raw_instance)),
-            });
-        }
-
-        Rectangle {
+        let rectangle = Rectangle {
             id,
+            border_ids: Rectangle::new_border_ids(),
             layer: layer_uuid,
             unit_square_mesh,
             white_texture,
             hidden,
             raw: raw_rectangle,
             sender: sender.to_owned(),
-        }
+        };
+        rectangle.sync(false, false);
+
+        rectangle
     }
 
     fn into_raw(self, defaults: &RenderDefaults) -> RawRectangle<Strong> {
@@ -206,8 +297,12 @@ impl RectangleBuilder<Strong> {
             size: self.size,
             scale: self.scale,
             tint: self.tint,
+            custom: self.custom,
+            fill: self.fill,
+            border: self.border,
             world: self.world,
             world_z_index: self.world_z_index,
+            group: self.group,
         }
     }
 }
@@ -231,8 +326,12 @@ impl<S> Default for RectangleBuilder<S> {
             size: super::vector2_one(),
             scale: super::vector2_one(),
             tint: super::arr4_one(),
+            custom: [0.0; 4],
+            fill: true,
+            border: None,
             world: Similarity2::identity(),
             world_z_index: 0.0,
+            group: None,
             hidden: false,
         }
     }
@@ -241,6 +340,7 @@ impl<S> Default for RectangleBuilder<S> {
 #[derive(Debug)]
 pub struct Rectangle {
     id: Uuid,
+    border_ids: [Uuid; 4],
     layer: Uuid,
     unit_square_mesh: StrongAssetId<Mesh>,
     white_texture: StrongAssetId<Texture>,
@@ -264,9 +364,79 @@ impl Rectangle {
     pub fn modify(&mut self) -> RectangleModify {
         RectangleModify {
             new_hidden: self.hidden,
+            fill_was_visible: self.is_fill_visible(),
+            border_was_visible: self.is_border_visible(),
             underlying: self,
         }
     }
+
+    #[inline]
+    fn new_border_ids() -> [Uuid; 4] {
+        [
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        ]
+    }
+
+    #[inline]
+    fn is_fill_visible(&self) -> bool {
+        !self.hidden && self.raw.fill
+    }
+
+    #[inline]
+    fn is_border_visible(&self) -> bool {
+        !self.hidden && self.raw.border.is_some()
+    }
+
+    fn send(&self, id: Uuid, kind: InstanceEventKind) {
+        self.sender.send(InstanceEvent {
+            id,
+            layer: self.layer,
+            kind,
+        });
+    }
+
+    /// sends the instance events of the fill and the border based on their previous visibility
+    fn sync(&self, fill_was_visible: bool, border_was_visible: bool) {
+        let raw = self.raw.to_weak();
+        let unit_square_mesh = self.unit_square_mesh.to_weak();
+        let white_texture = self.white_texture.to_weak();
+
+        if self.is_fill_visible() {
+            let raw_instance = Box::new(raw.into_raw_instance(unit_square_mesh, white_texture));
+            if fill_was_visible {
+                self.send(self.id, InstanceEventKind::Modified(raw_instance));
+            } else {
+                self.send(self.id, InstanceEventKind::Created(raw_instance));
+            }
+        } else if fill_was_visible {
+            self.send(self.id, InstanceEventKind::Dropped);
+        }
+
+        let border_instances = raw
+            .border_raw_instances(unit_square_mesh, white_texture)
+            .filter(|_| self.is_border_visible());
+        match border_instances {
+            Some(raw_instances) => {
+                for (id, raw_instance) in self.border_ids.iter().zip(raw_instances) {
+                    let raw_instance = Box::new(raw_instance);
+                    if border_was_visible {
+                        self.send(*id, InstanceEventKind::Modified(raw_instance));
+                    } else {
+                        self.send(*id, InstanceEventKind::Created(raw_instance));
+                    }
+                }
+            }
+            None if border_was_visible => {
+                for id in self.border_ids {
+                    self.send(id, InstanceEventKind::Dropped);
+                }
+            }
+            None => {}
+        }
+    }
 }
 
 impl Bounded for Rectangle {
@@ -296,42 +466,33 @@ impl Deref for Rectangle {
 impl Clone for Rectangle {
     #[inline]
     fn clone(&self) -> Self {
-        let id = Uuid::new_v4();
-
-        if !self.hidden {
-            let raw_instance = self.raw.to_weak().into_raw_instance(
-                self.unit_square_mesh.to_weak(),
-                self.white_texture.to_weak(),
-            );
-
-            self.sender.send(InstanceEvent {
-                id,
-                layer: self.layer,
-                kind: InstanceEventKind::Created(Box::new(raw_instance)),
-            });
-        }
-
-        Rectangle {
-            id,
+        let rectangle = Rectangle {
+            id: Uuid::new_v4(),
+            border_ids: Rectangle::new_border_ids(),
             layer: self.layer,
             unit_square_mesh: self.unit_square_mesh.clone(),
             white_texture: self.white_texture.clone(),
             raw: self.raw.clone(),
             hidden: self.hidden,
             sender: self.sender.clone(),
-        }
+        };
+        rectangle.sync(false, false);
+
+        rectangle
     }
 }
 
 impl Drop for Rectangle {
     #[inline]
     fn drop(&mut self) {
-        if !self.hidden {
-            self.sender.send(InstanceEvent {
-                id: self.id,
-                layer: self.layer,
-                kind: InstanceEventKind::Dropped,
-            });
+        if self.is_fill_visible() {
+            self.send(self.id, InstanceEventKind::Dropped);
+        }
+
+        if self.is_border_visible() {
+            for id in self.border_ids {
+                self.send(id, InstanceEventKind::Dropped);
+            }
         }
     }
 }
@@ -339,6 +500,8 @@ impl Drop for Rectangle {
 #[derive(Debug)]
 pub struct RectangleModify<'a> {
     new_hidden: bool,
+    fill_was_visible: bool,
+    border_was_visible: bool,
     underlying: &'a mut Rectangle,
 }
 
@@ -371,25 +534,8 @@ impl<'a> DerefMut for RectangleModify<'a> {
 impl<'a> Drop for RectangleModify<'a> {
     #[inline]
     fn drop(&mut self) {
-        let visibility_changed = self.underlying.hidden != self.new_hidden;
         self.underlying.hidden = self.new_hidden;
-
-        if visibility_changed && self.underlying.hidden {
-            self.underlying.sender.send(InstanceEvent {
-                id: self.underlying.id,
-                layer: self.underlying.layer,
-                kind: InstanceEventKind::Dropped,
-            });
-        } else if !self.underlying.hidden {
-            let raw_instance = self.underlying.raw.to_weak().into_raw_instance(
-                self.underlying.unit_square_mesh.to_weak(),
-                self.underlying.white_texture.to_weak(),
-            );
-            self.underlying.sender.send(InstanceEvent {
-                id: self.underlying.id,
-                layer: self.underlying.layer,
-                kind: InstanceEventKind::Modified(Box::new(raw_instance)),
-            });
-        }
+        self.underlying
+            .sync(self.fill_was_visible, self.border_was_visible);
     }
 }
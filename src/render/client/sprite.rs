@@ -1,18 +1,22 @@
 use crate::asset::{AssetId, Strong, StrongAssetId, Weak};
 use crate::render::canvas::RawInstance;
-use crate::render::client::{CanvasLayer, LayerSpawner, RenderDefaults};
+use crate::render::client::{CanvasLayer, Group, LayerSpawner, RenderDefaults};
 use crate::render::mesh::Mesh;
 use crate::render::message::{InstanceEvent, InstanceEventKind};
 use crate::render::pipeline::Pipeline;
 use crate::render::view::Texture;
-use crate::util::{Bounded, Bounds};
+use crate::util::{Bounded, Bounds, HashMap};
 use nalgebra::{
     Isometry2, Isometry3, Point2, Rotation2, Similarity2, Similarity3, Translation3,
     UnitQuaternion, Vector2, Vector3,
 };
 use roundabout::prelude::MessageSender;
 use serde::Deserialize;
+use std::cell::RefCell;
+use std::num::NonZeroU32;
 use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+use std::time::Duration;
 use uuid::Uuid;
 
 #[derive(Debug, Copy, Clone)]
@@ -20,14 +24,20 @@ pub struct RawSprite<S> {
     pub pipeline: AssetId<Pipeline, S>,
     pub texture: AssetId<Texture, S>,
     pub texture_layer: u32,
+    pub flip_x: bool,
+    pub flip_y: bool,
     pub position: Point2<f32>,
     pub z_index: f32,
     pub rotation: Rotation2<f32>,
     pub size: Vector2<f32>,
     pub scale: Vector2<f32>,
     pub tint: [f32; 4],
+    /// passed to the shader as is
+    pub custom: [f32; 4],
     pub world: Similarity2<f32>,
     pub world_z_index: f32,
+    /// the world is relative to the one of the group
+    pub group: Option<Uuid>,
 }
 
 impl<S> RawSprite<S> {
@@ -36,14 +46,18 @@ impl<S> RawSprite<S> {
             pipeline: self.pipeline.to_weak(),
             texture: self.texture.to_weak(),
             texture_layer: self.texture_layer,
+            flip_x: self.flip_x,
+            flip_y: self.flip_y,
             position: self.position,
             z_index: self.z_index,
             rotation: self.rotation,
             size: self.size,
             scale: self.scale,
             tint: self.tint,
+            custom: self.custom,
             world: self.world,
             world_z_index: self.world_z_index,
+            group: self.group,
         }
     }
 
@@ -53,12 +67,15 @@ impl<S> RawSprite<S> {
             mesh: unit_square_mesh,
             texture: self.texture,
             texture_layer: self.texture_layer,
+            flip: [self.flip_x, self.flip_y],
             model: Isometry3::from_parts(
                 Translation3::new(self.position.x, self.position.y, self.z_index),
                 UnitQuaternion::from_axis_angle(&Vector3::z_axis(), self.rotation.angle()),
             ),
             scale: Vector3::new(self.size.x * self.scale.x, self.size.y * self.scale.y, 1.0),
             tint: self.tint,
+            custom: self.custom,
+            effects: Default::default(),
             world: Similarity3::from_parts(
                 Translation3::new(
                     self.world.isometry.translation.x,
@@ -71,11 +88,142 @@ impl<S> RawSprite<S> {
                 ),
                 self.world.scaling(),
             ),
+            group: self.group,
         }
     }
 }
 
-#[derive(Debug, Copy, Clone, Deserialize)]
+/// cycles the texture layer of a sprite, e.g. over the cells of an atlas
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpriteAnimation {
+    pub frames: Vec<u32>,
+    pub frame_duration: Duration,
+    #[serde(default = "super::bool_true")]
+    pub looping: bool,
+}
+
+impl SpriteAnimation {
+    #[inline]
+    pub fn new(frames: Vec<u32>, frame_duration: Duration, looping: bool) -> Self {
+        Self {
+            frames,
+            frame_duration,
+            looping,
+        }
+    }
+
+    /// texture layer after the animation has been running for the elapsed duration
+    pub fn texture_layer(&self, elapsed: Duration) -> u32 {
+        let last = self.frames.len().saturating_sub(1);
+        let index = if self.frame_duration.is_zero() {
+            last
+        } else {
+            (elapsed.as_secs_f64() / self.frame_duration.as_secs_f64()) as usize
+        };
+
+        let index = if self.looping {
+            index % (last + 1)
+        } else {
+            index.min(last)
+        };
+
+        self.frames.get(index).copied().unwrap_or_default()
+    }
+}
+
+#[derive(Debug)]
+struct AnimatedSprite {
+    layer: Uuid,
+    raw: Option<RawInstance<Weak>>,
+    animation: SpriteAnimation,
+    texture_layer: u32,
+    started: Option<Duration>,
+}
+
+/// animated sprites of a render client, advanced on every frame by the sim server
+#[derive(Debug, Default)]
+pub struct SpriteAnimations {
+    sprites: RefCell<HashMap<Uuid, AnimatedSprite>>,
+}
+
+impl SpriteAnimations {
+    fn insert(
+        &self,
+        id: Uuid,
+        layer: Uuid,
+        animation: SpriteAnimation,
+        raw: Option<RawInstance<Weak>>,
+    ) {
+        let texture_layer = animation.texture_layer(Duration::ZERO);
+        let sprite = AnimatedSprite {
+            layer,
+            raw,
+            animation,
+            texture_layer,
+            started: None,
+        };
+        self.sprites.borrow_mut().insert(id, sprite);
+    }
+
+    /// applies the current frame of the animation, `None` for hidden sprites
+    fn update(&self, id: &Uuid, raw: Option<RawInstance<Weak>>) -> Option<RawInstance<Weak>> {
+        let mut sprites = self.sprites.borrow_mut();
+        let sprite = match sprites.get_mut(id) {
+            Some(sprite) => sprite,
+            None => return raw,
+        };
+
+        sprite.raw = raw.map(|mut raw| {
+            raw.texture_layer = sprite.texture_layer;
+            raw
+        });
+        sprite.raw
+    }
+
+    fn duplicate(&self, id: &Uuid, new_id: Uuid) {
+        let mut sprites = self.sprites.borrow_mut();
+        if let Some(sprite) = sprites.get(id) {
+            let duplicate = AnimatedSprite {
+                layer: sprite.layer,
+                raw: sprite.raw,
+                animation: sprite.animation.clone(),
+                texture_layer: sprite.texture_layer,
+                started: sprite.started,
+            };
+            sprites.insert(new_id, duplicate);
+        }
+    }
+
+    fn remove(&self, id: &Uuid) {
+        self.sprites.borrow_mut().remove(id);
+    }
+
+    /// only sends instance updates for sprites whose frame changed
+    pub fn advance(&self, elapsed: Duration, sender: &MessageSender) {
+        for (id, sprite) in self.sprites.borrow_mut().iter_mut() {
+            let started = *sprite.started.get_or_insert(elapsed);
+            let texture_layer = sprite
+                .animation
+                .texture_layer(elapsed.saturating_sub(started));
+            if texture_layer == sprite.texture_layer {
+                continue;
+            }
+
+            sprite.texture_layer = texture_layer;
+            if let Some(raw) = &mut sprite.raw {
+                raw.texture_layer = texture_layer;
+                sender.send(InstanceEvent {
+                    id: *id,
+                    layer: sprite.layer,
+                    kind: InstanceEventKind::Modified(Box::new(*raw)),
+                });
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SpriteBuilder<S> {
     #[serde(default, bound(deserialize = "AssetId<Pipeline, S>: Deserialize<'de>"))]
@@ -84,6 +232,10 @@ pub struct SpriteBuilder<S> {
     pub texture: Option<AssetId<Texture, S>>,
     #[serde(default)]
     pub texture_layer: u32,
+    #[serde(default)]
+    pub flip_x: bool,
+    #[serde(default)]
+    pub flip_y: bool,
     #[serde(default = "Point2::origin")]
     pub position: Point2<f32>,
     #[serde(default)]
@@ -96,12 +248,18 @@ pub struct SpriteBuilder<S> {
     pub scale: Vector2<f32>,
     #[serde(default = "super::arr4_one")]
     pub tint: [f32; 4],
+    #[serde(default)]
+    pub custom: [f32; 4],
     #[serde(default = "Similarity2::identity")]
     pub world: Similarity2<f32>,
     #[serde(default)]
     pub world_z_index: f32,
+    #[serde(skip)]
+    pub group: Option<Uuid>,
     #[serde(default)]
     pub hidden: bool,
+    #[serde(default)]
+    pub animation: Option<SpriteAnimation>,
 }
 
 impl<S> SpriteBuilder<S> {
@@ -117,12 +275,33 @@ impl<S> SpriteBuilder<S> {
         self
     }
 
+    /// for atlas textures the layer is `y * columns + x` of the atlas cell
     #[inline]
     pub fn with_texture_layer(mut self, texture_layer: u32) -> Self {
         self.texture_layer = texture_layer;
         self
     }
 
+    /// selects the cell of an atlas texture, the atlas has to match the one of the texture
+    #[inline]
+    pub fn with_atlas_cell(mut self, atlas: [NonZeroU32; 2], cell: [u32; 2]) -> Self {
+        self.texture_layer = Texture::atlas_layer(atlas, cell);
+        self
+    }
+
+    /// mirrors the texture instead of the geometry, so the quad isn't back face culled
+    #[inline]
+    pub fn with_flip_x(mut self, flip_x: bool) -> Self {
+        self.flip_x = flip_x;
+        self
+    }
+
+    #[inline]
+    pub fn with_flip_y(mut self, flip_y: bool) -> Self {
+        self.flip_y = flip_y;
+        self
+    }
+
     #[inline]
     pub fn with_position(mut self, position: Point2<f32>) -> Self {
         self.position = position;
@@ -159,6 +338,13 @@ impl<S> SpriteBuilder<S> {
         self
     }
 
+    /// passed to the shader at location 3, e.g. a fill fraction
+    #[inline]
+    pub fn with_custom(mut self, custom: [f32; 4]) -> Self {
+        self.custom = custom;
+        self
+    }
+
     #[inline]
     pub fn with_world(mut self, world: Similarity2<f32>) -> Self {
         self.world = world;
@@ -171,25 +357,57 @@ impl<S> SpriteBuilder<S> {
         self
     }
 
+    /// the world becomes relative to the one of the group, which has to be on the same layer
+    #[inline]
+    pub fn with_group(mut self, group: &Group) -> Self {
+        self.group = Some(group.id());
+        self
+    }
+
     #[inline]
     pub fn with_hidden(mut self, hidden: bool) -> Self {
         self.hidden = hidden;
         self
     }
+
+    /// overrides the texture layer with the frames of the animation
+    #[inline]
+    pub fn with_animation(
+        mut self,
+        frames: Vec<u32>,
+        frame_duration: Duration,
+        looping: bool,
+    ) -> Self {
+        self.animation = Some(SpriteAnimation::new(frames, frame_duration, looping));
+        self
+    }
 }
 
 impl SpriteBuilder<Strong> {
-    fn finalize(self, layer: &CanvasLayer) -> Sprite {
+    fn finalize(mut self, layer: &CanvasLayer) -> Sprite {
         let id = Uuid::new_v4();
         let (layer_uuid, defaults, sender) = layer.parts();
         let hidden = self.hidden;
         let unit_square_mesh = defaults.unit_square_mesh.clone();
-        let raw_rectangle = self.into_raw(defaults);
 
-        if !hidden {
-            let raw_instance = raw_rectangle
+        let animation = self.animation.take().filter(|animation| {
+            if animation.frames.is_empty() {
+                log::warn!("ignore sprite animation without frames");
+            }
+            !animation.frames.is_empty()
+        });
+        if let Some(animation) = &animation {
+            self.texture_layer = animation.texture_layer(Duration::ZERO);
+        }
+
+        let raw_rectangle = self.into_raw(defaults);
+        let raw_instance = (!hidden).then(|| {
+            raw_rectangle
                 .to_weak()
-                .into_raw_instance(unit_square_mesh.to_weak());
+                .into_raw_instance(unit_square_mesh.to_weak())
+        });
+
+        if let Some(raw_instance) = raw_instance {
             sender.send(InstanceEvent {
                 id,
                 layer: layer_uuid,
@@ -197,12 +415,19 @@ impl SpriteBuilder<Strong> {
             });
         }
 
+        let animations = animation.map(|animation| {
+            let animations = layer.animations().clone();
+            animations.insert(id, layer_uuid, animation, raw_instance);
+            animations
+        });
+
         Sprite {
             id,
             layer: layer_uuid,
             unit_square_mesh,
             raw: raw_rectangle,
             hidden,
+            animations,
             sender: sender.to_owned(),
         }
     }
@@ -216,14 +441,18 @@ impl SpriteBuilder<Strong> {
                 .texture
                 .unwrap_or_else(|| defaults.white_texture.clone()),
             texture_layer: self.texture_layer,
+            flip_x: self.flip_x,
+            flip_y: self.flip_y,
             position: self.position,
             z_index: self.z_index,
             rotation: self.rotation,
             size: self.size,
             scale: self.scale,
             tint: self.tint,
+            custom: self.custom,
             world: self.world,
             world_z_index: self.world_z_index,
+            group: self.group,
         }
     }
 }
@@ -243,15 +472,20 @@ impl<S> Default for SpriteBuilder<S> {
             pipeline: None,
             texture: None,
             texture_layer: 0,
+            flip_x: false,
+            flip_y: false,
             position: Point2::origin(),
             z_index: 0.0,
             rotation: Rotation2::identity(),
             size: super::vector2_one(),
             scale: super::vector2_one(),
             tint: super::arr4_one(),
+            custom: [0.0; 4],
             world: Similarity2::identity(),
             world_z_index: 0.0,
+            group: None,
             hidden: false,
+            animation: None,
         }
     }
 }
@@ -263,6 +497,7 @@ pub struct Sprite {
     unit_square_mesh: StrongAssetId<Mesh>,
     raw: RawSprite<Strong>,
     hidden: bool,
+    animations: Option<Rc<SpriteAnimations>>,
     sender: MessageSender,
 }
 
@@ -315,11 +550,19 @@ impl Clone for Sprite {
     fn clone(&self) -> Self {
         let id = Uuid::new_v4();
 
+        if let Some(animations) = &self.animations {
+            animations.duplicate(&self.id, id);
+        }
+
         if !self.hidden {
             let raw_instance = self
                 .raw
                 .to_weak()
                 .into_raw_instance(self.unit_square_mesh.to_weak());
+            let raw_instance = match &self.animations {
+                Some(animations) => animations.update(&id, Some(raw_instance)).unwrap(),
+                None => raw_instance,
+            };
             self.sender.send(InstanceEvent {
                 id,
                 layer: self.layer,
@@ -333,6 +576,7 @@ impl Clone for Sprite {
             unit_square_mesh: self.unit_square_mesh.clone(),
             raw: self.raw.clone(),
             hidden: self.hidden,
+            animations: self.animations.clone(),
             sender: self.sender.clone(),
         }
     }
@@ -341,6 +585,10 @@ impl Clone for Sprite {
 impl Drop for Sprite {
     #[inline]
     fn drop(&mut self) {
+        if let Some(animations) = &self.animations {
+            animations.remove(&self.id);
+        }
+
         if !self.hidden {
             self.sender.send(InstanceEvent {
                 id: self.id,
@@ -389,18 +637,24 @@ impl<'a> Drop for SpriteModify<'a> {
         let visibility_changed = self.underlying.hidden != self.new_hidden;
         self.underlying.hidden = self.new_hidden;
 
+        let raw_instance = (!self.underlying.hidden).then(|| {
+            self.underlying
+                .raw
+                .to_weak()
+                .into_raw_instance(self.underlying.unit_square_mesh.to_weak())
+        });
+        let raw_instance = match &self.underlying.animations {
+            Some(animations) => animations.update(&self.underlying.id, raw_instance),
+            None => raw_instance,
+        };
+
         if visibility_changed && self.underlying.hidden {
             self.underlying.sender.send(InstanceEvent {
                 id: self.underlying.id,
                 layer: self.underlying.layer,
                 kind: InstanceEventKind::Dropped,
             });
-        } else if !self.underlying.hidden {
-            let raw_instance = self
-                .underlying
-                .raw
-                .to_weak()
-                .into_raw_instance(self.underlying.unit_square_mesh.to_weak());
+        } else if let Some(raw_instance) = raw_instance {
             self.underlying.sender.send(InstanceEvent {
                 id: self.underlying.id,
                 layer: self.underlying.layer,
@@ -1,13 +1,16 @@
 use crate::asset::{AssetId, Strong};
 use crate::prelude::MessageSender;
-use crate::render::client::{CanvasLayer, LayerSpawner, RenderDefaults};
-use crate::render::message::{TextEvent, TextEventKind};
+use crate::render::client::{CanvasLayer, Group, LayerSpawner, RenderDefaults};
+use crate::render::message::{TextEvent, TextEventKind, TextLaidOutEvent};
 use crate::render::pipeline::Pipeline;
-use crate::render::text::{Font, HorizontalAlignment, RawText, VerticalAlignment};
+use crate::render::text::{
+    Font, HorizontalAlignment, LineHeight, RawText, TextBackground, TextOutline, TextOverflow,
+    TextShadow, VerticalAlignment,
+};
 use crate::util::{Bounded, Bounds};
 use nalgebra::{Isometry2, Point2, Rotation2, Similarity2, Vector2};
 use serde::Deserialize;
-use std::ops::{Deref, DerefMut};
+use std::ops::{Deref, DerefMut, Range};
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -24,6 +27,9 @@ pub struct TextBuilder<S> {
     pub font: Option<AssetId<Font, S>>,
     #[serde(default = "arcstr_default")]
     pub content: Arc<str>,
+    /// colored byte ranges of the content, not char indices
+    #[serde(default)]
+    pub spans: Vec<(Range<usize>, [f32; 3])>,
     #[serde(default = "Point2::origin")]
     pub position: Point2<f32>,
     #[serde(default)]
@@ -36,20 +42,32 @@ pub struct TextBuilder<S> {
     pub width: Option<f32>,
     #[serde(default)]
     pub height: Option<f32>,
-    #[serde(default = "super::f32_one")]
-    pub line_height: f32,
+    #[serde(default)]
+    pub line_height: LineHeight,
     #[serde(default)]
     pub vertical_alignment: VerticalAlignment,
     #[serde(default)]
     pub horizontal_alignment: HorizontalAlignment,
+    #[serde(default = "Text::default_tab_size")]
+    pub tab_size: f32,
+    #[serde(default)]
+    pub overflow: TextOverflow,
     #[serde(default = "super::f32_one")]
     pub scale: f32,
     #[serde(default = "super::arr4_one")]
     pub tint: [f32; 4],
+    #[serde(default)]
+    pub background: Option<TextBackground>,
+    #[serde(default)]
+    pub outline: Option<TextOutline>,
+    #[serde(default)]
+    pub shadow: Option<TextShadow>,
     #[serde(default = "Similarity2::identity")]
     pub world: Similarity2<f32>,
     #[serde(default)]
     pub world_z_index: f32,
+    #[serde(skip)]
+    pub group: Option<Uuid>,
     #[serde(default)]
     pub hidden: bool,
 }
@@ -61,6 +79,8 @@ impl<S> TextBuilder<S> {
         self
     }
 
+    /// e.g. a font loaded from a json with the paths of its msdf atlas texture and layout,
+    /// texts are laid out once the font and its layout are loaded
     #[inline]
     pub fn with_font(mut self, font: AssetId<Font, S>) -> Self {
         self.font = Some(font);
@@ -110,8 +130,8 @@ impl<S> TextBuilder<S> {
     }
 
     #[inline]
-    pub fn with_line_height(mut self, line_height: f32) -> Self {
-        self.line_height = line_height;
+    pub fn with_line_height<I: Into<LineHeight>>(mut self, line_height: I) -> Self {
+        self.line_height = line_height.into();
         self
     }
 
@@ -127,6 +147,27 @@ impl<S> TextBuilder<S> {
         self
     }
 
+    /// colors the byte ranges of the content, e.g. from `str::find`, the colors are multiplied
+    /// with the tint, a span of char indices is off as soon as the content isn't ascii
+    #[inline]
+    pub fn with_spans(mut self, spans: Vec<(Range<usize>, [f32; 3])>) -> Self {
+        self.spans = spans;
+        self
+    }
+
+    /// distance between tab stops in multiples of the point size
+    #[inline]
+    pub fn with_tab_size(mut self, tab_size: f32) -> Self {
+        self.tab_size = tab_size;
+        self
+    }
+
+    #[inline]
+    pub fn with_overflow(mut self, overflow: TextOverflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
     #[inline]
     pub fn with_scale(mut self, scale: f32) -> Self {
         self.scale = scale;
@@ -139,6 +180,25 @@ impl<S> TextBuilder<S> {
         self
     }
 
+    #[inline]
+    pub fn with_background(mut self, color: [f32; 4], padding: f32) -> Self {
+        self.background = Some(TextBackground { color, padding });
+        self
+    }
+
+    /// the width is in text units
+    #[inline]
+    pub fn with_outline(mut self, width: f32, color: [f32; 3]) -> Self {
+        self.outline = Some(TextOutline { width, color });
+        self
+    }
+
+    #[inline]
+    pub fn with_shadow(mut self, offset: Vector2<f32>, color: [f32; 3]) -> Self {
+        self.shadow = Some(TextShadow { offset, color });
+        self
+    }
+
     #[inline]
     pub fn with_world(mut self, world: Similarity2<f32>) -> Self {
         self.world = world;
@@ -151,6 +211,13 @@ impl<S> TextBuilder<S> {
         self
     }
 
+    /// the world becomes relative to the one of the group, which has to be on the same layer
+    #[inline]
+    pub fn with_group(mut self, group: &Group) -> Self {
+        self.group = Some(group.id());
+        self
+    }
+
     #[inline]
     pub fn with_hidden(mut self, hidden: bool) -> Self {
         self.hidden = hidden;
@@ -183,6 +250,7 @@ impl TextBuilder<Strong> {
                 .unwrap_or_else(|| defaults.text_pipeline.clone()),
             font: self.font.unwrap_or_else(|| defaults.font.clone()),
             content: self.content,
+            spans: self.spans,
             position: self.position,
             z_index: self.z_index,
             rotation: self.rotation,
@@ -192,10 +260,16 @@ impl TextBuilder<Strong> {
             line_height: self.line_height,
             vertical_alignment: self.vertical_alignment,
             horizontal_alignment: self.horizontal_alignment,
+            tab_size: self.tab_size,
+            overflow: self.overflow,
             scale: self.scale,
             tint: self.tint,
+            background: self.background,
+            outline: self.outline,
+            shadow: self.shadow,
             world: self.world,
             world_z_index: self.world_z_index,
+            group: self.group,
         }
     }
 }
@@ -215,19 +289,26 @@ impl<S> Default for TextBuilder<S> {
             pipeline: None,
             font: None,
             content: arcstr_default(),
+            spans: Vec::new(),
             position: Point2::origin(),
             z_index: 0.0,
             rotation: Rotation2::identity(),
             point: Text::default_text_size(),
             width: None,
             height: None,
-            line_height: super::f32_one(),
+            line_height: Default::default(),
             vertical_alignment: Default::default(),
             horizontal_alignment: Default::default(),
+            tab_size: Text::default_tab_size(),
+            overflow: Default::default(),
             scale: super::f32_one(),
             tint: super::arr4_one(),
+            background: None,
+            outline: None,
+            shadow: None,
             world: Similarity2::identity(),
             world_z_index: 0.0,
+            group: None,
             hidden: false,
         }
     }
@@ -239,12 +320,14 @@ pub struct Text {
     layer: Uuid,
     raw: RawText<Strong>,
     major_hash: u64,
+    laid_out: Option<Vector2<f32>>,
     hidden: bool,
     sender: MessageSender,
 }
 
 impl Text {
     const DEFAULT_TEXT_SIZE: f32 = 1.0;
+    const DEFAULT_TAB_SIZE: f32 = 4.0;
 
     fn new(
         id: Uuid,
@@ -260,6 +343,7 @@ impl Text {
             layer,
             raw,
             major_hash,
+            laid_out: None,
             hidden,
             sender,
         }
@@ -275,6 +359,20 @@ impl Text {
         self.id
     }
 
+    /// the size of the wrapped lines as of the last layout of the text
+    #[inline]
+    pub fn laid_out(&self) -> Option<Vector2<f32>> {
+        self.laid_out
+    }
+
+    /// keeps the size of the matching `TextLaidOutEvent` to include the wrapped lines in the bounds
+    #[inline]
+    pub fn apply_laid_out(&mut self, event: &TextLaidOutEvent) {
+        if event.text_id == self.id {
+            self.laid_out = Some(event.size);
+        }
+    }
+
     #[inline]
     pub fn modify(&mut self) -> TextModify {
         TextModify {
@@ -286,21 +384,46 @@ impl Text {
     fn default_text_size() -> f32 {
         Self::DEFAULT_TEXT_SIZE
     }
+
+    fn default_tab_size() -> f32 {
+        Self::DEFAULT_TAB_SIZE
+    }
 }
 
 impl Bounded for Text {
     #[inline]
     fn bounds(&self) -> Bounds {
-        let extends = Vector2::new(
-            self.width.unwrap_or_default() * self.scale,
-            self.height.unwrap_or_default() * self.scale,
+        let rect = Vector2::new(
+            self.width.unwrap_or_default(),
+            self.height.unwrap_or_default(),
         );
-        let half_extends = extends / 2.0;
+        let mut min = -rect / 2.0;
+        let mut max = rect / 2.0;
+
+        // lines exceed the rect if there is none or if they overflow it
+        if let Some(laid_out) = self.laid_out {
+            let (left, right) = match self.horizontal_alignment {
+                HorizontalAlignment::Left | HorizontalAlignment::Justified => {
+                    (min.x, min.x + laid_out.x)
+                }
+                HorizontalAlignment::Right => (max.x - laid_out.x, max.x),
+                HorizontalAlignment::Center => (-laid_out.x / 2.0, laid_out.x / 2.0),
+            };
+            let (bottom, top) = match self.vertical_alignment {
+                VerticalAlignment::Top => (max.y - laid_out.y, max.y),
+                VerticalAlignment::Bottom => (min.y, min.y + laid_out.y),
+                VerticalAlignment::Center => (-laid_out.y / 2.0, laid_out.y / 2.0),
+            };
+            min = min.inf(&Vector2::new(left, bottom));
+            max = max.sup(&Vector2::new(right, top));
+        }
+
+        let (min, max) = (min * self.scale, max * self.scale);
         let model = self.world * Isometry2::new(self.position.coords, self.rotation.angle());
 
-        let o = model * Point2::from(half_extends);
-        let w = model * Point2::new(half_extends.x, -half_extends.y);
-        let h = model * Point2::new(-half_extends.x, half_extends.y);
+        let o = model * Point2::from(max);
+        let w = model * Point2::new(max.x, min.y);
+        let h = model * Point2::new(min.x, max.y);
 
         Bounds { o, w, h }
     }
@@ -333,6 +456,7 @@ impl Clone for Text {
             layer: self.layer,
             raw: self.raw.clone(),
             major_hash: self.major_hash,
+            laid_out: self.laid_out,
             hidden: self.hidden,
             sender: self.sender.clone(),
         }
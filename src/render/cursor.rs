@@ -0,0 +1,95 @@
+use crate::asset::storage::AssetsClient;
+use crate::asset::{AssetPath, StrongAssetId};
+use crate::platform::cursor::CursorImage;
+use crate::render::client::{CanvasLayer, Sprite};
+use crate::render::view::Texture;
+use crate::util::HashMap;
+use nalgebra::{Point2, Vector2};
+
+/// draws the cursor image in place of the hidden system cursor, on the overlay layer
+#[derive(Default)]
+pub(crate) struct CursorOverlay {
+    image: Option<CursorImage>,
+    position: Option<Vector2<f64>>,
+    /// the frames of the current cursor are kept loaded, so they aren't reloaded per frame
+    textures: HashMap<AssetPath, StrongAssetId<Texture>>,
+    sprite: Option<Sprite>,
+}
+
+impl CursorOverlay {
+    /// above the stats overlay
+    const Z_INDEX: f32 = 0.1;
+
+    pub fn set_image(
+        &mut self,
+        image: Option<CursorImage>,
+        textures: &[AssetPath],
+        layer: &CanvasLayer,
+        assets: &AssetsClient,
+    ) {
+        self.textures.retain(|path, _| textures.contains(path));
+        let image_texture = image.as_ref().map(|image| image.texture);
+        for path in textures.iter().copied().chain(image_texture) {
+            self.textures
+                .entry(path)
+                .or_insert_with(|| assets.load(path));
+        }
+
+        self.image = image;
+        self.update(layer);
+    }
+
+    /// the raw transform of the cursor, in pixels from the top left corner of the window
+    pub fn set_position(&mut self, position: Option<Vector2<f64>>, layer: &CanvasLayer) {
+        self.position = position;
+        if self.image.is_some() || self.sprite.is_some() {
+            self.update(layer);
+        }
+    }
+
+    fn update(&mut self, layer: &CanvasLayer) {
+        let (image, position) = match (self.image, self.position) {
+            (Some(image), Some(position)) => (image, position),
+            (None, _) => {
+                // releases the texture of the replaced cursor
+                self.sprite = None;
+                return;
+            }
+            (Some(_), None) => {
+                if let Some(sprite) = &mut self.sprite {
+                    sprite.modify().hide();
+                }
+                return;
+            }
+        };
+
+        let texture = self.textures[&image.texture].clone();
+        let size = Vector2::new(image.size[0], image.size[1]);
+        // the overlay camera has its origin in the top left corner with y pointing up
+        let position = Point2::new(
+            position.x as f32 - image.hotspot[0] + size.x / 2.0,
+            -(position.y as f32 - image.hotspot[1] + size.y / 2.0),
+        );
+
+        match &mut self.sprite {
+            Some(sprite) => {
+                let mut modify = sprite.modify();
+                modify.show();
+                modify.texture = texture;
+                modify.size = size;
+                modify.position = position;
+            }
+            None => {
+                self.sprite = Some(
+                    layer.spawn(
+                        Sprite::builder()
+                            .with_texture(texture)
+                            .with_size(size)
+                            .with_position(position)
+                            .with_z_index(Self::Z_INDEX),
+                    ),
+                );
+            }
+        }
+    }
+}
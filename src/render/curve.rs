@@ -7,6 +7,9 @@ use crate::render::mesh::Mesh;
 use crate::render::pipeline::Pipeline;
 use crate::util::HashMap;
 use ahash::AHasher;
+use lyon::math::Point as LPoint;
+use lyon::path::iterator::PathIterator;
+use lyon::path::PathEvent;
 use lyon::tessellation::{
     BuffersBuilder, StrokeOptions as LStrokeOptions, StrokeTessellator, StrokeVertex, VertexBuffers,
 };
@@ -35,15 +38,38 @@ pub enum LineJoin {
     Bevel,
 }
 
-#[derive(Debug, Copy, Clone, Deserialize, Serialize)]
+/// screen space line widths are in pixels and independent of the camera zoom and curve scale
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum WorldOrScreen {
+    World,
+    Screen,
+}
+
+impl Default for WorldOrScreen {
+    #[inline]
+    fn default() -> Self {
+        WorldOrScreen::World
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct StrokeOptions {
     pub start_cap: LineCap,
     pub end_cap: LineCap,
     pub line_join: LineJoin,
     pub line_width: f32,
+    #[serde(default)]
+    pub line_width_space: WorldOrScreen,
     pub miter_limit: f32,
     pub tolerance: f32,
+    /// alternating dash and gap lengths in local units, an odd count is repeated once
+    #[serde(default)]
+    pub dash: Option<Vec<f32>>,
+    /// distance into the dash pattern at which each sub path starts
+    #[serde(default)]
+    pub dash_offset: f32,
 }
 
 impl Default for StrokeOptions {
@@ -53,9 +79,42 @@ impl Default for StrokeOptions {
             end_cap: LineCap::Butt,
             line_join: LineJoin::Miter,
             line_width: 1.0,
+            line_width_space: WorldOrScreen::World,
             miter_limit: 4.0,
             tolerance: 0.1,
+            dash: None,
+            dash_offset: 0.0,
+        }
+    }
+}
+
+impl StrokeOptions {
+    fn to_lyon(&self, line_width: f32) -> LStrokeOptions {
+        fn cap_to_lyon(cap: LineCap) -> lyon::tessellation::LineCap {
+            match cap {
+                LineCap::Butt => lyon::tessellation::LineCap::Butt,
+                LineCap::Square => lyon::tessellation::LineCap::Square,
+                LineCap::Round => lyon::tessellation::LineCap::Round,
+            }
         }
+
+        let line_join = match self.line_join {
+            LineJoin::Miter => lyon::tessellation::LineJoin::Miter,
+            LineJoin::MiterClip => lyon::tessellation::LineJoin::MiterClip,
+            LineJoin::Round => lyon::tessellation::LineJoin::Round,
+            LineJoin::Bevel => lyon::tessellation::LineJoin::Bevel,
+        };
+
+        let mut stroke = LStrokeOptions::default();
+
+        stroke.start_cap = cap_to_lyon(self.start_cap);
+        stroke.end_cap = cap_to_lyon(self.end_cap);
+        stroke.line_join = line_join;
+        stroke.line_width = line_width;
+        stroke.miter_limit = self.miter_limit;
+        stroke.tolerance = self.tolerance;
+
+        stroke
     }
 }
 
@@ -65,8 +124,19 @@ impl Hash for StrokeOptions {
         self.end_cap.hash(state);
         self.line_join.hash(state);
         self.line_width.to_ne_bytes().hash(state);
+        self.line_width_space.hash(state);
         self.miter_limit.to_ne_bytes().hash(state);
         self.tolerance.to_ne_bytes().hash(state);
+        match &self.dash {
+            Some(dash) => {
+                dash.len().hash(state);
+                for length in dash {
+                    length.to_ne_bytes().hash(state);
+                }
+            }
+            None => usize::MAX.hash(state),
+        }
+        self.dash_offset.to_ne_bytes().hash(state);
     }
 }
 
@@ -75,6 +145,15 @@ impl Hash for StrokeOptions {
 pub enum Segment {
     Begin(Point2<f32>),
     Line(Point2<f32>),
+    Quadratic {
+        ctrl: Point2<f32>,
+        to: Point2<f32>,
+    },
+    Cubic {
+        ctrl1: Point2<f32>,
+        ctrl2: Point2<f32>,
+        to: Point2<f32>,
+    },
     End,
     Close,
 }
@@ -99,6 +178,20 @@ impl Hash for Segment {
             Segment::Close => {
                 3.hash(state);
             }
+            Segment::Quadratic { ctrl, to } => {
+                4.hash(state);
+                for point in [ctrl, to] {
+                    point.x.to_ne_bytes().hash(state);
+                    point.y.to_ne_bytes().hash(state);
+                }
+            }
+            Segment::Cubic { ctrl1, ctrl2, to } => {
+                5.hash(state);
+                for point in [ctrl1, ctrl2, to] {
+                    point.x.to_ne_bytes().hash(state);
+                    point.y.to_ne_bytes().hash(state);
+                }
+            }
         }
     }
 }
@@ -121,6 +214,18 @@ impl PathBuilder {
         self
     }
 
+    #[inline]
+    pub fn quadratic_to(mut self, ctrl: Point2<f32>, to: Point2<f32>) -> Self {
+        self.segments.push(Segment::Quadratic { ctrl, to });
+        self
+    }
+
+    #[inline]
+    pub fn cubic_to(mut self, ctrl1: Point2<f32>, ctrl2: Point2<f32>, to: Point2<f32>) -> Self {
+        self.segments.push(Segment::Cubic { ctrl1, ctrl2, to });
+        self
+    }
+
     #[inline]
     pub fn end(mut self) -> Self {
         self.segments.push(Segment::End);
@@ -166,6 +271,42 @@ impl Path {
     pub fn segments(&self) -> &[Segment] {
         &self.segments
     }
+
+    pub(crate) fn to_lyon(&self) -> lyon::path::Path {
+        let mut builder = lyon::path::Path::builder();
+
+        for segment in self.segments() {
+            match segment {
+                Segment::Begin(point) => {
+                    builder.begin(lyon::geom::point(point.x, point.y));
+                }
+                Segment::Line(point) => {
+                    builder.line_to(lyon::geom::point(point.x, point.y));
+                }
+                Segment::Quadratic { ctrl, to } => {
+                    builder.quadratic_bezier_to(
+                        lyon::geom::point(ctrl.x, ctrl.y),
+                        lyon::geom::point(to.x, to.y),
+                    );
+                }
+                Segment::Cubic { ctrl1, ctrl2, to } => {
+                    builder.cubic_bezier_to(
+                        lyon::geom::point(ctrl1.x, ctrl1.y),
+                        lyon::geom::point(ctrl2.x, ctrl2.y),
+                        lyon::geom::point(to.x, to.y),
+                    );
+                }
+                Segment::End => {
+                    builder.end(false);
+                }
+                Segment::Close => {
+                    builder.end(true);
+                }
+            }
+        }
+
+        builder.build()
+    }
 }
 
 impl From<Vec<Segment>> for Path {
@@ -200,6 +341,8 @@ pub struct RawCurve<S> {
     pub tint: [f32; 4],
     pub world: Similarity2<f32>,
     pub world_z_index: f32,
+    /// the world is relative to the one of the group
+    pub group: Option<Uuid>,
 }
 
 impl<S: Clone> RawCurve<S> {
@@ -207,7 +350,7 @@ impl<S: Clone> RawCurve<S> {
         RawCurve {
             pipeline: self.pipeline.to_weak(),
             path: self.path.clone(),
-            stroke: self.stroke,
+            stroke: self.stroke.clone(),
             position: self.position,
             z_index: self.z_index,
             rotation: self.rotation,
@@ -215,6 +358,7 @@ impl<S: Clone> RawCurve<S> {
             tint: self.tint,
             world: self.world,
             world_z_index: self.world_z_index,
+            group: self.group,
         }
     }
 
@@ -228,12 +372,15 @@ impl<S: Clone> RawCurve<S> {
             mesh: path_mesh,
             texture: white_texture,
             texture_layer: 0,
+            flip: Default::default(),
             model: Isometry3::from_parts(
                 Translation3::new(self.position.x, self.position.y, self.z_index),
                 UnitQuaternion::from_axis_angle(&Vector3::z_axis(), self.rotation.angle()),
             ),
             scale: Vector3::new(self.scale.x, self.scale.y, 1.0),
             tint: self.tint,
+            custom: [0.0; 4],
+            effects: Default::default(),
             world: Similarity3::from_parts(
                 Translation3::new(
                     self.world.isometry.translation.x,
@@ -246,6 +393,23 @@ impl<S: Clone> RawCurve<S> {
                 ),
                 self.world.scaling(),
             ),
+            group: self.group,
+        }
+    }
+
+    /// the line width in local units, screen space widths require the pixels per world unit
+    fn local_line_width(&self, px_factor: Option<f32>) -> f32 {
+        match self.stroke.line_width_space {
+            WorldOrScreen::World => self.stroke.line_width,
+            WorldOrScreen::Screen => {
+                let scale = (self.scale.x.abs() + self.scale.y.abs()) / 2.0 * self.world.scaling();
+                let px_per_unit = px_factor.unwrap_or(1.0) * scale;
+                if px_per_unit > f32::EPSILON {
+                    self.stroke.line_width / px_per_unit
+                } else {
+                    self.stroke.line_width
+                }
+            }
         }
     }
 
@@ -259,10 +423,153 @@ impl<S: Clone> RawCurve<S> {
     }
 }
 
+/// splits the flattened sub paths of a path into dashes
+struct Dasher<'a> {
+    pattern: &'a [f32],
+    offset: f32,
+    index: usize,
+    remaining: f32,
+    on: bool,
+    builder: lyon::path::path::Builder,
+}
+
+impl<'a> Dasher<'a> {
+    fn dash(
+        path: &lyon::path::Path,
+        pattern: &'a [f32],
+        offset: f32,
+        tolerance: f32,
+    ) -> lyon::path::Path {
+        let mut dasher = Self {
+            pattern,
+            offset,
+            index: 0,
+            remaining: 0.0,
+            on: false,
+            builder: lyon::path::Path::builder(),
+        };
+
+        for event in path.iter().flattened(tolerance) {
+            match event {
+                PathEvent::Begin { at } => dasher.begin(at),
+                PathEvent::Line { from, to } => dasher.line(from, to),
+                PathEvent::End { last, first, close } => {
+                    if close {
+                        dasher.line(last, first);
+                    }
+                    dasher.end();
+                }
+                _ => {}
+            }
+        }
+
+        dasher.builder.build()
+    }
+
+    fn begin(&mut self, at: LPoint) {
+        self.index = 0;
+        self.remaining = self.pattern[0];
+        self.on = true;
+
+        let cycle_factor = if self.pattern.len() % 2 == 0 {
+            1.0
+        } else {
+            2.0
+        };
+        let cycle = self.pattern.iter().sum::<f32>() * cycle_factor;
+        let mut offset = self.offset.rem_euclid(cycle);
+        while offset > self.remaining {
+            offset -= self.remaining;
+            self.advance();
+        }
+        self.remaining -= offset;
+
+        if self.on {
+            self.builder.begin(at);
+        }
+    }
+
+    fn line(&mut self, from: LPoint, to: LPoint) {
+        let length = (to - from).length();
+        let mut travelled = 0.0;
+
+        while length - travelled > self.remaining {
+            travelled += self.remaining;
+            let point = from.lerp(to, travelled / length);
+            if self.on {
+                self.builder.line_to(point);
+                self.builder.end(false);
+            } else {
+                self.builder.begin(point);
+            }
+            self.advance();
+        }
+
+        self.remaining -= length - travelled;
+        if self.on {
+            self.builder.line_to(to);
+        }
+    }
+
+    fn end(&mut self) {
+        if self.on {
+            self.builder.end(false);
+            self.on = false;
+        }
+    }
+
+    fn advance(&mut self) {
+        self.index = (self.index + 1) % self.pattern.len();
+        self.remaining = self.pattern[self.index];
+        self.on = !self.on;
+    }
+}
+
+/// appends the colored triangles of the, optionally dashed, path stroke to the geometry
+pub(crate) fn tessellate_stroke(
+    path: &Path,
+    stroke: &StrokeOptions,
+    line_width: f32,
+    color: [f32; 4],
+    geometry: &mut VertexBuffers<Vertex, u32>,
+) -> anyhow::Result<()> {
+    let path = path.to_lyon();
+    let path = match stroke.dash.as_deref() {
+        Some(pattern)
+            if pattern.iter().all(|length| *length >= 0.0) && pattern.iter().sum::<f32>() > 0.0 =>
+        {
+            Dasher::dash(&path, pattern, stroke.dash_offset, stroke.tolerance)
+        }
+        Some(pattern) => {
+            log::warn!("ignore invalid dash pattern: {:?}", pattern);
+            path
+        }
+        None => path,
+    };
+
+    StrokeTessellator::new()
+        .tessellate_path(
+            path.as_slice(),
+            &stroke.to_lyon(line_width),
+            &mut BuffersBuilder::new(geometry, |v: StrokeVertex| {
+                let pos = v.position();
+                Vertex {
+                    position: [pos.x, pos.y, 0.0],
+                    tex_coords: [0.0, 0.0],
+                    color,
+                }
+            }),
+        )
+        .map_err(|e| anyhow::anyhow!("Missing attribute: {:?}", e))?;
+
+    Ok(())
+}
+
 #[allow(dead_code)]
 pub struct RealizedCurve {
     pub(crate) raw: RawCurve<Weak>,
     pub(crate) mesh: StrongAssetId<Mesh>,
+    pub(crate) line_width: f32,
     pub(crate) canvas_layer_id: Uuid,
 }
 
@@ -285,90 +592,20 @@ impl Curves {
         canvas_layer_id: Uuid,
         curve_id: Uuid,
         raw: RawCurve<Weak>,
+        px_factor: Option<f32>,
     ) -> anyhow::Result<RawInstance<Weak>> {
         log::debug!("upsert curve: {:?}", curve_id);
         // Optimization: share mesh assets for same path
         // Optimization: move mesh generation from render thread
 
-        let path = {
-            let mut builder = lyon::path::Path::builder();
-
-            for segment in raw.path.segments() {
-                match segment {
-                    Segment::Begin(point) => {
-                        builder.begin(lyon::geom::point(point.x, point.y));
-                    }
-                    Segment::Line(point) => {
-                        builder.line_to(lyon::geom::point(point.x, point.y));
-                    }
-                    Segment::End => {
-                        builder.end(false);
-                    }
-                    Segment::Close => {
-                        builder.end(true);
-                    }
-                }
-            }
-
-            builder.build()
-        };
-
-        let stroke = {
-            fn cap_to_lyon(cap: LineCap) -> lyon::tessellation::LineCap {
-                match cap {
-                    LineCap::Butt => lyon::tessellation::LineCap::Butt,
-                    LineCap::Square => lyon::tessellation::LineCap::Square,
-                    LineCap::Round => lyon::tessellation::LineCap::Round,
-                }
-            }
-
-            let line_join = match raw.stroke.line_join {
-                LineJoin::Miter => lyon::tessellation::LineJoin::Miter,
-                LineJoin::MiterClip => lyon::tessellation::LineJoin::MiterClip,
-                LineJoin::Round => lyon::tessellation::LineJoin::Round,
-                LineJoin::Bevel => lyon::tessellation::LineJoin::Bevel,
-            };
-
-            let mut stroke = LStrokeOptions::default();
-
-            stroke.start_cap = cap_to_lyon(raw.stroke.start_cap);
-            stroke.end_cap = cap_to_lyon(raw.stroke.end_cap);
-            stroke.line_join = line_join;
-            stroke.line_width = raw.stroke.line_width;
-            stroke.miter_limit = raw.stroke.miter_limit;
-            stroke.tolerance = raw.stroke.tolerance;
-
-            stroke
-        };
-
-        let mut geometry = VertexBuffers::new();
-        StrokeTessellator::new()
-            .tessellate_path(
-                path.as_slice(),
-                &stroke,
-                &mut BuffersBuilder::new(&mut geometry, |v: StrokeVertex| {
-                    let pos = v.position();
-                    Vertex {
-                        position: [pos.x, pos.y, 0.0],
-                        tex_coords: [0.0, 0.0],
-                    }
-                }),
-            )
-            .map_err(|e| anyhow::anyhow!("Missing attribute: {:?}", e))?;
-        geometry.indices.reverse();
-
-        let mesh = assets.store(
-            curve_id,
-            Mesh {
-                vertices: geometry.vertices,
-                indices: geometry.indices,
-            },
-        );
+        let line_width = raw.local_line_width(px_factor);
+        let mesh = assets.store(curve_id, Self::tessellate(&raw, line_width)?);
         let raw_instance = raw.to_raw_instance(mesh.to_weak(), self.white_texture.to_weak());
 
         let realized = RealizedCurve {
             raw,
             mesh,
+            line_width,
             canvas_layer_id,
         };
         self.loaded.insert(curve_id, realized);
@@ -376,6 +613,53 @@ impl Curves {
         Ok(raw_instance)
     }
 
+    /// re-tessellates screen space curves for which the pixels per unit of their layer changed
+    pub fn update_screen_curves<F>(&mut self, assets: &AssetsClient, px_factor: F)
+    where
+        F: Fn(&Uuid) -> Option<f32>,
+    {
+        for (curve_id, realized) in &mut self.loaded {
+            if realized.raw.stroke.line_width_space != WorldOrScreen::Screen {
+                continue;
+            }
+
+            let line_width = realized
+                .raw
+                .local_line_width(px_factor(&realized.canvas_layer_id));
+            if (line_width - realized.line_width).abs() <= realized.line_width * 0.01 {
+                continue;
+            }
+
+            log::debug!("re-tessellate screen space curve: {:?}", curve_id);
+            match Self::tessellate(&realized.raw, line_width) {
+                Ok(mesh) => {
+                    realized.mesh = assets.store(*curve_id, mesh);
+                    realized.line_width = line_width;
+                }
+                Err(e) => {
+                    log::warn!("failed to re-tessellate curve {:?}: {}", curve_id, e);
+                }
+            }
+        }
+    }
+
+    fn tessellate(raw: &RawCurve<Weak>, line_width: f32) -> anyhow::Result<Mesh> {
+        let mut geometry = VertexBuffers::new();
+        tessellate_stroke(
+            &raw.path,
+            &raw.stroke,
+            line_width,
+            Vertex::default_color(),
+            &mut geometry,
+        )?;
+        geometry.indices.reverse();
+
+        Ok(Mesh {
+            vertices: geometry.vertices,
+            indices: geometry.indices,
+        })
+    }
+
     pub fn minor_update_curve(
         &mut self,
         canvas_layer_id: Uuid,
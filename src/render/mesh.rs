@@ -2,7 +2,9 @@ use crate::asset::loader::{AssetCursor, AssetLoader};
 use crate::asset::storage::AssetsClient;
 use crate::asset::{StrongAssetId, WeakAssetId};
 use crate::render::buffer::Vertex;
+use crate::render::svg::svg_mesh;
 use crate::util::HashMap;
+use nalgebra::{Matrix4, Point3};
 use serde::Deserialize;
 use uuid::Uuid;
 use wgpu::util::DeviceExt;
@@ -42,6 +44,7 @@ impl AssetLoader for MeshLoader {
         let bytes = cursor.read()?;
         let asset = match extension {
             "json" => serde_json::from_slice(&bytes)?,
+            "svg" => svg_mesh(&bytes)?,
             s => Err(anyhow::anyhow!(
                 "unhandled file type for mesh asset loader: {}",
                 s
@@ -52,10 +55,72 @@ impl AssetLoader for MeshLoader {
     }
 }
 
+/// axis aligned bounding box
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Aabb {
+    pub min: Point3<f32>,
+    pub max: Point3<f32>,
+}
+
+impl Aabb {
+    pub fn from_points<I: IntoIterator<Item = Point3<f32>>>(points: I) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+
+        Some(points.fold(
+            Aabb {
+                min: first,
+                max: first,
+            },
+            |aabb, p| Aabb {
+                min: aabb.min.inf(&p),
+                max: aabb.max.sup(&p),
+            },
+        ))
+    }
+
+    pub fn corners(&self) -> [Point3<f32>; 8] {
+        let (min, max) = (self.min, self.max);
+        [
+            Point3::new(min.x, min.y, min.z),
+            Point3::new(max.x, min.y, min.z),
+            Point3::new(min.x, max.y, min.z),
+            Point3::new(max.x, max.y, min.z),
+            Point3::new(min.x, min.y, max.z),
+            Point3::new(max.x, min.y, max.z),
+            Point3::new(min.x, max.y, max.z),
+            Point3::new(max.x, max.y, max.z),
+        ]
+    }
+
+    /// bounds of the transformed box
+    pub fn transform(&self, matrix: &Matrix4<f32>) -> Self {
+        Self::from_points(
+            self.corners()
+                .iter()
+                .map(|corner| matrix.transform_point(corner)),
+        )
+        .unwrap()
+    }
+
+    /// if any part of the box might be inside of the clip space of the view projection
+    pub fn intersects_clip(&self, view_proj: &Matrix4<f32>) -> bool {
+        let clip = self
+            .corners()
+            .map(|corner| view_proj * corner.to_homogeneous());
+
+        !(clip.iter().all(|c| c.x > c.w)
+            || clip.iter().all(|c| c.x < -c.w)
+            || clip.iter().all(|c| c.y > c.w)
+            || clip.iter().all(|c| c.y < -c.w))
+    }
+}
+
 pub struct RealizedMesh {
     pub(crate) vertex_buffer: wgpu::Buffer,
     pub(crate) index_buffer: wgpu::Buffer,
     pub(crate) index_length: u32,
+    pub(crate) bounds: Option<Aabb>,
 }
 
 impl RealizedMesh {
@@ -76,6 +141,7 @@ impl RealizedMesh {
             vertex_buffer,
             index_buffer,
             index_length: m.indices.len() as u32,
+            bounds: Aabb::from_points(m.vertices.iter().map(|v| Point3::from(v.position))),
         }
     }
 }
@@ -104,18 +170,22 @@ impl Meshes {
                     Vertex {
                         position: [-0.5, 0.5, 0.0],
                         tex_coords: [0.0, 0.0],
+                        color: Vertex::default_color(),
                     },
                     Vertex {
                         position: [0.5, 0.5, 0.0],
                         tex_coords: [1.0, 0.0],
+                        color: Vertex::default_color(),
                     },
                     Vertex {
                         position: [0.5, -0.5, 0.0],
                         tex_coords: [1.0, 1.0],
+                        color: Vertex::default_color(),
                     },
                     Vertex {
                         position: [-0.5, -0.5, 0.0],
                         tex_coords: [0.0, 1.0],
+                        color: Vertex::default_color(),
                     },
                 ],
                 indices: vec![1, 0, 3, 3, 2, 1],
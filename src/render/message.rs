@@ -1,30 +1,46 @@
-use crate::asset::Weak;
-use crate::render::camera::RawCamera;
-use crate::render::canvas::{CanvasFrame, RawInstance};
+use crate::asset::{Weak, WeakAssetId};
+use crate::render::camera::{CameraSnapshot, RawCamera};
+use crate::render::canvas::{CanvasFrame, CanvasSnapshot, ClipRect, LayerSnapshot, RawInstance};
 use crate::render::client::{RenderClient, RenderDefaults};
 use crate::render::curve::RawCurve;
+use crate::render::pipeline::WGSLSource;
+use crate::render::post::PostEffect;
 use crate::render::text::RawText;
+use crate::render::view::Texture;
+use crate::render::{PresentMode, Samples};
+use image::DynamicImage;
+use nalgebra::{Point2, Similarity3, Vector2};
 use parking_lot::Mutex;
 use roundabout::prelude::MessageSender;
 use std::ops::Deref;
 use std::rc::Rc;
+use std::sync::Arc;
 use uuid::Uuid;
 
 #[derive(Debug)]
 pub struct RenderCreatedEvent {
     pub defaults: Box<RenderDefaults>,
+    pub samples: Samples,
+    pub supported_samples: Vec<Samples>,
 }
 
 impl RenderCreatedEvent {
     #[inline]
     pub fn render_client(&self, sender: MessageSender) -> RenderClient {
-        RenderClient::new(Rc::new(self.defaults.deref().to_owned()), sender)
+        RenderClient::new(
+            Rc::new(self.defaults.deref().to_owned()),
+            self.samples,
+            self.supported_samples.clone(),
+            sender,
+        )
     }
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct DrawnEvent {
     pub frame: u64,
+    pub instances: usize,
+    pub assets: usize,
 }
 
 #[derive(Debug)]
@@ -47,9 +63,13 @@ pub struct CanvasLayerEvent {
     pub kind: CanvasLayerEventKind,
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum CanvasLayerEventKind {
     Created,
+    Opacity(f32),
+    Tint([f32; 3]),
+    Culling(bool),
+    Clip(Option<ClipRect>),
     Dropped,
 }
 
@@ -70,7 +90,9 @@ pub struct CanvasEventCreated {
     pub size: Option<[u32; 2]>,
     pub priority: usize,
     pub frame: bool,
+    pub depth: bool,
     pub frames: Vec<CanvasFrame<'static>>,
+    pub post_effects: Vec<PostEffect<Weak>>,
 }
 
 #[derive(Debug)]
@@ -87,6 +109,20 @@ pub enum InstanceEventKind {
     Dropped,
 }
 
+#[derive(Debug)]
+pub struct GroupEvent {
+    pub id: Uuid,
+    pub layer: Uuid,
+    pub kind: GroupEventKind,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GroupEventKind {
+    Created(Similarity3<f32>),
+    Modified(Similarity3<f32>),
+    Dropped,
+}
+
 #[derive(Debug)]
 pub struct TextEvent {
     pub id: Uuid,
@@ -104,6 +140,14 @@ pub enum TextEventKind {
     Dropped,
 }
 
+/// sent by the render server after the mesh of a text has been generated
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TextLaidOutEvent {
+    pub text_id: Uuid,
+    pub size: Vector2<f32>,
+    pub line_count: usize,
+}
+
 #[derive(Debug)]
 pub struct CurveEvent {
     pub id: Uuid,
@@ -120,3 +164,79 @@ pub enum CurveEventKind {
     },
     Dropped,
 }
+
+/// requests a `DebugSceneEvent` from the render server
+#[derive(Debug, Copy, Clone)]
+pub struct DebugSceneRequestEvent;
+
+/// snapshot of the render state, e.g. for editors and debug inspectors
+#[derive(Debug, Clone)]
+pub struct DebugSceneEvent {
+    pub cameras: Vec<CameraSnapshot>,
+    pub layers: Vec<LayerSnapshot>,
+    pub canvasses: Vec<CanvasSnapshot>,
+}
+
+/// requests a `PickedEvent` for the instance of the layer at the world point
+#[derive(Debug, Copy, Clone)]
+pub struct PickRequestEvent {
+    pub layer: Uuid,
+    pub world_point: Point2<f32>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PickedEvent {
+    pub layer: Uuid,
+    pub world_point: Point2<f32>,
+    pub instance: Option<Uuid>,
+}
+
+/// requests a `CapturedFrameEvent` with the current frame from the render server
+#[derive(Debug, Copy, Clone)]
+pub struct CaptureFrameEvent;
+
+/// rgba image of the frame, e.g. for screenshots and visual tests
+#[derive(Debug, Clone)]
+pub struct CapturedFrameEvent {
+    pub image: Arc<DynamicImage>,
+}
+
+/// replaces a rectangle of an image texture layer without uploading the whole image,
+/// the image asset stays as is, so a reload of the texture discards the update
+#[derive(Debug, Clone)]
+pub struct UpdateTextureRegionEvent {
+    pub texture: WeakAssetId<Texture>,
+    pub layer: u32,
+    pub origin: [u32; 2],
+    pub size: [u32; 2],
+    pub rgba: Vec<u8>,
+}
+
+/// applies `frame * multiply + add` to the composited frame before it is presented
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SetColorGradingEvent {
+    pub multiply: [f32; 4],
+    pub add: [f32; 4],
+}
+
+impl Default for SetColorGradingEvent {
+    fn default() -> Self {
+        Self {
+            multiply: [1.0; 4],
+            add: [0.0; 4],
+        }
+    }
+}
+
+/// the shader or the pipelines using it failed to compile, e.g. after a hot reload,
+/// the previous version of the shader stays in use
+#[derive(Debug, Clone)]
+pub struct ShaderCompileFailedEvent {
+    pub id: WeakAssetId<WGSLSource>,
+    pub message: String,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SetPresentModeEvent {
+    pub present_mode: PresentMode,
+}
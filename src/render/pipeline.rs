@@ -2,14 +2,16 @@ use crate::asset::loader::{AssetCursor, AssetLoader};
 use crate::asset::storage::AssetsClient;
 use crate::asset::{StrongAssetId, WeakAssetId};
 use crate::render::buffer::{Instance, Vertex};
-use crate::render::view::RealizedView;
-use crate::render::Samples;
+use crate::render::{DepthFormat, Samples};
 use crate::some_or_return;
 use crate::util::{HashMap, OrderWindow};
+use nalgebra::Vector3;
+use parking_lot::Mutex;
 use serde::Deserialize;
 use std::borrow::Cow;
 use std::collections::BTreeSet;
 use std::marker::PhantomData;
+use std::sync::Arc;
 use uuid::Uuid;
 
 #[derive(Debug, Copy, Clone)]
@@ -28,6 +30,10 @@ pub struct PipelineBuilder<TS> {
     pub(crate) color_blend: wgpu::BlendComponent,
     pub(crate) alpha_blend: wgpu::BlendComponent,
     pub(crate) priority: usize,
+    pub(crate) stencil: StencilConfig,
+    pub(crate) depth_write: bool,
+    pub(crate) depth_compare: wgpu::CompareFunction,
+    pub(crate) instance_scale: InstanceScale,
     _pd: PhantomData<TS>,
 }
 
@@ -49,6 +55,32 @@ impl<T> PipelineBuilder<T> {
         self.alpha_blend = alpha_blend;
         self
     }
+
+    #[inline]
+    pub fn with_stencil(mut self, stencil: StencilConfig) -> Self {
+        self.stencil = stencil;
+        self
+    }
+
+    /// disable for passes that should draw in submission order, e.g. additive particles
+    #[inline]
+    pub fn with_depth_write(mut self, depth_write: bool) -> Self {
+        self.depth_write = depth_write;
+        self
+    }
+
+    /// `Always` disables the depth test
+    #[inline]
+    pub fn with_depth_compare(mut self, depth_compare: wgpu::CompareFunction) -> Self {
+        self.depth_compare = depth_compare;
+        self
+    }
+
+    #[inline]
+    pub fn with_instance_scale(mut self, instance_scale: InstanceScale) -> Self {
+        self.instance_scale = instance_scale;
+        self
+    }
 }
 
 impl PipelineBuilder<EmptyPipelineBuilder> {
@@ -63,6 +95,10 @@ impl PipelineBuilder<EmptyPipelineBuilder> {
             color_blend: self.color_blend,
             alpha_blend: self.alpha_blend,
             priority: self.priority,
+            stencil: self.stencil,
+            depth_write: self.depth_write,
+            depth_compare: self.depth_compare,
+            instance_scale: self.instance_scale,
             _pd: Default::default(),
         }
     }
@@ -80,6 +116,10 @@ impl PipelineBuilder<VSPipelineBuilder> {
             color_blend: self.color_blend,
             alpha_blend: self.alpha_blend,
             priority: self.priority,
+            stencil: self.stencil,
+            depth_write: self.depth_write,
+            depth_compare: self.depth_compare,
+            instance_scale: self.instance_scale,
             _pd: Default::default(),
         }
     }
@@ -94,6 +134,120 @@ impl PipelineBuilder<CompletePipelineBuilder> {
             color_blend: self.color_blend,
             alpha_blend: self.alpha_blend,
             priority: self.priority,
+            stencil: self.stencil,
+            depth_write: self.depth_write,
+            depth_compare: self.depth_compare,
+            instance_scale: self.instance_scale,
+        }
+    }
+}
+
+/// how the shader applies the scale of an instance to its mesh, used to cull and pick instances
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InstanceScale {
+    /// the scale is applied per axis
+    Axes,
+    /// only the x scale is applied to all axes, e.g. text passes shader params in y and z
+    Uniform,
+}
+
+impl InstanceScale {
+    #[inline]
+    pub fn spatial(self, scale: [f32; 3]) -> Vector3<f32> {
+        match self {
+            InstanceScale::Axes => Vector3::from(scale),
+            InstanceScale::Uniform => Vector3::repeat(scale[0]),
+        }
+    }
+}
+
+impl Default for InstanceScale {
+    fn default() -> Self {
+        InstanceScale::Axes
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StencilMode {
+    Disabled,
+    /// writes the reference into the stencil buffer for every drawn fragment
+    Write,
+    /// only draws fragments where the stencil buffer equals the reference
+    Equal,
+    /// only draws fragments where the stencil buffer differs from the reference
+    NotEqual,
+}
+
+impl Default for StencilMode {
+    fn default() -> Self {
+        StencilMode::Disabled
+    }
+}
+
+/// masking requires a depth format with a stencil aspect, see `DepthFormat::has_stencil`,
+/// e.g. opt in with the `depth24PlusStencil8` depth format of the display config
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StencilConfig {
+    #[serde(default)]
+    pub mode: StencilMode,
+    #[serde(default)]
+    pub reference: u32,
+}
+
+impl StencilConfig {
+    #[inline]
+    pub fn write(reference: u32) -> Self {
+        Self {
+            mode: StencilMode::Write,
+            reference,
+        }
+    }
+
+    #[inline]
+    pub fn equal(reference: u32) -> Self {
+        Self {
+            mode: StencilMode::Equal,
+            reference,
+        }
+    }
+
+    #[inline]
+    pub fn not_equal(reference: u32) -> Self {
+        Self {
+            mode: StencilMode::NotEqual,
+            reference,
+        }
+    }
+}
+
+impl From<StencilConfig> for wgpu::StencilState {
+    fn from(config: StencilConfig) -> Self {
+        let face = match config.mode {
+            StencilMode::Disabled => return Default::default(),
+            StencilMode::Write => wgpu::StencilFaceState {
+                compare: wgpu::CompareFunction::Always,
+                fail_op: wgpu::StencilOperation::Keep,
+                depth_fail_op: wgpu::StencilOperation::Keep,
+                pass_op: wgpu::StencilOperation::Replace,
+            },
+            StencilMode::Equal => wgpu::StencilFaceState {
+                compare: wgpu::CompareFunction::Equal,
+                ..wgpu::StencilFaceState::IGNORE
+            },
+            StencilMode::NotEqual => wgpu::StencilFaceState {
+                compare: wgpu::CompareFunction::NotEqual,
+                ..wgpu::StencilFaceState::IGNORE
+            },
+        };
+
+        wgpu::StencilState {
+            front: face,
+            back: face,
+            read_mask: !0,
+            write_mask: !0,
         }
     }
 }
@@ -109,10 +263,17 @@ pub struct Pipeline {
     pub alpha_blend: wgpu::BlendComponent,
     #[serde(default)]
     pub priority: usize,
+    #[serde(default)]
+    pub stencil: StencilConfig,
+    #[serde(default = "Pipeline::default_depth_write")]
+    pub depth_write: bool,
+    #[serde(default = "Pipeline::default_depth_compare")]
+    pub depth_compare: wgpu::CompareFunction,
+    #[serde(default)]
+    pub instance_scale: InstanceScale,
 }
 
 impl Pipeline {
-    // TODO: wrong alpha order for text?
     pub const UNLIT_PRIORITY: usize = 0;
     pub const ALPHA_PRIORITY: usize = 1;
     pub const TEXT_PRIORITY: usize = 2;
@@ -150,6 +311,12 @@ impl Pipeline {
         0x9d, 0x85, 0x89, 0x97, 0xfe, 0x4e, 0x26, 0x46
     ]);
 
+    /// instances of blended pipelines are drawn back to front after the opaque ones
+    #[inline]
+    pub fn is_blended(&self) -> bool {
+        self.color_blend != wgpu::BlendComponent::REPLACE
+    }
+
     #[inline]
     pub fn builder() -> PipelineBuilder<EmptyPipelineBuilder> {
         PipelineBuilder {
@@ -158,13 +325,28 @@ impl Pipeline {
             color_blend: wgpu::BlendComponent::REPLACE,
             alpha_blend: wgpu::BlendComponent::REPLACE,
             priority: 0,
+            stencil: Default::default(),
+            depth_write: Pipeline::default_depth_write(),
+            depth_compare: Pipeline::default_depth_compare(),
+            instance_scale: Default::default(),
             _pd: Default::default(),
         }
     }
+
+    #[inline]
+    pub fn default_depth_write() -> bool {
+        true
+    }
+
+    #[inline]
+    pub fn default_depth_compare() -> wgpu::CompareFunction {
+        wgpu::CompareFunction::LessEqual
+    }
 }
 
 pub struct RealizedPipeline {
     pub(crate) render_pipeline: wgpu::RenderPipeline,
+    pub(crate) flat_render_pipeline: wgpu::RenderPipeline,
     pub(crate) pipeline: Pipeline,
 }
 
@@ -191,6 +373,8 @@ pub struct Pipelines {
     shader_index: BTreeSet<(WeakAssetId<WGSLSource>, OrderWindow<WeakAssetId<Pipeline>>)>,
     render_pipeline_layout: wgpu::PipelineLayout,
     samples: Samples,
+    frame_format: wgpu::TextureFormat,
+    depth_format: DepthFormat,
     // defaults
     pub(crate) unlit_pipeline: StrongAssetId<Pipeline>,
     pub(crate) unlit_alpha_pipeline: StrongAssetId<Pipeline>,
@@ -202,6 +386,8 @@ impl Pipelines {
         assets: &AssetsClient,
         render_pipeline_layout: wgpu::PipelineLayout,
         samples: Samples,
+        frame_format: wgpu::TextureFormat,
+        depth_format: DepthFormat,
     ) -> Self {
         let unlit_source = assets.store(
             Pipeline::UNLIT_SHADER_UUID,
@@ -259,6 +445,7 @@ impl Pipelines {
                     operation: wgpu::BlendOperation::Add,
                 })
                 .with_priority(Pipeline::TEXT_PRIORITY)
+                .with_instance_scale(InstanceScale::Uniform)
                 .finalize(),
         );
 
@@ -269,6 +456,8 @@ impl Pipelines {
             shader_index: Default::default(),
             render_pipeline_layout,
             samples,
+            frame_format,
+            depth_format,
             unlit_pipeline,
             unlit_alpha_pipeline,
             text_pipeline,
@@ -290,20 +479,25 @@ impl Pipelines {
             .collect()
     }
 
+    /// keeps the previous shader and its pipelines on a compile error
     pub fn upsert_shader(
         &mut self,
         device: &wgpu::Device,
         id: WeakAssetId<WGSLSource>,
         source: &WGSLSource,
-    ) {
+    ) -> anyhow::Result<()> {
         log::debug!("upsert shader: {:?}", id);
 
-        let module = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
-            label: Some("module"),
-            source: wgpu::ShaderSource::Wgsl(source.0.clone()),
-        });
+        let module = validated(device, || {
+            device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+                label: Some("module"),
+                source: wgpu::ShaderSource::Wgsl(source.0.clone()),
+            })
+        })?;
 
-        if self.shaders.insert(id, module).is_some() {
+        if let Some(previous) = self.shaders.insert(id, module) {
+            // the previous pipelines are restored along with the previous shader if one fails
+            let mut replaced = Vec::new();
             for (p_id, pipeline) in self
                 .loaded
                 .iter()
@@ -314,12 +508,18 @@ impl Pipelines {
                 })
                 .collect::<Vec<_>>()
             {
-                assert!(
-                    self.upsert_pipeline(device, p_id, &pipeline),
-                    "upsert of pipeline on upsert shader"
-                );
+                let realized = self.loaded.remove(&p_id);
+                replaced.extend(realized.map(|realized| (p_id, realized)));
+
+                if let Err(e) = self.upsert_pipeline(device, p_id, &pipeline) {
+                    self.shaders.insert(id, previous);
+                    self.loaded.extend(replaced);
+                    return Err(e);
+                }
             }
         }
+
+        Ok(())
     }
 
     pub fn remove_shader(&mut self, id: &WeakAssetId<WGSLSource>) {
@@ -343,31 +543,97 @@ impl Pipelines {
         self.loaded.get(pipeline_id)
     }
 
+    /// false if the shaders of the pipeline aren't loaded yet,
+    /// keeps the previous pipeline if the shaders don't fit the pipeline
     pub fn upsert_pipeline(
         &mut self,
         device: &wgpu::Device,
         pipeline_id: WeakAssetId<Pipeline>,
         pipeline: &Pipeline,
-    ) -> bool {
+    ) -> anyhow::Result<bool> {
         log::debug!("upsert pipeline: {:?}", pipeline_id);
         self.remove_queued_pipeline(pipeline_id);
 
-        let vs_module = some_or_return!(self.shaders.get(&pipeline.vs_source.to_weak()), || false);
-        let fs_module = some_or_return!(self.shaders.get(&pipeline.fs_source.to_weak()), || false);
+        let vs_source = pipeline.vs_source.to_weak();
+        let fs_source = pipeline.fs_source.to_weak();
+        let vs_module = some_or_return!(self.shaders.get(&vs_source), || Ok(false));
+        let fs_module = some_or_return!(self.shaders.get(&fs_source), || Ok(false));
+
+        let stencil = if self.depth_format.has_stencil() {
+            pipeline.stencil.into()
+        } else {
+            if pipeline.stencil.mode != StencilMode::Disabled {
+                log::warn!(
+                    "ignore stencil of pipeline {:?} as {:?} has no stencil aspect",
+                    pipeline_id,
+                    self.depth_format
+                );
+            }
+            Default::default()
+        };
+        let depth_stencil = wgpu::DepthStencilState {
+            format: self.depth_format.into(),
+            depth_write_enabled: pipeline.depth_write,
+            depth_compare: pipeline.depth_compare,
+            stencil,
+            bias: wgpu::DepthBiasState {
+                constant: 2,
+                slope_scale: 2.0,
+                clamp: 0.0,
+            },
+        };
+        let (render_pipeline, flat_render_pipeline) = validated(device, || {
+            (
+                self.create_render_pipeline(
+                    device,
+                    vs_module,
+                    fs_module,
+                    pipeline,
+                    Some(depth_stencil),
+                ),
+                // used for canvasses without a depth attachment
+                self.create_render_pipeline(device, vs_module, fs_module, pipeline, None),
+            )
+        })?;
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        self.remove_loaded_pipeline(pipeline_id);
+
+        let raw = RealizedPipeline {
+            render_pipeline,
+            flat_render_pipeline,
+            pipeline: pipeline.to_owned(),
+        };
+
+        self.loaded.insert(pipeline_id, raw);
+        self.shader_index
+            .insert((pipeline.vs_source.to_weak(), OrderWindow::new(pipeline_id)));
+        self.shader_index
+            .insert((pipeline.fs_source.to_weak(), OrderWindow::new(pipeline_id)));
+
+        Ok(true)
+    }
+
+    fn create_render_pipeline(
+        &self,
+        device: &wgpu::Device,
+        vs_module: &wgpu::ShaderModule,
+        fs_module: &wgpu::ShaderModule,
+        pipeline: &Pipeline,
+        depth_stencil: Option<wgpu::DepthStencilState>,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Render Pipeline"),
             layout: Some(&self.render_pipeline_layout),
             vertex: wgpu::VertexState {
-                module: &vs_module,
+                module: vs_module,
                 entry_point: "main",
                 buffers: &[Vertex::desc(), Instance::desc()],
             },
             fragment: Some(wgpu::FragmentState {
-                module: &fs_module,
+                module: fs_module,
                 entry_point: "main",
                 targets: &[wgpu::ColorTargetState {
-                    format: RealizedView::FRAME_TEXTURE_FORMAT,
+                    format: self.frame_format,
                     write_mask: wgpu::ColorWrites::ALL,
                     blend: Some(wgpu::BlendState {
                         color: pipeline.color_blend,
@@ -385,38 +651,13 @@ impl Pipelines {
                 // TODO: enable?
                 conservative: false,
             },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: RealizedView::DEPTH_TEXTURE_FORMAT,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::LessEqual,
-                stencil: Default::default(),
-                bias: wgpu::DepthBiasState {
-                    constant: 2,
-                    slope_scale: 2.0,
-                    clamp: 0.0,
-                },
-            }),
+            depth_stencil,
             multisample: wgpu::MultisampleState {
                 count: self.samples.into(),
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
-        });
-
-        self.remove_loaded_pipeline(pipeline_id);
-
-        let raw = RealizedPipeline {
-            render_pipeline,
-            pipeline: pipeline.to_owned(),
-        };
-
-        self.loaded.insert(pipeline_id, raw);
-        self.shader_index
-            .insert((pipeline.vs_source.to_weak(), OrderWindow::new(pipeline_id)));
-        self.shader_index
-            .insert((pipeline.fs_source.to_weak(), OrderWindow::new(pipeline_id)));
-
-        true
+        })
     }
 
     pub fn remove_pipeline(&mut self, pipeline_id: WeakAssetId<Pipeline>) {
@@ -447,3 +688,27 @@ impl Pipelines {
         }
     }
 }
+
+/// captures the validation errors of the created wgpu resources instead of panicking on them,
+/// e.g. for hot reloaded shaders with syntax errors,
+/// the native backends report the errors while the resources are created
+pub(crate) fn validated<T, F>(device: &wgpu::Device, f: F) -> anyhow::Result<T>
+where
+    F: FnOnce() -> T,
+{
+    let error: Arc<Mutex<Option<String>>> = Arc::default();
+    let captured = error.clone();
+    device.on_uncaptured_error(move |e| {
+        captured.lock().get_or_insert_with(|| e.to_string());
+    });
+
+    let value = f();
+    // errors outside of validated calls stay fatal like with the default handler of wgpu
+    device.on_uncaptured_error(|e| panic!("wgpu error: {}", e));
+
+    let error = error.lock().take();
+    match error {
+        Some(e) => Err(anyhow::anyhow!("{}", e)),
+        None => Ok(value),
+    }
+}
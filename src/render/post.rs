@@ -0,0 +1,478 @@
+use crate::asset::storage::AssetsClient;
+use crate::asset::{AssetId, StrongAssetId, Weak, WeakAssetId};
+use crate::render::pipeline::{validated, WGSLSource};
+use crate::render::view::RealizedView;
+use crate::some_or_return;
+use crate::util::HashMap;
+use uuid::Uuid;
+use wgpu::util::DeviceExt;
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct PostUniforms {
+    params: [f32; 4],
+    // input texel size in xy, output texel size in zw
+    texel_size: [f32; 4],
+}
+
+/**
+A fullscreen pass of the post processing chain of a canvas.
+The shader has a vertex and a fragment `main` entry point (see grading.wgsl) with group 0 bound to:
+    * 0: the uniforms `params: vec4<f32>` and `texel_size: vec4<f32>`
+    * 1: the output of the previous pass
+    * 2: the output of the canvas, e.g. for combining bloom
+    * 3: a linear clamped sampler
+*/
+#[derive(Debug, Clone)]
+pub struct PostEffect<S> {
+    pub shader: AssetId<WGSLSource, S>,
+    pub params: [f32; 4],
+    /// size of the output relative to the canvas, e.g. 0.5 for a downsample pass,
+    /// ignored for the last pass which is alpha blended onto the frame
+    pub scale: f32,
+}
+
+impl<S> PostEffect<S> {
+    #[inline]
+    pub fn new(shader: AssetId<WGSLSource, S>) -> Self {
+        Self {
+            shader,
+            params: [0.0; 4],
+            scale: 1.0,
+        }
+    }
+
+    #[inline]
+    pub fn with_params(mut self, params: [f32; 4]) -> Self {
+        self.params = params;
+        self
+    }
+
+    #[inline]
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    #[inline]
+    pub fn to_weak(&self) -> PostEffect<Weak> {
+        PostEffect {
+            shader: self.shader.to_weak(),
+            params: self.params,
+            scale: self.scale,
+        }
+    }
+}
+
+pub struct RealizedPostChain {
+    pub(crate) effects: Vec<PostEffect<Weak>>,
+    source: RealizedView,
+    stages: Vec<RealizedView>,
+    overlay: RealizedView,
+    bind_groups: Vec<wgpu::BindGroup>,
+    overlay_bind_group: wgpu::BindGroup,
+    _uniform_buffers: Vec<wgpu::Buffer>,
+}
+
+impl RealizedPostChain {
+    /// the canvas is resolved into the source instead of the frame
+    #[inline]
+    pub fn source(&self) -> &wgpu::TextureView {
+        &self.source.view
+    }
+
+    /// the overlay is resolved into its own buffer, so it isn't affected by the effects
+    #[inline]
+    pub fn overlay(&self) -> &wgpu::TextureView {
+        &self.overlay.view
+    }
+}
+
+struct PostPipelines {
+    /// writes into the buffer of the next pass
+    stage: wgpu::RenderPipeline,
+    /// the last pass, blended onto the frame canvasses drawn before
+    composite: wgpu::RenderPipeline,
+}
+
+pub struct PostProcessing {
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline_layout: wgpu::PipelineLayout,
+    sampler: wgpu::Sampler,
+    pipelines: HashMap<WeakAssetId<WGSLSource>, PostPipelines>,
+    blit_pipeline: wgpu::RenderPipeline,
+    frame_format: wgpu::TextureFormat,
+    // defaults
+    pub(crate) crt_shader: StrongAssetId<WGSLSource>,
+}
+
+impl PostProcessing {
+    #[rustfmt::skip]
+    const CRT_SHADER_UUID: Uuid = Uuid::from_bytes([
+        0x5f, 0x3a, 0x91, 0x0e, 0x7c, 0x42, 0x4b, 0x9d,
+        0x86, 0x1d, 0x2e, 0xc4, 0x70, 0xb3, 0x58, 0xa1
+    ]);
+
+    pub fn new(
+        assets: &AssetsClient,
+        device: &wgpu::Device,
+        frame_format: wgpu::TextureFormat,
+    ) -> Self {
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                texture_entry(1),
+                texture_entry(2),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler {
+                        filtering: true,
+                        comparison: false,
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("post_bind_group_layout"),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Post Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let blit_module = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("post_blit_module"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../../asset/shader/blit.wgsl").into()),
+        });
+        // the blitted and composited canvasses hold premultiplied colors
+        let blit_pipeline = create_pipeline(
+            device,
+            &pipeline_layout,
+            &blit_module,
+            frame_format,
+            Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+        );
+
+        let crt_shader = assets.store(
+            Self::CRT_SHADER_UUID,
+            WGSLSource(include_str!("../../asset/shader/crt.wgsl").into()),
+        );
+
+        Self {
+            bind_group_layout,
+            pipeline_layout,
+            sampler,
+            pipelines: Default::default(),
+            blit_pipeline,
+            frame_format,
+            crt_shader,
+        }
+    }
+
+    #[inline]
+    pub fn has_shader(&self, id: &WeakAssetId<WGSLSource>) -> bool {
+        self.pipelines.contains_key(id)
+    }
+
+    pub fn upsert_shader(
+        &mut self,
+        device: &wgpu::Device,
+        id: WeakAssetId<WGSLSource>,
+        source: &WGSLSource,
+    ) -> anyhow::Result<()> {
+        log::debug!("upsert post shader: {:?}", id);
+
+        let module = validated(device, || {
+            device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+                label: Some("post_module"),
+                source: wgpu::ShaderSource::Wgsl(source.0.clone()),
+            })
+        })?;
+
+        let stage = validated(device, || {
+            create_pipeline(
+                device,
+                &self.pipeline_layout,
+                &module,
+                self.frame_format,
+                None,
+            )
+        })?;
+        let composite = validated(device, || {
+            create_pipeline(
+                device,
+                &self.pipeline_layout,
+                &module,
+                self.frame_format,
+                Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+            )
+        })?;
+
+        self.pipelines
+            .insert(id, PostPipelines { stage, composite });
+        Ok(())
+    }
+
+    pub fn remove_shader(&mut self, id: &WeakAssetId<WGSLSource>) {
+        if self.pipelines.remove(id).is_some() {
+            log::debug!("remove post shader: {:?}", id);
+        }
+    }
+
+    pub fn create_chain(
+        &self,
+        device: &wgpu::Device,
+        size: [u32; 2],
+        effects: Vec<PostEffect<Weak>>,
+    ) -> RealizedPostChain {
+        let source =
+            RealizedView::post_buffer(device, size, self.frame_format, Some("post_source_buffer"));
+        let last = effects.len().saturating_sub(1);
+
+        let stages: Vec<RealizedView> = effects[..last]
+            .iter()
+            .map(|effect| {
+                let stage_size = [
+                    ((size[0] as f32 * effect.scale) as u32).max(1),
+                    ((size[1] as f32 * effect.scale) as u32).max(1),
+                ];
+                RealizedView::post_buffer(
+                    device,
+                    stage_size,
+                    self.frame_format,
+                    Some("post_stage_buffer"),
+                )
+            })
+            .collect();
+        let overlay =
+            RealizedView::post_buffer(device, size, self.frame_format, Some("post_overlay_buffer"));
+
+        let mut bind_groups = Vec::with_capacity(effects.len());
+        let mut uniform_buffers = Vec::with_capacity(effects.len());
+        for (i, effect) in effects.iter().enumerate() {
+            let input = if i == 0 { &source } else { &stages[i - 1] };
+            let output_size = stages.get(i).map(|s| s.size).unwrap_or(source.size);
+
+            let uniforms = PostUniforms {
+                params: effect.params,
+                texel_size: [
+                    1.0 / input.size.width as f32,
+                    1.0 / input.size.height as f32,
+                    1.0 / output_size.width as f32,
+                    1.0 / output_size.height as f32,
+                ],
+            };
+            let (bind_group, uniform_buffer) =
+                self.create_bind_group(device, uniforms, input, &source, "post_bind_group");
+            bind_groups.push(bind_group);
+            uniform_buffers.push(uniform_buffer);
+        }
+
+        let texel_size = [
+            1.0 / overlay.size.width as f32,
+            1.0 / overlay.size.height as f32,
+            1.0 / source.size.width as f32,
+            1.0 / source.size.height as f32,
+        ];
+        let overlay_uniforms = PostUniforms {
+            params: [0.0; 4],
+            texel_size,
+        };
+        let (overlay_bind_group, uniform_buffer) = self.create_bind_group(
+            device,
+            overlay_uniforms,
+            &overlay,
+            &source,
+            "post_overlay_bind_group",
+        );
+        uniform_buffers.push(uniform_buffer);
+
+        RealizedPostChain {
+            effects,
+            source,
+            stages,
+            overlay,
+            bind_groups,
+            overlay_bind_group,
+            _uniform_buffers: uniform_buffers,
+        }
+    }
+
+    fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        uniforms: PostUniforms,
+        input: &RealizedView,
+        source: &RealizedView,
+        label: &str,
+    ) -> (wgpu::BindGroup, wgpu::Buffer) {
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Post Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[uniforms]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&input.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&source.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+            label: Some(label),
+        });
+
+        (bind_group, uniform_buffer)
+    }
+
+    /// the chain is only applied once the shaders of all its effects are available
+    #[inline]
+    pub fn is_ready(&self, chain: &RealizedPostChain) -> bool {
+        chain
+            .effects
+            .iter()
+            .all(|effect| self.pipelines.contains_key(&effect.shader))
+    }
+
+    /// the last pass is blended onto the target, so the frame canvasses drawn before remain
+    pub fn draw(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        chain: &RealizedPostChain,
+        target: &wgpu::TextureView,
+    ) {
+        for (i, effect) in chain.effects.iter().enumerate() {
+            let pipelines = some_or_return!(self.pipelines.get(&effect.shader));
+            let (view, pipeline, load) = match chain.stages.get(i) {
+                Some(stage) => (
+                    &stage.view,
+                    &pipelines.stage,
+                    wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                ),
+                None => (target, &pipelines.composite, wgpu::LoadOp::Load),
+            };
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                color_attachments: &[wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations { load, store: true },
+                }],
+                depth_stencil_attachment: None,
+                label: Some("post_pass"),
+            });
+
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, &chain.bind_groups[i], &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+    }
+
+    /// blends the overlay onto the target after the effects have been applied
+    pub fn draw_overlay(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        chain: &RealizedPostChain,
+        target: &wgpu::TextureView,
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+            label: Some("post_overlay_pass"),
+        });
+
+        render_pass.set_pipeline(&self.blit_pipeline);
+        render_pass.set_bind_group(0, &chain.overlay_bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+fn create_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    module: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    blend: Option<wgpu::BlendState>,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Post Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module,
+            entry_point: "main",
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module,
+            entry_point: "main",
+            targets: &[wgpu::ColorTargetState {
+                format,
+                write_mask: wgpu::ColorWrites::ALL,
+                blend,
+            }],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            clamp_depth: false,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+    })
+}
@@ -0,0 +1,164 @@
+use crate::platform::action::ActionState;
+use crate::platform::message::{ActionEvent, FrameRequestedEvent};
+use crate::render::client::{CanvasLayer, Text};
+use crate::render::text::{HorizontalAlignment, VerticalAlignment};
+use internment::Intern;
+use nalgebra::Point2;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+/// developer hud of the display config showing the frame rate, frame times and instance / asset
+/// counts, e.g. `"statsOverlay": {"visible": true, "toggleAction": "toggleStats"}`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct StatsOverlayConfig {
+    pub visible: bool,
+    /// action of the actions config toggling the overlay, e.g. bound to a function key
+    pub toggle_action: Option<Intern<String>>,
+    /// number of frames the frame times are averaged over
+    pub sample_max: usize,
+    /// interval the text is updated in, to avoid generating a new text mesh every frame
+    pub refresh_millis: u64,
+    pub point: f32,
+}
+
+impl StatsOverlayConfig {
+    /// the overlay is only created if it is visible or can be toggled
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.visible || self.toggle_action.is_some()
+    }
+}
+
+impl Default for StatsOverlayConfig {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            toggle_action: None,
+            sample_max: 120,
+            refresh_millis: 500,
+            point: 16.0,
+        }
+    }
+}
+
+/// drawn by the render server on top of the last frame canvas, in pixels from the top left corner
+pub(crate) struct StatsOverlay {
+    config: StatsOverlayConfig,
+    frame_times: VecDeque<Duration>,
+    refreshed_at: Option<Instant>,
+    requested_at: Option<Instant>,
+    instances: usize,
+    assets: usize,
+    visible: bool,
+    text: Text,
+}
+
+impl StatsOverlay {
+    const PADDING: f32 = 8.0;
+
+    pub fn new(config: StatsOverlayConfig, layer: &CanvasLayer) -> Self {
+        let text = layer.spawn(
+            Text::builder()
+                .with_point(config.point)
+                .with_position(Point2::new(Self::PADDING, -Self::PADDING))
+                .with_horizontal_alignment(HorizontalAlignment::Left)
+                .with_vertical_alignment(VerticalAlignment::Top)
+                .with_background([0.0, 0.0, 0.0, 0.5], Self::PADDING / 2.0)
+                .with_hidden(!config.visible),
+        );
+
+        Self {
+            visible: config.visible,
+            config,
+            frame_times: Default::default(),
+            refreshed_at: None,
+            requested_at: None,
+            instances: 0,
+            assets: 0,
+            text,
+        }
+    }
+
+    pub fn fps(&self) -> f32 {
+        let frame_time = self.frame_time();
+        if frame_time > Duration::ZERO {
+            1.0 / frame_time.as_secs_f32()
+        } else {
+            0.0
+        }
+    }
+
+    /// average frame time over the sampled frames
+    pub fn frame_time(&self) -> Duration {
+        let total: Duration = self.frame_times.iter().sum();
+        total
+            .checked_div(self.frame_times.len() as u32)
+            .unwrap_or_default()
+    }
+
+    pub fn max_frame_time(&self) -> Duration {
+        self.frame_times.iter().max().copied().unwrap_or_default()
+    }
+
+    pub fn on_frame_requested(&mut self, event: &FrameRequestedEvent) {
+        // the delta of the event is zero while the sim is paused
+        if let Some(requested_at) = self.requested_at.replace(event.at) {
+            self.frame_times
+                .push_back(event.at.duration_since(requested_at));
+        }
+        while self.frame_times.len() > self.config.sample_max.max(1) {
+            self.frame_times.pop_front();
+        }
+
+        let refresh = self
+            .refreshed_at
+            .map(|at| {
+                event.at.duration_since(at) >= Duration::from_millis(self.config.refresh_millis)
+            })
+            .unwrap_or(true);
+        if refresh && self.visible {
+            self.refreshed_at = Some(event.at);
+            self.update_text();
+        }
+    }
+
+    #[inline]
+    pub fn on_drawn(&mut self, instances: usize, assets: usize) {
+        self.instances = instances;
+        self.assets = assets;
+    }
+
+    pub fn on_action(&mut self, event: &ActionEvent) {
+        if event.state == ActionState::Start && Some(event.name) == self.config.toggle_action {
+            self.visible = !self.visible;
+            self.update_text();
+        }
+    }
+
+    fn update_text(&mut self) {
+        let mut modify = self.text.modify();
+        if !self.visible {
+            modify.hide();
+            return;
+        }
+
+        let mut content = String::new();
+        let _ = write!(
+            content,
+            "fps: {:.1}\nframe: {:.2}ms (max {:.2}ms)\ninstances: {}\nassets: {}",
+            self.fps(),
+            self.frame_time().as_secs_f64() * 1000.0,
+            self.max_frame_time().as_secs_f64() * 1000.0,
+            self.instances,
+            self.assets,
+        );
+
+        modify.show();
+        if *modify.content != *content {
+            modify.content = content.into();
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use crate::render::buffer::Vertex;
+use crate::render::curve::{
+    tessellate_stroke, LineCap, LineJoin, Path, Segment, StrokeOptions, WorldOrScreen,
+};
+use crate::render::mesh::Mesh;
+use lyon::tessellation::{
+    BuffersBuilder, FillOptions, FillRule, FillTessellator, FillVertex, VertexBuffers,
+};
+use nalgebra::Point2;
+use usvg::NodeExt;
+
+/// flattening tolerance within the unit square
+const TOLERANCE: f32 = 0.001;
+
+/// tessellates the filled and stroked paths of a svg into a colored mesh,
+/// which is fitted into a unit square centered at the origin like the unit square mesh,
+/// the mesh loader hands svg files to it
+pub fn svg_mesh(bytes: &[u8]) -> anyhow::Result<Mesh> {
+    let tree = usvg::Tree::from_data(bytes, &usvg::Options::default().to_ref())?;
+    let view_box = tree.svg_node().view_box.rect;
+    let extent = view_box.width().max(view_box.height());
+    if extent <= 0.0 {
+        return Err(anyhow::anyhow!("svg has an empty view box"));
+    }
+
+    let center_x = view_box.x() + view_box.width() / 2.0;
+    let center_y = view_box.y() + view_box.height() / 2.0;
+    let mut geometry = VertexBuffers::new();
+
+    for node in tree.root().descendants() {
+        let kind = node.borrow();
+        let svg_path = match &*kind {
+            usvg::NodeKind::Path(svg_path) => svg_path,
+            _ => continue,
+        };
+
+        // the y axis of svgs points down
+        let transform = node.abs_transform();
+        let path = unit_path(&svg_path.data, |x, y| {
+            let (x, y) = transform.apply(x, y);
+            Point2::new(
+                ((x - center_x) / extent) as f32,
+                ((center_y - y) / extent) as f32,
+            )
+        });
+
+        if let Some(fill) = &svg_path.fill {
+            match paint_color(&fill.paint, fill.opacity.value()) {
+                Some(color) => tessellate_fill(&path, fill.rule, color, &mut geometry)?,
+                None => log::warn!("ignore unsupported svg fill paint"),
+            }
+        }
+
+        if let Some(stroke) = &svg_path.stroke {
+            let color = match paint_color(&stroke.paint, stroke.opacity.value()) {
+                Some(color) => color,
+                None => {
+                    log::warn!("ignore unsupported svg stroke paint");
+                    continue;
+                }
+            };
+
+            let scale_x = (transform.a * transform.a + transform.b * transform.b).sqrt();
+            let scale_y = (transform.c * transform.c + transform.d * transform.d).sqrt();
+            let scale = (scale_x + scale_y) / 2.0 / extent;
+            let line_cap = match stroke.linecap {
+                usvg::LineCap::Butt => LineCap::Butt,
+                usvg::LineCap::Round => LineCap::Round,
+                usvg::LineCap::Square => LineCap::Square,
+            };
+
+            let options = StrokeOptions {
+                start_cap: line_cap,
+                end_cap: line_cap,
+                line_join: match stroke.linejoin {
+                    usvg::LineJoin::Miter => LineJoin::Miter,
+                    usvg::LineJoin::Round => LineJoin::Round,
+                    usvg::LineJoin::Bevel => LineJoin::Bevel,
+                },
+                line_width: (stroke.width.value() * scale) as f32,
+                line_width_space: WorldOrScreen::World,
+                miter_limit: stroke.miterlimit.value() as f32,
+                tolerance: TOLERANCE,
+                dash: stroke
+                    .dasharray
+                    .as_ref()
+                    .map(|dash| dash.iter().map(|l| (l * scale) as f32).collect()),
+                dash_offset: stroke.dashoffset * scale as f32,
+            };
+            tessellate_stroke(&path, &options, options.line_width, color, &mut geometry)?;
+        }
+    }
+
+    // the y flip reverses the winding, culling requires counter clockwise triangles
+    let vertices = &geometry.vertices;
+    for triangle in geometry.indices.chunks_exact_mut(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| vertices[triangle[i] as usize].position);
+        let area = (b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1]);
+        if area < 0.0 {
+            triangle.swap(1, 2);
+        }
+    }
+
+    Ok(Mesh {
+        vertices: geometry.vertices,
+        indices: geometry.indices,
+    })
+}
+
+fn unit_path<F>(data: &usvg::PathData, to_unit: F) -> Path
+where
+    F: Fn(f64, f64) -> Point2<f32>,
+{
+    let mut segments = Vec::default();
+    let mut open = false;
+
+    for segment in data.iter() {
+        match *segment {
+            usvg::PathSegment::MoveTo { x, y } => {
+                if open {
+                    segments.push(Segment::End);
+                }
+                segments.push(Segment::Begin(to_unit(x, y)));
+                open = true;
+            }
+            usvg::PathSegment::LineTo { x, y } => {
+                segments.push(Segment::Line(to_unit(x, y)));
+            }
+            usvg::PathSegment::CurveTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+            } => {
+                segments.push(Segment::Cubic {
+                    ctrl1: to_unit(x1, y1),
+                    ctrl2: to_unit(x2, y2),
+                    to: to_unit(x, y),
+                });
+            }
+            usvg::PathSegment::ClosePath => {
+                if open {
+                    segments.push(Segment::Close);
+                }
+                open = false;
+            }
+        }
+    }
+
+    if open {
+        segments.push(Segment::End);
+    }
+
+    Path::from(segments)
+}
+
+fn paint_color(paint: &usvg::Paint, opacity: f64) -> Option<[f32; 4]> {
+    match paint {
+        usvg::Paint::Color(color) => Some([
+            color.red as f32 / 255.0,
+            color.green as f32 / 255.0,
+            color.blue as f32 / 255.0,
+            opacity as f32,
+        ]),
+        _ => None,
+    }
+}
+
+fn tessellate_fill(
+    path: &Path,
+    rule: usvg::FillRule,
+    color: [f32; 4],
+    geometry: &mut VertexBuffers<Vertex, u32>,
+) -> anyhow::Result<()> {
+    let fill_rule = match rule {
+        usvg::FillRule::NonZero => FillRule::NonZero,
+        usvg::FillRule::EvenOdd => FillRule::EvenOdd,
+    };
+
+    FillTessellator::new()
+        .tessellate_path(
+            path.to_lyon().as_slice(),
+            &FillOptions::tolerance(TOLERANCE).with_fill_rule(fill_rule),
+            &mut BuffersBuilder::new(geometry, |v: FillVertex| {
+                let pos = v.position();
+                Vertex {
+                    position: [pos.x, pos.y, 0.0],
+                    tex_coords: [0.0, 0.0],
+                    color,
+                }
+            }),
+        )
+        .map_err(|e| anyhow::anyhow!("svg fill tessellation failed: {:?}", e))?;
+
+    Ok(())
+}
@@ -2,20 +2,24 @@ use crate::asset::storage::AssetsClient;
 use crate::asset::{AssetId, StrongAssetId, Weak, WeakAssetId};
 use crate::prelude::Texture;
 use crate::render::buffer::Vertex;
-use crate::render::canvas::RawInstance;
+use crate::render::canvas::{DistanceEffects, RawInstance};
 use crate::render::mesh::Mesh;
-use crate::render::pipeline::Pipeline;
-use crate::render::view::FilterMode;
+use crate::render::message::TextLaidOutEvent;
+use crate::render::pipeline::{Pipeline, Pipelines};
+use crate::render::view::{FilterMode, Textures};
+use crate::some_or_return;
 use crate::util::{HashMap, OrderWindow};
 use ahash::AHasher;
 use copyless::VecHelper;
 use nalgebra::{
-    Isometry3, Point2, Rotation2, Similarity2, Similarity3, Translation3, UnitQuaternion, Vector3,
+    Isometry3, Point2, Rotation2, Similarity2, Similarity3, Translation3, UnitQuaternion, Vector2,
+    Vector3,
 };
 use serde::Deserialize;
 use std::collections::BTreeSet;
 use std::convert::TryFrom;
 use std::hash::{Hash, Hasher};
+use std::ops::Range;
 use std::sync::Arc;
 use unicode_linebreak::BreakOpportunity;
 use uuid::Uuid;
@@ -57,6 +61,8 @@ impl Font {
 struct Atom {
     glyph: Glyph,
     whitespace: bool,
+    tab: bool,
+    color: [f32; 4],
 
     line: usize,
     line_until_advance: f32,
@@ -114,11 +120,26 @@ impl<T: Clone + Iterator<Item = (usize, BreakOpportunity)>> LinebreakIter<T> {
 pub struct FontLayout {
     pub glyphs: HashMap<char, Glyph>,
     pub line_height: f32,
+    pub ascender: f32,
+    pub descender: f32,
     pub size: f32,
     pub distance_range: f32,
+    pub texel_epsilon: f32,
+}
+
+#[derive(Debug)]
+pub struct TextMesh {
+    pub glyphs: Mesh,
+    pub background: Option<Mesh>,
+    /// widest line and the height of all lines
+    pub size: Vector2<f32>,
+    pub line_count: usize,
 }
 
 impl FontLayout {
+    /// half a texel inset of the glyph uvs against bleeding of neighbouring glyphs
+    pub const DEFAULT_TEXEL_EPSILON: f32 = 0.5;
+
     pub(crate) fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
         let layout_data = serde_json::from_slice(bytes)?;
         Self::from_data(layout_data)
@@ -130,8 +151,9 @@ impl FontLayout {
         let mut glyphs: HashMap<char, Glyph> = Default::default();
 
         let height = data.atlas.height;
-        let epsilon_x_half = 1.0 / (data.atlas.width * 2.0);
-        let epsilon_y_half = 1.0 / (data.atlas.height * 2.0);
+        let texel_epsilon = data.texel_epsilon.unwrap_or(Self::DEFAULT_TEXEL_EPSILON);
+        let epsilon_x = texel_epsilon / data.atlas.width;
+        let epsilon_y = texel_epsilon / data.atlas.height;
 
         for l_glyph in &data.glyphs {
             let unicode = std::char::from_u32(l_glyph.unicode).ok_or_else(|| {
@@ -146,32 +168,32 @@ impl FontLayout {
                     GlyphVertex {
                         position: Point2::new(plane_bounds.left, plane_bounds.top),
                         tex_coords: [
-                            atlas_bounds.left / data.atlas.width + epsilon_x_half,
-                            (height - atlas_bounds.top) / data.atlas.height + epsilon_y_half,
+                            atlas_bounds.left / data.atlas.width + epsilon_x,
+                            (height - atlas_bounds.top) / data.atlas.height + epsilon_y,
                         ],
                     },
                     // top-right
                     GlyphVertex {
                         position: Point2::new(plane_bounds.right, plane_bounds.top),
                         tex_coords: [
-                            atlas_bounds.right / data.atlas.width - epsilon_x_half,
-                            (height - atlas_bounds.top) / data.atlas.height + epsilon_y_half,
+                            atlas_bounds.right / data.atlas.width - epsilon_x,
+                            (height - atlas_bounds.top) / data.atlas.height + epsilon_y,
                         ],
                     },
                     // bottom-right
                     GlyphVertex {
                         position: Point2::new(plane_bounds.right, plane_bounds.bottom),
                         tex_coords: [
-                            atlas_bounds.right / data.atlas.width - epsilon_x_half,
-                            (height - atlas_bounds.bottom) / data.atlas.height - epsilon_y_half,
+                            atlas_bounds.right / data.atlas.width - epsilon_x,
+                            (height - atlas_bounds.bottom) / data.atlas.height - epsilon_y,
                         ],
                     },
                     // bottom-left
                     GlyphVertex {
                         position: Point2::new(plane_bounds.left, plane_bounds.bottom),
                         tex_coords: [
-                            atlas_bounds.left / data.atlas.width + epsilon_x_half,
-                            (height - atlas_bounds.bottom) / data.atlas.height - epsilon_y_half,
+                            atlas_bounds.left / data.atlas.width + epsilon_x,
+                            (height - atlas_bounds.bottom) / data.atlas.height - epsilon_y,
                         ],
                     },
                 ])
@@ -191,13 +213,72 @@ impl FontLayout {
         Ok(Self {
             glyphs,
             line_height,
+            ascender: data.metrics.ascender,
+            descender: data.metrics.descender,
             size: data.atlas.size,
             distance_range: data.atlas.distance_range,
+            texel_epsilon,
         })
     }
 
+    /// truncates the last line until the ellipsis fits into the width of the text
+    fn append_ellipsis<S>(&self, atoms: &mut Vec<Atom>, text: &RawText<S>) {
+        let (glyph, count) = match self.glyphs.get(&'…') {
+            Some(glyph) => (*glyph, 1),
+            None => (*some_or_return!(self.glyphs.get(&'.')), 3),
+        };
+        let scaled_advance = glyph.advance * text.point;
+        let ellipsis_advance = scaled_advance * count as f32;
+        let line = atoms.last().map(|a| a.line).unwrap_or_default();
+        let color = atoms
+            .last()
+            .map(|a| a.color)
+            .unwrap_or_else(Vertex::default_color);
+        let width = text.width.unwrap_or(f32::INFINITY);
+
+        while let Some(last) = atoms.last() {
+            let fits = last.line_until_advance + last.scaled_advance + ellipsis_advance <= width;
+            if last.line != line || (fits && !last.whitespace) {
+                break;
+            }
+            atoms.pop();
+        }
+
+        let (mut line_until_advance, mut advance_count, allowed_break_count) = match atoms.last() {
+            Some(last) if last.line == line => (
+                last.line_until_advance + last.scaled_advance,
+                last.line_until_advance_count,
+                last.line_until_allowed_break_count,
+            ),
+            _ => (0.0, 0, 0),
+        };
+
+        for _ in 0..count {
+            advance_count += 1;
+            atoms.push(Atom {
+                glyph,
+                whitespace: false,
+                tab: false,
+                color,
+
+                line,
+                line_until_advance,
+                scaled_advance,
+                is_advance: true,
+
+                do_break: false,
+                mandatory_break: false,
+                allowed_break: false,
+
+                line_until_advance_count: advance_count,
+                line_until_allowed_break_count: allowed_break_count,
+            });
+            line_until_advance += scaled_advance;
+        }
+    }
+
     #[inline]
-    pub fn generate_mesh<S>(&self, text: &RawText<S>) -> anyhow::Result<Mesh> {
+    pub fn generate_mesh<S>(&self, text: &RawText<S>) -> anyhow::Result<TextMesh> {
         let mut linebreaker = LinebreakIter::new(unicode_linebreak::linebreaks(&text.content));
         let mut atoms: Vec<Atom> = Vec::with_capacity(text.content.len());
 
@@ -222,9 +303,20 @@ impl FontLayout {
                 linebreaker.advance();
             }
 
+            // spans are byte ranges like glyph_i, later spans take precedence over earlier ones
+            let color = text
+                .spans
+                .iter()
+                .rev()
+                .find(|(range, _)| range.contains(&glyph_i))
+                .map(|(_, [r, g, b])| [*r, *g, *b, 1.0])
+                .unwrap_or_else(Vertex::default_color);
+
             atoms.push(Atom {
                 glyph: *glyph,
                 whitespace: c.is_whitespace(),
+                tab: c == '\t',
+                color,
 
                 line: 0,
                 line_until_advance: 0.0,
@@ -240,6 +332,7 @@ impl FontLayout {
             });
         }
 
+        let tab_stop = text.tab_size * text.point;
         let mut linebreaks: Vec<usize> = Vec::default();
         let mut atom_index = 0;
         let mut last_possible_break = 0;
@@ -296,13 +389,21 @@ impl FontLayout {
                     current.line_until_advance_count = 0;
                     current.line_until_allowed_break_count = 0;
                 } else {
-                    current.is_advance = current.glyph.advance > 0.01;
+                    // tabs end at their tab stop, justified alignment doesn't stretch them
+                    current.is_advance = current.glyph.advance > 0.01 && !current.tab;
                     current.line = line;
                     current.line_until_advance = line_until_advance;
                     current.line_until_advance_count =
                         line_until_advance_count + current.is_advance as usize;
-                    current.line_until_allowed_break_count =
-                        line_until_allowed_break_count + current.allowed_break as usize;
+                    current.line_until_allowed_break_count = line_until_allowed_break_count
+                        + (current.allowed_break && !current.tab) as usize;
+                }
+
+                if current.tab && tab_stop > 0.0 {
+                    // advance to the next tab stop
+                    let next_stop =
+                        ((current.line_until_advance / tab_stop).floor() + 1.0) * tab_stop;
+                    current.scaled_advance = next_stop - current.line_until_advance;
                 }
 
                 atom_index += 1;
@@ -311,27 +412,41 @@ impl FontLayout {
 
         let mut vertices = Vec::default();
         let mut indices = Vec::default();
+        let mut background = text.background.map(|_| Mesh {
+            vertices: Vec::default(),
+            indices: Vec::default(),
+        });
+        let line_height = text.line_height.scaled(self.line_height, text.point);
+        if let (TextOverflow::Clip | TextOverflow::Ellipsis, Some(height)) =
+            (text.overflow, text.height)
+        {
+            let max_lines = ((height / line_height).floor() as usize).max(1);
+            if let Some(&hidden_atom) = linebreaks.get(max_lines - 1) {
+                atoms.truncate(hidden_atom);
+                linebreaks.truncate(max_lines - 1);
+                if text.overflow == TextOverflow::Ellipsis {
+                    self.append_ellipsis(&mut atoms, text);
+                }
+            }
+        }
         let translation_y = match text.vertical_alignment {
             VerticalAlignment::Top => {
                 let rect_height = text.height.unwrap_or_default();
-                -self.line_height * text.line_height * text.point + rect_height / 2.0
+                -line_height + rect_height / 2.0
             }
             VerticalAlignment::Center => {
-                let text_height = (linebreaks.len() as f32 - 0.5)
-                    * self.line_height
-                    * text.line_height
-                    * text.point;
+                let text_height = (linebreaks.len() as f32 - 0.5) * line_height;
                 text_height / 2.0
             }
             VerticalAlignment::Bottom => {
                 let rect_height = text.height.unwrap_or_default();
-                let text_height =
-                    linebreaks.len() as f32 * self.line_height * text.line_height * text.point;
+                let text_height = linebreaks.len() as f32 * line_height;
 
                 -rect_height / 2.0 + text_height
             }
         };
         let mut vertex_index = 0;
+        let mut max_line_width: f32 = 0.0;
 
         let mut current_line = usize::MAX;
         let mut translation_x = 0.0;
@@ -348,27 +463,24 @@ impl FontLayout {
                     atoms.last().unwrap()
                 };
 
+                let line_advance = if prev_break_atom.whitespace {
+                    prev_break_atom.line_until_advance
+                } else {
+                    prev_break_atom.line_until_advance + prev_break_atom.scaled_advance
+                };
+                let mut line_width = line_advance;
+
                 match text.horizontal_alignment {
                     HorizontalAlignment::Left => {
                         translation_x = -text.width.unwrap_or_default() / 2.0;
                     }
                     HorizontalAlignment::Right => {
                         let rect_width = text.width.unwrap_or_default();
-                        let line_advance = if prev_break_atom.whitespace {
-                            prev_break_atom.line_until_advance
-                        } else {
-                            prev_break_atom.line_until_advance + prev_break_atom.scaled_advance
-                        };
                         translation_x =
                             text.width.unwrap_or_default() - line_advance - rect_width / 2.0;
                     }
                     HorizontalAlignment::Center => {
                         let rect_width = text.width.unwrap_or_default();
-                        let line_advance = if prev_break_atom.whitespace {
-                            prev_break_atom.line_until_advance
-                        } else {
-                            prev_break_atom.line_until_advance + prev_break_atom.scaled_advance
-                        };
                         translation_x =
                             (text.width.unwrap_or_default() - line_advance - rect_width) / 2.0;
                     }
@@ -379,9 +491,7 @@ impl FontLayout {
                         let break_aspect = prev_break_atom.line_until_allowed_break_count as f32
                             / prev_break_atom.line_until_advance_count as f32;
 
-                        let mut line_advance = prev_break_atom.line_until_advance;
                         if !prev_break_atom.whitespace {
-                            line_advance += prev_break_atom.scaled_advance;
                             non_break_count += 1;
                         }
                         let negative_advance = text.width.unwrap_or_default() - line_advance;
@@ -398,11 +508,44 @@ impl FontLayout {
                                 * prev_break_atom.line_until_allowed_break_count as f32;
                         non_break_advance_offset =
                             (remaining_negative_advance / non_break_count as f32).max(0.0);
+
+                        line_width += break_advance_offset
+                            * prev_break_atom.line_until_allowed_break_count as f32
+                            + non_break_advance_offset * non_break_count as f32;
                     }
                 };
+                max_line_width = max_line_width.max(line_width);
+
+                if let (Some(mesh), Some(text_background)) = (&mut background, &text.background) {
+                    if line_width > 0.0 {
+                        let baseline = translation_y - current_line as f32 * line_height;
+                        let padding = text_background.padding;
+                        let left = translation_x - padding;
+                        let right = translation_x + line_width + padding;
+                        let top = baseline + self.ascender * text.point + padding;
+                        let bottom = baseline + self.descender * text.point - padding;
+
+                        let index = mesh.vertices.len() as u32;
+                        for (x, y) in [(left, top), (right, top), (right, bottom), (left, bottom)] {
+                            mesh.vertices.push(Vertex {
+                                position: [x, y, 0.0],
+                                tex_coords: [0.0, 0.0],
+                                color: Vertex::default_color(),
+                            });
+                        }
+                        mesh.indices.extend_from_slice(&[
+                            index,
+                            index + 3,
+                            index + 1,
+                            index + 1,
+                            index + 3,
+                            index + 2,
+                        ]);
+                    }
+                }
             }
 
-            translation_x += if atom.allowed_break && !atom.do_break {
+            translation_x += if atom.allowed_break && !atom.do_break && !atom.tab {
                 break_advance_offset
             } else if atom.is_advance {
                 non_break_advance_offset
@@ -413,12 +556,12 @@ impl FontLayout {
             if let Some(g_vertices) = &atom.glyph.vertices {
                 for gv in g_vertices {
                     let x = translation_x + gv.position.x * text.point;
-                    let y = translation_y
-                        - atom.line as f32 * self.line_height * text.line_height * text.point
-                        + gv.position.y * text.point;
+                    let y =
+                        translation_y - atom.line as f32 * line_height + gv.position.y * text.point;
                     vertices.push(Vertex {
                         position: [x, y, 0.0],
                         tex_coords: gv.tex_coords,
+                        color: atom.color,
                     });
                 }
 
@@ -436,7 +579,14 @@ impl FontLayout {
             translation_x += atom.scaled_advance;
         }
 
-        Ok(Mesh { vertices, indices })
+        let line_count = atoms.last().map(|a| a.line + 1).unwrap_or_default();
+
+        Ok(TextMesh {
+            glyphs: Mesh { vertices, indices },
+            background,
+            size: Vector2::new(max_line_width, line_count as f32 * line_height),
+            line_count,
+        })
     }
 }
 
@@ -478,6 +628,9 @@ struct FontLayoutData {
     atlas: Atlas,
     metrics: Metrics,
     glyphs: Vec<LayoutGlyph>,
+    /// uv inset in texels, e.g. 0.0 for atlases with enough padding between glyphs
+    #[serde(default)]
+    texel_epsilon: Option<f32>,
     // TODO: kerning
 }
 
@@ -549,24 +702,141 @@ impl Default for VerticalAlignment {
     }
 }
 
+/// how lines exceeding the height of the text are handled
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TextOverflow {
+    /// drop the lines that don't fit
+    Clip,
+    /// drop the lines that don't fit and end the last visible line with an ellipsis
+    Ellipsis,
+    /// keep wrapping lines past the height
+    Wrap,
+}
+
+impl Default for TextOverflow {
+    #[inline]
+    fn default() -> Self {
+        Self::Wrap
+    }
+}
+
+/// relative line heights are multiples of the font line height, absolute ones are in text units,
+/// a bare number like `1.2` is a relative line height
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize)]
+#[serde(from = "LineHeightDef")]
+pub enum LineHeight {
+    Relative(f32),
+    Absolute(f32),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LineHeightDef {
+    Factor(f32),
+    Tagged(TaggedLineHeight),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum TaggedLineHeight {
+    Relative(f32),
+    Absolute(f32),
+}
+
+impl From<LineHeightDef> for LineHeight {
+    #[inline]
+    fn from(def: LineHeightDef) -> Self {
+        match def {
+            LineHeightDef::Factor(factor) => LineHeight::Relative(factor),
+            LineHeightDef::Tagged(TaggedLineHeight::Relative(factor)) => {
+                LineHeight::Relative(factor)
+            }
+            LineHeightDef::Tagged(TaggedLineHeight::Absolute(line_height)) => {
+                LineHeight::Absolute(line_height)
+            }
+        }
+    }
+}
+
+impl LineHeight {
+    #[inline]
+    pub fn scaled(self, font_line_height: f32, point: f32) -> f32 {
+        match self {
+            LineHeight::Relative(factor) => font_line_height * factor * point,
+            LineHeight::Absolute(line_height) => line_height,
+        }
+    }
+}
+
+impl Default for LineHeight {
+    #[inline]
+    fn default() -> Self {
+        Self::Relative(1.0)
+    }
+}
+
+impl From<f32> for LineHeight {
+    #[inline]
+    fn from(factor: f32) -> Self {
+        Self::Relative(factor)
+    }
+}
+
+/// box behind each line sized to the line advance and the font ascender / descender
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextBackground {
+    pub color: [f32; 4],
+    #[serde(default)]
+    pub padding: f32,
+}
+
+/// outline around the glyphs drawn with the distance field, the width is in text units
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextOutline {
+    pub width: f32,
+    pub color: [f32; 3],
+}
+
+/// shadow of the glyphs and their outline drawn with the distance field,
+/// offsets are limited by the padding of the glyphs in the atlas
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextShadow {
+    pub offset: Vector2<f32>,
+    pub color: [f32; 3],
+}
+
 #[derive(Debug, Clone)]
 pub struct RawText<S> {
     pub pipeline: AssetId<Pipeline, S>,
     pub font: AssetId<Font, S>,
     pub content: Arc<str>,
+    /// colored byte ranges of the content, not char indices
+    pub spans: Vec<(Range<usize>, [f32; 3])>,
     pub position: Point2<f32>,
     pub z_index: f32,
     pub rotation: Rotation2<f32>,
     pub point: f32,
     pub width: Option<f32>,
     pub height: Option<f32>,
-    pub line_height: f32,
+    pub line_height: LineHeight,
     pub vertical_alignment: VerticalAlignment,
     pub horizontal_alignment: HorizontalAlignment,
+    pub tab_size: f32,
+    pub overflow: TextOverflow,
     pub scale: f32,
     pub tint: [f32; 4],
+    pub background: Option<TextBackground>,
+    pub outline: Option<TextOutline>,
+    pub shadow: Option<TextShadow>,
     pub world: Similarity2<f32>,
     pub world_z_index: f32,
+    /// the world is relative to the one of the group
+    pub group: Option<Uuid>,
 }
 
 impl<S: Clone> RawText<S> {
@@ -575,6 +845,7 @@ impl<S: Clone> RawText<S> {
             pipeline: self.pipeline.to_weak(),
             font: self.font.to_weak(),
             content: self.content.clone(),
+            spans: self.spans.clone(),
             position: self.position,
             z_index: self.z_index,
             rotation: self.rotation,
@@ -584,13 +855,45 @@ impl<S: Clone> RawText<S> {
             line_height: self.line_height,
             vertical_alignment: self.vertical_alignment,
             horizontal_alignment: self.horizontal_alignment,
+            tab_size: self.tab_size,
+            overflow: self.overflow,
             scale: self.scale,
             tint: self.tint,
+            background: self.background,
+            outline: self.outline,
+            shadow: self.shadow,
             world: self.world,
             world_z_index: self.world_z_index,
+            group: self.group,
         }
     }
 
+    fn distance_effects(
+        &self,
+        font_layout_size: f32,
+        font_layout_distance_range: f32,
+    ) -> DistanceEffects {
+        // text units to atlas texels
+        let texel_scale = font_layout_size / self.point;
+        let mut effects = DistanceEffects::default();
+
+        if let Some(outline) = self.outline {
+            let width = outline.width * texel_scale / font_layout_distance_range;
+            effects.outline = [outline.color[0], outline.color[1], outline.color[2], width];
+        }
+
+        if let Some(shadow) = self.shadow {
+            effects.shadow = [shadow.color[0], shadow.color[1], shadow.color[2], 1.0];
+            // the atlas is flipped on the y axis
+            effects.shadow_offset = [
+                shadow.offset.x * texel_scale,
+                -shadow.offset.y * texel_scale,
+            ];
+        }
+
+        effects
+    }
+
     fn to_raw_instance(
         &self,
         font_layout_size: f32,
@@ -603,6 +906,7 @@ impl<S: Clone> RawText<S> {
             mesh: text_mesh,
             texture: font_texture,
             texture_layer: 0,
+            flip: Default::default(),
             model: Isometry3::from_parts(
                 Translation3::new(self.position.x, self.position.y, self.z_index),
                 UnitQuaternion::from_axis_angle(&Vector3::z_axis(), self.rotation.angle()),
@@ -614,6 +918,8 @@ impl<S: Clone> RawText<S> {
                 font_layout_distance_range,
             ),
             tint: self.tint,
+            custom: [0.0; 4],
+            effects: self.distance_effects(font_layout_size, font_layout_distance_range),
             world: Similarity3::from_parts(
                 Translation3::new(
                     self.world.isometry.translation.x,
@@ -626,14 +932,59 @@ impl<S: Clone> RawText<S> {
                 ),
                 self.world.scaling(),
             ),
+            group: self.group,
         }
     }
 
+    fn to_raw_background_instance(
+        &self,
+        background_pipeline: AssetId<Pipeline, S>,
+        background_texture: AssetId<Texture, S>,
+        background_mesh: AssetId<Mesh, S>,
+    ) -> Option<RawInstance<S>> {
+        let background = self.background?;
+
+        Some(RawInstance {
+            pipeline: background_pipeline,
+            mesh: background_mesh,
+            texture: background_texture,
+            texture_layer: 0,
+            flip: Default::default(),
+            model: Isometry3::from_parts(
+                Translation3::new(self.position.x, self.position.y, self.z_index),
+                UnitQuaternion::from_axis_angle(&Vector3::z_axis(), self.rotation.angle()),
+            ),
+            scale: Vector3::new(self.scale, self.scale, self.scale),
+            tint: background.color,
+            custom: [0.0; 4],
+            effects: Default::default(),
+            world: Similarity3::from_parts(
+                Translation3::new(
+                    self.world.isometry.translation.x,
+                    self.world.isometry.translation.y,
+                    self.world_z_index,
+                ),
+                UnitQuaternion::from_axis_angle(
+                    &Vector3::z_axis(),
+                    self.world.isometry.rotation.angle(),
+                ),
+                self.world.scaling(),
+            ),
+            group: self.group,
+        })
+    }
+
     pub(crate) fn major_hash(&self) -> u64 {
         let mut hasher = AHasher::default();
 
         self.font.hash(&mut hasher);
         self.content.hash(&mut hasher);
+        for (range, color) in &self.spans {
+            range.hash(&mut hasher);
+            for channel in color {
+                channel.to_ne_bytes().hash(&mut hasher);
+            }
+        }
         self.width
             .unwrap_or_else(|| f32::INFINITY)
             .to_ne_bytes()
@@ -642,9 +993,25 @@ impl<S: Clone> RawText<S> {
             .unwrap_or_else(|| f32::INFINITY)
             .to_ne_bytes()
             .hash(&mut hasher);
-        self.line_height.to_ne_bytes().hash(&mut hasher);
+        match self.line_height {
+            LineHeight::Relative(factor) => {
+                0u8.hash(&mut hasher);
+                factor.to_ne_bytes().hash(&mut hasher);
+            }
+            LineHeight::Absolute(line_height) => {
+                1u8.hash(&mut hasher);
+                line_height.to_ne_bytes().hash(&mut hasher);
+            }
+        }
         self.vertical_alignment.hash(&mut hasher);
         self.horizontal_alignment.hash(&mut hasher);
+        self.overflow.hash(&mut hasher);
+        self.background
+            .map(|b| b.padding)
+            .unwrap_or(f32::NAN)
+            .to_ne_bytes()
+            .hash(&mut hasher);
+        self.tab_size.to_ne_bytes().hash(&mut hasher);
 
         hasher.finish()
     }
@@ -654,6 +1021,7 @@ impl<S: Clone> RawText<S> {
 pub struct RealizedText {
     pub(crate) raw: RawText<Weak>,
     pub(crate) mesh: StrongAssetId<Mesh>,
+    pub(crate) background_mesh: Option<StrongAssetId<Mesh>>,
     pub(crate) font_texture: WeakAssetId<Texture>,
     pub(crate) layout: WeakAssetId<FontLayout>,
     pub(crate) font_layout_size: f32,
@@ -668,9 +1036,22 @@ pub struct QueuedText {
     pub(crate) canvas_layer_id: Uuid,
 }
 
+/// the instances of a text, the background is drawn as its own instance
+#[derive(Debug)]
+pub struct TextInstances {
+    pub text: RawInstance<Weak>,
+    pub background: Option<(Uuid, RawInstance<Weak>)>,
+    pub dropped_background: Option<Uuid>,
+    /// only available if the mesh was generated
+    pub laid_out: Option<TextLaidOutEvent>,
+}
+
 pub struct Texts {
     loaded: HashMap<Uuid, RealizedText>,
     queued: HashMap<Uuid, QueuedText>,
+    backgrounds: HashMap<Uuid, Uuid>,
+    background_pipeline: WeakAssetId<Pipeline>,
+    background_texture: WeakAssetId<Texture>,
     font_index: BTreeSet<(WeakAssetId<Font>, OrderWindow<Uuid>)>,
     font_layout_index: BTreeSet<(WeakAssetId<FontLayout>, OrderWindow<Uuid>)>,
     // defaults
@@ -678,7 +1059,11 @@ pub struct Texts {
 }
 
 impl Texts {
-    pub fn new(assets: &AssetsClient) -> anyhow::Result<Self> {
+    pub fn new(
+        assets: &AssetsClient,
+        pipelines: &Pipelines,
+        textures: &Textures,
+    ) -> anyhow::Result<Self> {
         let font_image = assets.store(
             Font::FONT_IMAGE_UUID,
             image::load_from_memory(include_bytes!(
@@ -702,6 +1087,9 @@ impl Texts {
         Ok(Self {
             loaded: Default::default(),
             queued: Default::default(),
+            backgrounds: Default::default(),
+            background_pipeline: pipelines.unlit_alpha_pipeline.to_weak(),
+            background_texture: textures.white_texture.to_weak(),
             font_index: Default::default(),
             font_layout_index: Default::default(),
             font,
@@ -776,13 +1164,19 @@ impl Texts {
         font_texture_id: WeakAssetId<Texture>,
         font_layout_id: WeakAssetId<FontLayout>,
         font_layout: &FontLayout,
-    ) -> anyhow::Result<RawInstance<Weak>> {
+    ) -> anyhow::Result<TextInstances> {
         log::debug!("upsert text: {:?}", text_id);
         self.remove_queued_text(text_id);
         self.remove_loaded_text(text_id);
 
         // Optimization: move mesh generation from render thread
-        let mesh = assets.store(text_id, font_layout.generate_mesh(&raw)?);
+        let text_mesh = font_layout.generate_mesh(&raw)?;
+        let laid_out = TextLaidOutEvent {
+            text_id,
+            size: text_mesh.size,
+            line_count: text_mesh.line_count,
+        };
+        let mesh = assets.store(text_id, text_mesh.glyphs);
         let raw_instance = raw.to_raw_instance(
             font_layout.size,
             font_layout.distance_range,
@@ -790,6 +1184,22 @@ impl Texts {
             mesh.to_weak(),
         );
 
+        let (background_mesh, background, dropped_background) = match text_mesh.background {
+            Some(background_mesh) => {
+                let background_id = *self.backgrounds.entry(text_id).or_insert_with(Uuid::new_v4);
+                let background_mesh = assets.store(background_id, background_mesh);
+                let background = raw
+                    .to_raw_background_instance(
+                        self.background_pipeline,
+                        self.background_texture,
+                        background_mesh.to_weak(),
+                    )
+                    .map(|instance| (background_id, instance));
+                (Some(background_mesh), background, None)
+            }
+            None => (None, None, self.backgrounds.remove(&text_id)),
+        };
+
         self.font_index
             .insert((raw.font, OrderWindow::new(text_id)));
         self.font_layout_index
@@ -797,6 +1207,7 @@ impl Texts {
         let realized = RealizedText {
             raw,
             mesh,
+            background_mesh,
             font_texture: font_texture_id,
             layout: font_layout_id,
             font_layout_size: font_layout.size,
@@ -805,7 +1216,12 @@ impl Texts {
         };
         self.loaded.insert(text_id, realized);
 
-        Ok(raw_instance)
+        Ok(TextInstances {
+            text: raw_instance,
+            background,
+            dropped_background,
+            laid_out: Some(laid_out),
+        })
     }
 
     pub fn minor_update_text(
@@ -813,24 +1229,49 @@ impl Texts {
         canvas_layer_id: Uuid,
         text_id: &Uuid,
         raw: RawText<Weak>,
-    ) -> Option<RawInstance<Weak>> {
+    ) -> Option<TextInstances> {
+        let background_pipeline = self.background_pipeline;
+        let background_texture = self.background_texture;
+        let background_id = self.backgrounds.get(text_id).copied();
+
         self.loaded.get_mut(text_id).map(|realized| {
             log::debug!("minor update text: {:?}", text_id);
             realized.canvas_layer_id = canvas_layer_id;
             realized.raw = raw;
-            realized.raw.to_raw_instance(
-                realized.font_layout_size,
-                realized.font_layout_distance_range,
-                realized.font_texture,
-                realized.mesh.to_weak(),
-            )
+
+            let background = background_id
+                .zip(realized.background_mesh.as_ref())
+                .and_then(|(background_id, background_mesh)| {
+                    realized
+                        .raw
+                        .to_raw_background_instance(
+                            background_pipeline,
+                            background_texture,
+                            background_mesh.to_weak(),
+                        )
+                        .map(|instance| (background_id, instance))
+                });
+
+            TextInstances {
+                text: realized.raw.to_raw_instance(
+                    realized.font_layout_size,
+                    realized.font_layout_distance_range,
+                    realized.font_texture,
+                    realized.mesh.to_weak(),
+                ),
+                background,
+                dropped_background: None,
+                laid_out: None,
+            }
         })
     }
 
-    pub fn remove_text(&mut self, text_id: Uuid) {
+    /// returns the id of the background instance of the text if any
+    pub fn remove_text(&mut self, text_id: Uuid) -> Option<Uuid> {
         log::debug!("remove text: {:?}", text_id);
         self.remove_queued_text(text_id);
         self.remove_loaded_text(text_id);
+        self.backgrounds.remove(&text_id)
     }
 
     fn remove_queued_text(&mut self, text_id: Uuid) {
@@ -3,10 +3,11 @@ use crate::asset::storage::AssetsClient;
 use crate::asset::{StrongAssetId, WeakAssetId};
 use crate::prelude::AssetLoader;
 use crate::util::{HashMap, OrderWindow};
+use image::imageops::{self, FilterType};
 use image::{DynamicImage, GenericImageView, ImageBuffer};
 use serde::Deserialize;
 use std::collections::BTreeSet;
-use std::num::NonZeroU32;
+use std::num::{NonZeroU32, NonZeroU8};
 use uuid::Uuid;
 use wgpu::TextureAspect;
 
@@ -25,15 +26,15 @@ pub struct RealizedView {
 }
 
 impl RealizedView {
-    // TODO: let preferred_format = adapter.get_swap_chain_preferred_format(&surface);
+    /// used if the surface has no preferred format
     pub const FRAME_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Bgra8UnormSrgb;
     pub const IMAGE_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
-    pub const DEPTH_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
 
     pub fn frame_buffer(
         device: &wgpu::Device,
         size: [u32; 2],
         samples: u32,
+        format: wgpu::TextureFormat,
         label: Option<&str>,
     ) -> Self {
         let size = wgpu::Extent3d {
@@ -48,7 +49,7 @@ impl RealizedView {
             mip_level_count: 1,
             sample_count: samples,
             dimension: wgpu::TextureDimension::D2,
-            format: Self::FRAME_TEXTURE_FORMAT,
+            format,
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
         };
 
@@ -64,10 +65,81 @@ impl RealizedView {
         }
     }
 
+    /// single sampled frame buffer that can be sampled, e.g. by post processing passes
+    pub fn post_buffer(
+        device: &wgpu::Device,
+        size: [u32; 2],
+        format: wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> Self {
+        let size = wgpu::Extent3d {
+            width: size[0],
+            height: size[1],
+            depth_or_array_layers: 1,
+        };
+
+        let desc = wgpu::TextureDescriptor {
+            label,
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        };
+
+        let texture = device.create_texture(&desc);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        RealizedView {
+            texture,
+            view,
+            samples: 1,
+            kind: BufferKind::Frame,
+            size,
+        }
+    }
+
+    /// single sampled frame buffer that can be copied into a buffer
+    pub fn capture_buffer(
+        device: &wgpu::Device,
+        size: [u32; 2],
+        format: wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> Self {
+        let size = wgpu::Extent3d {
+            width: size[0],
+            height: size[1],
+            depth_or_array_layers: 1,
+        };
+
+        let desc = wgpu::TextureDescriptor {
+            label,
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        };
+
+        let texture = device.create_texture(&desc);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        RealizedView {
+            texture,
+            view,
+            samples: 1,
+            kind: BufferKind::Frame,
+            size,
+        }
+    }
+
     pub fn depth_buffer(
         device: &wgpu::Device,
         size: [u32; 2],
         samples: u32,
+        format: wgpu::TextureFormat,
         label: Option<&str>,
     ) -> Self {
         let size = wgpu::Extent3d {
@@ -81,7 +153,7 @@ impl RealizedView {
             mip_level_count: 1,
             sample_count: samples,
             dimension: wgpu::TextureDimension::D2,
-            format: Self::DEPTH_TEXTURE_FORMAT,
+            format,
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
         };
         let texture = device.create_texture(&desc);
@@ -97,46 +169,56 @@ impl RealizedView {
         }
     }
 
+    /// each atlas cell is stored in its own layer, row by row starting top left,
+    /// with mipmaps each layer is downsampled on its own so cells don't bleed into each other
     pub fn image_texture_buffer(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         image: &DynamicImage,
         atlas: [NonZeroU32; 2],
+        mipmaps: bool,
         label: Option<&str>,
     ) -> Self {
         let dimensions = image.dimensions();
         let width = dimensions.0 / atlas[0].get();
         let height = dimensions.1 / atlas[1].get();
         let layers = unsafe { NonZeroU32::new_unchecked(atlas[0].get() * atlas[1].get()) };
+        let mip_level_count = if mipmaps {
+            32 - width.max(height).max(1).leading_zeros()
+        } else {
+            1
+        };
         let rgba = image.to_rgba8();
 
-        let realized = Self::empty_image_texture_buffer(device, [width, height], layers, label);
+        let realized = Self::empty_image_texture_buffer(
+            device,
+            [width, height],
+            layers,
+            mip_level_count,
+            label,
+        );
+
+        for layer in 0..layers.get() {
+            let x = layer % atlas[0].get();
+            let y = layer / atlas[0].get();
+            let mut level_image = rgba.view(x * width, y * height, width, height).to_image();
+
+            for mip_level in 0..mip_level_count {
+                if mip_level > 0 {
+                    let level_width = (width >> mip_level).max(1);
+                    let level_height = (height >> mip_level).max(1);
+                    level_image = imageops::resize(
+                        &level_image,
+                        level_width,
+                        level_height,
+                        FilterType::Triangle,
+                    );
+                }
 
-        if layers.get() == 1 {
-            queue.write_texture(
-                wgpu::ImageCopyTexture {
-                    texture: &realized.texture,
-                    mip_level: 0,
-                    origin: wgpu::Origin3d::ZERO,
-                    aspect: TextureAspect::All,
-                },
-                rgba.as_raw(),
-                wgpu::ImageDataLayout {
-                    offset: 0,
-                    bytes_per_row: NonZeroU32::new(4 * width),
-                    rows_per_image: NonZeroU32::new(height),
-                },
-                realized.size,
-            );
-        } else {
-            for layer in 0..layers.get() {
-                let x = layer % atlas[0].get();
-                let y = layer / atlas[0].get();
-                let view = rgba.view(x * width, y * height, width, height).to_image();
                 queue.write_texture(
                     wgpu::ImageCopyTexture {
                         texture: &realized.texture,
-                        mip_level: 0,
+                        mip_level,
                         origin: wgpu::Origin3d {
                             x: 0,
                             y: 0,
@@ -144,15 +226,15 @@ impl RealizedView {
                         },
                         aspect: TextureAspect::All,
                     },
-                    view.as_raw(),
+                    level_image.as_raw(),
                     wgpu::ImageDataLayout {
                         offset: 0,
-                        bytes_per_row: NonZeroU32::new(4 * width),
-                        rows_per_image: NonZeroU32::new(height),
+                        bytes_per_row: NonZeroU32::new(4 * level_image.width()),
+                        rows_per_image: NonZeroU32::new(level_image.height()),
                     },
                     wgpu::Extent3d {
-                        width,
-                        height,
+                        width: level_image.width(),
+                        height: level_image.height(),
                         depth_or_array_layers: 1,
                     },
                 );
@@ -162,10 +244,101 @@ impl RealizedView {
         realized
     }
 
+    /// uploads the blocks of each mip level, UASTC blocks are transcoded to the format first
+    pub fn compressed_texture_buffer(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: &CompressedImage,
+        format: wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> anyhow::Result<Self> {
+        let info = format.describe();
+        let (block_width, block_height) = info.block_dimensions;
+        let (block_width, block_height) = (block_width as u32, block_height as u32);
+        let block_size = info.block_size as u32;
+
+        let size = wgpu::Extent3d {
+            width: image.size[0],
+            height: image.size[1],
+            depth_or_array_layers: image.layers.get(),
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size,
+            mip_level_count: image.levels.len() as u32,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        });
+
+        for (mip_level, level) in image.levels.iter().enumerate() {
+            let level_width = (image.size[0] >> mip_level).max(1);
+            let level_height = (image.size[1] >> mip_level).max(1);
+            let blocks_x = (level_width + block_width - 1) / block_width;
+            let blocks_y = (level_height + block_height - 1) / block_height;
+
+            let transcoded;
+            let data = match image.format {
+                CompressedFormat::Native(_) => level.as_slice(),
+                CompressedFormat::Uastc { .. } => {
+                    transcoded =
+                        transcode_uastc(level, format, [level_width, level_height], image.layers)?;
+                    transcoded.as_slice()
+                }
+            };
+
+            let layer_bytes = (blocks_x * blocks_y * block_size) as usize;
+            if data.len() < layer_bytes * image.layers.get() as usize {
+                return Err(anyhow::anyhow!(
+                    "mip level {} is too small for the {:?} format",
+                    mip_level,
+                    format
+                ));
+            }
+
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: mip_level as u32,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: TextureAspect::All,
+                },
+                data,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(blocks_x * block_size),
+                    rows_per_image: NonZeroU32::new(blocks_y),
+                },
+                wgpu::Extent3d {
+                    width: blocks_x * block_width,
+                    height: blocks_y * block_height,
+                    depth_or_array_layers: image.layers.get(),
+                },
+            );
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            array_layer_count: Some(image.layers),
+            ..Default::default()
+        });
+
+        Ok(RealizedView {
+            texture,
+            view,
+            samples: 1,
+            kind: BufferKind::Image,
+            size,
+        })
+    }
+
     pub fn empty_image_texture_buffer(
         device: &wgpu::Device,
         size: [u32; 2],
         layers: NonZeroU32,
+        mip_level_count: u32,
         label: Option<&str>,
     ) -> Self {
         let size = wgpu::Extent3d {
@@ -177,7 +350,7 @@ impl RealizedView {
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label,
             size,
-            mip_level_count: 1,
+            mip_level_count,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format: Self::IMAGE_TEXTURE_FORMAT,
@@ -213,6 +386,144 @@ impl AssetLoader for ImageLoader {
     }
 }
 
+/// block format of a compressed image
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CompressedFormat {
+    /// uploaded as is, e.g. BC7 or ETC2 blocks
+    Native(wgpu::TextureFormat),
+    /// Basis Universal blocks, transcoded on upload to a format the adapter supports
+    Uastc { srgb: bool },
+}
+
+/// gpu compressed image, each layer is a cell of the texture atlas
+pub struct CompressedImage {
+    pub format: CompressedFormat,
+    pub size: [u32; 2],
+    pub layers: NonZeroU32,
+    /// the blocks of all layers per mip level, starting with the largest level
+    pub levels: Vec<Vec<u8>>,
+}
+
+/// loads KTX2 files without supercompression
+pub struct Ktx2Loader;
+
+impl AssetLoader for Ktx2Loader {
+    type Asset = CompressedImage;
+
+    fn load(cursor: &mut AssetCursor) -> anyhow::Result<Self::Asset> {
+        let bytes = cursor.read()?;
+        let reader = ktx2::Reader::new(&bytes[..])
+            .map_err(|e| anyhow::anyhow!("invalid ktx2 file: {:?}", e))?;
+        let header = reader.header();
+
+        if let Some(scheme) = header.supercompression_scheme {
+            return Err(anyhow::anyhow!(
+                "unsupported ktx2 supercompression: {:?}",
+                scheme
+            ));
+        }
+        if header.face_count > 1 || header.pixel_depth > 1 {
+            return Err(anyhow::anyhow!("only 2d ktx2 textures are supported"));
+        }
+
+        let format = match header.format {
+            // the vk format of basis universal files is left undefined
+            None => CompressedFormat::Uastc {
+                srgb: ktx2_is_srgb(&reader),
+            },
+            Some(format) => CompressedFormat::Native(
+                ktx2_texture_format(format)
+                    .ok_or_else(|| anyhow::anyhow!("unsupported ktx2 format: {:?}", format))?,
+            ),
+        };
+
+        Ok(CompressedImage {
+            format,
+            size: [header.pixel_width, header.pixel_height.max(1)],
+            layers: NonZeroU32::new(header.layer_count)
+                .unwrap_or_else(|| NonZeroU32::new(1).unwrap()),
+            levels: reader.levels().map(|level| level.to_vec()).collect(),
+        })
+    }
+}
+
+fn ktx2_texture_format(format: ktx2::Format) -> Option<wgpu::TextureFormat> {
+    use wgpu::TextureFormat as Tf;
+
+    let texture_format = match format {
+        ktx2::Format::BC1_RGBA_UNORM_BLOCK => Tf::Bc1RgbaUnorm,
+        ktx2::Format::BC1_RGBA_SRGB_BLOCK => Tf::Bc1RgbaUnormSrgb,
+        ktx2::Format::BC3_UNORM_BLOCK => Tf::Bc3RgbaUnorm,
+        ktx2::Format::BC3_SRGB_BLOCK => Tf::Bc3RgbaUnormSrgb,
+        ktx2::Format::BC4_UNORM_BLOCK => Tf::Bc4RUnorm,
+        ktx2::Format::BC5_UNORM_BLOCK => Tf::Bc5RgUnorm,
+        ktx2::Format::BC7_UNORM_BLOCK => Tf::Bc7RgbaUnorm,
+        ktx2::Format::BC7_SRGB_BLOCK => Tf::Bc7RgbaUnormSrgb,
+        ktx2::Format::ETC2_R8G8B8_UNORM_BLOCK => Tf::Etc2RgbUnorm,
+        ktx2::Format::ETC2_R8G8B8_SRGB_BLOCK => Tf::Etc2RgbUnormSrgb,
+        ktx2::Format::ASTC_4x4_UNORM_BLOCK => Tf::Astc4x4RgbaUnorm,
+        ktx2::Format::ASTC_4x4_SRGB_BLOCK => Tf::Astc4x4RgbaUnormSrgb,
+        _ => return None,
+    };
+
+    Some(texture_format)
+}
+
+/// the transfer function of the basic data format descriptor, KTX2 defaults to linear
+fn ktx2_is_srgb(reader: &ktx2::Reader<&[u8]>) -> bool {
+    use ktx2::{BasicDataFormatDescriptor, DataFormatDescriptorHeader, TransferFunction};
+
+    reader
+        .data_format_descriptors()
+        .filter(|descriptor| descriptor.header == DataFormatDescriptorHeader::BASIC)
+        .filter_map(|descriptor| BasicDataFormatDescriptor::parse(descriptor.data).ok())
+        .any(|descriptor| descriptor.transfer_function == Some(TransferFunction::SRGB))
+}
+
+/// transcodes the 4x4 UASTC blocks of each layer
+fn transcode_uastc(
+    level: &[u8],
+    format: wgpu::TextureFormat,
+    size: [u32; 2],
+    layers: NonZeroU32,
+) -> anyhow::Result<Vec<u8>> {
+    use basis_universal::{
+        DecodeFlags, LowLevelUastcTranscoder, SliceParametersUastc, TranscoderBlockFormat,
+    };
+
+    let block_format = match format {
+        wgpu::TextureFormat::Bc7RgbaUnorm | wgpu::TextureFormat::Bc7RgbaUnormSrgb => {
+            TranscoderBlockFormat::BC7
+        }
+        wgpu::TextureFormat::Astc4x4RgbaUnorm | wgpu::TextureFormat::Astc4x4RgbaUnormSrgb => {
+            TranscoderBlockFormat::ASTC_4x4
+        }
+        format => return Err(anyhow::anyhow!("can't transcode uastc to {:?}", format)),
+    };
+
+    let num_blocks_x = (size[0] + 3) / 4;
+    let num_blocks_y = (size[1] + 3) / 4;
+    let layer_bytes = (num_blocks_x * num_blocks_y * 16) as usize;
+    let transcoder = LowLevelUastcTranscoder::new();
+    let mut transcoded = Vec::default();
+
+    for layer in level.chunks(layer_bytes).take(layers.get() as usize) {
+        let params = SliceParametersUastc {
+            num_blocks_x,
+            num_blocks_y,
+            has_alpha: true,
+            original_width: size[0],
+            original_height: size[1],
+        };
+        let mut blocks = transcoder
+            .transcode_slice(layer, params, DecodeFlags::HIGH_QUALITY, block_format)
+            .map_err(|e| anyhow::anyhow!("uastc transcoding failed: {:?}", e))?;
+        transcoded.append(&mut blocks);
+    }
+
+    Ok(transcoded)
+}
+
 #[repr(u8)]
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -238,6 +549,33 @@ impl Into<wgpu::FilterMode> for FilterMode {
     }
 }
 
+#[repr(u8)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AddressMode {
+    ClampToEdge = 0,
+    Repeat = 1,
+    MirrorRepeat = 2,
+}
+
+impl Default for AddressMode {
+    #[inline]
+    fn default() -> Self {
+        AddressMode::ClampToEdge
+    }
+}
+
+impl Into<wgpu::AddressMode> for AddressMode {
+    #[inline]
+    fn into(self) -> wgpu::AddressMode {
+        match self {
+            AddressMode::ClampToEdge => wgpu::AddressMode::ClampToEdge,
+            AddressMode::Repeat => wgpu::AddressMode::Repeat,
+            AddressMode::MirrorRepeat => wgpu::AddressMode::MirrorRepeat,
+        }
+    }
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Texture {
@@ -246,8 +584,18 @@ pub struct Texture {
     pub mag_filter: FilterMode,
     #[serde(default)]
     pub min_filter: FilterMode,
+    #[serde(default)]
+    pub address_mode: AddressMode,
+    #[serde(default)]
+    pub anisotropy_clamp: Option<NonZeroU8>,
     #[serde(default = "non_zero_grid_one")]
     pub atlas: [NonZeroU32; 2],
+    #[serde(default)]
+    pub mipmaps: bool,
+    /// uploaded instead of the image if the adapter supports its format,
+    /// its layers and mip levels take the place of the atlas and mipmaps
+    #[serde(default)]
+    pub compressed: Option<StrongAssetId<CompressedImage>>,
 }
 
 impl Texture {
@@ -269,7 +617,11 @@ impl Texture {
             image,
             mag_filter: FilterMode::default(),
             min_filter: FilterMode::default(),
+            address_mode: AddressMode::default(),
+            anisotropy_clamp: None,
             atlas: non_zero_grid_one(),
+            mipmaps: false,
+            compressed: None,
         }
     }
 
@@ -285,18 +637,65 @@ impl Texture {
         self
     }
 
+    /// repeating modes tile the texture for texture coordinates outside of 0 to 1
+    #[inline]
+    pub fn with_address_mode(mut self, address_mode: AddressMode) -> Self {
+        self.address_mode = address_mode;
+        self
+    }
+
+    /// one of 1, 2, 4, 8 or 16, ignored if the adapter doesn't support anisotropic filtering
+    #[inline]
+    pub fn with_anisotropy(mut self, anisotropy_clamp: NonZeroU8) -> Self {
+        self.anisotropy_clamp = Some(anisotropy_clamp);
+        self
+    }
+
     #[inline]
     pub fn with_atlas(mut self, atlas: [NonZeroU32; 2]) -> Self {
         self.atlas = atlas;
         self
     }
+
+    /// generates the mip chain on upload, the mipmaps are sampled with the min filter
+    #[inline]
+    pub fn with_mipmaps(mut self, mipmaps: bool) -> Self {
+        self.mipmaps = mipmaps;
+        self
+    }
+
+    #[inline]
+    pub fn with_compressed(mut self, compressed: StrongAssetId<CompressedImage>) -> Self {
+        self.compressed = Some(compressed);
+        self
+    }
+
+    /// texture layer of the atlas cell, `layer = y * columns + x`
+    /// cells outside of the atlas are clamped to its last column or row
+    pub fn atlas_layer(atlas: [NonZeroU32; 2], cell: [u32; 2]) -> u32 {
+        let [columns, rows] = [atlas[0].get(), atlas[1].get()];
+        if cell[0] >= columns || cell[1] >= rows {
+            log::warn!(
+                "atlas cell {:?} is outside of the {:?} atlas",
+                cell,
+                [columns, rows]
+            );
+        }
+
+        let x = cell[0].min(columns - 1);
+        let y = cell[1].min(rows - 1);
+        y * columns + x
+    }
 }
 
 pub struct RealizedTexture {
     pub(crate) view: RealizedView,
     pub(crate) sampler: wgpu::Sampler,
     pub(crate) bind_group: wgpu::BindGroup,
-    pub(crate) image: WeakAssetId<DynamicImage>,
+    pub(crate) format: wgpu::TextureFormat,
+    pub(crate) mipmaps: bool,
+    pub(crate) image: Option<WeakAssetId<DynamicImage>>,
+    pub(crate) compressed: Option<WeakAssetId<CompressedImage>>,
 }
 
 impl RealizedTexture {
@@ -306,21 +705,87 @@ impl RealizedTexture {
         texture: &Texture,
         texture_bind_group_layout: &wgpu::BindGroupLayout,
         image: &DynamicImage,
+        anisotropic_filtering: bool,
     ) -> Self {
         let view = RealizedView::image_texture_buffer(
             device,
             queue,
             image,
             texture.atlas,
+            texture.mipmaps,
             Some("texture_buffer"),
         );
+
+        Self::with_view(
+            device,
+            texture,
+            texture_bind_group_layout,
+            view,
+            RealizedView::IMAGE_TEXTURE_FORMAT,
+            texture.mipmaps,
+            anisotropic_filtering,
+        )
+    }
+
+    pub fn compressed(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture: &Texture,
+        texture_bind_group_layout: &wgpu::BindGroupLayout,
+        image: &CompressedImage,
+        format: wgpu::TextureFormat,
+        anisotropic_filtering: bool,
+    ) -> anyhow::Result<Self> {
+        let view = RealizedView::compressed_texture_buffer(
+            device,
+            queue,
+            image,
+            format,
+            Some("compressed_texture_buffer"),
+        )?;
+
+        Ok(Self::with_view(
+            device,
+            texture,
+            texture_bind_group_layout,
+            view,
+            format,
+            image.levels.len() > 1,
+            anisotropic_filtering,
+        ))
+    }
+
+    fn with_view(
+        device: &wgpu::Device,
+        texture: &Texture,
+        texture_bind_group_layout: &wgpu::BindGroupLayout,
+        view: RealizedView,
+        format: wgpu::TextureFormat,
+        mipmaps: bool,
+        anisotropic_filtering: bool,
+    ) -> Self {
+        let anisotropy_clamp = texture.anisotropy_clamp.filter(|clamp| {
+            let valid = matches!(clamp.get(), 1 | 2 | 4 | 8 | 16);
+            if !valid {
+                log::warn!("ignore invalid texture anisotropy: {}", clamp);
+            } else if !anisotropic_filtering {
+                log::debug!("ignore texture anisotropy as it isn't supported");
+            }
+            valid && anisotropic_filtering
+        });
+
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            address_mode_u: texture.address_mode.into(),
+            address_mode_v: texture.address_mode.into(),
+            address_mode_w: texture.address_mode.into(),
             mag_filter: texture.mag_filter.into(),
             min_filter: texture.min_filter.into(),
-            mipmap_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: if mipmaps {
+                texture.min_filter.into()
+            } else {
+                wgpu::FilterMode::Nearest
+            },
+            anisotropy_clamp,
             ..Default::default()
         });
 
@@ -343,22 +808,96 @@ impl RealizedTexture {
             view,
             sampler,
             bind_group,
-            image: texture.image.to_weak(),
+            format,
+            mipmaps,
+            image: Some(texture.image.to_weak()),
+            compressed: texture.compressed.as_ref().map(|c| c.to_weak()),
+        }
+    }
+
+    /// texture that a canvas resolves its frame into
+    pub fn canvas(
+        device: &wgpu::Device,
+        size: [u32; 2],
+        format: wgpu::TextureFormat,
+        texture_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let view = RealizedView::post_buffer(device, size, format, Some("canvas_texture_buffer"));
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        // the texture pipelines sample array textures
+        let array_view = view.texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            array_layer_count: NonZeroU32::new(1),
+            ..Default::default()
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&array_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+            label: Some("canvas_texture_bind_group"),
+        });
+
+        Self {
+            view,
+            sampler,
+            bind_group,
+            format,
+            mipmaps: false,
+            image: None,
+            compressed: None,
         }
     }
 }
 
 pub struct Textures {
     loaded: HashMap<WeakAssetId<Texture>, RealizedTexture>,
+    canvasses: HashMap<WeakAssetId<Texture>, RealizedTexture>,
     queued: HashMap<WeakAssetId<Texture>, WeakAssetId<DynamicImage>>,
+    queued_compressed: HashMap<WeakAssetId<Texture>, WeakAssetId<CompressedImage>>,
     image_index: BTreeSet<(WeakAssetId<DynamicImage>, OrderWindow<WeakAssetId<Texture>>)>,
+    compressed_index: BTreeSet<(
+        WeakAssetId<CompressedImage>,
+        OrderWindow<WeakAssetId<Texture>>,
+    )>,
     texture_bind_group_layout: wgpu::BindGroupLayout,
+    anisotropic_filtering: bool,
+    compression: wgpu::Features,
+    frame_format: wgpu::TextureFormat,
     // defaults
     pub(crate) white_texture: StrongAssetId<Texture>,
 }
 
 impl Textures {
-    pub fn new(assets: &AssetsClient, texture_bind_group_layout: wgpu::BindGroupLayout) -> Self {
+    /// the texture compression features requested from the adapter if available
+    pub const COMPRESSION_FEATURES: wgpu::Features = wgpu::Features::TEXTURE_COMPRESSION_BC
+        .union(wgpu::Features::TEXTURE_COMPRESSION_ETC2)
+        .union(wgpu::Features::TEXTURE_COMPRESSION_ASTC_LDR);
+
+    pub fn new(
+        assets: &AssetsClient,
+        texture_bind_group_layout: wgpu::BindGroupLayout,
+        anisotropic_filtering: bool,
+        compression: wgpu::Features,
+        frame_format: wgpu::TextureFormat,
+    ) -> Self {
         let white_image = assets.store(
             Texture::WHITE_IMAGE_UUID,
             DynamicImage::ImageRgba8(ImageBuffer::from_fn(1, 1, |_x, _y| {
@@ -374,13 +913,45 @@ impl Textures {
 
         Self {
             loaded: Default::default(),
+            canvasses: Default::default(),
             queued: Default::default(),
+            queued_compressed: Default::default(),
             image_index: Default::default(),
+            compressed_index: Default::default(),
             texture_bind_group_layout,
+            anisotropic_filtering,
+            compression: compression & Self::COMPRESSION_FEATURES,
+            frame_format,
             white_texture,
         }
     }
 
+    /// the texture format the compressed format is uploaded as, if the adapter supports any
+    pub fn compressed_target(&self, format: CompressedFormat) -> Option<wgpu::TextureFormat> {
+        match format {
+            CompressedFormat::Native(format) => {
+                let required = format.describe().required_features;
+                self.compression.contains(required).then(|| format)
+            }
+            // wgpu has no ETC2 format with a full alpha channel to transcode to
+            CompressedFormat::Uastc { srgb } => [
+                (
+                    wgpu::Features::TEXTURE_COMPRESSION_BC,
+                    wgpu::TextureFormat::Bc7RgbaUnorm,
+                    wgpu::TextureFormat::Bc7RgbaUnormSrgb,
+                ),
+                (
+                    wgpu::Features::TEXTURE_COMPRESSION_ASTC_LDR,
+                    wgpu::TextureFormat::Astc4x4RgbaUnorm,
+                    wgpu::TextureFormat::Astc4x4RgbaUnormSrgb,
+                ),
+            ]
+            .into_iter()
+            .find(|(feature, _, _)| self.compression.contains(*feature))
+            .map(|(_, linear, srgb_format)| if srgb { srgb_format } else { linear }),
+        }
+    }
+
     pub fn textures_for_image(
         &self,
         image_id: WeakAssetId<DynamicImage>,
@@ -396,39 +967,229 @@ impl Textures {
             .collect()
     }
 
+    pub fn textures_for_compressed_image(
+        &self,
+        image_id: WeakAssetId<CompressedImage>,
+    ) -> Vec<WeakAssetId<Texture>> {
+        use std::ops::Bound::Included;
+
+        self.compressed_index
+            .range((
+                Included(&(image_id, OrderWindow::Start)),
+                Included(&(image_id, OrderWindow::End)),
+            ))
+            .filter_map(|(_, t)| t.as_option().copied())
+            .collect()
+    }
+
     pub fn queue_texture(&mut self, texture_id: WeakAssetId<Texture>, texture: &Texture) {
         self.remove_queued_texture(texture_id);
         self.queued.insert(texture_id, texture.image.to_weak());
         self.image_index
             .insert((texture.image.to_weak(), OrderWindow::new(texture_id)));
+
+        if let Some(compressed) = &texture.compressed {
+            self.queued_compressed
+                .insert(texture_id, compressed.to_weak());
+            self.compressed_index
+                .insert((compressed.to_weak(), OrderWindow::new(texture_id)));
+        }
     }
 
     pub fn get_texture(&self, texture_id: &WeakAssetId<Texture>) -> Option<&RealizedTexture> {
-        self.loaded.get(texture_id)
+        self.loaded
+            .get(texture_id)
+            .or_else(|| self.canvasses.get(texture_id))
+    }
+
+    pub fn upsert_canvas_texture(
+        &mut self,
+        device: &wgpu::Device,
+        canvas_id: Uuid,
+        size: [u32; 2],
+    ) {
+        log::debug!("upsert canvas texture: {:?}", canvas_id);
+        let realized = RealizedTexture::canvas(
+            device,
+            size,
+            self.frame_format,
+            &self.texture_bind_group_layout,
+        );
+        self.canvasses
+            .insert(WeakAssetId::uuid(canvas_id), realized);
     }
 
+    /// the view the canvas resolves its frame into
+    pub fn canvas_target(&self, canvas_id: Uuid) -> Option<&wgpu::TextureView> {
+        self.canvasses
+            .get(&WeakAssetId::uuid(canvas_id))
+            .map(|realized| &realized.view.view)
+    }
+
+    pub fn remove_canvas_texture(&mut self, canvas_id: Uuid) {
+        if self
+            .canvasses
+            .remove(&WeakAssetId::uuid(canvas_id))
+            .is_some()
+        {
+            log::debug!("remove canvas texture: {:?}", canvas_id);
+        }
+    }
+
+    /// uploads the compressed image if the adapter supports its format and the image otherwise,
+    /// the texture stays queued until the images it is uploaded from are loaded
     pub fn upsert_texture(
         &mut self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
+        assets: &AssetsClient,
         texture_id: WeakAssetId<Texture>,
         texture: &Texture,
-        image: &DynamicImage,
     ) {
+        let compressed = match &texture.compressed {
+            Some(compressed_id) => match assets.try_get(compressed_id) {
+                Some(compressed) => {
+                    let target = self.compressed_target(compressed.format);
+                    if target.is_none() {
+                        log::debug!(
+                            "compressed format {:?} isn't supported by the adapter",
+                            compressed.format
+                        );
+                    }
+                    target.map(|format| (compressed, format))
+                }
+                None => {
+                    self.queue_texture(texture_id, texture);
+                    return;
+                }
+            },
+            None => None,
+        };
+
+        let realized = compressed.and_then(|(compressed, format)| {
+            RealizedTexture::compressed(
+                device,
+                queue,
+                texture,
+                &self.texture_bind_group_layout,
+                compressed,
+                format,
+                self.anisotropic_filtering,
+            )
+            .map_err(|e| log::warn!("fall back to the image of {:?}: {}", texture_id, e))
+            .ok()
+        });
+
+        let realized = match realized {
+            Some(realized) => realized,
+            None => match assets.try_get(&texture.image) {
+                Some(image) => RealizedTexture::new(
+                    device,
+                    queue,
+                    texture,
+                    &self.texture_bind_group_layout,
+                    image,
+                    self.anisotropic_filtering,
+                ),
+                None => {
+                    self.queue_texture(texture_id, texture);
+                    return;
+                }
+            },
+        };
+
         log::debug!("upsert texture: {:?}", texture_id);
         self.remove_texture(texture_id);
 
-        let realized = RealizedTexture::new(
-            device,
-            queue,
-            texture,
-            &self.texture_bind_group_layout,
-            image,
-        );
-
-        self.loaded.insert(texture_id, realized);
         self.image_index
             .insert((texture.image.to_weak(), OrderWindow::new(texture_id)));
+        if let Some(compressed) = &realized.compressed {
+            self.compressed_index
+                .insert((*compressed, OrderWindow::new(texture_id)));
+        }
+        self.loaded.insert(texture_id, realized);
+    }
+
+    /// writes rgba pixels into a rectangle of an image texture layer without mipmaps,
+    /// e.g. for a software rendered minimap
+    pub fn update_region(
+        &self,
+        queue: &wgpu::Queue,
+        texture_id: &WeakAssetId<Texture>,
+        layer: u32,
+        origin: [u32; 2],
+        size: [u32; 2],
+        rgba: &[u8],
+    ) -> anyhow::Result<()> {
+        let realized = self
+            .loaded
+            .get(texture_id)
+            .ok_or_else(|| anyhow::anyhow!("texture isn't loaded"))?;
+        if realized.format != RealizedView::IMAGE_TEXTURE_FORMAT {
+            return Err(anyhow::anyhow!(
+                "{:?} textures can't be updated",
+                realized.format
+            ));
+        }
+        if realized.mipmaps {
+            // the lower mip levels would keep showing the previous image
+            return Err(anyhow::anyhow!("textures with mipmaps can't be updated"));
+        }
+
+        let extent = realized.view.size;
+        let end = origin[0]
+            .checked_add(size[0])
+            .zip(origin[1].checked_add(size[1]));
+        let exceeds = match end {
+            Some((x, y)) => x > extent.width || y > extent.height,
+            None => true,
+        };
+        if layer >= extent.depth_or_array_layers || exceeds {
+            return Err(anyhow::anyhow!(
+                "region {:?} + {:?} of layer {} exceeds the texture size {:?}",
+                origin,
+                size,
+                layer,
+                extent
+            ));
+        }
+
+        let expected = 4 * size[0] as usize * size[1] as usize;
+        if rgba.len() != expected {
+            return Err(anyhow::anyhow!(
+                "expected {} rgba bytes for the region {:?}, got {}",
+                expected,
+                size,
+                rgba.len()
+            ));
+        }
+
+        log::debug!("update texture region: {:?}", texture_id);
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &realized.view.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: origin[0],
+                    y: origin[1],
+                    z: layer,
+                },
+                aspect: TextureAspect::All,
+            },
+            rgba,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: NonZeroU32::new(4 * size[0]),
+                rows_per_image: NonZeroU32::new(size[1]),
+            },
+            wgpu::Extent3d {
+                width: size[0],
+                height: size[1],
+                depth_or_array_layers: 1,
+            },
+        );
+
+        Ok(())
     }
 
     pub fn remove_texture(&mut self, texture_id: WeakAssetId<Texture>) {
@@ -442,12 +1203,22 @@ impl Textures {
             self.image_index
                 .remove(&(image, OrderWindow::new(texture_id)));
         }
+        if let Some(compressed) = self.queued_compressed.remove(&texture_id) {
+            self.compressed_index
+                .remove(&(compressed, OrderWindow::new(texture_id)));
+        }
     }
 
     fn remove_loaded_texture(&mut self, texture_id: WeakAssetId<Texture>) {
         if let Some(realized) = self.loaded.remove(&texture_id) {
-            self.image_index
-                .remove(&(realized.image, OrderWindow::new(texture_id)));
+            if let Some(image) = realized.image {
+                self.image_index
+                    .remove(&(image, OrderWindow::new(texture_id)));
+            }
+            if let Some(compressed) = realized.compressed {
+                self.compressed_index
+                    .remove(&(compressed, OrderWindow::new(texture_id)));
+            }
         }
     }
 }
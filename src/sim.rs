@@ -1,11 +1,20 @@
+pub mod console;
+pub mod harness;
+pub mod input;
+pub mod stack;
+
 use crate::asset::storage::Assets;
 use crate::asset::AssetsCreatedEvent;
-use crate::platform::message::FrameRequestedEvent;
+use crate::platform::message::{FrameRequestedEvent, ShutdownRequestedEvent};
 use crate::render::client::RenderClient;
 use crate::render::message::RenderCreatedEvent;
+use crate::sim::input::InputSnapshot;
+use crate::sim::stack::{StateStack, StateStackContext};
 use crate::some_or_return;
+use crate::ShutdownHandle;
 use roundabout::prelude::*;
 use std::marker::PhantomData;
+use std::time::Duration;
 
 pub enum StateInstruction<T> {
     Stay,
@@ -56,7 +65,28 @@ pub struct SimResources<T> {
     pub context: RuntimeContext,
     pub assets: Assets,
     pub render: RenderClient,
+    pub input: InputSnapshot,
     pub resource: T,
+    state_names: Vec<String>,
+}
+
+impl<T> SimResources<T> {
+    #[inline]
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle::new(&self.context)
+    }
+
+    /// number of states on the sim state stack
+    #[inline]
+    pub fn state_depth(&self) -> usize {
+        self.state_names.len()
+    }
+
+    /// names of the states on the sim state stack, from the bottom to the head
+    #[inline]
+    pub fn state_names(&self) -> &[String] {
+        &self.state_names
+    }
 }
 
 impl<T> AsRef<RuntimeContext> for SimResources<T> {
@@ -75,6 +105,11 @@ where
         resources: &mut SimResources<R>,
         message: &M,
     ) -> Option<StateInstruction<S>>;
+
+    /// used to introspect the sim state stack
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
 }
 
 pub type OpenSimHandlerBuilder<T, R, S> =
@@ -88,241 +123,97 @@ pub type InitSimHandlerBuilder<T, R, S> =
 
 pub type SimHandler<T, R, S> = MessageHandler<T, SimResources<R>, StateInstruction<S>>;
 
-pub struct SimStackEntry<T>(usize, Option<StateInstruction<T>>);
-
 struct SimHState<R, S: SimState<R>> {
-    states: Vec<S>,
+    stack: StateStack<S>,
     head_message: Option<InlineMessageView<SimStateEvent>>,
     tail_message: Option<InlineMessageView<SimStateEvent>>,
     stop_message: Option<InlineMessageView<SimStateEvent>>,
-    stack: Vec<SimStackEntry<S>>,
     _pd: PhantomData<R>,
 }
 
 impl<R: 'static, S: SimState<R>> SimHState<R, S> {
     pub fn initial<A: AsRef<RuntimeContext>>(initial: S, res: A) -> Self {
-        let initial_stack_entry = SimStackEntry(0, Some(StateInstruction::push(initial)));
         let head_message = InlineMessageView::new(SimStateEvent::Head, &res);
         let tail_message = InlineMessageView::new(SimStateEvent::Tail, &res);
         let stop_message = InlineMessageView::new(SimStateEvent::Stop, &res);
 
         Self {
-            states: vec![],
+            stack: StateStack::initial(initial),
             head_message,
             tail_message,
             stop_message,
-            stack: vec![initial_stack_entry],
             _pd: Default::default(),
         }
     }
 
-    fn inject_state_event(
-        state: &mut S,
-        res: &mut SimResources<R>,
-        message: &Option<InlineMessageView<SimStateEvent>>,
-    ) -> Option<StateInstruction<S>> {
-        message.as_ref().and_then(|m| state.handle(res, m))
-    }
-
     fn handle<M: MessageView>(&mut self, res: &mut SimResources<R>, message: &M) {
-        // TODO: pop on shutdown event?
-        // Optimization: filter messages that are not handed by any state
-
-        let mut current: usize = 0;
+        let mut context = SimHStateContext {
+            res,
+            message,
+            head_message: &self.head_message,
+            tail_message: &self.tail_message,
+            stop_message: &self.stop_message,
+        };
+        self.stack.handle(&mut context);
+        self.update_state_names(res);
+    }
 
-        // Optimal path with no stat changes
-        for state in &mut self.states {
-            match state.handle(res, message) {
-                None => {
-                    current += 1;
-                }
-                Some(StateInstruction::Stay) => {
-                    current += 1;
-                }
-                Some(instruction) => {
-                    let stack_entry = SimStackEntry(current, Some(instruction));
-                    self.stack.push(stack_entry);
-                    current += 1;
-                    break;
-                }
-            }
+    fn update_state_names(&self, res: &mut SimResources<R>) {
+        let states = self.stack.states();
+        let unchanged = res.state_names.len() == states.len()
+            && res
+                .state_names
+                .iter()
+                .zip(states)
+                .all(|(name, state)| name == state.name());
+        if unchanged {
+            return;
         }
 
-        // println!("states.len: {}", self.states.len());
-        // TODO: print stack state? State name?
-        loop {
-            match self.stack.pop() {
-                None => {
-                    let state = some_or_return!(self.states.get_mut(current as usize));
-                    let instruction = state.handle(res, message);
-                    self.stack
-                        .push(SimStackEntry(current as usize, instruction));
-                    current += 1;
-                }
-                Some(SimStackEntry(_at, None)) => {}
-                Some(SimStackEntry(_at, Some(StateInstruction::Stay))) => {}
-                Some(SimStackEntry(at, Some(StateInstruction::Switch(new)))) => {
-                    log::info!("switch state at {}", at);
-
-                    let is_head = at + 1 == self.states.len();
-                    let state = self.states.get_mut(at).unwrap();
-                    let instruction = Self::inject_state_event(state, res, &self.stop_message);
-                    assert!(
-                        instruction.map(|i| i.is_stay()).unwrap_or(true),
-                        "non stay instructions are not supported when state handles SimStateEvent::Stop"
-                    );
-                    *state = new;
-
-                    if is_head {
-                        let instruction = Self::inject_state_event(state, res, &self.head_message);
-                        self.stack.push(SimStackEntry(at, instruction));
-                    } else {
-                        let instruction = Self::inject_state_event(state, res, &self.tail_message);
-                        self.stack.push(SimStackEntry(at, instruction));
-                    }
-
-                    if at < current {
-                        current = at;
-                    }
-                }
-                Some(SimStackEntry(at, Some(StateInstruction::Push(new)))) => {
-                    log::info!("push {} state(s) at {}", new.len(), at);
-
-                    let next_idx = at + 1;
-                    let is_tail = next_idx < self.states.len();
-                    let new_len = new.len();
-
-                    if is_tail {
-                        for mut state in self.states.drain(next_idx..).rev() {
-                            let instruction =
-                                Self::inject_state_event(&mut state, res, &self.stop_message);
-                            assert!(
-                                instruction.map(|i| i.is_stay()).unwrap_or(true),
-                                "non stay instructions are not supported when state handles SimStateEvent::Stop"
-                            );
-                        }
-                    }
-
-                    if new_len > 0 {
-                        self.states.extend(new);
-
-                        for (idx, new_tail) in self
-                            .states
-                            .iter_mut()
-                            .enumerate()
-                            .skip(next_idx - !is_tail as usize)
-                            .take(new_len + !is_tail as usize - 1)
-                        {
-                            let instruction =
-                                Self::inject_state_event(new_tail, res, &self.tail_message);
-                            self.stack.push(SimStackEntry(idx, instruction));
-                        }
-                    }
-
-                    if (new_len == 0 && is_tail) || new_len > 0 {
-                        let last_idx = self.states.len().saturating_sub(1);
-                        if let Some(last) = self.states.last_mut() {
-                            let instruction =
-                                Self::inject_state_event(last, res, &self.head_message);
-                            self.stack.push(SimStackEntry(last_idx, instruction));
-                        }
-                    }
-                }
-                Some(SimStackEntry(at, Some(StateInstruction::Pop))) => {
-                    log::info!("pop state at {}", at);
-
-                    for mut state in self.states.drain(at..).rev() {
-                        let instruction =
-                            Self::inject_state_event(&mut state, res, &self.stop_message);
-                        assert!(
-                            instruction.map(|i| i.is_stay()).unwrap_or(true),
-                            "non stay instructions are not supported when state handles SimStateEvent::Stop"
-                        );
-                    }
+        res.state_names = states.iter().map(|s| s.name().to_owned()).collect();
+        log::debug!("sim state stack: {:?}", res.state_names);
+    }
+}
 
-                    let last_idx = self.states.len().saturating_sub(1);
-                    if let Some(last) = self.states.last_mut() {
-                        let instruction = Self::inject_state_event(last, res, &self.head_message);
-                        self.stack.push(SimStackEntry(last_idx, instruction));
-                    } else {
-                        res.context.shutdown_switch().request_shutdown();
-                    }
-                }
-                Some(SimStackEntry(at, Some(StateInstruction::PopPush(new)))) => {
-                    log::info!("pop push {} state(s) at {}", new.len(), at);
-
-                    let new_len = new.len();
-
-                    for mut state in self.states.drain(at..).rev() {
-                        let instruction =
-                            Self::inject_state_event(&mut state, res, &self.stop_message);
-                        assert!(
-                            instruction.map(|i| i.is_stay()).unwrap_or(true),
-                            "non stay instructions are not supported when state handles SimStateEvent::Stop"
-                        );
-                    }
+struct SimHStateContext<'a, R, M> {
+    res: &'a mut SimResources<R>,
+    message: &'a M,
+    head_message: &'a Option<InlineMessageView<SimStateEvent>>,
+    tail_message: &'a Option<InlineMessageView<SimStateEvent>>,
+    stop_message: &'a Option<InlineMessageView<SimStateEvent>>,
+}
 
-                    if new_len > 0 {
-                        self.states.extend(new);
-
-                        for (idx, new_tail) in self
-                            .states
-                            .iter_mut()
-                            .enumerate()
-                            .skip(at)
-                            .take(new_len - 1)
-                        {
-                            let instruction =
-                                Self::inject_state_event(new_tail, res, &self.tail_message);
-                            self.stack.push(SimStackEntry(idx, instruction));
-                        }
-                    }
+impl<'a, R, M: MessageView, S: SimState<R>> StateStackContext<S> for SimHStateContext<'a, R, M> {
+    #[inline]
+    fn handle_message(&mut self, state: &mut S) -> Option<StateInstruction<S>> {
+        state.handle(self.res, self.message)
+    }
 
-                    let last_idx = self.states.len().saturating_sub(1);
-                    if let Some(last) = self.states.last_mut() {
-                        let instruction = Self::inject_state_event(last, res, &self.head_message);
-                        self.stack.push(SimStackEntry(last_idx, instruction));
-                    } else {
-                        res.context.shutdown_switch().request_shutdown();
-                    }
+    fn handle_state_event(
+        &mut self,
+        state: &mut S,
+        event: SimStateEvent,
+    ) -> Option<StateInstruction<S>> {
+        let message = match event {
+            SimStateEvent::Head => self.head_message,
+            SimStateEvent::Tail => self.tail_message,
+            SimStateEvent::Stop => self.stop_message,
+        };
 
-                    if at < current {
-                        current = at;
-                    }
-                }
-                Some(SimStackEntry(at, Some(StateInstruction::Extract))) => {
-                    log::info!("extract state at {}", at);
-
-                    let mut state = self.states.remove(at);
-                    let instruction = Self::inject_state_event(&mut state, res, &self.stop_message);
-                    assert!(
-                        instruction.map(|i| i.is_stay()).unwrap_or(true),
-                        "non stay instructions are not supported when state handles SimStateEvent::Stop"
-                    );
-
-                    if at == self.states.len() {
-                        let last_idx = self.states.len().saturating_sub(1);
-                        if let Some(last) = self.states.last_mut() {
-                            let instruction =
-                                Self::inject_state_event(last, res, &self.head_message);
-                            self.stack.push(SimStackEntry(last_idx, instruction));
-                        }
-                    } else if self.states.is_empty() {
-                        res.context.shutdown_switch().request_shutdown();
-                    }
+        message.as_ref().and_then(|m| state.handle(self.res, m))
+    }
 
-                    if at < current {
-                        current = at;
-                    }
-                }
-            }
-        }
+    /// the runtime stops once the final frame has been drawn and simulated
+    #[inline]
+    fn request_shutdown(&mut self) {
+        self.res.context.sender().send(ShutdownRequestedEvent);
     }
 }
 
 pub struct SimServer<'a, R, S> {
     group: MessageGroupBuilder<'a>,
     resource_init: Box<dyn FnOnce() -> R + Send + 'static>,
+    fixed_timestep: Option<Duration>,
     _pd: PhantomData<S>,
 }
 
@@ -335,10 +226,18 @@ impl<'a, R: 'static, S: SimState<R>> SimServer<'a, R, S> {
         SimServer {
             group,
             resource_init: Box::new(resource_init),
+            fixed_timestep: None,
             _pd: Default::default(),
         }
     }
 
+    /// states receive a `FixedUpdateEvent` for every elapsed step before the frame is simulated
+    pub fn with_fixed_timestep(mut self, step: Duration) -> Self {
+        assert!(!step.is_zero(), "fixed timestep must be greater than zero");
+        self.fixed_timestep = Some(step);
+        self
+    }
+
     pub fn init_fn<F>(mut self, state_init: F) -> MessageGroup
     where
         F: FnOnce(&SimResources<R>) -> S + Send + 'static,
@@ -354,11 +253,26 @@ impl<'a, R: 'static, S: SimState<R>> SimServer<'a, R, S> {
             .group
             .register(|b| b.on(on_frame_requested_event::<()>).init_default());
 
+        let animation_builder = self
+            .group
+            .register(|b| b.on(on_frame_requested_animation_event).init_default());
+
+        let input_builder = self.group.register(InputSnapshot::handler);
+
+        let fixed_timestep = self.fixed_timestep;
+        let fixed_builder = self.group.register(|b| {
+            b.on(on_frame_requested_fixed_event)
+                .init(FixedTimestep::new(fixed_timestep))
+        });
+
         let resource_init = self.resource_init;
 
         self.group.init(move |mut recv, mut context| {
             let mut setup = setup_builder.finish(&context).unwrap();
             let mut simulated = simulated_builder.finish(&context).unwrap();
+            let mut animation = animation_builder.finish(&context).unwrap();
+            let mut input = input_builder.finish(&context).unwrap();
+            let mut fixed = fixed_builder.finish(&context).unwrap();
 
             let setup_result = recv.recv_while(|message| {
                 setup.handle(&mut context, message);
@@ -378,13 +292,27 @@ impl<'a, R: 'static, S: SimState<R>> SimServer<'a, R, S> {
                 context,
                 assets: setup.state.assets.unwrap(),
                 render: setup.state.render.unwrap(),
+                input: input.state.clone(),
                 resource: (resource_init)(),
+                state_names: Vec::new(),
             };
 
+            animation.state = Some(res.render.clone());
+
             let initial_state = state_init(&res);
             let mut h_state = SimHState::initial(initial_state, &res);
+            let fixed_message =
+                fixed_timestep.and_then(|dt| InlineMessageView::new(FixedUpdateEvent { dt }, &res));
 
             recv.stream(|message| {
+                input.handle(&mut res.context, message);
+                animation.handle(&mut res.context, message);
+                fixed.handle(&mut res.context, message);
+                if let Some(fixed_message) = &fixed_message {
+                    for _ in 0..std::mem::take(&mut fixed.state.pending) {
+                        h_state.handle(&mut res, fixed_message);
+                    }
+                }
                 h_state.handle(&mut res, message);
                 simulated.handle(&mut res.context, message);
             })
@@ -422,6 +350,58 @@ fn on_frame_requested_event<T>(
     context.sender().send(SimulatedEvent { frame: event.frame });
 }
 
+struct FixedTimestep {
+    step: Option<Duration>,
+    accumulator: Duration,
+    pending: u32,
+}
+
+impl FixedTimestep {
+    /// fixed steps simulated in one frame at most, the accumulated time beyond them is discarded
+    const MAX_STEPS: u32 = 8;
+
+    fn new(step: Option<Duration>) -> Self {
+        Self {
+            step,
+            accumulator: Duration::ZERO,
+            pending: 0,
+        }
+    }
+}
+
+fn on_frame_requested_fixed_event(
+    state: &mut FixedTimestep,
+    _context: &mut RuntimeContext,
+    event: &FrameRequestedEvent,
+) {
+    let step = some_or_return!(state.step);
+
+    state.accumulator += event.delta;
+    while state.accumulator >= step {
+        state.accumulator -= step;
+        state.pending += 1;
+
+        if state.pending == FixedTimestep::MAX_STEPS {
+            log::warn!(
+                "fixed timestep can't keep up, dropping {:?}",
+                state.accumulator
+            );
+            state.accumulator = Duration::ZERO;
+            break;
+        }
+    }
+}
+
+fn on_frame_requested_animation_event(
+    state: &mut Option<RenderClient>,
+    _context: &mut RuntimeContext,
+    event: &FrameRequestedEvent,
+) {
+    if let Some(render) = state {
+        render.animate(event);
+    }
+}
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum SimStateEvent {
     Stop,
@@ -434,4 +414,8 @@ pub struct SimulatedEvent {
     pub frame: u64,
 }
 
-// TODO: test SimHState
+/// a step of the fixed timestep configured with `SimServer::with_fixed_timestep`
+#[derive(Debug, Clone, Copy)]
+pub struct FixedUpdateEvent {
+    pub dt: Duration,
+}
@@ -0,0 +1,183 @@
+use crate::asset::Strong;
+use crate::platform::key::ScanCode;
+use crate::platform::message::KeyInputEvent;
+use crate::render::client::{CanvasLayer, Text, TextBuilder};
+use crate::sim::SimResources;
+use crate::some_or_return;
+use crate::util::HashMap;
+use std::collections::VecDeque;
+
+pub type ConsoleCommand<R> =
+    Box<dyn FnMut(&[&str], &mut SimResources<R>) -> anyhow::Result<()> + 'static>;
+
+/// commands without a registered closure are sent as this event to all servers
+#[derive(Debug, Clone)]
+pub struct ConsoleCommandEvent {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+/// developer console, which dispatches submitted lines to the registered commands
+pub struct Console<R> {
+    commands: HashMap<String, ConsoleCommand<R>>,
+    input: String,
+    log: VecDeque<String>,
+    log_max: usize,
+    open: bool,
+    overlay: Option<Text>,
+}
+
+impl<R> Console<R> {
+    pub fn new() -> Self {
+        Self {
+            commands: Default::default(),
+            input: Default::default(),
+            log: Default::default(),
+            log_max: 16,
+            open: false,
+            overlay: None,
+        }
+    }
+
+    #[inline]
+    pub fn with_command<F>(mut self, name: &str, command: F) -> Self
+    where
+        F: FnMut(&[&str], &mut SimResources<R>) -> anyhow::Result<()> + 'static,
+    {
+        self.register(name, command);
+        self
+    }
+
+    #[inline]
+    pub fn with_log_max(mut self, log_max: usize) -> Self {
+        self.log_max = log_max;
+        self
+    }
+
+    pub fn register<F>(&mut self, name: &str, command: F)
+    where
+        F: FnMut(&[&str], &mut SimResources<R>) -> anyhow::Result<()> + 'static,
+    {
+        self.commands.insert(name.to_owned(), Box::new(command));
+    }
+
+    /// the overlay shows the log and the current input, it is hidden while the console is closed
+    pub fn attach(&mut self, layer: &CanvasLayer, builder: TextBuilder<Strong>) {
+        self.overlay = Some(layer.spawn(builder.with_hidden(!self.open)));
+        self.update_overlay();
+    }
+
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn set_open(&mut self, open: bool) {
+        self.open = open;
+        self.update_overlay();
+    }
+
+    #[inline]
+    pub fn toggle(&mut self) {
+        self.set_open(!self.open);
+    }
+
+    #[inline]
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    pub fn push_str(&mut self, s: &str) {
+        self.input.extend(s.chars().filter(|c| !c.is_control()));
+        self.update_overlay();
+    }
+
+    pub fn backspace(&mut self) {
+        self.input.pop();
+        self.update_overlay();
+    }
+
+    /// handles submit and backspace of an open console, returns if the key was consumed
+    pub fn handle_key(&mut self, event: &KeyInputEvent, res: &mut SimResources<R>) -> bool {
+        if !self.open {
+            return false;
+        }
+
+        if event.value.abs() > f32::EPSILON {
+            match event.scan {
+                ScanCode::Enter | ScanCode::PadEnter => self.submit(res),
+                ScanCode::Backspace => self.backspace(),
+                _ => {}
+            }
+        }
+
+        true
+    }
+
+    pub fn submit(&mut self, res: &mut SimResources<R>) {
+        let line = std::mem::take(&mut self.input);
+        self.execute(&line, res);
+    }
+
+    pub fn execute(&mut self, line: &str, res: &mut SimResources<R>) {
+        let mut parts = line.split_whitespace();
+        let name = some_or_return!(parts.next());
+        let args: Vec<&str> = parts.collect();
+
+        self.push_log(format!("> {}", line.trim()));
+        match self.commands.get_mut(name) {
+            Some(command) => {
+                log::info!("execute console command: {}", name);
+                if let Err(e) = command(&args, res) {
+                    log::warn!("console command {} failed: {}", name, e);
+                    self.push_log(format!("error: {}", e));
+                }
+            }
+            None => {
+                log::info!("dispatch console command: {}", name);
+                res.context.sender().send(ConsoleCommandEvent {
+                    name: name.to_owned(),
+                    args: args.iter().map(|arg| (*arg).to_owned()).collect(),
+                });
+            }
+        }
+
+        self.update_overlay();
+    }
+
+    pub fn push_log<T: Into<String>>(&mut self, line: T) {
+        self.log.push_back(line.into());
+        while self.log.len() > self.log_max {
+            self.log.pop_front();
+        }
+        self.update_overlay();
+    }
+
+    fn update_overlay(&mut self) {
+        let overlay = some_or_return!(self.overlay.as_mut());
+        let mut content = String::new();
+        for line in &self.log {
+            content.push_str(line);
+            content.push('\n');
+        }
+        content.push_str("> ");
+        content.push_str(&self.input);
+
+        let mut modify = overlay.modify();
+        if self.open {
+            modify.show();
+        } else {
+            modify.hide();
+        }
+        if *modify.content != *content {
+            modify.content = content.into();
+        }
+    }
+}
+
+impl<R> Default for Console<R> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
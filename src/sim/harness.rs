@@ -0,0 +1,70 @@
+use crate::sim::stack::StateStackContext;
+use crate::sim::{SimStateEvent, StateInstruction};
+use std::collections::VecDeque;
+
+/// answers every handled message with the next scripted instruction, e.g. to test the
+/// transitions of a `StateStack` without the message runtime
+pub struct ScriptedState {
+    name: &'static str,
+    script: VecDeque<Option<StateInstruction<ScriptedState>>>,
+}
+
+impl ScriptedState {
+    #[inline]
+    pub fn new(name: &'static str) -> Self {
+        Self::scripted(name, vec![])
+    }
+
+    /// the state stays once the script has run out
+    #[inline]
+    pub fn scripted(name: &'static str, script: Vec<Option<StateInstruction<Self>>>) -> Self {
+        Self {
+            name,
+            script: script.into(),
+        }
+    }
+
+    #[inline]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// drives scripted states and records what they received, in the order they received it
+#[derive(Debug, Default)]
+pub struct StateRecorder {
+    pub handled: Vec<&'static str>,
+    pub events: Vec<(&'static str, SimStateEvent)>,
+    pub shutdown: bool,
+}
+
+impl StateRecorder {
+    /// forgets the handled messages and events, but not a requested shutdown
+    pub fn clear(&mut self) {
+        self.handled.clear();
+        self.events.clear();
+    }
+}
+
+impl StateStackContext<ScriptedState> for StateRecorder {
+    fn handle_message(
+        &mut self,
+        state: &mut ScriptedState,
+    ) -> Option<StateInstruction<ScriptedState>> {
+        self.handled.push(state.name);
+        state.script.pop_front().flatten()
+    }
+
+    fn handle_state_event(
+        &mut self,
+        state: &mut ScriptedState,
+        event: SimStateEvent,
+    ) -> Option<StateInstruction<ScriptedState>> {
+        self.events.push((state.name, event));
+        None
+    }
+
+    fn request_shutdown(&mut self) {
+        self.shutdown = true;
+    }
+}
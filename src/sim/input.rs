@@ -0,0 +1,146 @@
+use crate::platform::action::ActionState;
+use crate::platform::input::{Cursor, Modifiers, MouseButton, PointerKind};
+use crate::platform::key::ScanCode;
+use crate::platform::message::{
+    ActionEvent, CursorInputEvent, KeyInputEvent, MouseInputEvent, PointerInputEvent,
+};
+use crate::util::{HashMap, HashSet};
+use internment::Intern;
+use roundabout::prelude::*;
+use std::cell::{Ref, RefCell};
+use std::rc::Rc;
+
+#[derive(Debug, Default)]
+struct InputState {
+    actions: HashMap<Intern<String>, f32>,
+    keys: HashSet<ScanCode>,
+    modifiers: Modifiers,
+    buttons: HashSet<MouseButton>,
+    pointers: HashSet<PointerKind>,
+    cursor: Option<Cursor>,
+}
+
+/// read only snapshot of the latest applied inputs, kept in sync with the input events
+#[derive(Debug, Default, Clone)]
+pub struct InputSnapshot(Rc<RefCell<InputState>>);
+
+impl InputSnapshot {
+    pub(crate) fn handler(
+        handler: OpenMessageHandlerBuilder<InputSnapshot>,
+    ) -> InitMessageHandlerBuilder<InputSnapshot> {
+        handler
+            .on(on_action_event)
+            .on(on_key_input_event)
+            .on(on_mouse_input_event)
+            .on(on_pointer_input_event)
+            .on(on_cursor_input_event)
+            .init_default()
+    }
+
+    #[inline]
+    fn state(&self) -> Ref<InputState> {
+        self.0.borrow()
+    }
+
+    #[inline]
+    pub fn is_action_down(&self, name: &str) -> bool {
+        self.state()
+            .actions
+            .contains_key(&Intern::new(name.to_owned()))
+    }
+
+    #[inline]
+    pub fn axis(&self, name: &str) -> f32 {
+        self.state()
+            .actions
+            .get(&Intern::new(name.to_owned()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn is_key_down(&self, scan: ScanCode) -> bool {
+        self.state().keys.contains(&scan)
+    }
+
+    #[inline]
+    pub fn modifiers(&self) -> Modifiers {
+        self.state().modifiers
+    }
+
+    #[inline]
+    pub fn is_mouse_down(&self, button: MouseButton) -> bool {
+        self.state().buttons.contains(&button)
+    }
+
+    #[inline]
+    pub fn is_pointer_down(&self, kind: PointerKind) -> bool {
+        self.state().pointers.contains(&kind)
+    }
+
+    #[inline]
+    pub fn pointer(&self) -> Option<Cursor> {
+        self.state().cursor
+    }
+}
+
+fn on_action_event(state: &mut InputSnapshot, _context: &mut RuntimeContext, event: &ActionEvent) {
+    let mut state = state.0.borrow_mut();
+    match event.state {
+        ActionState::Start | ActionState::Hold => {
+            state.actions.insert(event.name, event.value);
+        }
+        ActionState::End => {
+            state.actions.remove(&event.name);
+        }
+    }
+}
+
+fn on_key_input_event(
+    state: &mut InputSnapshot,
+    _context: &mut RuntimeContext,
+    event: &KeyInputEvent,
+) {
+    let mut state = state.0.borrow_mut();
+    state.modifiers = event.modifiers;
+    if event.value.abs() <= f32::EPSILON {
+        state.keys.remove(&event.scan);
+    } else {
+        state.keys.insert(event.scan);
+    }
+}
+
+fn on_mouse_input_event(
+    state: &mut InputSnapshot,
+    _context: &mut RuntimeContext,
+    event: &MouseInputEvent,
+) {
+    let mut state = state.0.borrow_mut();
+    if event.value.abs() <= f32::EPSILON {
+        state.buttons.remove(&event.button);
+    } else {
+        state.buttons.insert(event.button);
+    }
+}
+
+fn on_pointer_input_event(
+    state: &mut InputSnapshot,
+    _context: &mut RuntimeContext,
+    event: &PointerInputEvent,
+) {
+    let mut state = state.0.borrow_mut();
+    if event.ended(event.kind) {
+        state.pointers.remove(&event.kind);
+    } else {
+        state.pointers.insert(event.kind);
+    }
+    state.cursor = Some(event.cursor);
+}
+
+fn on_cursor_input_event(
+    state: &mut InputSnapshot,
+    _context: &mut RuntimeContext,
+    event: &CursorInputEvent,
+) {
+    state.0.borrow_mut().cursor = Some(event.cursor);
+}
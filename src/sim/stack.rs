@@ -0,0 +1,418 @@
+use crate::sim::{SimStateEvent, StateInstruction};
+use crate::some_or_return;
+
+/// connects the state stack to the states, e.g. to the sim server runtime or a `StateRecorder`
+pub trait StateStackContext<S> {
+    /// forwards the currently handled message to the state
+    fn handle_message(&mut self, state: &mut S) -> Option<StateInstruction<S>>;
+
+    /// informs the state about its position in the stack
+    fn handle_state_event(
+        &mut self,
+        state: &mut S,
+        event: SimStateEvent,
+    ) -> Option<StateInstruction<S>>;
+
+    /// the last state has been removed from the stack
+    fn request_shutdown(&mut self);
+}
+
+pub struct SimStackEntry<T>(usize, Option<StateInstruction<T>>);
+
+/// the stack of sim states and the transitions between them, independent of the message runtime
+pub struct StateStack<S> {
+    states: Vec<S>,
+    stack: Vec<SimStackEntry<S>>,
+}
+
+impl<S> StateStack<S> {
+    /// the initial state is pushed with the first handled message
+    pub fn initial(initial: S) -> Self {
+        let initial_stack_entry = SimStackEntry(0, Some(StateInstruction::push(initial)));
+
+        Self {
+            states: vec![],
+            stack: vec![initial_stack_entry],
+        }
+    }
+
+    /// states from the bottom to the head of the stack
+    #[inline]
+    pub fn states(&self) -> &[S] {
+        &self.states
+    }
+
+    fn inject_stop_event<C: StateStackContext<S>>(state: &mut S, context: &mut C) {
+        let instruction = context.handle_state_event(state, SimStateEvent::Stop);
+        assert!(
+            instruction.map(|i| i.is_stay()).unwrap_or(true),
+            "non stay instructions are not supported when state handles SimStateEvent::Stop"
+        );
+    }
+
+    pub fn handle<C: StateStackContext<S>>(&mut self, context: &mut C) {
+        // TODO: pop on shutdown event?
+        // Optimization: filter messages that are not handed by any state
+
+        let mut current: usize = 0;
+
+        // Optimal path with no stat changes
+        for state in &mut self.states {
+            match context.handle_message(state) {
+                None => {
+                    current += 1;
+                }
+                Some(StateInstruction::Stay) => {
+                    current += 1;
+                }
+                Some(instruction) => {
+                    let stack_entry = SimStackEntry(current, Some(instruction));
+                    self.stack.push(stack_entry);
+                    current += 1;
+                    break;
+                }
+            }
+        }
+
+        loop {
+            match self.stack.pop() {
+                None => {
+                    let state = some_or_return!(self.states.get_mut(current));
+                    let instruction = context.handle_message(state);
+                    self.stack.push(SimStackEntry(current, instruction));
+                    current += 1;
+                }
+                Some(SimStackEntry(_at, None)) => {}
+                Some(SimStackEntry(_at, Some(StateInstruction::Stay))) => {}
+                Some(SimStackEntry(at, Some(StateInstruction::Switch(new)))) => {
+                    log::info!("switch state at {}", at);
+
+                    let is_head = at + 1 == self.states.len();
+                    let state = self.states.get_mut(at).unwrap();
+                    Self::inject_stop_event(state, context);
+                    *state = new;
+
+                    if is_head {
+                        let instruction = context.handle_state_event(state, SimStateEvent::Head);
+                        self.stack.push(SimStackEntry(at, instruction));
+                    } else {
+                        let instruction = context.handle_state_event(state, SimStateEvent::Tail);
+                        self.stack.push(SimStackEntry(at, instruction));
+                    }
+
+                    if at < current {
+                        current = at;
+                    }
+                }
+                Some(SimStackEntry(at, Some(StateInstruction::Push(new)))) => {
+                    log::info!("push {} state(s) at {}", new.len(), at);
+
+                    let next_idx = at + 1;
+                    let is_tail = next_idx < self.states.len();
+                    let new_len = new.len();
+
+                    if is_tail {
+                        for mut state in self.states.drain(next_idx..).rev() {
+                            Self::inject_stop_event(&mut state, context);
+                        }
+                    }
+
+                    if new_len > 0 {
+                        self.states.extend(new);
+
+                        for (idx, new_tail) in self
+                            .states
+                            .iter_mut()
+                            .enumerate()
+                            .skip(next_idx - !is_tail as usize)
+                            .take(new_len + !is_tail as usize - 1)
+                        {
+                            let instruction =
+                                context.handle_state_event(new_tail, SimStateEvent::Tail);
+                            self.stack.push(SimStackEntry(idx, instruction));
+                        }
+                    }
+
+                    if (new_len == 0 && is_tail) || new_len > 0 {
+                        let last_idx = self.states.len().saturating_sub(1);
+                        if let Some(last) = self.states.last_mut() {
+                            let instruction = context.handle_state_event(last, SimStateEvent::Head);
+                            self.stack.push(SimStackEntry(last_idx, instruction));
+                        }
+                    }
+                }
+                Some(SimStackEntry(at, Some(StateInstruction::Pop))) => {
+                    log::info!("pop state at {}", at);
+
+                    for mut state in self.states.drain(at..).rev() {
+                        Self::inject_stop_event(&mut state, context);
+                    }
+
+                    let last_idx = self.states.len().saturating_sub(1);
+                    if let Some(last) = self.states.last_mut() {
+                        let instruction = context.handle_state_event(last, SimStateEvent::Head);
+                        self.stack.push(SimStackEntry(last_idx, instruction));
+                    } else {
+                        context.request_shutdown();
+                    }
+                }
+                Some(SimStackEntry(at, Some(StateInstruction::PopPush(new)))) => {
+                    log::info!("pop push {} state(s) at {}", new.len(), at);
+
+                    let new_len = new.len();
+
+                    for mut state in self.states.drain(at..).rev() {
+                        Self::inject_stop_event(&mut state, context);
+                    }
+
+                    if new_len > 0 {
+                        self.states.extend(new);
+
+                        for (idx, new_tail) in self
+                            .states
+                            .iter_mut()
+                            .enumerate()
+                            .skip(at)
+                            .take(new_len - 1)
+                        {
+                            let instruction =
+                                context.handle_state_event(new_tail, SimStateEvent::Tail);
+                            self.stack.push(SimStackEntry(idx, instruction));
+                        }
+                    }
+
+                    let last_idx = self.states.len().saturating_sub(1);
+                    if let Some(last) = self.states.last_mut() {
+                        let instruction = context.handle_state_event(last, SimStateEvent::Head);
+                        self.stack.push(SimStackEntry(last_idx, instruction));
+                    } else {
+                        context.request_shutdown();
+                    }
+
+                    if at < current {
+                        current = at;
+                    }
+                }
+                Some(SimStackEntry(at, Some(StateInstruction::Extract))) => {
+                    log::info!("extract state at {}", at);
+
+                    let mut state = self.states.remove(at);
+                    Self::inject_stop_event(&mut state, context);
+
+                    if self.states.is_empty() {
+                        context.request_shutdown();
+                    } else if at == self.states.len() {
+                        let last_idx = self.states.len() - 1;
+                        let last = self.states.last_mut().unwrap();
+                        let instruction = context.handle_state_event(last, SimStateEvent::Head);
+                        self.stack.push(SimStackEntry(last_idx, instruction));
+                    }
+
+                    if at < current {
+                        current = at;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::harness::{ScriptedState, StateRecorder};
+
+    fn names(stack: &StateStack<ScriptedState>) -> Vec<&'static str> {
+        stack.states().iter().map(ScriptedState::name).collect()
+    }
+
+    /// the stack `[a, b, c]` after the first message, with the scripts for the following messages
+    fn three_states(
+        mut a: Vec<Option<StateInstruction<ScriptedState>>>,
+        mut b: Vec<Option<StateInstruction<ScriptedState>>>,
+        mut c: Vec<Option<StateInstruction<ScriptedState>>>,
+    ) -> (StateStack<ScriptedState>, StateRecorder) {
+        // the pushed states handle the first message as well
+        b.insert(0, None);
+        c.insert(0, None);
+        let push = StateInstruction::push_iter([
+            ScriptedState::scripted("b", b),
+            ScriptedState::scripted("c", c),
+        ]);
+        a.insert(0, Some(push));
+
+        let mut stack = StateStack::initial(ScriptedState::scripted("a", a));
+        let mut context = StateRecorder::default();
+        stack.handle(&mut context);
+        assert_eq!(names(&stack), ["a", "b", "c"]);
+        context.clear();
+
+        (stack, context)
+    }
+
+    #[test]
+    fn initial_push() {
+        let mut stack = StateStack::initial(ScriptedState::new("a"));
+        let mut context = StateRecorder::default();
+        stack.handle(&mut context);
+
+        assert_eq!(names(&stack), ["a"]);
+        assert_eq!(
+            context.events,
+            [("a", SimStateEvent::Tail), ("a", SimStateEvent::Head)]
+        );
+        assert_eq!(context.handled, ["a"]);
+        assert!(!context.shutdown);
+    }
+
+    #[test]
+    fn push_at_head() {
+        let (mut stack, mut context) = three_states(
+            vec![],
+            vec![],
+            vec![Some(StateInstruction::push(ScriptedState::new("d")))],
+        );
+        stack.handle(&mut context);
+
+        assert_eq!(names(&stack), ["a", "b", "c", "d"]);
+        assert_eq!(
+            context.events,
+            [("c", SimStateEvent::Tail), ("d", SimStateEvent::Head)]
+        );
+        assert_eq!(context.handled, ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn push_at_tail() {
+        let (mut stack, mut context) = three_states(
+            vec![Some(StateInstruction::push(ScriptedState::new("d")))],
+            vec![],
+            vec![],
+        );
+        stack.handle(&mut context);
+
+        assert_eq!(names(&stack), ["a", "d"]);
+        assert_eq!(
+            context.events,
+            [
+                ("c", SimStateEvent::Stop),
+                ("b", SimStateEvent::Stop),
+                ("d", SimStateEvent::Head)
+            ]
+        );
+        assert_eq!(context.handled, ["a", "d"]);
+    }
+
+    #[test]
+    fn switch_tail() {
+        let (mut stack, mut context) = three_states(
+            vec![],
+            vec![Some(StateInstruction::switch(ScriptedState::new("d")))],
+            vec![],
+        );
+        stack.handle(&mut context);
+
+        assert_eq!(names(&stack), ["a", "d", "c"]);
+        assert_eq!(
+            context.events,
+            [("b", SimStateEvent::Stop), ("d", SimStateEvent::Tail)]
+        );
+        assert_eq!(context.handled, ["a", "b", "d", "c"]);
+    }
+
+    #[test]
+    fn pop_push_middle() {
+        let pop_push =
+            StateInstruction::pop_push_iter([ScriptedState::new("d"), ScriptedState::new("e")]);
+        let (mut stack, mut context) = three_states(vec![], vec![Some(pop_push)], vec![]);
+        stack.handle(&mut context);
+
+        assert_eq!(names(&stack), ["a", "d", "e"]);
+        assert_eq!(
+            context.events,
+            [
+                ("c", SimStateEvent::Stop),
+                ("b", SimStateEvent::Stop),
+                ("d", SimStateEvent::Tail),
+                ("e", SimStateEvent::Head)
+            ]
+        );
+        assert_eq!(context.handled, ["a", "b", "d", "e"]);
+    }
+
+    #[test]
+    fn pop_middle() {
+        let (mut stack, mut context) =
+            three_states(vec![], vec![Some(StateInstruction::Pop)], vec![]);
+        stack.handle(&mut context);
+
+        assert_eq!(names(&stack), ["a"]);
+        assert_eq!(
+            context.events,
+            [
+                ("c", SimStateEvent::Stop),
+                ("b", SimStateEvent::Stop),
+                ("a", SimStateEvent::Head)
+            ]
+        );
+        assert!(!context.shutdown);
+    }
+
+    #[test]
+    fn pop_to_empty() {
+        let (mut stack, mut context) =
+            three_states(vec![Some(StateInstruction::Pop)], vec![], vec![]);
+        stack.handle(&mut context);
+
+        assert!(names(&stack).is_empty());
+        assert_eq!(
+            context.events,
+            [
+                ("c", SimStateEvent::Stop),
+                ("b", SimStateEvent::Stop),
+                ("a", SimStateEvent::Stop)
+            ]
+        );
+        assert!(context.shutdown);
+    }
+
+    #[test]
+    fn extract_middle() {
+        let (mut stack, mut context) =
+            three_states(vec![], vec![Some(StateInstruction::Extract)], vec![]);
+        stack.handle(&mut context);
+
+        assert_eq!(names(&stack), ["a", "c"]);
+        assert_eq!(context.events, [("b", SimStateEvent::Stop)]);
+        assert_eq!(context.handled, ["a", "b", "c"]);
+        assert!(!context.shutdown);
+    }
+
+    #[test]
+    fn extract_head() {
+        let (mut stack, mut context) =
+            three_states(vec![], vec![], vec![Some(StateInstruction::Extract)]);
+        stack.handle(&mut context);
+
+        assert_eq!(names(&stack), ["a", "b"]);
+        assert_eq!(
+            context.events,
+            [("c", SimStateEvent::Stop), ("b", SimStateEvent::Head)]
+        );
+        assert!(!context.shutdown);
+    }
+
+    #[test]
+    fn extract_last() {
+        let a = ScriptedState::scripted("a", vec![None, Some(StateInstruction::Extract)]);
+        let mut stack = StateStack::initial(a);
+        let mut context = StateRecorder::default();
+        stack.handle(&mut context);
+        context.clear();
+        stack.handle(&mut context);
+
+        assert!(names(&stack).is_empty());
+        assert_eq!(context.events, [("a", SimStateEvent::Stop)]);
+        assert!(context.shutdown);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::platform::message::FrameRequestedEvent;
+use crate::util::HashMap;
 use roundabout::prelude::*;
 use std::borrow::Borrow;
 use std::cmp::Ordering;
@@ -22,9 +23,104 @@ impl Ord for ScheduleKey {
     }
 }
 
-pub struct TimeServer {
+type MessageFactory = Box<dyn FnMut(&MessageSender) -> Option<UntypedMessage> + Send>;
+
+struct Interval {
+    period: Duration,
+    next: Duration,
+    factory: MessageFactory,
+}
+
+impl Interval {
+    /// missed periods beyond this many fires in one frame are skipped, the next fire stays in phase
+    const MAX_FIRES: u32 = 8;
+
+    /// the next fire time after elapsed, in phase with the schedule time
+    fn skip_to(&mut self, elapsed: Duration) {
+        if self.next <= elapsed {
+            let missed = (elapsed - self.next).as_nanos() / self.period.as_nanos() + 1;
+            self.next += Duration::from_nanos((self.period.as_nanos() * missed) as u64);
+        }
+    }
+}
+
+/// cancels the recurring timer on drop
+#[must_use]
+#[derive(Debug)]
+pub struct TimerHandle {
+    id: Uuid,
+    sender: MessageSender,
+}
+
+impl TimerHandle {
+    #[inline]
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+}
+
+impl Drop for TimerHandle {
+    fn drop(&mut self) {
+        self.sender.send(CancelTimerEvent { id: self.id });
+    }
+}
+
+/// the time timers are scheduled against
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TimerClock {
+    /// wall clock time since the start, it keeps advancing while the sim is paused
+    Wall,
+    /// sim time since the start, it doesn't advance while the sim is paused
+    Sim,
+}
+
+#[derive(Default)]
+struct Timers {
     elapsed: Duration,
     scheduled: BTreeMap<ScheduleKey, UntypedMessage>,
+    intervals: HashMap<Uuid, Interval>,
+}
+
+impl Timers {
+    fn advance(&mut self, elapsed: Duration, sender: &MessageSender) {
+        self.elapsed = elapsed;
+
+        // Optimization: batching | append vs prepend
+        while let Some(entry) = self.scheduled.last_entry() {
+            if entry.key().0 > self.elapsed {
+                break;
+            }
+
+            log::debug!("trigger timer event: {}", entry.key().1);
+            sender.send_untyped(entry.remove());
+        }
+
+        for (id, interval) in &mut self.intervals {
+            let mut fires = 0;
+            while interval.next <= self.elapsed {
+                if fires == Interval::MAX_FIRES {
+                    log::warn!("interval can't keep up, skipping missed fires: {}", id);
+                    interval.skip_to(self.elapsed);
+                    break;
+                }
+
+                log::debug!("trigger interval event: {}", id);
+                interval.next += interval.period;
+                fires += 1;
+                match (interval.factory)(sender) {
+                    Some(message) => sender.send_untyped(message),
+                    None => log::warn!("skipping unhandled interval event: {}", id),
+                }
+            }
+        }
+    }
+}
+
+/// timers run on the wall clock by default, so they keep firing while the sim is paused,
+/// the `_sim` variants opt into the sim time of the frames instead
+pub struct TimeServer {
+    wall: Timers,
+    sim: Timers,
 }
 
 impl TimeServer {
@@ -34,22 +130,53 @@ impl TimeServer {
         handler
             .on(on_frame_requested_event)
             .on(on_schedule_timer_event)
+            .on(on_schedule_interval_event)
+            .on(on_cancel_timer_event)
             .init(TimeServer {
-                elapsed: Default::default(),
-                scheduled: Default::default(),
+                wall: Default::default(),
+                sim: Default::default(),
             })
     }
 
+    #[inline]
+    fn timers(&mut self, clock: TimerClock) -> &mut Timers {
+        match clock {
+            TimerClock::Wall => &mut self.wall,
+            TimerClock::Sim => &mut self.sim,
+        }
+    }
+
+    #[inline]
     pub fn schedule<E: 'static + Send + Sync>(
         duration: Duration,
         event: E,
         sender: &MessageSender,
+    ) {
+        Self::schedule_on(TimerClock::Wall, duration, event, sender);
+    }
+
+    /// like `schedule`, but the timer is paused together with the sim
+    #[inline]
+    pub fn schedule_sim<E: 'static + Send + Sync>(
+        duration: Duration,
+        event: E,
+        sender: &MessageSender,
+    ) {
+        Self::schedule_on(TimerClock::Sim, duration, event, sender);
+    }
+
+    pub fn schedule_on<E: 'static + Send + Sync>(
+        clock: TimerClock,
+        duration: Duration,
+        event: E,
+        sender: &MessageSender,
     ) {
         let sender = sender.borrow();
         match sender.prepare(event) {
             Some(scheduled) => {
                 sender.send(ScheduleTimerEvent {
                     id: Uuid::new_v4(),
+                    clock,
                     duration,
                     scheduled: Mutex::new(Some(scheduled)),
                 });
@@ -62,6 +189,63 @@ impl TimeServer {
             }
         }
     }
+
+    /// fires a fresh event of the factory every period until the handle is dropped,
+    /// the fire times are multiples of the period after the schedule time so they don't drift,
+    /// they are checked against the wall clock elapsed time of the requested frames,
+    /// after a stall only a few missed fires are caught up and the rest is skipped
+    #[inline]
+    pub fn schedule_interval<E, F>(
+        period: Duration,
+        factory: F,
+        sender: &MessageSender,
+    ) -> TimerHandle
+    where
+        E: 'static + Send + Sync,
+        F: FnMut() -> E + Send + 'static,
+    {
+        Self::schedule_interval_on(TimerClock::Wall, period, factory, sender)
+    }
+
+    /// like `schedule_interval`, but the interval is paused together with the sim
+    #[inline]
+    pub fn schedule_interval_sim<E, F>(
+        period: Duration,
+        factory: F,
+        sender: &MessageSender,
+    ) -> TimerHandle
+    where
+        E: 'static + Send + Sync,
+        F: FnMut() -> E + Send + 'static,
+    {
+        Self::schedule_interval_on(TimerClock::Sim, period, factory, sender)
+    }
+
+    pub fn schedule_interval_on<E, F>(
+        clock: TimerClock,
+        period: Duration,
+        mut factory: F,
+        sender: &MessageSender,
+    ) -> TimerHandle
+    where
+        E: 'static + Send + Sync,
+        F: FnMut() -> E + Send + 'static,
+    {
+        let id = Uuid::new_v4();
+        let factory: MessageFactory =
+            Box::new(move |sender: &MessageSender| sender.prepare(factory()));
+        sender.send(ScheduleIntervalEvent {
+            id,
+            clock,
+            period,
+            factory: Mutex::new(Some(factory)),
+        });
+
+        TimerHandle {
+            id,
+            sender: sender.clone(),
+        }
+    }
 }
 
 fn on_frame_requested_event(
@@ -69,17 +253,8 @@ fn on_frame_requested_event(
     context: &mut RuntimeContext,
     event: &FrameRequestedEvent,
 ) {
-    state.elapsed = event.elapsed;
-
-    // Optimization: batching | append vs prepend
-    while let Some(entry) = state.scheduled.last_entry() {
-        if entry.key().0 > state.elapsed {
-            break;
-        }
-
-        log::debug!("trigger timer event: {}", entry.key().1);
-        context.sender().send_untyped(entry.remove());
-    }
+    state.wall.advance(event.wall_elapsed, context.sender());
+    state.sim.advance(event.elapsed, context.sender());
 }
 
 fn on_schedule_timer_event(
@@ -87,17 +262,65 @@ fn on_schedule_timer_event(
     _context: &mut RuntimeContext,
     event: &ScheduleTimerEvent,
 ) {
-    let at = state.elapsed + event.duration;
+    let timers = state.timers(event.clock);
+    let at = timers.elapsed + event.duration;
     log::debug!("schedule timer event: {}", event.id);
     // Optimization: batching | append vs prepend
-    state.scheduled.insert(
+    timers.scheduled.insert(
         ScheduleKey(at, event.id),
         event.scheduled.lock().unwrap().take().unwrap(),
     );
 }
 
+fn on_schedule_interval_event(
+    state: &mut TimeServer,
+    _context: &mut RuntimeContext,
+    event: &ScheduleIntervalEvent,
+) {
+    if event.period.is_zero() {
+        log::warn!("skipping interval with a zero period: {}", event.id);
+        return;
+    }
+
+    log::debug!("schedule interval event: {}", event.id);
+    let timers = state.timers(event.clock);
+    let next = timers.elapsed + event.period;
+    timers.intervals.insert(
+        event.id,
+        Interval {
+            period: event.period,
+            next,
+            factory: event.factory.lock().unwrap().take().unwrap(),
+        },
+    );
+}
+
+fn on_cancel_timer_event(
+    state: &mut TimeServer,
+    _context: &mut RuntimeContext,
+    event: &CancelTimerEvent,
+) {
+    log::debug!("cancel interval event: {}", event.id);
+    if state.wall.intervals.remove(&event.id).is_none() {
+        state.sim.intervals.remove(&event.id);
+    }
+}
+
 pub struct ScheduleTimerEvent {
     id: Uuid,
+    clock: TimerClock,
     duration: Duration,
     scheduled: Mutex<Option<UntypedMessage>>,
 }
+
+pub struct ScheduleIntervalEvent {
+    id: Uuid,
+    clock: TimerClock,
+    period: Duration,
+    factory: Mutex<Option<MessageFactory>>,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct CancelTimerEvent {
+    id: Uuid,
+}
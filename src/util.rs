@@ -1,4 +1,4 @@
-use nalgebra::Point2;
+use nalgebra::{Point2, Similarity2};
 use std::ops::{Deref, DerefMut};
 
 pub type HashMap<K, T> = std::collections::HashMap<K, T, ahash::RandomState>;
@@ -225,9 +225,27 @@ pub struct Bounds {
     pub h: Point2<f32>,
 }
 
+impl Bounds {
+    #[inline]
+    pub fn transform(&self, world: &Similarity2<f32>) -> Self {
+        Self {
+            o: world * self.o,
+            w: world * self.w,
+            h: world * self.h,
+        }
+    }
+}
+
 pub trait Bounded {
+    /// relative to the world of the group for grouped objects
     fn bounds(&self) -> Bounds;
 
+    /// the bounds composed with a world, e.g. `sprite.bounds_in(&group.world)` of its group
+    #[inline]
+    fn bounds_in(&self, world: &Similarity2<f32>) -> Bounds {
+        self.bounds().transform(world)
+    }
+
     #[inline]
     fn contains(&self, point: Point2<f32>) -> bool {
         // see https://math.stackexchange.com/questions/1805724/detect-if-point-is-within-rotated-rectangles-bounds